@@ -3,7 +3,11 @@ use proc_macro2::{Span, TokenTree};
 use quote::{quote, quote_spanned};
 use std::borrow::Cow;
 use std::error::Error;
-use syn::{spanned::Spanned, Attribute, Data, DeriveInput, Field, Fields, Meta, Type};
+use syn::{
+    meta::ParseNestedMeta, punctuated::Punctuated, spanned::Spanned, Attribute, Data, DeriveInput,
+    Expr, ExprLit, Field, Fields, GenericArgument, Lit, LitStr, Meta, MetaNameValue, PathArguments,
+    Token, Type,
+};
 
 type Result<T, E = ConfigurationError> = std::result::Result<T, E>;
 
@@ -21,19 +25,57 @@ impl std::fmt::Display for ConfigurationError {
 
 impl Error for ConfigurationError {}
 
+impl From<syn::Error> for ConfigurationError {
+    fn from(err: syn::Error) -> Self {
+        Self {
+            span: err.span(),
+            reason: err.to_string().into(),
+        }
+    }
+}
+
 struct ConfigFieldMetadata {
     is_password: bool,
     is_text_area: bool,
     is_read_only: bool,
+    description: Option<String>,
+    placeholder: Option<String>,
+    group: Option<String>,
+    is_advanced: bool,
+    is_template: bool,
 }
 
 struct ConfigField<'a> {
     name: String,
     ty: ConfigFieldType,
     metadata: ConfigFieldMetadata,
+    rules: Vec<ConfigRule>,
     name_ident: &'a proc_macro2::Ident,
 }
 
+// validation rule, declared with `#[validate(...)]`
+enum ConfigRule {
+    Required,
+    Min(f64),
+    Max(f64),
+    Regex(String),
+    Url,
+    OneOf(Vec<String>),
+}
+
+impl From<&ConfigRule> for proc_macro2::TokenStream {
+    fn from(val: &ConfigRule) -> Self {
+        match val {
+            ConfigRule::Required => quote! { config::Rule::Required },
+            ConfigRule::Min(min) => quote! { config::Rule::Min(#min) },
+            ConfigRule::Max(max) => quote! { config::Rule::Max(#max) },
+            ConfigRule::Regex(regex) => quote! { config::Rule::Regex(#regex) },
+            ConfigRule::Url => quote! { config::Rule::Url },
+            ConfigRule::OneOf(values) => quote! { config::Rule::OneOf(&[#(#values),*]) },
+        }
+    }
+}
+
 #[derive(Clone)]
 enum ConfigFieldType {
     Usize,
@@ -45,9 +87,67 @@ enum ConfigFieldType {
     U16,
     U32,
     U64,
+    F64,
     String,
     Bool,
-    //    Vec(Box<ConfigFieldType>),
+    Duration,
+    StringList,
+    // any other type is expected to implement `config::Enum`
+    Enum(Box<Type>),
+    Option(Box<ConfigFieldType>),
+}
+
+impl ConfigFieldType {
+    fn inner(&self) -> &ConfigFieldType {
+        match self {
+            ConfigFieldType::Option(ty) => ty.inner(),
+            ty => ty,
+        }
+    }
+
+    fn is_enum(&self) -> bool {
+        match self {
+            ConfigFieldType::Enum(_) => true,
+            ConfigFieldType::Option(ty) => ty.is_enum(),
+            _ => false,
+        }
+    }
+
+    // expression to build config::FieldValue out of field reference
+    fn field_value(&self, access: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match self {
+            ConfigFieldType::Enum(_) => {
+                quote! { config::FieldValue::String(config::Enum::as_str(#access)) }
+            }
+            ConfigFieldType::Option(ty) if ty.is_enum() => {
+                let value = ty.field_value(quote! { value });
+                quote! {
+                    match #access {
+                        Some(value) => #value,
+                        None => config::FieldValue::Null,
+                    }
+                }
+            }
+            _ => quote! { (#access).into() },
+        }
+    }
+
+    // expression to convert config::FieldValue `value` into field type
+    fn try_from_value(&self) -> proc_macro2::TokenStream {
+        match self {
+            ConfigFieldType::Enum(_) => quote! { config::Enum::try_from_field_value(value)? },
+            ConfigFieldType::Option(ty) if ty.is_enum() => {
+                let value = ty.try_from_value();
+                quote! {
+                    match value {
+                        value if value.is_empty() => None,
+                        value => Some(#value),
+                    }
+                }
+            }
+            _ => quote! { value.try_into()? },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -81,25 +181,34 @@ impl From<&ConfigFieldType> for proc_macro2::TokenStream {
             ConfigFieldType::U16 => quote! { config::FieldType::U16 },
             ConfigFieldType::U32 => quote! { config::FieldType::U32 },
             ConfigFieldType::U64 => quote! { config::FieldType::U64 },
+            ConfigFieldType::F64 => quote! { config::FieldType::F64 },
             ConfigFieldType::String => quote! { config::FieldType::String },
             ConfigFieldType::Bool => quote! { config::FieldType::Bool },
-            //     ConfigFieldType::Vec(ty) => {
-            //         let tokens: proc_macro2::TokenStream = (&**ty).into();
-            //         quote! { config::FieldType::Vec(Box::new(#tokens)) }
-            //     }
+            ConfigFieldType::Duration => quote! { config::FieldType::Duration },
+            ConfigFieldType::StringList => quote! { config::FieldType::StringList },
+            ConfigFieldType::Enum(ty) => {
+                quote! { config::FieldType::Enum(<#ty as config::Enum>::VARIANTS) }
+            }
+            ConfigFieldType::Option(ty) => {
+                let tokens: proc_macro2::TokenStream = (&**ty).into();
+                quote! {{
+                    static TY: config::FieldType = #tokens;
+                    config::FieldType::Option(&TY)
+                }}
+            }
         }
     }
 }
 
 fn parse_section_attr(
     attrs: &[Attribute],
-) -> Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
-    let (i, o) = attrs
+) -> Result<(proc_macro2::TokenStream, proc_macro2::TokenStream, u32)> {
+    let (i, o, version) = attrs
         .iter()
         .filter(|attr| attr.path().is_ident("section"))
         .try_fold(
-            (SectionIO::None, SectionIO::None),
-            |(mut i, mut o), attr| {
+            (SectionIO::None, SectionIO::None, 1),
+            |(mut i, mut o, mut version), attr| {
                 let tokens = match &attr.meta {
                     Meta::List(list) => &list.tokens,
                     other => {
@@ -142,6 +251,17 @@ fn parse_section_attr(
                                 ("output", "=", "bin_or_dataframe") => {
                                     o = SectionIO::BinOrDataFrame
                                 }
+                                ("version", "=", value) => {
+                                    version = match value.parse::<u32>() {
+                                        Ok(version) if version > 0 => version,
+                                        _ => {
+                                            return Err(ConfigurationError {
+                                                span: attr.span(),
+                                                reason: "version should be positive integer".into(),
+                                            })
+                                        }
+                                    }
+                                }
                                 other => {
                                     return Err(ConfigurationError {
                                         span: attr.span(),
@@ -158,134 +278,329 @@ fn parse_section_attr(
                         }
                     }
                 }
-                Ok((i, o))
+                Ok((i, o, version))
             },
         )?;
-    Ok((i.into(), o.into()))
+    Ok((i.into(), o.into(), version))
+}
+
+// `#[migrate(from = N, with = path::to::fn)]` struct attributes
+fn parse_migrate_attrs(attrs: &[Attribute], version: u32) -> Result<Vec<(u32, syn::Path)>> {
+    let mut migrations: Vec<(u32, syn::Path)> = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("migrate")) {
+        let mut from = None;
+        let mut with = None;
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("from") {
+                from = Some(
+                    meta.value()?
+                        .parse::<syn::LitInt>()?
+                        .base10_parse::<u32>()?,
+                );
+                return Ok(());
+            }
+            if meta.path.is_ident("with") {
+                with = Some(meta.value()?.parse::<syn::Path>()?);
+                return Ok(());
+            }
+            Err(meta.error("expected `from = N` or `with = path::to::fn`"))
+        })?;
+        let (from, with) = match (from, with) {
+            (Some(from), Some(with)) => (from, with),
+            _ => Err(ConfigurationError {
+                span: attr.span(),
+                reason: "migration requires both `from` and `with`".into(),
+            })?,
+        };
+        if from == 0 || from >= version {
+            Err(ConfigurationError {
+                span: attr.span(),
+                reason: format!(
+                    "migration from version {from} is out of range, config version is {version}"
+                )
+                .into(),
+            })?
+        }
+        if migrations.iter().any(|(f, _)| *f == from) {
+            Err(ConfigurationError {
+                span: attr.span(),
+                reason: format!("duplicate migration from version {from}").into(),
+            })?
+        }
+        migrations.push((from, with));
+    }
+    Ok(migrations)
+}
+
+// doc comments are joined into single description, empty lines separate paragraphs
+fn parse_doc(attrs: &[Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(MetaNameValue {
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(doc), ..
+                    }),
+                ..
+            }) => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let description = lines
+        .split(String::is_empty)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| paragraph.join(" "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!description.is_empty()).then_some(description)
 }
 
-fn parse_field_attributes(field_attributes: &[Attribute]) -> Result<ConfigFieldMetadata> {
-    let mut is_password = false;
-    let mut is_text_area = false;
-    let mut is_read_only = false;
-    match field_attributes {
-        [attr] if attr.path().is_ident("field_type") => {
+// `#[field(description = "...", placeholder = "...", group = "...", advanced)]`
+fn parse_field_attr(attr: &Attribute, metadata: &mut ConfigFieldMetadata) -> Result<()> {
+    attr.parse_nested_meta(|meta| {
+        let ident = meta
+            .path
+            .get_ident()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        match ident.as_str() {
+            "description" => metadata.description = Some(meta.value()?.parse::<LitStr>()?.value()),
+            "placeholder" => metadata.placeholder = Some(meta.value()?.parse::<LitStr>()?.value()),
+            "group" => metadata.group = Some(meta.value()?.parse::<LitStr>()?.value()),
+            "advanced" => metadata.is_advanced = true,
+            "template" => metadata.is_template = true,
+            _ => return Err(meta.error("unsupported field attribute")),
+        };
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn parse_field_attributes(
+    field_attributes: &[Attribute],
+) -> Result<(ConfigFieldMetadata, Vec<(ConfigRule, Span)>)> {
+    let mut metadata = ConfigFieldMetadata {
+        is_password: false,
+        is_text_area: false,
+        is_read_only: false,
+        description: parse_doc(field_attributes),
+        placeholder: None,
+        group: None,
+        is_advanced: false,
+        is_template: false,
+    };
+    let mut rules = vec![];
+    for attr in field_attributes {
+        if attr.path().is_ident("doc") {
+            continue;
+        } else if attr.path().is_ident("field") {
+            parse_field_attr(attr, &mut metadata)?;
+        } else if attr.path().is_ident("field_type") {
             attr.parse_nested_meta(|meta| {
                 if meta.path.is_ident("password") {
-                    is_password = true;
+                    metadata.is_password = true;
                     return Ok(());
                 };
                 if meta.path.is_ident("text_area") {
-                    is_text_area = true;
+                    metadata.is_text_area = true;
                     return Ok(());
                 };
                 if meta.path.is_ident("read_only") {
-                    is_read_only = true;
+                    metadata.is_read_only = true;
                     return Ok(());
                 };
                 Ok(())
-            })
-            .unwrap();
+            })?;
+        } else if attr.path().is_ident("validate") {
+            attr.parse_nested_meta(|meta| {
+                rules.push((parse_rule(&meta)?, meta.path.span()));
+                Ok(())
+            })?;
+        } else {
+            Err(ConfigurationError {
+                span: attr.span(),
+                reason: format!("unsupported attribute: {:?}", attr.path()).into(),
+            })?
         }
-        [attr] if attr.path().is_ident("validate") => {}
-        [attr] => Err(ConfigurationError {
-            span: attr.span(),
-            reason: format!("unsupported attribute: {:?}", attr.path()).into(),
-        })?,
-        _ => (),
+    }
+    Ok((metadata, rules))
+}
+
+fn parse_rule(meta: &ParseNestedMeta) -> syn::Result<ConfigRule> {
+    let ident = meta
+        .path
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let rule = match ident.as_str() {
+        "required" => ConfigRule::Required,
+        "url" => ConfigRule::Url,
+        "min" => ConfigRule::Min(parse_number(meta)?),
+        "max" => ConfigRule::Max(parse_number(meta)?),
+        "regex" => {
+            let regex = meta.value()?.parse::<LitStr>()?;
+            if let Err(e) = regex::Regex::new(&regex.value()) {
+                return Err(meta.error(format!("invalid regex: {e}")));
+            }
+            ConfigRule::Regex(regex.value())
+        }
+        "one_of" => {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            let values = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+            if values.is_empty() {
+                return Err(meta.error("one_of expects at least one value"));
+            }
+            ConfigRule::OneOf(values.iter().map(LitStr::value).collect())
+        }
+        _ => return Err(meta.error("unsupported validation rule")),
     };
-    Ok(ConfigFieldMetadata {
-        is_password,
-        is_text_area,
-        is_read_only,
-    })
+    Ok(rule)
+}
+
+fn parse_number(meta: &ParseNestedMeta) -> syn::Result<f64> {
+    let input = meta.value()?;
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let value = match input.parse::<Lit>()? {
+        Lit::Int(int) => int.base10_parse::<f64>()?,
+        Lit::Float(float) => float.base10_parse::<f64>()?,
+        lit => return Err(syn::Error::new(lit.span(), "expected number")),
+    };
+    Ok(if negative { -value } else { value })
+}
+
+// check if rule can be applied to field type
+fn check_rule(ty: &ConfigFieldType, rule: &ConfigRule, span: Span) -> Result<()> {
+    let inner = ty.inner();
+    let is_text = matches!(inner, ConfigFieldType::String | ConfigFieldType::StringList);
+    let (supported, reason) = match rule {
+        ConfigRule::Required => (
+            is_text || matches!(ty, ConfigFieldType::Option(_)),
+            "required is only supported for String, Vec<String> and Option fields",
+        ),
+        ConfigRule::Min(_) | ConfigRule::Max(_) => (
+            !matches!(inner, ConfigFieldType::Bool | ConfigFieldType::Enum(_)),
+            "min/max are not supported for bool and enum fields",
+        ),
+        ConfigRule::Regex(_) | ConfigRule::Url | ConfigRule::OneOf(_) => (
+            is_text,
+            "regex, url and one_of are only supported for String and Vec<String> fields",
+        ),
+    };
+    match supported {
+        true => Ok(()),
+        false => Err(ConfigurationError {
+            span,
+            reason: reason.into(),
+        }),
+    }
+}
+
+// struct level `#[validate(with = path::to::fn)]`
+fn parse_struct_validate_attr(attrs: &[Attribute]) -> Result<Option<syn::Path>> {
+    let mut with = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("validate")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("with") {
+                with = Some(meta.value()?.parse::<syn::Path>()?);
+                return Ok(());
+            }
+            Err(meta.error("only `with = path::to::fn` is supported on struct"))
+        })?;
+    }
+    Ok(with)
 }
 
 fn get_field_type(field: &Field) -> Result<ConfigFieldType> {
-    let path = match &field.ty {
-        Type::Path(p) => p,
+    parse_field_type(&field.ty)
+}
+
+fn parse_field_type(ty: &Type) -> Result<ConfigFieldType> {
+    let unsupported = |span: Span, name: &str| ConfigurationError {
+        span,
+        reason: format!("Unsupported field type: {name:?}").into(),
+    };
+    let path = match ty {
+        Type::Path(p) if p.qself.is_none() => p,
         ty => Err(ConfigurationError {
             span: ty.span(),
             reason: format!("unsupported field type: {ty:?}").into(),
         })?,
     };
-    let ty = match path
-        .path
-        .get_ident()
-        .map(|ident| ident.to_string())
-        .as_deref()
-    {
-        Some("usize") => ConfigFieldType::Usize,
-        Some("i8") => ConfigFieldType::I8,
-        Some("i16") => ConfigFieldType::I16,
-        Some("i32") => ConfigFieldType::I32,
-        Some("i64") => ConfigFieldType::I64,
-        Some("u8") => ConfigFieldType::U8,
-        Some("u16") => ConfigFieldType::U16,
-        Some("u32") => ConfigFieldType::U32,
-        Some("u64") => ConfigFieldType::U64,
-        Some("String") => ConfigFieldType::String,
-        Some("bool") => ConfigFieldType::Bool,
-        other => Err(ConfigurationError {
-            span: path.span(),
-            reason: format!("Unsupported field type: {other:?}").into(),
-        })?,
-        //None => get_field_complex_type(path)?,
+    let segment = match path.path.segments.last() {
+        Some(segment) => segment,
+        None => Err(unsupported(path.span(), ""))?,
+    };
+    let name = segment.ident.to_string();
+    let ty = match (&segment.arguments, name.as_str()) {
+        (PathArguments::None, "usize") => ConfigFieldType::Usize,
+        (PathArguments::None, "i8") => ConfigFieldType::I8,
+        (PathArguments::None, "i16") => ConfigFieldType::I16,
+        (PathArguments::None, "i32") => ConfigFieldType::I32,
+        (PathArguments::None, "i64") => ConfigFieldType::I64,
+        (PathArguments::None, "u8") => ConfigFieldType::U8,
+        (PathArguments::None, "u16") => ConfigFieldType::U16,
+        (PathArguments::None, "u32") => ConfigFieldType::U32,
+        (PathArguments::None, "u64") => ConfigFieldType::U64,
+        (PathArguments::None, "f64") => ConfigFieldType::F64,
+        (PathArguments::None, "String") => ConfigFieldType::String,
+        (PathArguments::None, "bool") => ConfigFieldType::Bool,
+        (PathArguments::None, "Duration") => ConfigFieldType::Duration,
+        (PathArguments::None, "f32" | "i128" | "u128" | "isize" | "char" | "str") => {
+            Err(unsupported(path.span(), &name))?
+        }
+        (PathArguments::None, _) => ConfigFieldType::Enum(Box::new(ty.clone())),
+        (PathArguments::AngleBracketed(args), "Vec" | "Option") => {
+            let arg = match args.args.iter().collect::<Vec<_>>().as_slice() {
+                [GenericArgument::Type(arg)] => arg,
+                _ => Err(ConfigurationError {
+                    span: args.span(),
+                    reason: format!("unexpected {name} arguments").into(),
+                })?,
+            };
+            match (name.as_str(), parse_field_type(arg)?) {
+                ("Vec", ConfigFieldType::String) => ConfigFieldType::StringList,
+                ("Vec", _) => Err(ConfigurationError {
+                    span: arg.span(),
+                    reason: "only Vec<String> is supported".into(),
+                })?,
+                (_, ConfigFieldType::Option(_)) => Err(ConfigurationError {
+                    span: arg.span(),
+                    reason: "nested options are not supported".into(),
+                })?,
+                (_, ty) => ConfigFieldType::Option(Box::new(ty)),
+            }
+        }
+        _ => Err(unsupported(path.span(), &name))?,
     };
     Ok(ty)
 }
 
-// FIXME: unused for now
-//fn get_field_complex_type(path: &TypePath) -> Result<ConfigFieldType> {
-//    match path.path.segments.iter().collect::<Vec<_>>().as_slice() {
-//        &[PathSegment {
-//            ident,
-//            arguments: PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }),
-//        }] if ident == "Vec" => match args.iter().collect::<Vec<_>>().as_slice() {
-//            &[GenericArgument::Type(Type::Path(path))] => {
-//                let ty = match path
-//                    .path
-//                    .get_ident()
-//                    .map(|ident| ident.to_string())
-//                    .as_deref()
-//                {
-//                    Some("i8") => ConfigFieldType::I8,
-//                    Some("i16") => ConfigFieldType::I16,
-//                    Some("i32") => ConfigFieldType::I32,
-//                    Some("i64") => ConfigFieldType::I64,
-//                    Some("u8") => ConfigFieldType::U8,
-//                    Some("u16") => ConfigFieldType::U16,
-//                    Some("u32") => ConfigFieldType::U32,
-//                    Some("u64") => ConfigFieldType::U64,
-//                    Some("String") => ConfigFieldType::String,
-//                    Some("bool") => ConfigFieldType::Bool,
-//                    Some(other) => Err(ConfigurationError {
-//                        span: path.span(),
-//                        reason: format!("Unsupported field type: {other}").into(),
-//                    })?,
-//                    None => get_field_complex_type(path)?,
-//                };
-//                Ok(ConfigFieldType::Vec(Box::new(ty)))
-//            }
-//            _ => Err(ConfigurationError {
-//                span: args.span(),
-//                reason: "unexpected Vec arguments".into(),
-//            })?,
-//        },
-//        _ => Err(ConfigurationError {
-//            span: path.span(),
-//            reason: "unexpected complex type".into(),
-//        })?,
-//    }
-//}
-
 fn build_config_field(field: &Field) -> Result<ConfigField<'_>> {
-    let metadata = parse_field_attributes(field.attrs.as_slice())?;
+    let (metadata, rules) = parse_field_attributes(field.attrs.as_slice())?;
     let field_type = get_field_type(field)?;
+    if metadata.is_template
+        && !matches!(
+            field_type.inner(),
+            ConfigFieldType::String | ConfigFieldType::StringList
+        )
+    {
+        Err(ConfigurationError {
+            span: field.ty.span(),
+            reason: "only String and Vec<String> fields can be templates".into(),
+        })?
+    }
+    let rules = rules
+        .into_iter()
+        .map(|(rule, span)| check_rule(&field_type, &rule, span).map(|_| rule))
+        .collect::<Result<Vec<_>>>()?;
     Ok(ConfigField {
         name: field.ident.as_ref().unwrap().to_string(),
         ty: field_type,
         metadata,
+        rules,
         name_ident: field.ident.as_ref().unwrap(),
     })
 }
@@ -298,7 +613,9 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
             span: input.span(),
         })?;
     }
-    let (section_input, section_output) = parse_section_attr(&input.attrs)?;
+    let (section_input, section_output, version) = parse_section_attr(&input.attrs)?;
+    let migrations = parse_migrate_attrs(&input.attrs, version)?;
+    let cross_validate = parse_struct_validate_attr(&input.attrs)?;
     let ident = &input.ident;
     let strct = match &input.data {
         Data::Union(_) => Err(ConfigurationError {
@@ -332,13 +649,25 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
                  ty,
                  metadata,
                  name_ident,
+                 ..
              }| {
+                let value = ty.field_value(quote! { &self.#name_ident });
                 let ty: proc_macro2::TokenStream = ty.into();
                 let ConfigFieldMetadata {
                     is_password,
                     is_text_area,
                     is_read_only,
+                    description,
+                    placeholder,
+                    group,
+                    is_advanced,
+                    is_template,
                 } = metadata;
+                let (description, placeholder, group) = (
+                    option_str(description),
+                    option_str(placeholder),
+                    option_str(group),
+                );
                 quote! {
                     config::Field{
                         name: #name,
@@ -347,8 +676,13 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
                             is_password: #is_password,
                             is_text_area: #is_text_area,
                             is_read_only: #is_read_only,
+                            description: #description,
+                            placeholder: #placeholder,
+                            group: #group,
+                            is_advanced: #is_advanced,
+                            is_template: #is_template,
                         },
-                        value: (&self.#name_ident).into(),
+                        value: #value,
                     }
                 }
             },
@@ -360,10 +694,14 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
         .iter()
         .map(
             |ConfigField {
-                 name, name_ident, ..
+                 name,
+                 name_ident,
+                 ty,
+                 ..
              }| {
+                let value = ty.field_value(quote! { &self.#name_ident });
                 quote! {
-                    #name => { Ok((&self.#name_ident).into()) }
+                    #name => { Ok(#value) }
                 }
             },
         )
@@ -374,10 +712,14 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
         .iter()
         .map(
             |ConfigField {
-                 name, name_ident, ..
+                 name,
+                 name_ident,
+                 ty,
+                 ..
              }| {
+                let value = ty.try_from_value();
                 quote! {
-                    #name => { self.#name_ident = value.try_into()?; }
+                    #name => { self.#name_ident = #value; }
                 }
             },
         )
@@ -390,6 +732,11 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
             let value: proc_macro2::TokenStream = match ty {
                 ConfigFieldType::Bool => quote! { false },
                 ConfigFieldType::String => quote! { String::new() },
+                ConfigFieldType::F64 => quote! { 0.0 },
+                ConfigFieldType::Option(_) => quote! { None },
+                ConfigFieldType::Duration
+                | ConfigFieldType::StringList
+                | ConfigFieldType::Enum(_) => quote! { Default::default() },
                 _ => quote! { 0 },
             };
             quote! {
@@ -398,6 +745,72 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
         })
         .collect::<Vec<proc_macro2::TokenStream>>();
 
+    let field_rules_arms = config_fields
+        .iter()
+        .filter(|ConfigField { rules, .. }| !rules.is_empty())
+        .map(|ConfigField { name, rules, .. }| {
+            let rules = rules.iter().map(proc_macro2::TokenStream::from);
+            quote! {
+                #name => &[#(#rules),*],
+            }
+        })
+        .collect::<Vec<proc_macro2::TokenStream>>();
+    // default trait implementations are used if no rules declared
+    let field_rules_impl = match field_rules_arms.is_empty() {
+        true => quote! {},
+        false => quote! {
+            fn field_rules(&self, name: &str) -> &'static [config::Rule] {
+                match name {
+                    #(#field_rules_arms)*
+                    _ => &[],
+                }
+            }
+        },
+    };
+    let cross_validate_impl = match cross_validate {
+        None => quote! {},
+        Some(path) => quote! {
+            fn cross_validate(&self) -> Result<(), config::ValidationErrors> {
+                #path(self)
+            }
+        },
+    };
+
+    // default trait implementations are used for unversioned configs
+    let version_impl = match version {
+        1 => quote! {},
+        version => quote! {
+            fn version(&self) -> u32 {
+                #version
+            }
+        },
+    };
+    let migration_arms = migrations
+        .iter()
+        .map(|(from, path)| quote! { #from => Some(#path as config::Migration), })
+        .collect::<Vec<proc_macro2::TokenStream>>();
+    let migration_impl = match migration_arms.is_empty() {
+        true => quote! {},
+        false => quote! {
+            fn migration(&self, from_version: u32) -> Option<config::Migration> {
+                match from_version {
+                    #(#migration_arms)*
+                    _ => None,
+                }
+            }
+        },
+    };
+
+    // struct doc comments describe config
+    let description_impl = match parse_doc(&input.attrs) {
+        None => quote! {},
+        Some(description) => quote! {
+            fn description(&self) -> Option<&'static str> {
+                Some(#description)
+            }
+        },
+    };
+
     let name = ident.to_string();
     let tokens = quote! {
         #[automatically_derived]
@@ -414,6 +827,12 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
                 #section_output
             }
 
+            #description_impl
+
+            #version_impl
+
+            #migration_impl
+
             fn fields(&self) -> Vec<config::Field> {
                 vec![
                     #(#fields_impl),*
@@ -439,6 +858,10 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
                 #(#strip_secrets_impl)*
             }
 
+            #field_rules_impl
+
+            #cross_validate_impl
+
             fn clone_config(&self) -> Box<dyn config::Config> {
                 Box::new(self.clone())
             }
@@ -447,7 +870,91 @@ fn parse_config(input: TokenStream) -> Result<TokenStream> {
     Ok(tokens.into())
 }
 
-#[proc_macro_derive(Configuration, attributes(section, field_type, validate))]
+fn option_str(value: &Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() && prev_lower {
+            out.push('_');
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn parse_enum(input: TokenStream) -> Result<TokenStream> {
+    let input: DeriveInput = syn::parse(input).unwrap();
+    if !input.generics.params.is_empty() {
+        Err(ConfigurationError {
+            reason: "generics are not supported".into(),
+            span: input.span(),
+        })?;
+    }
+    let ident = &input.ident;
+    let enm = match &input.data {
+        Data::Enum(enm) => enm,
+        _ => Err(ConfigurationError {
+            span: input.span(),
+            reason: "only enums are supported".into(),
+        })?,
+    };
+    if enm.variants.is_empty() {
+        Err(ConfigurationError {
+            span: input.span(),
+            reason: "enum should have at least one variant".into(),
+        })?;
+    }
+    let variants = enm
+        .variants
+        .iter()
+        .map(|variant| match variant.fields {
+            Fields::Unit => Ok(&variant.ident),
+            _ => Err(ConfigurationError {
+                span: variant.span(),
+                reason: "only unit variants are supported".into(),
+            }),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let names = variants
+        .iter()
+        .map(|variant| to_snake_case(&variant.to_string()))
+        .collect::<Vec<_>>();
+    let tokens = quote! {
+        #[automatically_derived]
+        impl config::Enum for #ident {
+            const VARIANTS: &'static [&'static str] = &[#(#names),*];
+
+            fn as_str(&self) -> &'static str {
+                match self {
+                    #(Self::#variants => #names),*
+                }
+            }
+
+            fn from_variant(variant: &str) -> Option<Self> {
+                #(
+                    if variant.eq_ignore_ascii_case(#names) {
+                        return Some(Self::#variants);
+                    }
+                )*
+                None
+            }
+        }
+    };
+    Ok(tokens.into())
+}
+
+#[proc_macro_derive(
+    Configuration,
+    attributes(section, field, field_type, validate, migrate)
+)]
 pub fn configuration(input: TokenStream) -> TokenStream {
     match parse_config(input) {
         Ok(tokens) => tokens,
@@ -456,3 +963,13 @@ pub fn configuration(input: TokenStream) -> TokenStream {
         }
     }
 }
+
+#[proc_macro_derive(Enum)]
+pub fn config_enum(input: TokenStream) -> TokenStream {
+    match parse_enum(input) {
+        Ok(tokens) => tokens,
+        Err(ConfigurationError { reason, span }) => {
+            quote_spanned! { span => compile_error!(#reason); }.into()
+        }
+    }
+}
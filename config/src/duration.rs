@@ -0,0 +1,106 @@
+//! Human readable durations, e.g. `30s`, `5m`, `1h30m`, `250ms`
+//!
+//! Plain numbers are interpreted as seconds, so previously stored integer intervals keep working.
+
+use std::time::Duration;
+
+use crate::StdError;
+
+const UNITS: &[(&str, Duration)] = &[
+    ("d", Duration::from_secs(86400)),
+    ("h", Duration::from_secs(3600)),
+    ("m", Duration::from_secs(60)),
+    ("s", Duration::from_secs(1)),
+    ("ms", Duration::from_millis(1)),
+    ("us", Duration::from_micros(1)),
+    ("ns", Duration::from_nanos(1)),
+];
+
+pub(crate) fn parse(input: &str) -> Result<Duration, StdError> {
+    let input = input.trim();
+    if input.is_empty() {
+        Err("duration can't be empty")?
+    }
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let mut rest = input;
+    let mut duration = Duration::ZERO;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let letters = rest[digits..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .map(|pos| digits + pos)
+            .unwrap_or(rest.len());
+        let (number, unit) = (&rest[..digits], &rest[digits..letters]);
+        let unit = UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, unit)| *unit);
+        let part = match (number.parse::<u32>(), unit) {
+            (Ok(number), Some(unit)) => unit.checked_mul(number),
+            _ => None,
+        };
+        duration = part
+            .and_then(|part| duration.checked_add(part))
+            .ok_or_else(|| {
+                format!("malformed duration: '{input}', expected e.g. `30s`, `5m`, `1h30m`")
+            })?;
+        rest = rest[letters..].trim_start();
+    }
+    Ok(duration)
+}
+
+pub(crate) fn format(duration: Duration) -> String {
+    if duration.is_zero() {
+        return "0s".into();
+    }
+    let mut rest = duration.as_nanos();
+    let mut out = String::new();
+    for (name, unit) in UNITS {
+        let unit = unit.as_nanos();
+        if rest >= unit {
+            out.push_str(&format!("{}{name}", rest / unit));
+            rest %= unit;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse(" 1h 30m ").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("2d").unwrap(), Duration::from_secs(172800));
+        assert_eq!(parse("1s250ms").unwrap(), Duration::from_millis(1250));
+        assert_eq!(parse("10us").unwrap(), Duration::from_micros(10));
+        assert_eq!(parse("0s").unwrap(), Duration::ZERO);
+        assert!(parse("").is_err());
+        assert!(parse("s").is_err());
+        assert!(parse("10").is_ok());
+        assert!(parse("10x").is_err());
+        assert!(parse("1.5h").is_err());
+        assert!(parse("-1s").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(Duration::ZERO), "0s");
+        assert_eq!(format(Duration::from_secs(30)), "30s");
+        assert_eq!(format(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format(Duration::from_millis(1250)), "1s250ms");
+        assert_eq!(format(Duration::from_secs(86400 + 1)), "1d1s");
+        for duration in [1, 59, 61, 3599, 3601, 86399, 1_000_000] {
+            let duration = Duration::from_millis(duration);
+            assert_eq!(parse(&format(duration)).unwrap(), duration);
+        }
+    }
+}
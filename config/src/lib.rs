@@ -1,12 +1,21 @@
+mod duration;
+mod migration;
 mod raw_config;
 mod ser;
+pub mod template;
+mod validate;
 
-pub use config_derive::Configuration;
+use std::time::Duration;
+
+pub use config_derive::{Configuration, Enum};
+pub use migration::Migration;
+pub use validate::{Rule, ValidationError, ValidationErrors};
 
 pub mod prelude {
     pub use super::raw_config::{de::deserialize_into_config, RawConfig};
     pub use super::Config;
-    pub use super::{Field, FieldType, FieldValue, Metadata, SectionIO};
+    pub use super::{Enum, Field, FieldType, FieldValue, Metadata, Migration, SectionIO};
+    pub use super::{Rule, ValidationError, ValidationErrors};
     pub use config_derive::Configuration;
 }
 
@@ -22,6 +31,17 @@ impl SectionIO {
     pub fn is_none(self) -> bool {
         SectionIO::None == self
     }
+
+    /// check if output of one section can be connected to input of another
+    ///
+    /// `BinOrDataFrame` sections pass through whatever they receive, so they connect to both kinds.
+    pub fn can_connect_to(self, input: SectionIO) -> bool {
+        match (self, input) {
+            (SectionIO::None, _) | (_, SectionIO::None) => false,
+            (SectionIO::BinOrDataFrame, _) | (_, SectionIO::BinOrDataFrame) => true,
+            (output, input) => output == input,
+        }
+    }
 }
 
 pub(crate) type StdError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -33,6 +53,21 @@ pub trait Config: std::fmt::Debug + Send + Sync + 'static {
 
     fn output(&self) -> SectionIO;
 
+    /// human readable description of config, taken from doc comments of config struct
+    fn description(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// config version, declared with `#[section(version = N)]`
+    fn version(&self) -> u32 {
+        1
+    }
+
+    /// migration from given version to the next one, declared with `#[migrate(from = N, with = path::to::fn)]`
+    fn migration(&self, _from_version: u32) -> Option<Migration> {
+        None
+    }
+
     fn fields(&self) -> Vec<Field<'_>>;
 
     fn get_field_value(&self, name: &str) -> Result<FieldValue<'_>, StdError>;
@@ -48,49 +83,122 @@ pub trait Config: std::fmt::Debug + Send + Sync + 'static {
             .filter(|field| field.name == field_name);
         match iter.next() {
             Some(field) => {
-                match field.ty {
-                    FieldType::Usize => {
-                        let _: usize = value.try_into()?;
-                    }
-                    FieldType::Bool => {
-                        let _: bool = value.try_into()?;
-                    }
-                    FieldType::U8 => {
-                        let _: u8 = value.try_into()?;
-                    }
-                    FieldType::U16 => {
-                        let _: u16 = value.try_into()?;
-                    }
-                    FieldType::U32 => {
-                        let _: u32 = value.try_into()?;
-                    }
-                    FieldType::U64 => {
-                        let _: u64 = value.try_into()?;
-                    }
-                    FieldType::I8 => {
-                        let _: i8 = value.try_into()?;
-                    }
-                    FieldType::I16 => {
-                        let _: i16 = value.try_into()?;
-                    }
-                    FieldType::I32 => {
-                        let _: i32 = value.try_into()?;
-                    }
-                    FieldType::I64 => {
-                        let _: i64 = value.try_into()?;
-                    }
-                    // anything can be converted to String
-                    FieldType::String => {}
-                }
-                Ok(())
+                validate_value(field.ty, value)?;
+                validate::check_rules(field.ty, self.field_rules(field_name), value)
             }
             None => Err("no such field")?,
         }
     }
 
+    /// validation rules, declared with `#[validate(...)]` on field
+    fn field_rules(&self, _field_name: &str) -> &'static [Rule] {
+        &[]
+    }
+
+    /// cross-field validation, declared with `#[validate(with = path::to::fn)]` on struct
+    fn cross_validate(&self) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+
+    /// validate current values of all fields, cross-field checks run only if all fields are valid
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        for field in self.fields() {
+            if let Err(e) = self.validate_field(field.name, field.value) {
+                errors.push(field.name, e.to_string());
+            }
+        }
+        if errors.is_empty() {
+            self.cross_validate()?;
+        }
+        errors.into_result()
+    }
+
     fn clone_config(&self) -> Box<dyn crate::Config>;
 }
 
+fn validate_value(ty: FieldType, value: FieldValue<'_>) -> Result<(), StdError> {
+    match ty {
+        FieldType::Usize => {
+            let _: usize = value.try_into()?;
+        }
+        FieldType::Bool => {
+            let _: bool = value.try_into()?;
+        }
+        FieldType::U8 => {
+            let _: u8 = value.try_into()?;
+        }
+        FieldType::U16 => {
+            let _: u16 = value.try_into()?;
+        }
+        FieldType::U32 => {
+            let _: u32 = value.try_into()?;
+        }
+        FieldType::U64 => {
+            let _: u64 = value.try_into()?;
+        }
+        FieldType::I8 => {
+            let _: i8 = value.try_into()?;
+        }
+        FieldType::I16 => {
+            let _: i16 = value.try_into()?;
+        }
+        FieldType::I32 => {
+            let _: i32 = value.try_into()?;
+        }
+        FieldType::I64 => {
+            let _: i64 = value.try_into()?;
+        }
+        FieldType::F64 => {
+            let _: f64 = value.try_into()?;
+        }
+        FieldType::Duration => {
+            let _: Duration = value.try_into()?;
+        }
+        FieldType::StringList => {
+            let _: Vec<String> = value.try_into()?;
+        }
+        FieldType::Enum(variants) => {
+            let value: String = value.try_into()?;
+            if !variants
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(value.trim()))
+            {
+                Err(format!("'{value}' is not one of {variants:?}"))?
+            }
+        }
+        FieldType::Option(ty) => {
+            if !value.is_empty() {
+                validate_value(*ty, value)?
+            }
+        }
+        // anything can be converted to String
+        FieldType::String => {}
+    }
+    Ok(())
+}
+
+/// Closed set of choices, which can be used as a config field type
+///
+/// Usually derived with `#[derive(config::Enum)]` on enums with unit variants,
+/// variant names are exposed in snake case, e.g. `JsonLines` becomes `json_lines`.
+pub trait Enum: Sized + 'static {
+    const VARIANTS: &'static [&'static str];
+
+    fn as_str(&self) -> &'static str;
+
+    /// case insensitive lookup of variant by name
+    fn from_variant(variant: &str) -> Option<Self>;
+
+    fn try_from_field_value(value: FieldValue<'_>) -> Result<Self, StdError> {
+        let value: String = value.try_into()?;
+        match Self::from_variant(value.trim()) {
+            Some(variant) => Ok(variant),
+            None => Err(format!("'{value}' is not one of {:?}", Self::VARIANTS))?,
+        }
+    }
+}
+
 impl Clone for Box<dyn Config> {
     fn clone(&self) -> Self {
         self.clone_config()
@@ -114,14 +222,56 @@ pub enum FieldType {
     U16,
     U32,
     U64,
+    F64,
     String,
     Bool,
+    // human readable duration, e.g. `1h30m`
+    Duration,
+    StringList,
+    // closed set of choices
+    Enum(&'static [&'static str]),
+    Option(&'static FieldType),
 }
 
 impl FieldType {
     pub fn is_bool(&self) -> bool {
         self == &Self::Bool
     }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(self, Self::Option(_))
+    }
+
+    /// field type with optionality stripped
+    pub fn inner(&self) -> FieldType {
+        match self {
+            Self::Option(ty) => ty.inner(),
+            ty => *ty,
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(
+            self.inner(),
+            Self::Usize
+                | Self::I8
+                | Self::I16
+                | Self::I32
+                | Self::I64
+                | Self::U8
+                | Self::U16
+                | Self::U32
+                | Self::U64
+                | Self::F64
+        )
+    }
+
+    pub fn variants(&self) -> Option<&'static [&'static str]> {
+        match self.inner() {
+            Self::Enum(variants) => Some(variants),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Default)]
@@ -129,6 +279,17 @@ pub struct Metadata {
     pub is_password: bool,
     pub is_text_area: bool,
     pub is_read_only: bool,
+    /// help text, taken from doc comments or `#[field(description = "...")]`
+    pub description: Option<&'static str>,
+    /// example value, shown in empty input
+    pub placeholder: Option<&'static str>,
+    /// name of group, fields of the same group are rendered together
+    pub group: Option<&'static str>,
+    /// advanced fields are hidden by default
+    pub is_advanced: bool,
+    /// string and string list values can reference variables, declared with `#[field(template)]`,
+    /// see [`template`]
+    pub is_template: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -150,11 +311,21 @@ pub enum FieldValue<'a> {
     U16(u16),
     U32(u32),
     U64(u64),
+    F64(f64),
     String(&'a str),
     Bool(bool),
+    Duration(Duration),
+    StringList(&'a [String]),
+    // value of unset optional field
+    Null,
 }
 
 impl FieldValue<'_> {
+    /// null and empty string both represent unset optional field
+    pub fn is_empty(&self) -> bool {
+        matches!(self, FieldValue::Null | FieldValue::String(""))
+    }
+
     pub fn field_type(&self) -> FieldType {
         match self {
             FieldValue::Usize(_) => FieldType::Usize,
@@ -166,8 +337,13 @@ impl FieldValue<'_> {
             FieldValue::U16(_) => FieldType::U16,
             FieldValue::U32(_) => FieldType::U32,
             FieldValue::U64(_) => FieldType::U64,
+            FieldValue::F64(_) => FieldType::F64,
             FieldValue::String(_) => FieldType::String,
             FieldValue::Bool(_) => FieldType::Bool,
+            FieldValue::Duration(_) => FieldType::Duration,
+            FieldValue::StringList(_) => FieldType::StringList,
+            // null carries no type information
+            FieldValue::Null => FieldType::String,
         }
     }
 }
@@ -184,8 +360,19 @@ impl std::fmt::Display for FieldValue<'_> {
             FieldValue::U16(v) => write!(f, "{v}"),
             FieldValue::U32(v) => write!(f, "{v}"),
             FieldValue::U64(v) => write!(f, "{v}"),
+            FieldValue::F64(v) => write!(f, "{v}"),
             FieldValue::String(v) => write!(f, "{v}"),
             FieldValue::Bool(v) => write!(f, "{v}"),
+            FieldValue::Duration(v) => write!(f, "{}", duration::format(*v)),
+            // JSON array keeps items with commas intact
+            FieldValue::StringList(v) => {
+                write!(
+                    f,
+                    "{}",
+                    serde_json::to_string(v).map_err(|_| std::fmt::Error)?
+                )
+            }
+            FieldValue::Null => Ok(()),
         }
     }
 }
@@ -209,8 +396,23 @@ impl_from_ref!(i8, I8, *);
 impl_from_ref!(i16, I16, *);
 impl_from_ref!(i32, I32, *);
 impl_from_ref!(i64, I64, *);
+impl_from_ref!(f64, F64, *);
 impl_from_ref!(bool, Bool, *);
 impl_from_ref!(String, String, &*);
+impl_from_ref!(Duration, Duration, *);
+impl_from_ref!(Vec<String>, StringList, &*);
+
+impl<'a, T> From<&'a Option<T>> for FieldValue<'a>
+where
+    FieldValue<'a>: From<&'a T>,
+{
+    fn from(value: &'a Option<T>) -> FieldValue<'a> {
+        match value {
+            Some(value) => value.into(),
+            None => FieldValue::Null,
+        }
+    }
+}
 
 macro_rules! impl_from_value {
     ($ty:ty, $arm:tt) => {
@@ -231,8 +433,11 @@ impl_from_value!(i8, I8);
 impl_from_value!(i16, I16);
 impl_from_value!(i32, I32);
 impl_from_value!(i64, I64);
+impl_from_value!(f64, F64);
 impl_from_value!(bool, Bool);
 impl_from_value!(&'a str, String);
+impl_from_value!(Duration, Duration);
+impl_from_value!(&'a [String], StringList);
 
 macro_rules! try_into_field_value_impl {
     (
@@ -364,4 +569,82 @@ try_into_field_value_impl!(bool, v,
     Bool => { v },
     String => { v.parse()? }
 );
+try_into_field_value_impl!(f64, v,
+    F64 => { v },
+    String => { v.trim().parse()? },
+    U8, U16, U32, I8, I16, I32 => { v.into() },
+    U64, I64, Usize => { v as f64 }
+);
 try_into_field_value_impl!(String, v, String => { v.to_string() });
+// strings are parsed as JSON array, as lists are rendered, or as comma separated list otherwise
+try_into_field_value_impl!(Vec<String>, v,
+    StringList => { v.to_vec() },
+    String => {
+        match v.trim_start().starts_with('[') {
+            true => serde_json::from_str(v)?,
+            false => v
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+);
+
+// integers are interpreted as seconds
+impl TryInto<Duration> for FieldValue<'_> {
+    type Error = StdError;
+
+    fn try_into(self) -> Result<Duration, Self::Error> {
+        let secs = match self {
+            FieldValue::Duration(v) => return Ok(v),
+            FieldValue::String(v) => return duration::parse(v),
+            FieldValue::U8(v) => v.into(),
+            FieldValue::U16(v) => v.into(),
+            FieldValue::U32(v) => v.into(),
+            FieldValue::U64(v) => v,
+            FieldValue::Usize(v) => v.try_into()?,
+            FieldValue::I8(v) => v.try_into()?,
+            FieldValue::I16(v) => v.try_into()?,
+            FieldValue::I32(v) => v.try_into()?,
+            FieldValue::I64(v) => v.try_into()?,
+            _ => Err(format!("Can't convert {:?} into Duration", self))?,
+        };
+        Ok(Duration::from_secs(secs))
+    }
+}
+
+macro_rules! impl_try_into_option {
+    ($($ty:ty),+) => {
+        $(
+            impl TryInto<Option<$ty>> for FieldValue<'_> {
+                type Error = StdError;
+
+                fn try_into(self) -> Result<Option<$ty>, Self::Error> {
+                    match self {
+                        value if value.is_empty() => Ok(None),
+                        value => Ok(Some(value.try_into()?)),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_try_into_option!(
+    usize,
+    u8,
+    u16,
+    u32,
+    u64,
+    i8,
+    i16,
+    i32,
+    i64,
+    f64,
+    bool,
+    String,
+    Duration,
+    Vec<String>
+);
@@ -0,0 +1,57 @@
+//! Config versioning
+//!
+//! Sections declare current config version with `#[section(version = N)]` and register migrations,
+//! which upgrade stored config from one version to the next:
+//! ```ignore
+//! #[derive(Debug, Clone, Configuration)]
+//! #[section(output = dataframe, version = 2)]
+//! #[migrate(from = 1, with = v1_to_v2)]
+//! struct Conf {
+//!     url: String,
+//! }
+//!
+//! fn v1_to_v2(raw: &mut RawConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     raw.rename_field("endpoint", "url");
+//!     Ok(())
+//! }
+//! ```
+//! Versions without registered migration are upgraded as is.
+
+use std::cmp::Ordering;
+
+use crate::{raw_config::RawConfig, Config, StdError};
+
+/// upgrade raw config from version to the next one
+pub type Migration = fn(&mut RawConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// upgrade `from` config to version of `to` config
+///
+/// returns None if versions match, configs with version newer than `to` version are rejected
+pub(crate) fn upgrade(from: &dyn Config, to: &dyn Config) -> Result<Option<RawConfig>, StdError> {
+    let (version, target) = (from.version(), to.version());
+    match version.cmp(&target) {
+        Ordering::Equal => Ok(None),
+        Ordering::Greater => Err(format!(
+            "config '{}' has version {version}, but only versions up to {target} are supported",
+            from.name()
+        ))?,
+        Ordering::Less => {
+            let mut raw = RawConfig::new(from.name())
+                .with_version(version)
+                .with_fields(from.fields().into_iter());
+            while raw.version() < target {
+                let version = raw.version();
+                if let Some(migration) = to.migration(version) {
+                    migration(&mut raw).map_err(|e| {
+                        format!(
+                            "failed to migrate config '{}' from version {version}: {e}",
+                            from.name()
+                        )
+                    })?;
+                }
+                raw = raw.with_version(version + 1);
+            }
+            Ok(Some(raw))
+        }
+    }
+}
@@ -3,7 +3,7 @@ use serde::{
     Deserialize, Deserializer,
 };
 
-use crate::{Config, StdError};
+use crate::{migration, Config, StdError};
 
 use super::{RawConfig, RawField, RawFieldValue};
 
@@ -78,6 +78,13 @@ impl<'de> Visitor<'de> for RawFieldValueVisitor {
         Ok(RawFieldValue::U64(v))
     }
 
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(RawFieldValue::F64(v))
+    }
+
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
     where
         E: Error,
@@ -91,6 +98,31 @@ impl<'de> Visitor<'de> for RawFieldValueVisitor {
     {
         Ok(RawFieldValue::String(v))
     }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(RawFieldValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(RawFieldValue::Null)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element::<String>()? {
+            values.push(value);
+        }
+        Ok(RawFieldValue::StringList(values))
+    }
 }
 
 impl<'de> Deserialize<'de> for RawFieldValue {
@@ -105,6 +137,7 @@ impl<'de> Deserialize<'de> for RawFieldValue {
 enum RawConfigField {
     ConfigName,
     Fields,
+    Version,
     Ignore,
 }
 
@@ -113,6 +146,7 @@ impl RawConfigField {
         match self {
             Self::ConfigName => "config_name",
             Self::Fields => "fields",
+            Self::Version => "version",
             Self::Ignore => "",
         }
     }
@@ -136,6 +170,7 @@ impl<'de> Visitor<'de> for RawConfigFieldVisitor {
         match value {
             0 => Ok(RawConfigField::ConfigName),
             1 => Ok(RawConfigField::Fields),
+            2 => Ok(RawConfigField::Version),
             _ => Ok(RawConfigField::Ignore),
         }
     }
@@ -144,6 +179,7 @@ impl<'de> Visitor<'de> for RawConfigFieldVisitor {
         match value {
             "config_name" => Ok(RawConfigField::ConfigName),
             "fields" => Ok(RawConfigField::Fields),
+            "version" => Ok(RawConfigField::Version),
             _ => Ok(RawConfigField::Ignore),
         }
     }
@@ -152,6 +188,7 @@ impl<'de> Visitor<'de> for RawConfigFieldVisitor {
         match value {
             b"config_name" => Ok(RawConfigField::ConfigName),
             b"fields" => Ok(RawConfigField::Fields),
+            b"version" => Ok(RawConfigField::Version),
             _ => Ok(RawConfigField::Ignore),
         }
     }
@@ -173,6 +210,7 @@ impl<'de> Visitor<'de> for RawConfigVisitor {
         {
             let mut config_name: Option<String> = None;
             let mut fields: Option<Vec<RawField>> = None;
+            let mut version: Option<u32> = None;
             while let Some(key) = map.next_key::<RawConfigField>()? {
                 match key {
                     RawConfigField::Ignore => Err(Error::custom("unexpected field"))?,
@@ -180,13 +218,18 @@ impl<'de> Visitor<'de> for RawConfigVisitor {
                         config_name = Some(map.next_value()?)
                     }
                     RawConfigField::Fields if fields.is_none() => fields = Some(map.next_value()?),
+                    RawConfigField::Version if version.is_none() => {
+                        version = Some(map.next_value()?)
+                    }
                     _ => Err(Error::duplicate_field(key.to_str()))?,
                 }
             }
             match (config_name, fields) {
+                // configs serialized before versioning was introduced have version 1
                 (Some(config_name), Some(fields)) => Ok(Box::new(RawConfig {
                     config_name,
                     fields,
+                    version: version.unwrap_or(1),
                 })),
                 (None, _) => Err(Error::missing_field(RawConfigField::ConfigName.to_str())),
                 (_, None) => Err(Error::missing_field(RawConfigField::Fields.to_str())),
@@ -204,7 +247,13 @@ impl<'de> Deserialize<'de> for Box<dyn Config> {
     }
 }
 
+/// Deserialize config into `to` config, upgrading `from` config to version of `to` config if needed
 pub fn deserialize_into_config(from: &dyn Config, to: &mut dyn Config) -> Result<(), StdError> {
+    let upgraded = migration::upgrade(from, to)?;
+    let from = match upgraded.as_ref() {
+        Some(upgraded) => upgraded as &dyn Config,
+        None => from,
+    };
     for field in from.fields() {
         to.set_field_value(field.name, field.value)?;
     }
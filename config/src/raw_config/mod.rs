@@ -13,6 +13,12 @@ pub(crate) mod ser;
 pub struct RawConfig {
     config_name: String,
     fields: Vec<RawField>,
+    #[serde(default = "default_version")]
+    version: u32,
+}
+
+fn default_version() -> u32 {
+    1
 }
 
 impl RawConfig {
@@ -20,22 +26,57 @@ impl RawConfig {
         Self {
             config_name: name.into(),
             fields: vec![],
+            version: default_version(),
         }
     }
 
-    pub fn with_fields<'a>(self, iter: impl Iterator<Item = Field<'a>>) -> Self {
-        let Self {
-            config_name,
-            mut fields,
-        } = self;
+    pub fn with_fields<'a>(mut self, iter: impl Iterator<Item = Field<'a>>) -> Self {
         for field in iter {
-            fields.push(field.into())
+            self.fields.push(field.into())
         }
-        Self {
-            config_name,
-            fields,
+        self
+    }
+
+    pub fn with_version(self, version: u32) -> Self {
+        Self { version, ..self }
+    }
+
+    // helpers for config migrations
+
+    pub fn get_field(&self, name: &str) -> Option<FieldValue<'_>> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| (&field.value).into())
+    }
+
+    /// set field value, field is added if not present
+    pub fn set_field(&mut self, name: &str, value: FieldValue<'_>) {
+        match self.fields.iter_mut().find(|field| field.name == name) {
+            Some(field) => field.value = value.into(),
+            None => self.fields.push(RawField {
+                name: name.into(),
+                value: value.into(),
+            }),
+        }
+    }
+
+    /// set field value only if field is not present
+    pub fn set_default(&mut self, name: &str, value: FieldValue<'_>) {
+        if self.get_field(name).is_none() {
+            self.set_field(name, value)
         }
     }
+
+    pub fn rename_field(&mut self, from: &str, to: &str) {
+        if let Some(field) = self.fields.iter_mut().find(|field| field.name == from) {
+            field.name = to.into();
+        }
+    }
+
+    pub fn remove_field(&mut self, name: &str) {
+        self.fields.retain(|field| field.name != name)
+    }
 }
 
 impl Config for RawConfig {
@@ -51,6 +92,10 @@ impl Config for RawConfig {
         SectionIO::None
     }
 
+    fn version(&self) -> u32 {
+        self.version
+    }
+
     fn fields(&self) -> Vec<Field> {
         self.fields
             .iter()
@@ -116,8 +161,11 @@ enum RawFieldValue {
     U16(u16),
     U32(u32),
     U64(u64),
+    F64(f64),
     String(String),
     Bool(bool),
+    StringList(Vec<String>),
+    Null,
 }
 
 impl From<FieldValue<'_>> for RawFieldValue {
@@ -132,8 +180,13 @@ impl From<FieldValue<'_>> for RawFieldValue {
             FieldValue::U16(v) => RawFieldValue::U16(v),
             FieldValue::U32(v) => RawFieldValue::U32(v),
             FieldValue::U64(v) => RawFieldValue::U64(v),
+            FieldValue::F64(v) => RawFieldValue::F64(v),
             FieldValue::String(v) => RawFieldValue::String(v.into()),
             FieldValue::Bool(v) => RawFieldValue::Bool(v),
+            // durations are stored in human readable form
+            FieldValue::Duration(_) => RawFieldValue::String(value.to_string()),
+            FieldValue::StringList(v) => RawFieldValue::StringList(v.to_vec()),
+            FieldValue::Null => RawFieldValue::Null,
         }
     }
 }
@@ -150,8 +203,11 @@ impl<'a> From<&'a RawFieldValue> for FieldValue<'a> {
             RawFieldValue::U16(v) => v.into(),
             RawFieldValue::U32(v) => v.into(),
             RawFieldValue::U64(v) => v.into(),
+            RawFieldValue::F64(v) => v.into(),
             RawFieldValue::String(v) => v.into(),
             RawFieldValue::Bool(v) => v.into(),
+            RawFieldValue::StringList(v) => v.into(),
+            RawFieldValue::Null => FieldValue::Null,
         }
     }
 }
@@ -14,9 +14,15 @@ impl Serialize for dyn Config {
         S: serde::Serializer,
     {
         // FIXME: for now only support structs
-        let mut top_level_struct = serializer.serialize_map(Some(2))?;
+        // version is omitted for version 1 to keep serialized form of unversioned configs intact
+        let version = self.version();
+        let len = if version == 1 { 2 } else { 3 };
+        let mut top_level_struct = serializer.serialize_map(Some(len))?;
         top_level_struct.serialize_entry("config_name", self.name())?;
         top_level_struct.serialize_entry("fields", &Slice(self.fields().as_slice()))?;
+        if version != 1 {
+            top_level_struct.serialize_entry("version", &version)?;
+        }
         top_level_struct.end()
     }
 }
@@ -51,8 +57,12 @@ impl<'a> Serialize for &'a Field<'a> {
             FieldValue::U16(v) => map.serialize_entry("value", &v)?,
             FieldValue::U32(v) => map.serialize_entry("value", &v)?,
             FieldValue::U64(v) => map.serialize_entry("value", &v)?,
+            FieldValue::F64(v) => map.serialize_entry("value", &v)?,
             FieldValue::Bool(v) => map.serialize_entry("value", &v)?,
             FieldValue::String(v) => map.serialize_entry("value", &v)?,
+            FieldValue::Duration(_) => map.serialize_entry("value", &self.value.to_string())?,
+            FieldValue::StringList(v) => map.serialize_entry("value", &v)?,
+            FieldValue::Null => map.serialize_entry("value", &None::<()>)?,
         };
         map.end()
     }
@@ -0,0 +1,264 @@
+//! Variable references in string fields
+//!
+//! String and string list fields, declared with `#[field(template)]`, can reference workspace variables
+//! as `${name}` and daemon variables as `${daemon.name}`, `$${` produces literal `${`.
+//! Values of other fields are taken literally.
+//! References are substituted by control plane, when graph is built for daemon, so until then
+//! fields with references are only checked for reference syntax.
+
+use crate::{Config, FieldType, FieldValue, StdError, ValidationErrors};
+
+/// prefix of daemon variables
+pub const DAEMON_PREFIX: &str = "daemon.";
+
+enum Token<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+fn tokenize(value: &str) -> Result<Vec<Token<'_>>, StdError> {
+    let mut tokens = vec![];
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        let (text, tail) = rest.split_at(pos);
+        tokens.push(Token::Text(text));
+        if let Some(tail) = tail.strip_prefix("$${") {
+            tokens.push(Token::Text("${"));
+            rest = tail;
+        } else if let Some(tail) = tail.strip_prefix("${") {
+            let end = match tail.find('}') {
+                Some(end) => end,
+                None => Err(format!("unterminated variable reference in '{value}'"))?,
+            };
+            let name = tail[..end].trim();
+            if !is_valid_reference(name) {
+                Err(format!("invalid variable reference '{name}'"))?
+            }
+            tokens.push(Token::Variable(name));
+            rest = &tail[end + 1..];
+        } else {
+            tokens.push(Token::Text("$"));
+            rest = &tail[1..];
+        }
+    }
+    tokens.push(Token::Text(rest));
+    Ok(tokens)
+}
+
+/// variable name should start with letter or underscore and contain only alphanumerics and underscores
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_valid_reference(name: &str) -> bool {
+    is_valid_name(name.strip_prefix(DAEMON_PREFIX).unwrap_or(name))
+}
+
+/// check if value references any variable, malformed references are errors
+pub fn has_references(value: &str) -> Result<bool, StdError> {
+    Ok(tokenize(value)?
+        .iter()
+        .any(|token| matches!(token, Token::Variable(_))))
+}
+
+/// replace variable references with values, returned by lookup
+pub fn substitute(
+    value: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, StdError> {
+    tokenize(value)?
+        .into_iter()
+        .try_fold(String::with_capacity(value.len()), |mut acc, token| {
+            match token {
+                Token::Text(text) => acc.push_str(text),
+                Token::Variable(name) => match lookup(name) {
+                    Some(value) => acc.push_str(&value),
+                    None => Err(format!("variable '{name}' is not defined"))?,
+                },
+            };
+            Ok(acc)
+        })
+}
+
+/// escape value, so it's substituted into itself
+pub fn escape(value: &str) -> String {
+    value.replace("${", "$${")
+}
+
+// check if string value or any list item references variables
+fn field_has_references(value: FieldValue<'_>) -> Result<bool, StdError> {
+    match value {
+        FieldValue::String(value) => has_references(value),
+        FieldValue::StringList(items) => items
+            .iter()
+            .try_fold(false, |acc, item| Ok(acc | has_references(item)?)),
+        _ => Ok(false),
+    }
+}
+
+/// validate config, where fields may reference variables
+///
+/// fields with references are only checked for reference syntax,
+/// cross-field checks are skipped if any field references variables
+pub fn validate<C: Config + ?Sized>(config: &C) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    let mut has_references = false;
+    for field in config.fields() {
+        let references = match field.metadata.is_template {
+            true => field_has_references(field.value),
+            false => Ok(false),
+        };
+        match references {
+            Err(e) => errors.push(field.name, e.to_string()),
+            Ok(true) => has_references = true,
+            Ok(false) => {
+                if let Err(e) = config.validate_field(field.name, field.value) {
+                    errors.push(field.name, e.to_string());
+                }
+            }
+        }
+    }
+    if errors.is_empty() && !has_references {
+        config.cross_validate()?;
+    }
+    errors.into_result()
+}
+
+/// substitute variables in template fields of config
+///
+/// config should be built config, since raw config doesn't support field updates
+/// returns true if any field was changed
+pub fn substitute_config<C: Config + ?Sized>(
+    config: &mut C,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<bool, StdError> {
+    let mut strings = vec![];
+    let mut lists = vec![];
+    for field in config
+        .fields()
+        .into_iter()
+        .filter(|field| field.metadata.is_template)
+    {
+        let with_name = |e: StdError| format!("field '{}': {e}", field.name);
+        // escaped values are changed even without references
+        match (field.ty.inner(), field.value) {
+            (FieldType::String, FieldValue::String(value)) => {
+                let substituted = substitute(value, &lookup).map_err(with_name)?;
+                if substituted != value {
+                    strings.push((field.name.to_string(), substituted));
+                }
+            }
+            (FieldType::StringList, FieldValue::StringList(items)) => {
+                let substituted = items
+                    .iter()
+                    .map(|item| substitute(item, &lookup))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(with_name)?;
+                if substituted != items {
+                    lists.push((field.name.to_string(), substituted));
+                }
+            }
+            _ => (),
+        }
+    }
+    let changed = !strings.is_empty() || !lists.is_empty();
+    for (name, value) in strings {
+        config.set_field_value(&name, FieldValue::String(&value))?;
+    }
+    for (name, items) in lists {
+        config.set_field_value(&name, FieldValue::StringList(&items))?;
+    }
+    Ok(changed)
+}
+
+/// escape values of template fields, so config is substituted into itself
+///
+/// config should be built config, since raw config doesn't support field updates
+/// returns true if any field was changed
+pub fn escape_config<C: Config + ?Sized>(config: &mut C) -> Result<bool, StdError> {
+    let mut strings = vec![];
+    let mut lists = vec![];
+    for field in config
+        .fields()
+        .into_iter()
+        .filter(|field| field.metadata.is_template)
+    {
+        match field.value {
+            FieldValue::String(value) if value.contains("${") => {
+                strings.push((field.name.to_string(), escape(value)));
+            }
+            FieldValue::StringList(items) if items.iter().any(|item| item.contains("${")) => {
+                let items = items.iter().map(|item| escape(item)).collect::<Vec<_>>();
+                lists.push((field.name.to_string(), items));
+            }
+            _ => (),
+        }
+    }
+    let changed = !strings.is_empty() || !lists.is_empty();
+    for (name, value) in strings {
+        config.set_field_value(&name, FieldValue::String(&value))?;
+    }
+    for (name, items) in lists {
+        config.set_field_value(&name, FieldValue::StringList(&items))?;
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "site_id" => Some("eu-1".into()),
+            "daemon.name" => Some("edge".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(
+            substitute("s3://bucket/${site_id}/${ daemon.name }", lookup).unwrap(),
+            "s3://bucket/eu-1/edge"
+        );
+        assert_eq!(
+            substitute("$5 $${site_id}", lookup).unwrap(),
+            "$5 ${site_id}"
+        );
+        assert_eq!(substitute("", lookup).unwrap(), "");
+        assert_eq!(
+            substitute("${region}", lookup).unwrap_err().to_string(),
+            "variable 'region' is not defined"
+        );
+        assert!(substitute("${site_id", lookup).is_err());
+        assert!(substitute("${site-id}", lookup).is_err());
+        assert!(substitute("${daemon.}", lookup).is_err());
+    }
+
+    #[test]
+    fn test_has_references() {
+        assert!(has_references("${site_id}").unwrap());
+        assert!(!has_references("$${site_id}").unwrap());
+        assert!(!has_references("plain $ value").unwrap());
+        assert!(has_references("${}").is_err());
+    }
+
+    #[test]
+    fn test_escape() {
+        for value in ["${site_id}", "$${site_id}", "$$${", "a $ b ${", "plain"] {
+            assert_eq!(substitute(&escape(value), lookup).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("site_id"));
+        assert!(is_valid_name("_x1"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("1x"));
+        assert!(!is_valid_name("daemon.name"));
+    }
+}
@@ -0,0 +1,232 @@
+//! Declarative field validation
+//!
+//! Rules are attached to config fields with `#[validate(...)]`:
+//! ```ignore
+//! #[derive(Debug, Clone, Configuration)]
+//! #[validate(with = check_range)]
+//! struct Conf {
+//!     #[validate(required, url)]
+//!     endpoint: String,
+//!     #[validate(min = 1, max = 65535)]
+//!     port: u16,
+//! }
+//! ```
+//! All rules except `required` skip empty values, so optional fields are checked only when set.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::{FieldType, FieldValue, StdError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// value can't be empty
+    Required,
+    /// lower bound for numbers and durations (in seconds), min length for strings and lists
+    Min(f64),
+    /// upper bound for numbers and durations (in seconds), max length for strings and lists
+    Max(f64),
+    /// string or every list item should match regular expression
+    Regex(&'static str),
+    /// string or every list item should be valid url
+    Url,
+    /// string or every list item should be one of given values
+    OneOf(&'static [&'static str]),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Per-field validation errors of config
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationErrors {
+    errors: Vec<ValidationError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(ValidationError::new(field, message))
+    }
+
+    pub fn extend(&mut self, other: ValidationErrors) {
+        self.errors.extend(other.errors)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn errors(&self) -> &[ValidationError] {
+        self.errors.as_slice()
+    }
+
+    /// first error message for given field
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.errors
+            .iter()
+            .find(|error| error.field == field)
+            .map(|error| error.message.as_str())
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl From<ValidationError> for ValidationErrors {
+    fn from(error: ValidationError) -> Self {
+        Self {
+            errors: vec![error],
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors = self
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", errors.join(", "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+// value converted to representation rules operate on
+enum RuleValue {
+    Number(f64),
+    Text(String),
+    List(Vec<String>),
+    Other,
+}
+
+impl RuleValue {
+    fn new(ty: FieldType, value: FieldValue<'_>) -> Result<Self, StdError> {
+        let value = match ty.inner() {
+            FieldType::String => Self::Text(value.try_into()?),
+            FieldType::StringList => Self::List(value.try_into()?),
+            FieldType::Duration => {
+                let duration: Duration = value.try_into()?;
+                Self::Number(duration.as_secs_f64())
+            }
+            ty if ty.is_number() => Self::Number(value.try_into()?),
+            _ => Self::Other,
+        };
+        Ok(value)
+    }
+
+    fn measure(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::Text(s) => Some(s.chars().count() as f64),
+            Self::List(l) => Some(l.len() as f64),
+            Self::Other => None,
+        }
+    }
+
+    fn strings(&self) -> &[String] {
+        match self {
+            Self::Text(s) => std::slice::from_ref(s),
+            Self::List(l) => l.as_slice(),
+            _ => &[],
+        }
+    }
+
+    fn bound_error(&self, bound: &str, limit: f64) -> String {
+        match self {
+            Self::Text(_) => format!("must be {bound} {limit} characters long"),
+            Self::List(_) => format!("must contain {bound} {limit} items"),
+            _ => format!("must be {bound} {limit}"),
+        }
+    }
+}
+
+pub(crate) fn check_rules(
+    ty: FieldType,
+    rules: &[Rule],
+    value: FieldValue<'_>,
+) -> Result<(), StdError> {
+    if rules.is_empty() {
+        return Ok(());
+    }
+    let required = rules.contains(&Rule::Required);
+    if value.is_empty() {
+        return match required {
+            true => Err("value is required")?,
+            false => Ok(()),
+        };
+    }
+    let value = RuleValue::new(ty, value)?;
+    if let RuleValue::List(list) = &value {
+        match (list.is_empty(), required) {
+            (true, true) => Err("value is required")?,
+            (true, false) => return Ok(()),
+            _ => (),
+        }
+    }
+    for rule in rules {
+        match *rule {
+            Rule::Required => (),
+            Rule::Min(min) => {
+                if value.measure().is_some_and(|len| len < min) {
+                    Err(value.bound_error("at least", min))?
+                }
+            }
+            Rule::Max(max) => {
+                if value.measure().is_some_and(|len| len > max) {
+                    Err(value.bound_error("at most", max))?
+                }
+            }
+            Rule::Regex(pattern) => {
+                let regex = regex::Regex::new(pattern)?;
+                if let Some(s) = value.strings().iter().find(|s| !regex.is_match(s)) {
+                    Err(format!("'{s}' doesn't match pattern '{pattern}'"))?
+                }
+            }
+            Rule::Url => {
+                for s in value.strings() {
+                    url::Url::parse(s).map_err(|e| format!("'{s}' is not a valid url: {e}"))?;
+                }
+            }
+            Rule::OneOf(values) => {
+                if let Some(s) = value
+                    .strings()
+                    .iter()
+                    .find(|s| !values.contains(&s.as_str()))
+                {
+                    Err(format!("'{s}' is not one of {values:?}"))?
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use config::prelude::*;
+
+#[derive(Configuration)]
+struct UnknownFieldAttribute {
+    #[field(label = "Name")]
+    name: String,
+}
+
+#[derive(Configuration)]
+struct DescriptionIsNotString {
+    #[field(description = 1)]
+    name: String,
+}
+
+#[derive(Configuration)]
+struct TemplateIsNotString {
+    #[field(template)]
+    port: u16,
+}
+
+fn main() {}
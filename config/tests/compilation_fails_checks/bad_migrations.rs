@@ -0,0 +1,35 @@
+use config::prelude::*;
+
+fn migrate(_: &mut RawConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    Ok(())
+}
+
+#[derive(Configuration)]
+#[section(version = 0)]
+struct ZeroVersion {
+    name: String,
+}
+
+#[derive(Configuration)]
+#[section(version = 2)]
+#[migrate(from = 2, with = migrate)]
+struct MigrationFromCurrentVersion {
+    name: String,
+}
+
+#[derive(Configuration)]
+#[section(version = 3)]
+#[migrate(from = 1, with = migrate)]
+#[migrate(from = 1, with = migrate)]
+struct DuplicateMigration {
+    name: String,
+}
+
+#[derive(Configuration)]
+#[section(version = 2)]
+#[migrate(from = 1)]
+struct MissingMigrationFunction {
+    name: String,
+}
+
+fn main() {}
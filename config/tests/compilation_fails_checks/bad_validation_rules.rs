@@ -0,0 +1,39 @@
+use config::prelude::*;
+
+#[derive(Configuration)]
+struct RequiredNumber {
+    #[validate(required)]
+    port: u16,
+}
+
+#[derive(Configuration)]
+struct RegexOnNumber {
+    #[validate(regex = "^[0-9]+$")]
+    port: u16,
+}
+
+#[derive(Configuration)]
+struct MinOnBool {
+    #[validate(min = 1)]
+    flag: bool,
+}
+
+#[derive(Configuration)]
+struct InvalidRegex {
+    #[validate(regex = "(")]
+    name: String,
+}
+
+#[derive(Configuration)]
+struct UnknownRule {
+    #[validate(email)]
+    name: String,
+}
+
+#[derive(Configuration)]
+#[validate(check)]
+struct BadStructAttribute {
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use config::prelude::*;
+
+#[derive(Configuration)]
+struct VecOfNumbers {
+    list: Vec<u64>,
+}
+
+#[derive(Configuration)]
+struct NestedOption {
+    option: Option<Option<u8>>,
+}
+
+#[derive(Configuration)]
+struct F32 {
+    f32: f32,
+}
+
+#[derive(Enum)]
+enum NonUnitVariant {
+    Unit,
+    Tuple(u8),
+}
+
+#[derive(Enum)]
+struct NotEnum {}
+
+fn main() {}
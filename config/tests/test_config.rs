@@ -32,6 +32,7 @@ fn test_simple_config() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: false,
+                    ..Default::default()
                 },
                 value: "login".into(),
             },
@@ -42,6 +43,7 @@ fn test_simple_config() {
                     is_password: true,
                     is_text_area: true,
                     is_read_only: false,
+                    ..Default::default()
                 },
                 value: "password".into(),
             },
@@ -52,6 +54,7 @@ fn test_simple_config() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: false,
+                    ..Default::default()
                 },
                 value: 30303_u16.into(),
             },
@@ -62,6 +65,7 @@ fn test_simple_config() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: false,
+                    ..Default::default()
                 },
                 value: true.into()
             },
@@ -83,10 +87,135 @@ fn test_config_all_types() {
         u64: u64,
         bool: bool,
         string: String,
-        //v: Vec<String>,
+        f64: f64,
+        duration: std::time::Duration,
+        list: Vec<String>,
+        option: Option<u16>,
+        option_string: Option<String>,
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default, Enum)]
+enum DataFormat {
+    #[default]
+    Csv,
+    JsonLines,
+}
+
+#[test]
+fn test_enum() {
+    assert_eq!(DataFormat::VARIANTS, &["csv", "json_lines"]);
+    assert_eq!(DataFormat::JsonLines.as_str(), "json_lines");
+    assert_eq!(DataFormat::from_variant("CSV"), Some(DataFormat::Csv));
+    assert_eq!(
+        DataFormat::from_variant("json_lines"),
+        Some(DataFormat::JsonLines)
+    );
+    assert_eq!(DataFormat::from_variant("parquet"), None);
+}
+
+#[test]
+fn test_rich_field_types() {
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, Default, PartialEq, Configuration)]
+    struct Conf {
+        ratio: f64,
+        interval: Duration,
+        sheets: Vec<String>,
+        format: DataFormat,
+        limit: Option<u64>,
+        format_override: Option<DataFormat>,
+    }
+
+    let mut cfg = Conf {
+        ratio: 0.5,
+        interval: Duration::from_secs(90),
+        sheets: vec!["a".into(), "b".into()],
+        format: DataFormat::JsonLines,
+        limit: None,
+        format_override: Some(DataFormat::Csv),
+    };
+    let types = cfg
+        .fields()
+        .into_iter()
+        .map(|field| field.ty)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            FieldType::F64,
+            FieldType::Duration,
+            FieldType::StringList,
+            FieldType::Enum(&["csv", "json_lines"]),
+            FieldType::Option(&FieldType::U64),
+            FieldType::Option(&FieldType::Enum(&["csv", "json_lines"])),
+        ]
+    );
+    assert!(types[4].is_number());
+    assert!(types[4].is_optional());
+    assert_eq!(types[5].variants(), Some(&["csv", "json_lines"][..]));
+
+    // values are rendered as strings, which can be set back
+    let values = cfg
+        .fields()
+        .into_iter()
+        .map(|field| field.value.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        vec!["0.5", "1m30s", r#"["a","b"]"#, "json_lines", "", "csv"]
+    );
+    let mut from_strings = Conf::default();
+    for (field, value) in cfg.fields().into_iter().zip(values.iter()) {
+        from_strings
+            .set_field_value(field.name, value.as_str().into())
+            .unwrap();
+    }
+    assert_eq!(cfg, from_strings);
+
+    cfg.set_field_value("interval", 30_u64.into()).unwrap();
+    assert_eq!(cfg.interval, Duration::from_secs(30));
+    cfg.set_field_value("interval", "1h 5m".into()).unwrap();
+    assert_eq!(cfg.interval, Duration::from_secs(3900));
+    cfg.set_field_value("limit", "10".into()).unwrap();
+    assert_eq!(cfg.limit, Some(10));
+    cfg.set_field_value("limit", FieldValue::Null).unwrap();
+    assert_eq!(cfg.limit, None);
+    cfg.set_field_value("format_override", "".into()).unwrap();
+    assert_eq!(cfg.format_override, None);
+    cfg.set_field_value("sheets", " x , y ,".into()).unwrap();
+    assert_eq!(cfg.sheets, vec!["x".to_string(), "y".to_string()]);
+    assert!(cfg.set_field_value("sheets", "[\"x\"".into()).is_err());
+
+    // items with commas survive round trip through string
+    cfg.set_field_value(
+        "sheets",
+        vec!["a,b".to_string(), "c".to_string()].as_slice().into(),
+    )
+    .unwrap();
+    let value = cfg.get_field_value("sheets").unwrap().to_string();
+    let mut from_string = Conf::default();
+    from_string
+        .set_field_value("sheets", value.as_str().into())
+        .unwrap();
+    assert_eq!(from_string.sheets, vec!["a,b".to_string(), "c".to_string()]);
+    cfg.set_field_value("ratio", 2_u8.into()).unwrap();
+    assert_eq!(cfg.ratio, 2.0);
+
+    assert!(cfg.validate_field("ratio", "1e-3".into()).is_ok());
+    assert!(cfg.validate_field("ratio", "one".into()).is_err());
+    assert!(cfg.validate_field("interval", "5m".into()).is_ok());
+    assert!(cfg.validate_field("interval", "5 minutes".into()).is_err());
+    assert!(cfg.validate_field("format", "JSON_LINES".into()).is_ok());
+    assert!(cfg.validate_field("format", "parquet".into()).is_err());
+    assert!(cfg.validate_field("format", "".into()).is_err());
+    assert!(cfg.validate_field("format_override", "".into()).is_ok());
+    assert!(cfg.validate_field("limit", "".into()).is_ok());
+    assert!(cfg.validate_field("limit", "-1".into()).is_err());
+    assert!(cfg.set_field_value("format", "parquet".into()).is_err());
+}
+
 #[test]
 fn test_section_input_output() {
     #[derive(Debug, Clone, Configuration)]
@@ -119,6 +248,286 @@ fn test_section_input_output() {
     assert_eq!(OutputDf {}.output(), SectionIO::DataFrame);
 }
 
+#[test]
+fn test_section_io_connect() {
+    use SectionIO::*;
+    assert!(Bin.can_connect_to(Bin));
+    assert!(DataFrame.can_connect_to(DataFrame));
+    assert!(!Bin.can_connect_to(DataFrame));
+    assert!(!DataFrame.can_connect_to(Bin));
+    for io in [Bin, DataFrame, BinOrDataFrame] {
+        assert!(BinOrDataFrame.can_connect_to(io));
+        assert!(io.can_connect_to(BinOrDataFrame));
+        assert!(!None.can_connect_to(io));
+        assert!(!io.can_connect_to(None));
+    }
+}
+
+#[test]
+fn test_validation_rules() {
+    #[derive(Debug, Clone, Configuration)]
+    #[validate(with = check_range)]
+    struct Conf {
+        #[validate(required, url)]
+        endpoint: String,
+        #[validate(min = 1, max = 65535)]
+        port: u32,
+        #[validate(regex = "^[a-z_]+$", min = 2, max = 8)]
+        name: String,
+        #[validate(one_of("csv", "json"), max = 2)]
+        formats: Vec<String>,
+        #[validate(min = -1.5)]
+        ratio: Option<f64>,
+        #[validate(required)]
+        limit: Option<u64>,
+        #[validate(max = 60)]
+        interval: std::time::Duration,
+        from: u32,
+        to: u32,
+    }
+
+    fn check_range(conf: &Conf) -> Result<(), ValidationErrors> {
+        match conf.from <= conf.to {
+            true => Ok(()),
+            false => Err(ValidationError::new("to", "should be greater or equal to 'from'").into()),
+        }
+    }
+
+    let mut cfg = Conf {
+        endpoint: "https://example.com".into(),
+        port: 80,
+        name: "abc".into(),
+        formats: vec!["csv".into()],
+        ratio: None,
+        limit: Some(1),
+        interval: std::time::Duration::from_secs(30),
+        from: 1,
+        to: 2,
+    };
+    assert_eq!(cfg.validate(), Ok(()));
+    assert_eq!(
+        cfg.field_rules("port"),
+        &[Rule::Min(1.0), Rule::Max(65535.0)]
+    );
+    assert_eq!(cfg.field_rules("from"), &[]);
+
+    let invalid = [
+        ("endpoint", ""),
+        ("endpoint", "not a url"),
+        ("port", "0"),
+        ("port", "65536"),
+        ("name", "ABC"),
+        ("name", "a"),
+        ("name", "abcdefghi"),
+        ("formats", "csv,xml"),
+        ("formats", "csv,json,csv"),
+        ("ratio", "-2"),
+        ("limit", ""),
+        ("interval", "2m"),
+    ];
+    for (field, value) in invalid {
+        assert!(
+            cfg.validate_field(field, value.into()).is_err(),
+            "{field}: '{value}' expected to be invalid"
+        );
+    }
+    let valid = [
+        ("port", "65535"),
+        ("name", "ab_cd"),
+        ("formats", "json, csv"),
+        ("formats", ""),
+        ("ratio", ""),
+        ("ratio", "-1.5"),
+        ("limit", "0"),
+        ("interval", "1m"),
+    ];
+    for (field, value) in valid {
+        assert!(
+            cfg.validate_field(field, value.into()).is_ok(),
+            "{field}: '{value}' expected to be valid"
+        );
+    }
+    assert_eq!(
+        cfg.validate_field("port", "0".into())
+            .unwrap_err()
+            .to_string(),
+        "must be at least 1"
+    );
+    assert_eq!(
+        cfg.validate_field("name", "a".into())
+            .unwrap_err()
+            .to_string(),
+        "must be at least 2 characters long"
+    );
+
+    // per-field errors
+    cfg.port = 0;
+    cfg.name = "".into();
+    cfg.limit = None;
+    let errors = cfg.validate().unwrap_err();
+    assert_eq!(
+        errors
+            .errors()
+            .iter()
+            .map(|e| e.field.as_str())
+            .collect::<Vec<_>>(),
+        vec!["port", "limit"]
+    );
+    assert_eq!(errors.get("limit"), Some("value is required"));
+
+    // cross-field check
+    cfg.port = 80;
+    cfg.limit = Some(1);
+    cfg.from = 3;
+    let errors = cfg.validate().unwrap_err();
+    assert_eq!(
+        errors.get("to"),
+        Some("should be greater or equal to 'from'")
+    );
+}
+
+#[test]
+fn test_field_metadata() {
+    /// Connects to server
+    ///
+    /// Second paragraph
+    #[derive(Debug, Clone, Default, Configuration)]
+    struct Conf {
+        /// Server host,
+        /// without port
+        #[field(group = "Connection", placeholder = "localhost", template)]
+        host: String,
+        /// ignored doc comment
+        #[field(description = "Connection timeout", group = "Connection", advanced)]
+        timeout: u32,
+        name: String,
+    }
+
+    let cfg = Conf::default();
+    assert_eq!(
+        cfg.description(),
+        Some("Connects to server\nSecond paragraph")
+    );
+    let metadata = cfg
+        .fields()
+        .into_iter()
+        .map(|field| field.metadata)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        metadata,
+        vec![
+            Metadata {
+                description: Some("Server host, without port"),
+                placeholder: Some("localhost"),
+                group: Some("Connection"),
+                is_template: true,
+                ..Default::default()
+            },
+            Metadata {
+                description: Some("Connection timeout"),
+                group: Some("Connection"),
+                is_advanced: true,
+                ..Default::default()
+            },
+            Metadata::default(),
+        ]
+    );
+}
+
+#[test]
+fn test_variable_references() {
+    #[derive(Debug, Clone, Default, Configuration, PartialEq)]
+    #[validate(with = check)]
+    struct Conf {
+        #[field(template)]
+        #[validate(url)]
+        endpoint: String,
+        #[field(template)]
+        prefixes: Vec<String>,
+        port: u16,
+        // not a template, value is taken literally
+        query: String,
+    }
+
+    fn check(conf: &Conf) -> Result<(), ValidationErrors> {
+        match conf.endpoint.contains("localhost") {
+            true => Err(ValidationError::new("endpoint", "localhost is not allowed").into()),
+            false => Ok(()),
+        }
+    }
+
+    let mut cfg = Conf {
+        endpoint: "${scheme}://${host}:8080".into(),
+        prefixes: vec!["${daemon.site}/in".into(), "static".into()],
+        port: 0,
+        query: "select '${literal'".into(),
+    };
+    // references are not checked against field rules
+    assert!(cfg.validate().is_err());
+    assert_eq!(config::template::validate(&cfg), Ok(()));
+
+    let lookup = |name: &str| match name {
+        "scheme" => Some("https".to_string()),
+        "host" => Some("example.com".to_string()),
+        "daemon.site" => Some("eu-1".to_string()),
+        _ => None,
+    };
+    assert!(config::template::substitute_config(&mut cfg, lookup).unwrap());
+    assert_eq!(
+        cfg,
+        Conf {
+            endpoint: "https://example.com:8080".into(),
+            prefixes: vec!["eu-1/in".into(), "static".into()],
+            port: 0,
+            query: "select '${literal'".into(),
+        }
+    );
+    assert!(!config::template::substitute_config(&mut cfg, lookup).unwrap());
+
+    // substituted values are validated as usual
+    cfg.endpoint = "http://${host}".into();
+    assert_eq!(config::template::validate(&cfg), Ok(()));
+    config::template::substitute_config(&mut cfg, |_| Some("localhost".into())).unwrap();
+    assert_eq!(
+        config::template::validate(&cfg)
+            .unwrap_err()
+            .get("endpoint"),
+        Some("localhost is not allowed")
+    );
+
+    // escaped template fields are substituted into themselves
+    let mut escaped = Conf {
+        endpoint: "http://${host}".into(),
+        prefixes: vec!["${daemon.site}".into(), "$${site}".into()],
+        port: 0,
+        query: "select '${literal'".into(),
+    };
+    let original = escaped.clone();
+    assert!(config::template::escape_config(&mut escaped).unwrap());
+    assert_eq!(escaped.query, original.query);
+    assert!(config::template::substitute_config(&mut escaped, |_| None).unwrap());
+    assert_eq!(escaped, original);
+
+    // malformed references are reported as field errors
+    cfg.endpoint = "http://${host".into();
+    assert!(config::template::validate(&cfg)
+        .unwrap_err()
+        .get("endpoint")
+        .is_some());
+    let err = config::template::substitute_config(
+        &mut Conf {
+            endpoint: "${missing}".into(),
+            ..Default::default()
+        },
+        lookup,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "field 'endpoint': variable 'missing' is not defined"
+    );
+}
+
 #[test]
 fn test_compilations() {
     let t = trybuild::TestCases::new();
@@ -119,6 +119,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::Bool(true)
             },
@@ -129,6 +130,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::I64(-16)
             },
@@ -139,6 +141,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::I64(-32)
             },
@@ -149,6 +152,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::I64(-64)
             },
@@ -159,6 +163,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::I64(-8)
             },
@@ -169,6 +174,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::String("some string")
             },
@@ -179,6 +185,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::U64(16)
             },
@@ -189,6 +196,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::U64(32)
             },
@@ -199,6 +207,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::U64(64)
             },
@@ -209,6 +218,7 @@ fn test_serialization_deserialization() {
                     is_password: false,
                     is_text_area: false,
                     is_read_only: true,
+                    ..Default::default()
                 },
                 value: FieldValue::U64(8)
             }
@@ -220,3 +230,109 @@ fn test_serialization_deserialization() {
     let cfg2 = unsafe { &*(&*cfg2 as *const _ as *const () as *const Conf) };
     assert_eq!(cfg, cfg2);
 }
+
+#[test]
+fn test_rich_field_types_serialization_deserialization() {
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Default, Enum)]
+    enum Format {
+        #[default]
+        Csv,
+        Json,
+    }
+
+    #[derive(Debug, Clone, Configuration, Default, PartialEq)]
+    struct Conf {
+        f64: f64,
+        duration: Duration,
+        list: Vec<String>,
+        format: Format,
+        some: Option<u32>,
+        none: Option<String>,
+    }
+
+    let cfg: Box<dyn Config> = Box::new(Conf {
+        f64: 1.5,
+        duration: Duration::from_millis(1500),
+        list: vec!["a,b".into(), "c".into()],
+        format: Format::Json,
+        some: Some(1),
+        none: None,
+    });
+    let serialized = serde_json::to_string(&cfg).unwrap();
+    assert_eq!(
+        serialized,
+        r#"{"config_name":"Conf","fields":[{"name":"f64","value":1.5},{"name":"duration","value":"1s500ms"},{"name":"list","value":["a,b","c"]},{"name":"format","value":"json"},{"name":"some","value":1},{"name":"none","value":null}]}"#
+    );
+    let raw_config = serde_json::from_str::<RawConfig>(&serialized).unwrap();
+    let mut cfg2: Box<dyn Config> = Box::new(Conf::default());
+    deserialize_into_config(&raw_config, &mut *cfg2).unwrap();
+    let cfg = unsafe { &*(&*cfg as *const _ as *const () as *const Conf) };
+    let cfg2 = unsafe { &*(&*cfg2 as *const _ as *const () as *const Conf) };
+    assert_eq!(cfg, cfg2);
+}
+
+#[test]
+fn test_config_versioning() {
+    #[derive(Debug, Clone, Configuration, Default, PartialEq)]
+    #[section(output = dataframe, version = 3)]
+    #[migrate(from = 1, with = rename_endpoint)]
+    #[migrate(from = 2, with = default_port)]
+    struct Conf {
+        url: String,
+        port: u16,
+    }
+
+    fn rename_endpoint(
+        raw: &mut RawConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        raw.rename_field("endpoint", "url");
+        Ok(())
+    }
+
+    fn default_port(raw: &mut RawConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        raw.set_default("port", FieldValue::U16(8080));
+        Ok(())
+    }
+
+    assert_eq!(Conf::default().version(), 3);
+
+    // configs without version are of version 1
+    let raw_config = serde_json::from_str::<RawConfig>(
+        r#"{"config_name":"Conf","fields":[{"name":"endpoint","value":"http://localhost"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(raw_config.version(), 1);
+    let mut cfg: Box<dyn Config> = Box::new(Conf::default());
+    deserialize_into_config(&raw_config, &mut *cfg).unwrap();
+    let upgraded = unsafe { &*(&*cfg as *const _ as *const () as *const Conf) };
+    assert_eq!(
+        upgraded,
+        &Conf {
+            url: "http://localhost".into(),
+            port: 8080
+        }
+    );
+
+    // version is serialized, if it's not 1
+    let serialized = serde_json::to_string(&cfg).unwrap();
+    assert_eq!(
+        serialized,
+        r#"{"config_name":"Conf","fields":[{"name":"url","value":"http://localhost"},{"name":"port","value":8080}],"version":3}"#
+    );
+    let raw_config = serde_json::from_str::<RawConfig>(&serialized).unwrap();
+    assert_eq!(raw_config.version(), 3);
+
+    // configs of newer version are rejected
+    let raw_config = serde_json::from_str::<RawConfig>(
+        r#"{"config_name":"Conf","version":4,"fields":[{"name":"url","value":"http://localhost"},{"name":"port","value":8080}]}"#,
+    )
+    .unwrap();
+    let mut cfg: Box<dyn Config> = Box::new(Conf::default());
+    let err = deserialize_into_config(&raw_config, &mut *cfg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "config 'Conf' has version 4, but only versions up to 3 are supported"
+    );
+}
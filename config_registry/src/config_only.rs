@@ -6,16 +6,40 @@ use std::collections::BTreeMap;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-pub type ConfigConstructor = fn() -> Box<dyn Config>;
+pub type ConfigConstructor = Arc<dyn Fn() -> Box<dyn Config> + Send + Sync>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct ConfigMetaData {
     pub input: bool,
     pub output: bool,
     pub ty: Arc<str>,
+    pub description: Option<&'static str>,
     ctor: ConfigConstructor,
 }
 
+impl std::fmt::Debug for ConfigMetaData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigMetaData")
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .field("ty", &self.ty)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+impl PartialEq for ConfigMetaData {
+    fn eq(&self, other: &Self) -> bool {
+        self.input == other.input
+            && self.output == other.output
+            && self.ty == other.ty
+            && self.description == other.description
+            && Arc::ptr_eq(&self.ctor, &other.ctor)
+    }
+}
+
+impl Eq for ConfigMetaData {}
+
 impl ConfigMetaData {
     pub fn build_config(&self) -> Box<dyn Config> {
         (self.ctor)()
@@ -31,6 +55,15 @@ where
     _marker: PhantomData<Chan>,
 }
 
+impl<Chan: SectionChannel> Clone for ConfigRegistry<Chan> {
+    fn clone(&self) -> Self {
+        Self {
+            registry: self.registry.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
 impl<Chan: SectionChannel> ConfigRegistry<Chan> {
     pub fn new() -> Self {
         Self {
@@ -39,7 +72,11 @@ impl<Chan: SectionChannel> ConfigRegistry<Chan> {
         }
     }
 
-    pub fn add_config(&mut self, ctor: ConfigConstructor) -> Result<()> {
+    pub fn add_config(
+        &mut self,
+        ctor: impl Fn() -> Box<dyn Config> + Send + Sync + 'static,
+    ) -> Result<()> {
+        let ctor: ConfigConstructor = Arc::new(ctor);
         let config = ctor();
         let name: Arc<str> = Arc::from(config.name());
         let (input, output) = (!config.input().is_none(), !config.output().is_none());
@@ -47,6 +84,7 @@ impl<Chan: SectionChannel> ConfigRegistry<Chan> {
             input,
             output,
             ty: Arc::clone(&name),
+            description: config.description(),
             ctor,
         };
         if self.registry.contains_key(&*name) {
@@ -56,10 +94,22 @@ impl<Chan: SectionChannel> ConfigRegistry<Chan> {
         Ok(())
     }
 
+    /// register config of section, provided by plugin
+    pub fn add_plugin_config(&mut self, config: plugin::PluginConfig) -> Result<()> {
+        self.add_config(move || Box::new(config.clone()))
+    }
+
     pub fn iter_values(&self) -> impl Iterator<Item = ConfigMetaData> + '_ {
         self.registry.values().cloned()
     }
 
+    /// JSON Schema of every registered config, ordered by config name
+    pub fn json_schemas(&self) -> Vec<serde_json::Value> {
+        self.iter_values()
+            .map(|metadata| crate::schema::json_schema(&*metadata.build_config()))
+            .collect()
+    }
+
     pub fn build_config(&self, name: &str) -> Result<Box<dyn Config>> {
         match self.registry.get(name) {
             Some(metadata) => Ok(metadata.build_config()),
@@ -45,7 +45,7 @@ impl<Chan: SectionChannel> PartialEq for dyn Config<Chan> {
     }
 }
 
-pub type ConfigConstructor<Chan> = fn() -> Box<dyn Config<Chan>>;
+pub type ConfigConstructor<Chan> = Arc<dyn Fn() -> Box<dyn Config<Chan>> + Send + Sync>;
 
 impl<Chan: SectionChannel> Serialize for dyn Config<Chan> {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
@@ -93,6 +93,14 @@ impl BaseConfig for RawConfig {
         self.0.output()
     }
 
+    fn description(&self) -> Option<&'static str> {
+        self.0.description()
+    }
+
+    fn version(&self) -> u32 {
+        self.0.version()
+    }
+
     fn fields(&self) -> Vec<config::Field<'_>> {
         self.0.fields()
     }
@@ -114,11 +122,11 @@ impl BaseConfig for RawConfig {
     }
 }
 
-#[derive(Debug, Eq)]
 pub struct ConfigMetaData<Chan: SectionChannel = DummySectionChannel> {
     pub input: bool,
     pub output: bool,
     pub ty: Arc<str>,
+    pub description: Option<&'static str>,
     ctor: ConfigConstructor<Chan>,
 }
 
@@ -128,20 +136,35 @@ impl<Chan: SectionChannel> Clone for ConfigMetaData<Chan> {
             input: self.input,
             output: self.output,
             ty: Arc::clone(&self.ty),
-            ctor: self.ctor,
+            description: self.description,
+            ctor: Arc::clone(&self.ctor),
         }
     }
 }
 
+impl<Chan: SectionChannel> std::fmt::Debug for ConfigMetaData<Chan> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigMetaData")
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .field("ty", &self.ty)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
 impl<Chan: SectionChannel> PartialEq for ConfigMetaData<Chan> {
     fn eq(&self, other: &Self) -> bool {
         self.input == other.input
             && self.output == other.output
             && self.ty == other.ty
-            && self.ctor == other.ctor
+            && self.description == other.description
+            && Arc::ptr_eq(&self.ctor, &other.ctor)
     }
 }
 
+impl<Chan: SectionChannel> Eq for ConfigMetaData<Chan> {}
+
 impl<Chan: SectionChannel> ConfigMetaData<Chan> {
     pub fn build_config(&self) -> Box<dyn Config<Chan>> {
         (self.ctor)()
@@ -153,6 +176,14 @@ pub struct ConfigRegistry<Chan: SectionChannel = section::dummy::DummySectionCha
     registry: BTreeMap<Arc<str>, ConfigMetaData<Chan>>,
 }
 
+impl<Chan: SectionChannel> Clone for ConfigRegistry<Chan> {
+    fn clone(&self) -> Self {
+        Self {
+            registry: self.registry.clone(),
+        }
+    }
+}
+
 impl<Chan: SectionChannel> Default for ConfigRegistry<Chan> {
     fn default() -> Self {
         Self::new()
@@ -166,7 +197,11 @@ impl<Chan: SectionChannel> ConfigRegistry<Chan> {
         }
     }
 
-    pub fn add_config(&mut self, ctor: ConfigConstructor<Chan>) -> Result<()> {
+    pub fn add_config(
+        &mut self,
+        ctor: impl Fn() -> Box<dyn Config<Chan>> + Send + Sync + 'static,
+    ) -> Result<()> {
+        let ctor: ConfigConstructor<Chan> = Arc::new(ctor);
         let config = ctor();
         let name: Arc<str> = Arc::from(config.name());
         let (input, output) = (!config.input().is_none(), !config.output().is_none());
@@ -174,6 +209,7 @@ impl<Chan: SectionChannel> ConfigRegistry<Chan> {
             input,
             output,
             ty: Arc::clone(&name),
+            description: config.description(),
             ctor,
         };
         if self.registry.contains_key(&*name) {
@@ -183,6 +219,18 @@ impl<Chan: SectionChannel> ConfigRegistry<Chan> {
         Ok(())
     }
 
+    /// register config of section, provided by plugin
+    pub fn add_plugin_config(&mut self, config: plugin::PluginConfig) -> Result<()> {
+        self.add_config(move || Box::new(config.clone()))
+    }
+
+    /// JSON Schema of every registered config, ordered by config name
+    pub fn json_schemas(&self) -> Vec<serde_json::Value> {
+        self.iter_values()
+            .map(|metadata| crate::schema::json_schema(metadata.build_config().as_dyn_config_ref()))
+            .collect()
+    }
+
     pub fn build_config(&self, name: &str) -> Result<Box<dyn Config<Chan>>> {
         match self.registry.get(name) {
             Some(metadata) => Ok(metadata.build_config()),
@@ -16,8 +16,10 @@
 #[cfg_attr(not(feature = "section"), path = "config_only.rs")]
 #[cfg_attr(feature = "section", path = "config_section.rs")]
 mod config_registry_impl;
+mod schema;
 
 pub use config_registry_impl::{Config, ConfigMetaData, ConfigRegistry};
+pub use plugin;
 use section::prelude::SectionChannel;
 
 pub(crate) type Result<T, E = Box<dyn std::error::Error + Send + Sync + 'static>> =
@@ -25,15 +27,22 @@ pub(crate) type Result<T, E = Box<dyn std::error::Error + Send + Sync + 'static>
 
 pub fn new<Chan: SectionChannel>() -> Result<ConfigRegistry<Chan>> {
     let mut registry = ConfigRegistry::new();
+    registry.add_config(|| Box::from(cbor_transform::FromCbor::default()))?;
+    registry.add_config(|| Box::from(cbor_transform::ToCbor::default()))?;
     registry.add_config(|| Box::from(csv_transform::FromCsv::default()))?;
     registry.add_config(|| Box::from(csv_transform::ToCsv::default()))?;
     registry.add_config(|| Box::from(dir::DirSource::default()))?;
+    registry.add_config(|| Box::from(encryption::Decrypt::default()))?;
+    registry.add_config(|| Box::from(encryption::Encrypt::default()))?;
     registry.add_config(|| Box::from(excel_connector::Excel::default()))?;
+    registry.add_config(|| Box::from(excel_connector::ToXlsx::default()))?;
     registry.add_config(|| Box::from(inspect::Inspect {}))?;
     registry.add_config(|| Box::from(postgres_connector::PostgresDestination::default()))?;
     registry.add_config(|| Box::from(postgres_connector::PostgresSource::default()))?;
     registry.add_config(|| Box::from(redshift_loader::RedshiftLoader::default()))?;
     registry.add_config(|| Box::from(s3::S3Destination::default()))?;
     registry.add_config(|| Box::from(s3::S3Source::default()))?;
+    registry.add_config(|| Box::from(wasm_transform::WasmTransform::default()))?;
+    registry.add_config(|| Box::from(xml_transform::FromXml::default()))?;
     Ok(registry)
 }
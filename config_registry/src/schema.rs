@@ -0,0 +1,290 @@
+//! JSON Schema export of registered configs
+//!
+//! Each config is described as an object schema, where properties are config fields.
+//! Non-standard keywords are prefixed with `x-`:
+//! - `x-section`: section input and output
+//! - `x-version`: config version, declared with `#[section(version = N)]`
+//! - `x-metadata`: field metadata flags, placeholder and group
+//! - `x-rules`: validation rules declared with `#[validate(...)]`
+
+use config::{Config, Field, FieldType, Rule, SectionIO};
+use serde_json::{json, Map, Value};
+
+const JSON_SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+// mirrors syntax of `#[section(...)]` attribute
+fn section_io(io: SectionIO) -> &'static str {
+    match io {
+        SectionIO::None => "none",
+        SectionIO::Bin => "bin",
+        SectionIO::DataFrame => "dataframe",
+        SectionIO::BinOrDataFrame => "bin_or_dataframe",
+    }
+}
+
+fn integer(min: impl Into<Value>, max: impl Into<Value>) -> Value {
+    json!({"type": "integer", "minimum": min.into(), "maximum": max.into()})
+}
+
+// integral bounds are emitted as integers, since `minLength` and friends don't accept floats
+fn number(value: f64) -> Value {
+    match value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        true => (value as i64).into(),
+        false => value.into(),
+    }
+}
+
+fn type_schema(ty: FieldType) -> Map<String, Value> {
+    let schema = match ty {
+        FieldType::Usize => integer(usize::MIN, usize::MAX),
+        FieldType::U8 => integer(u8::MIN, u8::MAX),
+        FieldType::U16 => integer(u16::MIN, u16::MAX),
+        FieldType::U32 => integer(u32::MIN, u32::MAX),
+        FieldType::U64 => integer(u64::MIN, u64::MAX),
+        FieldType::I8 => integer(i8::MIN, i8::MAX),
+        FieldType::I16 => integer(i16::MIN, i16::MAX),
+        FieldType::I32 => integer(i32::MIN, i32::MAX),
+        FieldType::I64 => integer(i64::MIN, i64::MAX),
+        FieldType::F64 => json!({"type": "number"}),
+        FieldType::String => json!({"type": "string"}),
+        FieldType::Bool => json!({"type": "boolean"}),
+        // human readable duration or number of seconds
+        FieldType::Duration => json!({
+            "type": ["string", "integer"],
+            "pattern": r"^\s*(\d+\s*(d|h|m|s|ms|us|ns)\s*)+$|^\s*\d+\s*$",
+            "minimum": 0,
+        }),
+        FieldType::StringList => json!({"type": "array", "items": {"type": "string"}}),
+        FieldType::Enum(variants) => json!({"type": "string", "enum": variants}),
+        FieldType::Option(ty) => {
+            let mut schema = type_schema(*ty);
+            let types = match schema.remove("type") {
+                Some(Value::Array(mut types)) => {
+                    types.push("null".into());
+                    types
+                }
+                Some(ty) => vec![ty, "null".into()],
+                None => vec!["null".into()],
+            };
+            schema.insert("type".into(), types.into());
+            if let Some(Value::Array(variants)) = schema.get_mut("enum") {
+                variants.push(Value::Null);
+            }
+            return schema;
+        }
+    };
+    match schema {
+        Value::Object(schema) => schema,
+        _ => unreachable!(),
+    }
+}
+
+// translate validation rules into standard keywords where possible
+fn apply_rules(schema: &mut Map<String, Value>, ty: FieldType, rules: &[Rule]) {
+    let (min_key, max_key) = match ty.inner() {
+        FieldType::String => ("minLength", "maxLength"),
+        FieldType::StringList => ("minItems", "maxItems"),
+        ty if ty.is_number() => ("minimum", "maximum"),
+        // duration bounds are in seconds and can't be expressed for strings
+        _ => ("", ""),
+    };
+    let mut string_keywords = Map::new();
+    for rule in rules {
+        match *rule {
+            Rule::Min(min) if !min_key.is_empty() => {
+                schema.insert(min_key.into(), number(min));
+            }
+            Rule::Max(max) if !max_key.is_empty() => {
+                schema.insert(max_key.into(), number(max));
+            }
+            Rule::Regex(pattern) => {
+                string_keywords.insert("pattern".into(), pattern.into());
+            }
+            Rule::Url => {
+                string_keywords.insert("format".into(), "uri".into());
+            }
+            Rule::OneOf(values) => {
+                let mut values = json!(values);
+                if let (FieldType::Option(_), Value::Array(values)) = (ty, &mut values) {
+                    values.push(Value::Null);
+                }
+                string_keywords.insert("enum".into(), values);
+            }
+            _ => (),
+        }
+    }
+    // string rules apply to list items
+    let target = match ty.inner() {
+        FieldType::StringList => schema.get_mut("items").and_then(Value::as_object_mut),
+        _ => Some(schema),
+    };
+    if let Some(target) = target {
+        target.extend(string_keywords);
+    }
+}
+
+fn field_schema(config: &dyn Config, field: &Field<'_>) -> Value {
+    let rules = config.field_rules(field.name);
+    let mut schema = type_schema(field.ty);
+    apply_rules(&mut schema, field.ty, rules);
+    // secrets are not exposed, even if default
+    if !field.metadata.is_password {
+        let value = serde_json::to_value(field)
+            .ok()
+            .and_then(|mut value| value.get_mut("value").map(Value::take));
+        if let Some(value) = value {
+            schema.insert("default".into(), value);
+        }
+    }
+    if let Some(description) = field.metadata.description {
+        schema.insert("description".into(), description.into());
+    }
+    let mut metadata = json!({
+        "is_password": field.metadata.is_password,
+        "is_text_area": field.metadata.is_text_area,
+        "is_read_only": field.metadata.is_read_only,
+        "is_advanced": field.metadata.is_advanced,
+        "is_template": field.metadata.is_template,
+    });
+    if let Some(placeholder) = field.metadata.placeholder {
+        metadata["placeholder"] = placeholder.into();
+    }
+    if let Some(group) = field.metadata.group {
+        metadata["group"] = group.into();
+    }
+    schema.insert("x-metadata".into(), metadata);
+    if !rules.is_empty() {
+        schema.insert("x-rules".into(), json!(rules));
+    }
+    schema.into()
+}
+
+/// JSON Schema of config, config is expected to hold default values
+pub(crate) fn json_schema(config: &dyn Config) -> Value {
+    let fields = config.fields();
+    let properties = fields
+        .iter()
+        .map(|field| (field.name.to_string(), field_schema(config, field)))
+        .collect::<Map<String, Value>>();
+    let required = fields
+        .iter()
+        .filter(|field| config.field_rules(field.name).contains(&Rule::Required))
+        .map(|field| field.name)
+        .collect::<Vec<_>>();
+    let mut schema = json!({
+        "$schema": JSON_SCHEMA_DRAFT,
+        "title": config.name(),
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+        "x-section": {
+            "input": section_io(config.input()),
+            "output": section_io(config.output()),
+        },
+        "x-version": config.version(),
+    });
+    if let Some(description) = config.description() {
+        schema["description"] = description.into();
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, Copy, PartialEq, config::Enum)]
+    enum Format {
+        Csv,
+        Json,
+    }
+
+    /// Test config
+    #[derive(Debug, Clone, config::Configuration)]
+    #[section(input=bin, output=dataframe)]
+    struct Conf {
+        #[field(template)]
+        #[validate(required, url)]
+        endpoint: String,
+        #[field_type(password)]
+        secret: String,
+        /// Port to connect to
+        #[field(placeholder = "8080", group = "Connection", advanced)]
+        #[validate(min = 1)]
+        port: u16,
+        #[validate(one_of("a", "b"), max = 2)]
+        tags: Vec<String>,
+        format: Format,
+        limit: Option<u32>,
+        interval: Duration,
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let conf = Conf {
+            endpoint: "http://localhost".into(),
+            secret: "secret".into(),
+            port: 80,
+            tags: vec!["a".into()],
+            format: Format::Csv,
+            limit: None,
+            interval: Duration::from_secs(90),
+        };
+        let schema = json_schema(&conf);
+        assert_eq!(schema["title"], "Conf");
+        assert_eq!(schema["description"], "Test config");
+        assert_eq!(schema["required"], json!(["endpoint"]));
+        assert_eq!(
+            schema["x-section"],
+            json!({"input": "bin", "output": "dataframe"})
+        );
+        assert_eq!(schema["x-version"], 1);
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["endpoint"],
+            json!({
+                "type": "string",
+                "format": "uri",
+                "default": "http://localhost",
+                "x-metadata": {
+                    "is_password": false,
+                    "is_text_area": false,
+                    "is_read_only": false,
+                    "is_advanced": false,
+                    "is_template": true,
+                },
+                "x-rules": ["required", "url"],
+            })
+        );
+        assert!(properties["secret"].get("default").is_none());
+        assert_eq!(properties["secret"]["x-metadata"]["is_password"], true);
+        assert_eq!(properties["port"]["minimum"], 1);
+        assert_eq!(properties["port"]["maximum"], 65535);
+        assert_eq!(properties["port"]["description"], "Port to connect to");
+        assert_eq!(
+            properties["port"]["x-metadata"],
+            json!({
+                "is_password": false,
+                "is_text_area": false,
+                "is_read_only": false,
+                "is_advanced": true,
+                "is_template": false,
+                "placeholder": "8080",
+                "group": "Connection",
+            })
+        );
+        assert_eq!(properties["tags"]["maxItems"], 2);
+        assert_eq!(properties["tags"]["items"]["enum"], json!(["a", "b"]));
+        assert_eq!(
+            properties["tags"]["x-rules"],
+            json!([{"one_of": ["a", "b"]}, {"max": 2.0}])
+        );
+        assert_eq!(properties["format"]["enum"], json!(["csv", "json"]));
+        assert_eq!(properties["format"]["default"], "csv");
+        assert_eq!(properties["limit"]["type"], json!(["integer", "null"]));
+        assert_eq!(properties["limit"]["default"], Value::Null);
+        assert_eq!(properties["interval"]["default"], "1m30s");
+    }
+}
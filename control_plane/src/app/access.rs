@@ -0,0 +1,147 @@
+//! Organizations and workspace roles
+//!
+//! Workspaces, daemons and daemon tokens belong to organization.
+//! Organization admins manage its members and daemons and have admin role in every organization workspace,
+//! other users get access to workspace through workspace role.
+//! Instance admins have access to everything.
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use config::prelude::RawConfig;
+use config_registry::ConfigRegistry;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Result;
+
+/// name of organization, which adopts workspaces and daemons created before organizations were introduced
+pub const DEFAULT_ORGANIZATION: &str = "default";
+
+/// Role of user in workspace, each role includes permissions of previous one
+///
+/// - viewer reads workspace, configs of restricted sections are hidden
+/// - operator pauses, resumes and restarts nodes
+/// - editor changes workspace graph, variables and applies documents
+/// - admin manages workspace members and deletes workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Operator,
+    Editor,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Editor => "editor",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "operator" => Ok(Role::Operator),
+            "editor" => Ok(Role::Editor),
+            "admin" => Ok(Role::Admin),
+            _ => Err(anyhow::anyhow!("unknown role '{s}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrganizationRole {
+    Member,
+    Admin,
+}
+
+impl OrganizationRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrganizationRole::Member => "member",
+            OrganizationRole::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for OrganizationRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "member" => Ok(OrganizationRole::Member),
+            "admin" => Ok(OrganizationRole::Admin),
+            _ => Err(anyhow::anyhow!("unknown organization role '{s}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Organization {
+    #[serde(default)]
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizationMember {
+    pub user_id: Uuid,
+    pub email: String,
+    pub role: OrganizationRole,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceMember {
+    pub user_id: Uuid,
+    pub email: String,
+    pub role: Role,
+}
+
+/// Member to add or update, user is referenced by email
+#[derive(Debug, Deserialize)]
+pub struct MemberRequest<R> {
+    pub email: String,
+    pub role: R,
+}
+
+/// sections with secret fields are restricted
+fn is_restricted(config: &dyn config_registry::Config) -> bool {
+    config
+        .fields()
+        .iter()
+        .any(|field| field.metadata.is_password)
+}
+
+/// replace config of restricted section with empty config, so only section name and version are visible
+pub fn hide_restricted_config(
+    config_registry: &ConfigRegistry,
+    config: &mut Box<dyn config_registry::Config>,
+) -> Result<()> {
+    let restricted = match config_registry.deserialize_config(&**config) {
+        Ok(config) => is_restricted(&*config),
+        // configs, unknown to registry, are hidden
+        Err(_) => true,
+    };
+    if restricted {
+        let raw = RawConfig::new(config.name()).with_version(config.version());
+        *config = serde_json::from_value(serde_json::to_value(&raw)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Audit log of control plane mutations
+//!
+//! Every mutating `App` method appends entry with actor, source address, affected object and payload.
+//! Log is append-only, entries are never updated or deleted.
+//! Payloads are redacted: secret config fields are stripped, passwords, token secrets and variable values are
+//! not recorded.
+use chrono::{DateTime, Utc};
+use config_registry::ConfigRegistry;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::WorkspaceOperation;
+
+/// amount of entries, returned if limit is not set
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// max amount of entries, returned at once
+pub const MAX_PAGE_SIZE: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    WorkspaceCreate,
+    WorkspaceDelete,
+    WorkspaceUpdate,
+    WorkspaceMemberSet,
+    WorkspaceMemberRemove,
+    WorkspaceVariableSet,
+    WorkspaceVariableDelete,
+    OrganizationCreate,
+    OrganizationDelete,
+    OrganizationMemberSet,
+    OrganizationMemberRemove,
+    UserCreate,
+    UserDelete,
+    ApiTokenCreate,
+    ApiTokenDelete,
+    DaemonTokenCreate,
+    DaemonTokenDelete,
+    DaemonJoin,
+    DaemonDelete,
+    DaemonRevoke,
+    DaemonSetName,
+    DaemonVariableSet,
+    DaemonVariableDelete,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::WorkspaceCreate => "workspace.create",
+            AuditAction::WorkspaceDelete => "workspace.delete",
+            AuditAction::WorkspaceUpdate => "workspace.update",
+            AuditAction::WorkspaceMemberSet => "workspace.member.set",
+            AuditAction::WorkspaceMemberRemove => "workspace.member.remove",
+            AuditAction::WorkspaceVariableSet => "workspace.variable.set",
+            AuditAction::WorkspaceVariableDelete => "workspace.variable.delete",
+            AuditAction::OrganizationCreate => "organization.create",
+            AuditAction::OrganizationDelete => "organization.delete",
+            AuditAction::OrganizationMemberSet => "organization.member.set",
+            AuditAction::OrganizationMemberRemove => "organization.member.remove",
+            AuditAction::UserCreate => "user.create",
+            AuditAction::UserDelete => "user.delete",
+            AuditAction::ApiTokenCreate => "api_token.create",
+            AuditAction::ApiTokenDelete => "api_token.delete",
+            AuditAction::DaemonTokenCreate => "daemon_token.create",
+            AuditAction::DaemonTokenDelete => "daemon_token.delete",
+            AuditAction::DaemonJoin => "daemon.join",
+            AuditAction::DaemonDelete => "daemon.delete",
+            AuditAction::DaemonRevoke => "daemon.revoke",
+            AuditAction::DaemonSetName => "daemon.set_name",
+            AuditAction::DaemonVariableSet => "daemon.variable.set",
+            AuditAction::DaemonVariableDelete => "daemon.variable.delete",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    /// assigned by database on append
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    /// user, who made change, daemons have no user
+    pub user_id: Option<Uuid>,
+    /// user email or daemon id
+    pub actor: String,
+    /// source address of request
+    pub address: Option<String>,
+    /// organization of changed object
+    pub organization_id: Option<Uuid>,
+    pub action: String,
+    /// changed object: workspace or organization name, user email, token or daemon id
+    pub target: String,
+    pub payload: serde_json::Value,
+}
+
+/// Audit log query, entries are returned newest first
+///
+/// All filters are optional and matched exactly, `since` and `until` bound entry timestamp.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub organization_id: Option<Uuid>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// entries with lower id are returned, `next` of previous page
+    pub before: Option<i64>,
+    pub limit: Option<u64>,
+}
+
+impl AuditFilter {
+    pub fn page_size(&self) -> u64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntry>,
+    /// cursor of next page, if there can be more entries
+    pub next: Option<i64>,
+}
+
+/// operations with secret config fields stripped, configs, unknown to registry, are hidden
+pub(super) fn redact_operations(
+    config_registry: &ConfigRegistry,
+    operations: &[WorkspaceOperation],
+) -> Vec<WorkspaceOperation> {
+    let mut operations = operations.to_vec();
+    operations.iter_mut().for_each(|op| {
+        if op.strip_secrets(config_registry).is_err() {
+            if let Err(e) = op.hide_restricted_config(config_registry) {
+                tracing::error!("{e}");
+            }
+        }
+    });
+    operations
+}
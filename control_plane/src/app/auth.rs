@@ -0,0 +1,121 @@
+//! Users, UI sessions and API tokens
+//!
+//! Passwords are stored as argon2 hashes.
+//! Session and API token secrets are random and stored as sha256 hashes, so database contents
+//! can't be used to authenticate.
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use uuid::Uuid;
+
+use super::{AppError, Result};
+
+/// name of session cookie, set on login
+pub const SESSION_COOKIE: &str = "mycelial_session";
+
+/// session lifetime in seconds
+pub const SESSION_TTL: i64 = 7 * 24 * 60 * 60;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct User {
+    pub id: Uuid,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    /// instance admin, see [`super::access`]
+    pub is_admin: bool,
+    /// source address of authenticated request, not stored
+    #[serde(skip)]
+    pub address: Option<IpAddr>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Newly created API token, secret is returned only once
+#[derive(Debug, Serialize)]
+pub struct NewApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+/// random secret for sessions and API tokens
+pub fn generate_secret() -> String {
+    rand::random::<[u8; 32]>()
+        .into_iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+pub fn hash_secret(secret: &str) -> String {
+    sha2::Sha256::digest(secret.as_bytes())
+        .into_iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// emails are compared case-insensitively
+pub fn normalize_email(email: &str) -> Result<String> {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((user, domain)) if !user.is_empty() && !domain.is_empty() => Ok(email),
+        _ => Err(AppError::bad_request(anyhow::anyhow!(
+            "invalid email '{email}'"
+        ))),
+    }
+}
+
+pub fn validate_password(password: &str) -> Result<()> {
+    match password.chars().count() >= MIN_PASSWORD_LENGTH {
+        true => Ok(()),
+        false => Err(AppError::bad_request(anyhow::anyhow!(
+            "password should be at least {MIN_PASSWORD_LENGTH} characters long"
+        ))),
+    }
+}
+
+// argon2 is cpu intensive, so hashing is done on blocking thread pool
+
+pub async fn hash_password(password: String) -> Result<String> {
+    let hash = tokio::task::spawn_blocking(move || {
+        let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())?;
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await?
+    .map_err(|e| anyhow::anyhow!("failed to hash password: {e}"))?;
+    Ok(hash)
+}
+
+pub async fn verify_password(password: String, hash: String) -> Result<bool> {
+    let verified = tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash)?;
+        Ok::<_, argon2::password_hash::Error>(
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok(),
+        )
+    })
+    .await?
+    .map_err(|e| anyhow::anyhow!("failed to verify password: {e}"))?;
+    Ok(verified)
+}
@@ -6,6 +6,10 @@ use tokio::sync::{
     oneshot::{channel as oneshot_channel, Sender as OneshotSender},
 };
 use uuid::Uuid;
+
+/// node errors of daemon graph, keyed by node id
+pub type NodeErrors = BTreeMap<Uuid, String>;
+
 pub struct DaemonTracker {}
 
 impl DaemonTracker {
@@ -22,6 +26,8 @@ impl DaemonTracker {
 
     async fn enter_loop(&self, mut rx: Receiver<DaemonTrackerMessage>) -> Result<()> {
         let mut daemons = BTreeMap::new();
+        // errors of nodes, skipped in last graph, sent to daemon
+        let mut node_errors = BTreeMap::<Uuid, NodeErrors>::new();
         while let Some(message) = rx.recv().await {
             match message {
                 DaemonTrackerMessage::DaemonConnected { id, reply_to } => {
@@ -34,11 +40,23 @@ impl DaemonTracker {
                 DaemonTrackerMessage::DaemonDisconnected { id, reply_to } => {
                     tracing::info!("daemon disconnected: {id}");
                     daemons.remove(&id);
+                    node_errors.remove(&id);
                     reply_to.send(()).ok();
                 }
                 DaemonTrackerMessage::ListDaemons { reply_to } => {
                     reply_to.send(daemons.keys().copied().collect()).ok();
                 }
+                DaemonTrackerMessage::SetNodeErrors { id, errors } => match errors.is_empty() {
+                    true => {
+                        node_errors.remove(&id);
+                    }
+                    false => {
+                        node_errors.insert(id, errors);
+                    }
+                },
+                DaemonTrackerMessage::ListNodeErrors { reply_to } => {
+                    reply_to.send(node_errors.clone()).ok();
+                }
                 DaemonTrackerMessage::NotifyGraphUpdate => {
                     for daemon in daemons.values() {
                         daemon.notify_graph_update();
@@ -49,6 +67,11 @@ impl DaemonTracker {
                         daemon_handle.shutdown_connection()
                     }
                 }
+                DaemonTrackerMessage::NotifyDaemon { id, message } => {
+                    if let Some(daemon_handle) = daemons.get(&id) {
+                        daemon_handle.notify(message)
+                    }
+                }
             }
         }
         Err(anyhow::anyhow!("channel closed"))?
@@ -58,6 +81,10 @@ impl DaemonTracker {
 pub enum DaemonMessage {
     NotifyGraphUpdate,
     ShutdownConnection,
+    /// new messages were relayed to daemon nodes
+    RelayAvailable,
+    /// message, relayed by daemon, was acked by destination daemon
+    RelayAck(Uuid),
 }
 
 pub struct DaemonHandle {
@@ -72,6 +99,10 @@ impl DaemonHandle {
     pub fn shutdown_connection(&self) {
         self.tx.send(DaemonMessage::ShutdownConnection).ok();
     }
+
+    pub fn notify(&self, message: DaemonMessage) {
+        self.tx.send(message).ok();
+    }
 }
 
 enum DaemonTrackerMessage {
@@ -86,8 +117,19 @@ enum DaemonTrackerMessage {
     ListDaemons {
         reply_to: OneshotSender<Vec<Uuid>>,
     },
+    SetNodeErrors {
+        id: Uuid,
+        errors: NodeErrors,
+    },
+    ListNodeErrors {
+        reply_to: OneshotSender<BTreeMap<Uuid, NodeErrors>>,
+    },
     NotifyGraphUpdate,
     ShutdownDaemon(Uuid),
+    NotifyDaemon {
+        id: Uuid,
+        message: DaemonMessage,
+    },
 }
 
 pub struct DaemonTrackerHandle {
@@ -116,6 +158,22 @@ impl DaemonTrackerHandle {
         Ok(rx.await?)
     }
 
+    /// record errors of nodes, skipped in graph of daemon `id`
+    pub async fn set_node_errors(&self, id: Uuid, errors: NodeErrors) -> Result<()> {
+        self.tx
+            .send(DaemonTrackerMessage::SetNodeErrors { id, errors })
+            .await?;
+        Ok(())
+    }
+
+    /// node errors of connected daemons, keyed by daemon id
+    pub async fn list_node_errors(&self) -> Result<BTreeMap<Uuid, NodeErrors>> {
+        let (reply_to, rx) = oneshot_channel();
+        let message = DaemonTrackerMessage::ListNodeErrors { reply_to };
+        self.tx.send(message).await?;
+        Ok(rx.await?)
+    }
+
     pub async fn notify_graph_update(&self) -> Result<()> {
         self.tx
             .send(DaemonTrackerMessage::NotifyGraphUpdate)
@@ -129,4 +187,12 @@ impl DaemonTrackerHandle {
             .await?;
         Ok(())
     }
+
+    /// notification is dropped if daemon is offline
+    pub async fn notify_daemon(&self, id: Uuid, message: DaemonMessage) -> Result<()> {
+        self.tx
+            .send(DaemonTrackerMessage::NotifyDaemon { id, message })
+            .await?;
+        Ok(())
+    }
 }
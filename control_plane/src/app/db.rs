@@ -1,20 +1,22 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     ops::{Deref, DerefMut},
 };
 
+use crate::app::encryption::{self, MasterKey};
 use crate::app::migration;
 use crate::{
     app::tables::*,
     app::{AppError, Result},
 };
+use base64::Engine as _;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use config_registry::ConfigRegistry;
 use derive_trait::derive_trait;
 use futures::future::BoxFuture;
 use sea_query::{
-    Condition, Expr, MysqlQueryBuilder, Order, PostgresQueryBuilder, Query, QueryBuilder,
+    Condition, Expr, Func, MysqlQueryBuilder, Order, PostgresQueryBuilder, Query, QueryBuilder,
     SchemaBuilder, SqliteQueryBuilder,
 };
 use sea_query_binder::{SqlxBinder, SqlxValues};
@@ -25,12 +27,16 @@ use sqlx::{
 use uuid::Uuid;
 
 use super::{
-    Daemon, DaemonGraph, DaemonNode, DaemonToken, Edge, Workspace, WorkspaceGraph, WorkspaceNode,
-    WorkspaceOperation, WorkspaceUpdate,
+    access::{Organization, OrganizationMember, OrganizationRole, Role, WorkspaceMember},
+    audit::{AuditEntry, AuditFilter},
+    auth::{ApiToken, User},
+    check_compatibility, validate_edge, Daemon, DaemonGraph, DaemonNode, DaemonToken, Edge,
+    RelayMessage, RevokedDaemonCertificate, MAX_RELAY_MESSAGE_SIZE, SectionVersion, Variable, Workspace, WorkspaceGraph,
+    WorkspaceKey, WorkspaceNode, WorkspaceOperation, WorkspaceRevision, WorkspaceUpdate,
 };
 
 // FIXME: pool options and configurable pool size
-pub async fn new(database_url: &str) -> Result<Box<dyn DbTrait>> {
+pub async fn new(database_url: &str, master_key: Option<MasterKey>) -> Result<Box<dyn DbTrait>> {
     let mut database_url = url::Url::parse(database_url)?;
     let mut params: HashMap<Cow<str>, Cow<str>> = database_url.query_pairs().collect();
     let db: Box<dyn DbTrait> = match database_url.scheme() {
@@ -49,6 +55,7 @@ pub async fn new(database_url: &str) -> Result<Box<dyn DbTrait>> {
                     database_url.as_ref(),
                     Box::new(SqliteQueryBuilder),
                     Box::new(SqliteQueryBuilder),
+                    master_key,
                 )
                 .await?,
             )
@@ -58,6 +65,7 @@ pub async fn new(database_url: &str) -> Result<Box<dyn DbTrait>> {
                 database_url.as_ref(),
                 Box::new(PostgresQueryBuilder),
                 Box::new(PostgresQueryBuilder),
+                master_key,
             )
             .await?,
         ),
@@ -66,6 +74,7 @@ pub async fn new(database_url: &str) -> Result<Box<dyn DbTrait>> {
                 database_url.as_ref(),
                 Box::new(MysqlQueryBuilder),
                 Box::new(MysqlQueryBuilder),
+                master_key,
             )
             .await?,
         ),
@@ -77,6 +86,8 @@ pub struct Db<D: Database> {
     pool: Pool<D>,
     query_builder: Box<dyn QueryBuilder + Send + Sync>,
     schema_builder: Box<dyn SchemaBuilder + Send + Sync>,
+    // master key for envelope encryption of secret config fields
+    master_key: Option<MasterKey>,
 }
 
 impl<D: Database> Db<D> {
@@ -84,15 +95,153 @@ impl<D: Database> Db<D> {
         url: &str,
         query_builder: Box<dyn QueryBuilder + Send + Sync>,
         schema_builder: Box<dyn SchemaBuilder + Send + Sync>,
+        master_key: Option<MasterKey>,
     ) -> Result<Self> {
         Ok(Self {
             pool: Pool::<D>::connect(url).await?,
             query_builder,
             schema_builder,
+            master_key,
         })
     }
 }
 
+// substitute variables in config of daemon node, substituted config is validated
+// configs, unknown to registry, are left as is
+fn substitute_variables(
+    config_registry: &ConfigRegistry,
+    node_id: Uuid,
+    config: &mut Box<dyn config_registry::Config>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    if config_registry.build_config(config.name()).is_err() {
+        return Ok(());
+    }
+    let mut built = config_registry
+        .deserialize_config(&**config)
+        .map_err(|e| anyhow::anyhow!("failed to build config '{}': {e}", config.name()))?;
+    let substituted = config::template::substitute_config(&mut *built, lookup)
+        .map_err(|e| AppError::invalid_node_config(node_id, anyhow::anyhow!("{e}")))?;
+    if substituted {
+        built
+            .validate()
+            .map_err(|e| AppError::invalid_node_config(node_id, e))?;
+        *config = built;
+    }
+    Ok(())
+}
+
+// decrypt node config, substitute variables and build config through registry
+fn resolve_daemon_node(
+    config_registry: &ConfigRegistry,
+    master_key: Option<&MasterKey>,
+    node_id: Uuid,
+    config: serde_json::Value,
+    restarts: i64,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<DaemonNode> {
+    let mut config: Box<dyn config_registry::Config> = serde_json::from_value(config)?;
+    encryption::decrypt_config(config_registry, node_id, &mut config, master_key)?;
+    substitute_variables(config_registry, node_id, &mut config, lookup)?;
+    let mut node = DaemonNode {
+        id: node_id,
+        config,
+        restarts,
+    };
+    node.build_config(config_registry)?;
+    Ok(node)
+}
+
+// upgrade stored node config to current config version, returns None if config is up to date or unknown to registry
+//
+// encrypted values are bound to field names, which migrations can rename, so config is decrypted before upgrade
+// and encrypted again after
+fn upgrade_node_config(
+    config_registry: &ConfigRegistry,
+    master_key: Option<&MasterKey>,
+    node_id: Uuid,
+    config: serde_json::Value,
+) -> Result<Option<Box<dyn config_registry::Config>>> {
+    let mut config: Box<dyn config_registry::Config> = serde_json::from_value(config)?;
+    let current = match config_registry.build_config(config.name()) {
+        Ok(current) => current,
+        Err(_) => return Ok(None),
+    };
+    if config.version() == current.version() {
+        return Ok(None);
+    }
+    if config.version() > current.version() {
+        tracing::warn!(
+            "config '{}' of node {node_id} has version {}, but only versions up to {} are supported",
+            config.name(),
+            config.version(),
+            current.version()
+        );
+        return Ok(None);
+    }
+    encryption::decrypt_config(config_registry, node_id, &mut config, master_key)?;
+    let mut config = config_registry
+        .deserialize_config(&*config)
+        .map_err(|e| anyhow::anyhow!("failed to upgrade config of node {node_id}: {e}"))?;
+    encryption::encrypt_config(config_registry, node_id, &mut config, master_key)?;
+    Ok(Some(config))
+}
+
+// escape literal `${` in template fields of stored node config, returns None if nothing was escaped
+//
+// configs, unknown to registry, are left as is
+fn escape_node_config(
+    config_registry: &ConfigRegistry,
+    master_key: Option<&MasterKey>,
+    node_id: Uuid,
+    config: serde_json::Value,
+) -> Result<Option<Box<dyn config_registry::Config>>> {
+    let mut config: Box<dyn config_registry::Config> = serde_json::from_value(config)?;
+    if config_registry.build_config(config.name()).is_err() {
+        return Ok(None);
+    }
+    encryption::decrypt_config(config_registry, node_id, &mut config, master_key)?;
+    let mut config = config_registry
+        .deserialize_config(&*config)
+        .map_err(|e| anyhow::anyhow!("failed to build config of node {node_id}: {e}"))?;
+    let escaped = config::template::escape_config(&mut *config)
+        .map_err(|e| anyhow::anyhow!("failed to escape config of node {node_id}: {e}"))?;
+    if !escaped {
+        return Ok(None);
+    }
+    encryption::encrypt_config(config_registry, node_id, &mut config, master_key)?;
+    Ok(Some(config))
+}
+
+// re-encrypt secret fields of configs, carried by revision operations, returns true if any config changed
+fn reencrypt_operations(
+    config_registry: &ConfigRegistry,
+    operations: &mut [WorkspaceOperation],
+    old_key: Option<&MasterKey>,
+    new_key: Option<&MasterKey>,
+) -> Result<bool> {
+    let mut changed = false;
+    for operation in operations.iter_mut() {
+        if let WorkspaceOperation::AddNode { id, config, .. }
+        | WorkspaceOperation::UpdateNodeConfig { id, config } = operation
+        {
+            changed |= encryption::reencrypt_config(config_registry, *id, config, old_key, new_key)?;
+        }
+    }
+    Ok(changed)
+}
+
+// workspace names are unique within organization
+fn workspace_condition(organization_id: Option<Uuid>, name: &str) -> Condition {
+    let organization = match organization_id {
+        Some(organization_id) => Expr::col(Workspaces::OrganizationId).eq(organization_id),
+        None => Expr::col(Workspaces::OrganizationId).is_null(),
+    };
+    Condition::all()
+        .add(Expr::col(Workspaces::Name).eq(name))
+        .add(organization)
+}
+
 // automatically derives new trait with Send + Sync bounds
 // trait funcs are copied from impl block
 #[derive_trait(Send + Sync)]
@@ -100,6 +249,7 @@ impl<D> DbTrait for Db<D>
 where
     D: Database,
     // Types, that Database should support
+    for<'e> bool: sqlx::Type<D> + sqlx::Encode<'e, D> + sqlx::Decode<'e, D>,
     for<'e> i16: sqlx::Type<D> + sqlx::Encode<'e, D> + sqlx::Decode<'e, D>,
     for<'e> i32: sqlx::Type<D> + sqlx::Encode<'e, D> + sqlx::Decode<'e, D>,
     for<'e> i64: sqlx::Type<D> + sqlx::Encode<'e, D> + sqlx::Decode<'e, D>,
@@ -142,26 +292,78 @@ where
     }
 
     // workspaces API
-    fn create_workspace<'a>(&'a self, workspace: &'a Workspace) -> BoxFuture<'a, Result<()>> {
-        Box::pin(async {
+    // workspace is created with creator as workspace admin
+    fn create_workspace<'a>(
+        &'a self,
+        workspace: &'a Workspace,
+        user_id: Uuid,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let select_workspace_id = || {
+                Query::select()
+                    .columns([Workspaces::Id])
+                    .from(Workspaces::Table)
+                    .cond_where(workspace_condition(
+                        workspace.organization_id,
+                        &workspace.name,
+                    ))
+                    .build_any_sqlx(&*self.query_builder)
+            };
+            let (query, values) = select_workspace_id();
+            if sqlx::query_with(&query, values)
+                .fetch_optional(&mut *transaction)
+                .await?
+                .is_some()
+            {
+                Err(AppError::bad_request(anyhow::anyhow!(
+                    "workspace '{}' already exists",
+                    workspace.name
+                )))?
+            }
             let created_at = chrono::Utc::now();
             let (query, values) = Query::insert()
                 .columns([
-                    // FIXME: should be unique for (name, user_id) pair
                     Workspaces::Name,
-                    // FIXME: it's empty now
+                    // creator of workspace
                     Workspaces::UserId,
                     Workspaces::CreatedAt,
+                    Workspaces::OrganizationId,
                 ])
                 .into_table(Workspaces::Table)
                 .values_panic([
                     workspace.name.as_str().into(),
-                    // FIXME: user_id is empty
-                    "".into(),
+                    user_id.to_string().into(),
                     created_at.into(),
+                    workspace.organization_id.into(),
                 ])
                 .build_any_sqlx(&*self.query_builder);
-            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = select_workspace_id();
+            let workspace_id: i64 = sqlx::query_with(&query, values)
+                .fetch_one(&mut *transaction)
+                .await?
+                .get(0);
+            let (query, values) = Query::insert()
+                .into_table(WorkspaceMembers::Table)
+                .columns([
+                    WorkspaceMembers::WorkspaceId,
+                    WorkspaceMembers::UserId,
+                    WorkspaceMembers::Role,
+                ])
+                .values_panic([
+                    workspace_id.into(),
+                    user_id.into(),
+                    Role::Admin.as_str().into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
             Ok(())
         })
     }
@@ -169,9 +371,12 @@ where
     fn read_workspaces(&self) -> BoxFuture<'_, Result<Vec<Workspace>>> {
         Box::pin(async {
             let (query, values) = Query::select()
-                .columns([Workspaces::Name, Workspaces::CreatedAt])
+                .columns([
+                    Workspaces::Name,
+                    Workspaces::CreatedAt,
+                    Workspaces::OrganizationId,
+                ])
                 .from(Workspaces::Table)
-                .and_where(Expr::col(Workspaces::UserId).eq(""))
                 .order_by(Workspaces::CreatedAt, Order::Asc)
                 .build_any_sqlx(&*self.query_builder);
             let workspaces = sqlx::query_with(&query, values)
@@ -181,17 +386,18 @@ where
                 .map(|row| Workspace {
                     name: row.get(0),
                     created_at: Some(row.get(1)),
+                    organization_id: row.get(2),
                 })
                 .collect::<Vec<Workspace>>();
             Ok(workspaces)
         })
     }
 
-    fn delete_workspace<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+    fn delete_workspace<'a>(&'a self, workspace: &'a WorkspaceKey) -> BoxFuture<'a, Result<()>> {
         Box::pin(async move {
             let (query, values) = Query::delete()
                 .from_table(Workspaces::Table)
-                .and_where(Expr::col(Workspaces::Name).eq(name))
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
                 .build_any_sqlx(&*self.query_builder);
             sqlx::query_with(&query, values).execute(&self.pool).await?;
             Ok(())
@@ -201,13 +407,13 @@ where
     // Workspace api
     fn get_workspace<'a>(
         &'a self,
-        workspace_name: &'a str,
+        workspace: &'a WorkspaceKey,
     ) -> BoxFuture<'a, Result<WorkspaceGraph>> {
         Box::pin(async move {
             let (query, values) = Query::select()
                 .columns([Workspaces::Id])
                 .from(Workspaces::Table)
-                .and_where(Expr::col(Workspaces::Name).eq(workspace_name))
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
                 .build_any_sqlx(&*self.query_builder);
             let workspace_id: i64 = match sqlx::query_with(&query, values)
                 .fetch_optional(&self.pool)
@@ -215,7 +421,8 @@ where
             {
                 Some(row) => row.get(0),
                 None => Err(AppError::not_found(anyhow::anyhow!(
-                    "workspace '{workspace_name}"
+                    "workspace '{}'",
+                    workspace.name
                 )))?,
             };
 
@@ -227,6 +434,7 @@ where
                     Nodes::DaemonId,
                     Nodes::X,
                     Nodes::Y,
+                    Nodes::Paused,
                 ])
                 .from(Nodes::Table)
                 .and_where(Expr::col(Nodes::WorkspaceId).eq(workspace_id))
@@ -237,14 +445,16 @@ where
                 .into_iter()
                 .map(|row| {
                     let config = row.get::<Json<_>, _>(2).0;
-                    Ok(WorkspaceNode::new(
+                    let mut node = WorkspaceNode::new(
                         row.get(0),
                         row.get(1),
                         serde_json::from_value(config)?,
                         row.get(3),
                         row.get(4),
                         row.get(5),
-                    ))
+                    );
+                    node.paused = row.get(6);
+                    Ok(node)
                 })
                 .collect::<Result<Vec<_>>>()?;
 
@@ -272,24 +482,55 @@ where
         &'a self,
         config_registry: &'a ConfigRegistry,
         update: &'a WorkspaceUpdate,
+        author: Option<&'a str>,
     ) -> BoxFuture<'a, Result<()>> {
         Box::pin(async move {
             let conn = &mut *self.pool.acquire().await?;
             let mut transaction = conn.begin().await?;
             let workspace_name = update.name.as_str();
+            // workspace row is locked, since revisions are numbered per workspace
             let (query, values) = Query::select()
-                .columns([Workspaces::Id])
+                .columns([Workspaces::Id, Workspaces::OrganizationId])
                 .from(Workspaces::Table)
-                .and_where(Expr::col(Workspaces::Name).eq(workspace_name))
+                .cond_where(workspace_condition(update.organization_id, workspace_name))
+                .lock_exclusive()
                 .build_any_sqlx(&*self.query_builder);
-            let workspace_id = match sqlx::query_with(&query, values)
+            let (workspace_id, organization_id) = match sqlx::query_with(&query, values)
                 .fetch_optional(&mut *transaction)
                 .await?
             {
-                Some(row) => row.get::<i64, _>(0),
+                Some(row) => (row.get::<i64, _>(0), row.get::<Option<Uuid>, _>(1)),
                 None => Err(AppError::workspace_not_found(workspace_name))?,
             };
+            // operations can reference only nodes of updated workspace
+            let node_ids = update
+                .operations
+                .iter()
+                .flat_map(|op| op.node_ids())
+                .collect::<Vec<_>>();
+            let (query, values) = Query::select()
+                .columns([Nodes::Id, Nodes::WorkspaceId])
+                .from(Nodes::Table)
+                .and_where(Expr::col(Nodes::Id).is_in(node_ids))
+                .build_any_sqlx(&*self.query_builder);
+            for row in sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?
+            {
+                if row.get::<i64, _>(1) != workspace_id {
+                    let id: Uuid = row.get(0);
+                    Err(AppError::not_found(anyhow::anyhow!(
+                        "node {id} in workspace '{workspace_name}'"
+                    )))?
+                }
+            }
+            // operations are recorded with encrypted configs, as they are stored in nodes
+            let mut applied = Vec::with_capacity(update.operations.len());
+            // operations, which revert each applied operation
+            let mut inverse = Vec::with_capacity(update.operations.len());
             for op in update.operations.iter() {
+                let mut applied_op = op.clone();
+                let mut inverse_ops = vec![];
                 let (query, values) = match *op {
                     WorkspaceOperation::AddNode {
                         id,
@@ -297,7 +538,16 @@ where
                         y,
                         ref config,
                     } => {
-                        let config_json = serde_json::to_string(config)?;
+                        let mut config = config.clone();
+                        encryption::encrypt_config(
+                            config_registry,
+                            id,
+                            &mut config,
+                            self.master_key.as_ref(),
+                        )?;
+                        let config_json = serde_json::to_string(&*config)?;
+                        applied_op = WorkspaceOperation::AddNode { id, x, y, config };
+                        inverse_ops.push(WorkspaceOperation::RemoveNode(id));
                         Query::insert()
                             .columns([
                                 Nodes::Id,
@@ -316,24 +566,149 @@ where
                             ])
                             .build_any_sqlx(&*self.query_builder)
                     }
-                    WorkspaceOperation::RemoveNode(uuid) => Query::delete()
-                        .from_table(Nodes::Table)
-                        .and_where(Expr::col(Nodes::Id).eq(uuid))
-                        .build_any_sqlx(&*self.query_builder),
-                    WorkspaceOperation::UpdateNodePosition { uuid, x, y } => Query::update()
-                        .table(Nodes::Table)
-                        .values([(Nodes::X, x.into()), (Nodes::Y, y.into())])
-                        .and_where(Expr::col(Nodes::Id).eq(uuid))
-                        .build_any_sqlx(&*self.query_builder),
-                    WorkspaceOperation::AddEdge { from, to } => Query::insert()
-                        .columns([Edges::FromId, Edges::ToId])
-                        .into_table(Edges::Table)
-                        .values_panic([from.into(), to.into()])
-                        .build_any_sqlx(&*self.query_builder),
-                    WorkspaceOperation::RemoveEdge { from } => Query::delete()
-                        .from_table(Edges::Table)
-                        .and_where(Expr::col(Edges::FromId).eq(from))
-                        .build_any_sqlx(&*self.query_builder),
+                    WorkspaceOperation::RemoveNode(uuid) => {
+                        let (query, values) = Query::select()
+                            .columns([Nodes::X, Nodes::Y, Nodes::Config, Nodes::DaemonId])
+                            .from(Nodes::Table)
+                            .and_where(Expr::col(Nodes::Id).eq(uuid))
+                            .build_any_sqlx(&*self.query_builder);
+                        if let Some(row) = sqlx::query_with(&query, values)
+                            .fetch_optional(&mut *transaction)
+                            .await?
+                        {
+                            let config = row.get::<Json<_>, _>(2).0;
+                            inverse_ops.push(WorkspaceOperation::AddNode {
+                                id: uuid,
+                                x: row.get(0),
+                                y: row.get(1),
+                                config: serde_json::from_value(config)?,
+                            });
+                            if let Some(daemon_id) = row.get::<Option<Uuid>, _>(3) {
+                                inverse_ops.push(WorkspaceOperation::AssignNodeToDaemon {
+                                    node_id: uuid,
+                                    daemon_id,
+                                });
+                            }
+                        }
+                        // edges are removed together with node
+                        let (query, values) = Query::select()
+                            .columns([Edges::FromId, Edges::ToId])
+                            .from(Edges::Table)
+                            .cond_where(
+                                Condition::any()
+                                    .add(Expr::col(Edges::FromId).eq(uuid))
+                                    .add(Expr::col(Edges::ToId).eq(uuid)),
+                            )
+                            .build_any_sqlx(&*self.query_builder);
+                        for row in sqlx::query_with(&query, values)
+                            .fetch_all(&mut *transaction)
+                            .await?
+                        {
+                            inverse_ops.push(WorkspaceOperation::AddEdge {
+                                from: row.get(0),
+                                to: row.get(1),
+                            });
+                        }
+                        Query::delete()
+                            .from_table(Nodes::Table)
+                            .and_where(Expr::col(Nodes::Id).eq(uuid))
+                            .build_any_sqlx(&*self.query_builder)
+                    }
+                    WorkspaceOperation::UpdateNodePosition { uuid, x, y } => {
+                        let (query, values) = Query::select()
+                            .columns([Nodes::X, Nodes::Y])
+                            .from(Nodes::Table)
+                            .and_where(Expr::col(Nodes::Id).eq(uuid))
+                            .build_any_sqlx(&*self.query_builder);
+                        if let Some(row) = sqlx::query_with(&query, values)
+                            .fetch_optional(&mut *transaction)
+                            .await?
+                        {
+                            inverse_ops.push(WorkspaceOperation::UpdateNodePosition {
+                                uuid,
+                                x: row.get(0),
+                                y: row.get(1),
+                            });
+                        }
+                        Query::update()
+                            .table(Nodes::Table)
+                            .values([(Nodes::X, x.into()), (Nodes::Y, y.into())])
+                            .and_where(Expr::col(Nodes::Id).eq(uuid))
+                            .build_any_sqlx(&*self.query_builder)
+                    }
+                    WorkspaceOperation::AddEdge { from, to } => {
+                        // nodes can be added earlier in same update, so configs are read in transaction
+                        let (query, values) = Query::select()
+                            .columns([Nodes::Id, Nodes::Config])
+                            .from(Nodes::Table)
+                            .and_where(Expr::col(Nodes::Id).is_in([from, to]))
+                            .build_any_sqlx(&*self.query_builder);
+                        let mut config_names = HashMap::new();
+                        for row in sqlx::query_with(&query, values)
+                            .fetch_all(&mut *transaction)
+                            .await?
+                        {
+                            let config = row.get::<Json<_>, _>(1).0;
+                            let config: Box<dyn config_registry::Config> =
+                                serde_json::from_value(config)?;
+                            config_names.insert(row.get::<Uuid, _>(0), config.name().to_string());
+                        }
+                        if let (Some(from_config), Some(to_config)) =
+                            (config_names.get(&from), config_names.get(&to))
+                        {
+                            validate_edge(config_registry, (from, from_config), (to, to_config))?;
+                        }
+                        // cycle would deadlock section channels on daemon
+                        let (query, values) = Query::select()
+                            .columns([Edges::FromId, Edges::ToId])
+                            .from(Edges::Table)
+                            .and_where(
+                                Expr::col(Edges::FromId).in_subquery(
+                                    Query::select()
+                                        .column(Nodes::Id)
+                                        .from(Nodes::Table)
+                                        .and_where(Expr::col(Nodes::WorkspaceId).eq(workspace_id))
+                                        .take(),
+                                ),
+                            )
+                            .build_any_sqlx(&*self.query_builder);
+                        let mut graph = graph::Graph::<Uuid, ()>::new();
+                        for row in sqlx::query_with(&query, values)
+                            .fetch_all(&mut *transaction)
+                            .await?
+                        {
+                            graph.add_edge_unchecked(row.get(0), row.get(1));
+                        }
+                        if graph.creates_cycle(from, to) {
+                            Err(AppError::cyclic_edge(from, to))?
+                        }
+                        inverse_ops.push(WorkspaceOperation::RemoveEdge { from });
+                        Query::insert()
+                            .columns([Edges::FromId, Edges::ToId])
+                            .into_table(Edges::Table)
+                            .values_panic([from.into(), to.into()])
+                            .build_any_sqlx(&*self.query_builder)
+                    }
+                    WorkspaceOperation::RemoveEdge { from } => {
+                        let (query, values) = Query::select()
+                            .columns([Edges::ToId])
+                            .from(Edges::Table)
+                            .and_where(Expr::col(Edges::FromId).eq(from))
+                            .build_any_sqlx(&*self.query_builder);
+                        if let Some(row) = sqlx::query_with(&query, values)
+                            .fetch_optional(&mut *transaction)
+                            .await?
+                        {
+                            inverse_ops.push(WorkspaceOperation::AddEdge {
+                                from,
+                                to: row.get(0),
+                            });
+                        }
+                        Query::delete()
+                            .from_table(Edges::Table)
+                            .and_where(Expr::col(Edges::FromId).eq(from))
+                            .build_any_sqlx(&*self.query_builder)
+                    }
                     WorkspaceOperation::UpdateNodeConfig { id, ref config } => {
                         // UI sends partial config updates since UI is not able to send full updates due to stripped secret
                         // this function needs to extract old config value from database and update only specific fields
@@ -350,8 +725,18 @@ where
                         {
                             Some(row) => {
                                 let stored_config = row.get::<Json<_>, _>(0).0;
-                                let stored_config: Box<dyn config_registry::Config> =
+                                let mut stored_config: Box<dyn config_registry::Config> =
                                     serde_json::from_value(stored_config)?;
+                                inverse_ops.push(WorkspaceOperation::UpdateNodeConfig {
+                                    id,
+                                    config: stored_config.clone(),
+                                });
+                                encryption::decrypt_config(
+                                    config_registry,
+                                    id,
+                                    &mut stored_config,
+                                    self.master_key.as_ref(),
+                                )?;
                                 // build real config to properly type check incoming raw config
                                 let mut stored_config = config_registry
                                     .deserialize_config(&*stored_config)
@@ -370,6 +755,17 @@ where
                                             stored_config.name()
                                         )
                                     })?;
+                                // validate merged config before persisting
+                                // fields with variable references are validated after substitution
+                                config::template::validate(&*stored_config).map_err(|e| {
+                                    AppError::invalid_config_fields(stored_config.name(), e)
+                                })?;
+                                encryption::encrypt_config(
+                                    config_registry,
+                                    id,
+                                    &mut stored_config,
+                                    self.master_key.as_ref(),
+                                )?;
                                 stored_config
                             }
                             None => {
@@ -378,6 +774,11 @@ where
                             }
                         };
                         let json = serde_json::to_string(&*updated_config)?;
+                        // full config is recorded, so revision doesn't depend on previous state
+                        applied_op = WorkspaceOperation::UpdateNodeConfig {
+                            id,
+                            config: updated_config,
+                        };
                         Query::update()
                             .table(Nodes::Table)
                             .values([(Nodes::Config, json.into())])
@@ -385,27 +786,222 @@ where
                             .build_any_sqlx(&*self.query_builder)
                     }
                     WorkspaceOperation::AssignNodeToDaemon { node_id, daemon_id } => {
+                        let (query, values) = Query::select()
+                            .columns([Daemons::Version, Daemons::OrganizationId])
+                            .from(Daemons::Table)
+                            .and_where(Expr::col(Daemons::Id).eq(daemon_id))
+                            .build_any_sqlx(&*self.query_builder);
+                        let daemon = sqlx::query_with(&query, values)
+                            .fetch_optional(&mut *transaction)
+                            .await?
+                            .map(|row| {
+                                (
+                                    row.get::<Option<String>, _>(0),
+                                    row.get::<Option<Uuid>, _>(1),
+                                )
+                            });
+                        // daemons of other organizations are not visible
+                        if matches!(daemon, Some((_, daemon_organization_id)) if daemon_organization_id != organization_id)
+                        {
+                            Err(AppError::daemon_not_found(daemon_id))?
+                        }
+                        // daemons, which haven't reported capabilities yet, are not checked
+                        let reported = matches!(daemon, Some((Some(_), _)));
+                        let (query, values) = Query::select()
+                            .columns([Nodes::Config, Nodes::DaemonId])
+                            .from(Nodes::Table)
+                            .and_where(Expr::col(Nodes::Id).eq(node_id))
+                            .build_any_sqlx(&*self.query_builder);
+                        let row = sqlx::query_with(&query, values)
+                            .fetch_optional(&mut *transaction)
+                            .await?;
+                        if let Some(row) = row.as_ref() {
+                            inverse_ops.push(match row.get::<Option<Uuid>, _>(1) {
+                                Some(daemon_id) => {
+                                    WorkspaceOperation::AssignNodeToDaemon { node_id, daemon_id }
+                                }
+                                None => WorkspaceOperation::UnassignNodeFromDaemon { node_id },
+                            });
+                        }
+                        let config = match row {
+                            Some(row) if reported => Some(row.get::<Json<_>, _>(0).0),
+                            _ => None,
+                        };
+                        if let Some(config) = config {
+                            let config: Box<dyn config_registry::Config> =
+                                serde_json::from_value(config)?;
+                            let (query, values) = Query::select()
+                                .columns([DaemonSections::Name, DaemonSections::Version])
+                                .from(DaemonSections::Table)
+                                .and_where(Expr::col(DaemonSections::DaemonId).eq(daemon_id))
+                                .build_any_sqlx(&*self.query_builder);
+                            let sections = sqlx::query_with(&query, values)
+                                .fetch_all(&mut *transaction)
+                                .await?
+                                .into_iter()
+                                .map(|row| SectionVersion {
+                                    name: row.get(0),
+                                    version: row.get::<i32, _>(1) as u32,
+                                })
+                                .collect::<Vec<_>>();
+                            if let Some(reason) = check_compatibility(&*config, &sections) {
+                                Err(AppError::section_not_supported(node_id, daemon_id, &reason))?
+                            }
+                        }
                         Query::update()
                             .table(Nodes::Table)
                             .values([(Nodes::DaemonId, daemon_id.into())])
                             .and_where(Expr::col(Nodes::Id).eq(node_id))
                             .build_any_sqlx(&*self.query_builder)
                     }
-                    WorkspaceOperation::UnassignNodeFromDaemon { node_id } => Query::update()
+                    WorkspaceOperation::UnassignNodeFromDaemon { node_id } => {
+                        let (query, values) = Query::select()
+                            .columns([Nodes::DaemonId])
+                            .from(Nodes::Table)
+                            .and_where(Expr::col(Nodes::Id).eq(node_id))
+                            .build_any_sqlx(&*self.query_builder);
+                        if let Some(daemon_id) = sqlx::query_with(&query, values)
+                            .fetch_optional(&mut *transaction)
+                            .await?
+                            .and_then(|row| row.get::<Option<Uuid>, _>(0))
+                        {
+                            inverse_ops.push(WorkspaceOperation::AssignNodeToDaemon {
+                                node_id,
+                                daemon_id,
+                            });
+                        }
+                        Query::update()
+                            .table(Nodes::Table)
+                            .values([(Nodes::DaemonId, Option::<Uuid>::None.into())])
+                            .and_where(Expr::col(Nodes::Id).eq(node_id))
+                            .build_any_sqlx(&*self.query_builder)
+                    }
+                    WorkspaceOperation::PauseNode(id) | WorkspaceOperation::ResumeNode(id) => {
+                        let paused = matches!(op, WorkspaceOperation::PauseNode(_));
+                        let (query, values) = Query::select()
+                            .columns([Nodes::Paused])
+                            .from(Nodes::Table)
+                            .and_where(Expr::col(Nodes::Id).eq(id))
+                            .build_any_sqlx(&*self.query_builder);
+                        if let Some(row) = sqlx::query_with(&query, values)
+                            .fetch_optional(&mut *transaction)
+                            .await?
+                        {
+                            inverse_ops.push(match row.get::<bool, _>(0) {
+                                true => WorkspaceOperation::PauseNode(id),
+                                false => WorkspaceOperation::ResumeNode(id),
+                            });
+                        }
+                        Query::update()
+                            .table(Nodes::Table)
+                            .values([(Nodes::Paused, paused.into())])
+                            .and_where(Expr::col(Nodes::Id).eq(id))
+                            .build_any_sqlx(&*self.query_builder)
+                    }
+                    // restart can't be reverted
+                    WorkspaceOperation::RestartNode(id) => Query::update()
                         .table(Nodes::Table)
-                        .values([(Nodes::DaemonId, Option::<Uuid>::None.into())])
-                        .and_where(Expr::col(Nodes::Id).eq(node_id))
+                        .value(Nodes::Restarts, Expr::col(Nodes::Restarts).add(1))
+                        .and_where(Expr::col(Nodes::Id).eq(id))
                         .build_any_sqlx(&*self.query_builder),
                 };
                 sqlx::query_with(&query, values)
                     .execute(&mut *transaction)
                     .await?;
+                applied.push(applied_op);
+                inverse.push(inverse_ops);
+            }
+            if !applied.is_empty() {
+                let (query, values) = Query::select()
+                    .expr(Func::max(Expr::col(WorkspaceRevisions::Revision)))
+                    .from(WorkspaceRevisions::Table)
+                    .and_where(Expr::col(WorkspaceRevisions::WorkspaceId).eq(workspace_id))
+                    .build_any_sqlx(&*self.query_builder);
+                let revision = sqlx::query_with(&query, values)
+                    .fetch_one(&mut *transaction)
+                    .await?
+                    .get::<Option<i64>, _>(0)
+                    .unwrap_or(0)
+                    + 1;
+                // operations are reverted in reverse order
+                let inverse = inverse.into_iter().rev().flatten().collect::<Vec<_>>();
+                let (query, values) = Query::insert()
+                    .into_table(WorkspaceRevisions::Table)
+                    .columns([
+                        WorkspaceRevisions::WorkspaceId,
+                        WorkspaceRevisions::Revision,
+                        WorkspaceRevisions::Operations,
+                        WorkspaceRevisions::Inverse,
+                        WorkspaceRevisions::Author,
+                        WorkspaceRevisions::CreatedAt,
+                    ])
+                    .values_panic([
+                        workspace_id.into(),
+                        revision.into(),
+                        serde_json::to_string(&applied)?.into(),
+                        serde_json::to_string(&inverse)?.into(),
+                        author.into(),
+                        Utc::now().into(),
+                    ])
+                    .build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
             }
             transaction.commit().await?;
             Ok(())
         })
     }
 
+    fn list_workspace_revisions<'a>(
+        &'a self,
+        workspace: &'a WorkspaceKey,
+    ) -> BoxFuture<'a, Result<Vec<WorkspaceRevision>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Workspaces::Id])
+                .from(Workspaces::Table)
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
+                .build_any_sqlx(&*self.query_builder);
+            let workspace_id: i64 = match sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+            {
+                Some(row) => row.get(0),
+                None => Err(AppError::not_found(anyhow::anyhow!(
+                    "workspace '{}'",
+                    workspace.name
+                )))?,
+            };
+            let (query, values) = Query::select()
+                .columns([
+                    WorkspaceRevisions::Revision,
+                    WorkspaceRevisions::Author,
+                    WorkspaceRevisions::CreatedAt,
+                    WorkspaceRevisions::Operations,
+                    WorkspaceRevisions::Inverse,
+                ])
+                .from(WorkspaceRevisions::Table)
+                .and_where(Expr::col(WorkspaceRevisions::WorkspaceId).eq(workspace_id))
+                .order_by(WorkspaceRevisions::Revision, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| {
+                    Ok(WorkspaceRevision {
+                        revision: row.get(0),
+                        author: row.get(1),
+                        created_at: row.get(2),
+                        operations: serde_json::from_value(row.get::<Json<_>, _>(3).0)?,
+                        inverse: serde_json::from_value(row.get::<Json<_>, _>(4).0)?,
+                    })
+                })
+                .collect()
+        })
+    }
+
     fn get_ca_cert_key(&self) -> BoxFuture<'_, Result<Option<(String, String)>>> {
         Box::pin(async move {
             let (query, values) = Query::select()
@@ -515,6 +1111,7 @@ where
                 secret,
                 issued_at,
                 used_at,
+                organization_id,
             } = token;
             let (query, values) = Query::insert()
                 .into_table(DaemonTokens::Table)
@@ -523,12 +1120,14 @@ where
                     DaemonTokens::Secret,
                     DaemonTokens::IssuedAt,
                     DaemonTokens::UsedAt,
+                    DaemonTokens::OrganizationId,
                 ])
                 .values_panic([
                     (*id).into(),
                     secret.into(),
                     (*issued_at).into(),
                     (*used_at).into(),
+                    (*organization_id).into(),
                 ])
                 .build_any_sqlx(&*self.query_builder);
             sqlx::query_with(&query, values).execute(&self.pool).await?;
@@ -544,6 +1143,7 @@ where
                     DaemonTokens::Secret,
                     DaemonTokens::IssuedAt,
                     DaemonTokens::UsedAt,
+                    DaemonTokens::OrganizationId,
                 ])
                 .from(DaemonTokens::Table)
                 .build_any_sqlx(&*self.query_builder);
@@ -556,6 +1156,7 @@ where
                     secret: row.get(1),
                     issued_at: row.get(2),
                     used_at: row.get(3),
+                    organization_id: row.get(4),
                 })
                 .collect();
             Ok(tokens)
@@ -583,6 +1184,7 @@ where
                     DaemonTokens::Secret,
                     DaemonTokens::IssuedAt,
                     DaemonTokens::UsedAt,
+                    DaemonTokens::OrganizationId,
                 ])
                 .from(DaemonTokens::Table)
                 .and_where(Expr::col(DaemonTokens::Id).eq(id))
@@ -596,6 +1198,7 @@ where
                     secret: row.get(1),
                     issued_at: row.get(2),
                     used_at: row.get(3),
+                    organization_id: row.get(4),
                 });
             let token = match token {
                 None => return Ok(None),
@@ -617,12 +1220,17 @@ where
         })
     }
 
-    fn add_daemon(&self, id: Uuid) -> BoxFuture<'_, Result<()>> {
+    // daemon belongs to organization of its join token
+    fn add_daemon(&self, id: Uuid, organization_id: Option<Uuid>) -> BoxFuture<'_, Result<()>> {
         Box::pin(async move {
             let (query, values) = Query::insert()
-                .columns([Daemons::Id, Daemons::JoinedAt])
+                .columns([Daemons::Id, Daemons::JoinedAt, Daemons::OrganizationId])
                 .into_table(Daemons::Table)
-                .values_panic([id.into(), Expr::current_timestamp().into()])
+                .values_panic([
+                    id.into(),
+                    Expr::current_timestamp().into(),
+                    organization_id.into(),
+                ])
                 .build_any_sqlx(&*self.query_builder);
             sqlx::query_with(&query, values).execute(&self.pool).await?;
             Ok(())
@@ -638,6 +1246,9 @@ where
                     Daemons::Address,
                     Daemons::LastOnline,
                     Daemons::JoinedAt,
+                    Daemons::Version,
+                    Daemons::OrganizationId,
+                    Daemons::RevokedAt,
                 ])
                 .from(Daemons::Table)
                 .and_where(Expr::col(Daemons::Id).eq(id))
@@ -651,6 +1262,9 @@ where
                     address: row.get(2),
                     last_seen: row.get(3),
                     joined_at: row.get(4),
+                    version: row.get(5),
+                    organization_id: row.get(6),
+                    revoked_at: row.get(7),
                     status: Default::default(),
                 }))
         })
@@ -658,44 +1272,257 @@ where
 
     fn delete_daemon(&self, id: Uuid) -> BoxFuture<'_, Result<()>> {
         Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::delete()
+                .from_table(DaemonVariables::Table)
+                .and_where(Expr::col(DaemonVariables::DaemonId).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = Query::delete()
+                .from_table(DaemonSections::Table)
+                .and_where(Expr::col(DaemonSections::DaemonId).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
             let (query, values) = Query::delete()
                 .from_table(Daemons::Table)
                 .and_where(Expr::col(Daemons::Id).eq(id))
                 .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            // certificates of deleted daemon are revoked, so daemon can't reconnect
+            let (query, values) = Query::update()
+                .table(DaemonCertificates::Table)
+                .value(DaemonCertificates::RevokedAt, Utc::now())
+                .and_where(Expr::col(DaemonCertificates::DaemonId).eq(id))
+                .and_where(Expr::col(DaemonCertificates::RevokedAt).is_null())
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    // record certificate, issued to daemon on join
+    fn add_daemon_certificate<'a>(
+        &'a self,
+        daemon_id: Uuid,
+        serial_number: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::insert()
+                .into_table(DaemonCertificates::Table)
+                .columns([
+                    DaemonCertificates::SerialNumber,
+                    DaemonCertificates::DaemonId,
+                    DaemonCertificates::IssuedAt,
+                ])
+                .values_panic([serial_number.into(), daemon_id.into(), Utc::now().into()])
+                .build_any_sqlx(&*self.query_builder);
             sqlx::query_with(&query, values).execute(&self.pool).await?;
             Ok(())
         })
     }
 
-    fn daemon_set_last_seen(
-        &self,
-        id: Uuid,
-        last_seen: DateTime<Utc>,
-    ) -> BoxFuture<'_, Result<()>> {
+    // check if certificate was issued to daemon and not revoked
+    fn is_daemon_certificate_valid<'a>(
+        &'a self,
+        daemon_id: Uuid,
+        serial_number: &'a str,
+    ) -> BoxFuture<'a, Result<bool>> {
         Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([DaemonCertificates::SerialNumber])
+                .from(DaemonCertificates::Table)
+                .and_where(Expr::col(DaemonCertificates::SerialNumber).eq(serial_number))
+                .and_where(Expr::col(DaemonCertificates::DaemonId).eq(daemon_id))
+                .and_where(Expr::col(DaemonCertificates::RevokedAt).is_null())
+                .build_any_sqlx(&*self.query_builder);
+            Ok(sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some())
+        })
+    }
+
+    // daemon is revoked along with its recorded certificates,
+    // so certificates, which were never recorded, are rejected too
+    fn revoke_daemon_certificates(&self, daemon_id: Uuid) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let revoked_at = Utc::now();
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
             let (query, values) = Query::update()
                 .table(Daemons::Table)
-                .values([(Daemons::LastOnline, last_seen.into())])
-                .and_where(Expr::col(Daemons::Id).eq(id))
+                .value(Daemons::RevokedAt, revoked_at)
+                .and_where(Expr::col(Daemons::Id).eq(daemon_id))
+                .and_where(Expr::col(Daemons::RevokedAt).is_null())
                 .build_any_sqlx(&*self.query_builder);
-            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = Query::update()
+                .table(DaemonCertificates::Table)
+                .value(DaemonCertificates::RevokedAt, revoked_at)
+                .and_where(Expr::col(DaemonCertificates::DaemonId).eq(daemon_id))
+                .and_where(Expr::col(DaemonCertificates::RevokedAt).is_null())
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
             Ok(())
         })
     }
 
-    fn list_daemons(&self) -> BoxFuture<'_, Result<Vec<Daemon>>> {
+    fn list_revoked_certificates(&self) -> BoxFuture<'_, Result<Vec<RevokedDaemonCertificate>>> {
         Box::pin(async move {
             let (query, values) = Query::select()
                 .columns([
-                    Daemons::Id,
-                    Daemons::DisplayName,
-                    Daemons::Address,
-                    Daemons::LastOnline,
-                    Daemons::JoinedAt,
+                    DaemonCertificates::SerialNumber,
+                    DaemonCertificates::RevokedAt,
                 ])
-                .from(Daemons::Table)
+                .from(DaemonCertificates::Table)
+                .and_where(Expr::col(DaemonCertificates::RevokedAt).is_not_null())
+                .order_by(DaemonCertificates::RevokedAt, Order::Asc)
                 .build_any_sqlx(&*self.query_builder);
-            let daemons = sqlx::query_with(&query, values)
+            Ok(sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| RevokedDaemonCertificate {
+                    serial_number: row.get(0),
+                    revoked_at: row.get(1),
+                })
+                .collect())
+        })
+    }
+
+    // replace stored daemon version and sections
+    fn set_daemon_capabilities<'a>(
+        &'a self,
+        id: Uuid,
+        version: &'a str,
+        sections: &'a [SectionVersion],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::update()
+                .table(Daemons::Table)
+                .values([(Daemons::Version, version.into())])
+                .and_where(Expr::col(Daemons::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = Query::delete()
+                .from_table(DaemonSections::Table)
+                .and_where(Expr::col(DaemonSections::DaemonId).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            if !sections.is_empty() {
+                let mut query = Query::insert();
+                query.into_table(DaemonSections::Table).columns([
+                    DaemonSections::DaemonId,
+                    DaemonSections::Name,
+                    DaemonSections::Version,
+                ]);
+                for section in sections {
+                    query.values_panic([
+                        id.into(),
+                        section.name.as_str().into(),
+                        (section.version as i32).into(),
+                    ]);
+                }
+                let (query, values) = query.build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    // sections of daemons, which reported their capabilities
+    fn list_daemon_sections(&self) -> BoxFuture<'_, Result<HashMap<Uuid, Vec<SectionVersion>>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Daemons::Id])
+                .from(Daemons::Table)
+                .and_where(Expr::col(Daemons::Version).is_not_null())
+                .build_any_sqlx(&*self.query_builder);
+            let mut daemon_sections = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| (row.get(0), vec![]))
+                .collect::<HashMap<Uuid, Vec<SectionVersion>>>();
+            let (query, values) = Query::select()
+                .columns([
+                    DaemonSections::DaemonId,
+                    DaemonSections::Name,
+                    DaemonSections::Version,
+                ])
+                .from(DaemonSections::Table)
+                .build_any_sqlx(&*self.query_builder);
+            for row in sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+            {
+                if let Some(sections) = daemon_sections.get_mut(&row.get::<Uuid, _>(0)) {
+                    sections.push(SectionVersion {
+                        name: row.get(1),
+                        version: row.get::<i32, _>(2) as u32,
+                    });
+                }
+            }
+            Ok(daemon_sections)
+        })
+    }
+
+    fn daemon_set_last_seen(
+        &self,
+        id: Uuid,
+        last_seen: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::update()
+                .table(Daemons::Table)
+                .values([(Daemons::LastOnline, last_seen.into())])
+                .and_where(Expr::col(Daemons::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    fn list_daemons(&self) -> BoxFuture<'_, Result<Vec<Daemon>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([
+                    Daemons::Id,
+                    Daemons::DisplayName,
+                    Daemons::Address,
+                    Daemons::LastOnline,
+                    Daemons::JoinedAt,
+                    Daemons::Version,
+                    Daemons::OrganizationId,
+                    Daemons::RevokedAt,
+                ])
+                .from(Daemons::Table)
+                .build_any_sqlx(&*self.query_builder);
+            let daemons = sqlx::query_with(&query, values)
                 .fetch_all(&self.pool)
                 .await?
                 .into_iter()
@@ -705,6 +1532,9 @@ where
                     address: row.get(2),
                     last_seen: row.get(3),
                     joined_at: row.get(4),
+                    version: row.get(5),
+                    organization_id: row.get(6),
+                    revoked_at: row.get(7),
                     status: Default::default(),
                 })
                 .collect();
@@ -712,25 +1542,180 @@ where
         })
     }
 
-    fn get_daemon_graph(&self, id: Uuid) -> BoxFuture<'_, Result<DaemonGraph>> {
+    fn get_daemon_graph<'a>(
+        &'a self,
+        config_registry: &'a ConfigRegistry,
+        id: Uuid,
+    ) -> BoxFuture<'a, Result<(DaemonGraph, BTreeMap<Uuid, String>)>> {
         Box::pin(async move {
+            // daemon variables, `id` and `name` are taken from daemon itself
             let (query, values) = Query::select()
-                .columns([Nodes::Id, Nodes::Config])
+                .columns([Daemons::DisplayName])
+                .from(Daemons::Table)
+                .and_where(Expr::col(Daemons::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            let daemon_name: Option<String> = sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+                .and_then(|row| row.get(0));
+            let (query, values) = Query::select()
+                .columns([DaemonVariables::Name, DaemonVariables::Value])
+                .from(DaemonVariables::Table)
+                .and_where(Expr::col(DaemonVariables::DaemonId).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            // variables, which can't be decrypted, are left undefined, so nodes using them are skipped
+            let decrypt = |aad: &str, name: &str, value: &str| {
+                encryption::decrypt_value(self.master_key.as_ref(), aad, value)
+                    .map_err(|e| {
+                        tracing::error!("failed to decrypt variable '{name}': {:#}", e.err);
+                    })
+                    .ok()
+            };
+            let mut daemon_variables = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .filter_map(|row| {
+                    let name: String = row.get(0);
+                    let aad = encryption::daemon_variable_aad(id, &name);
+                    let value = decrypt(&aad, &name, &row.get::<String, _>(1))?;
+                    Some((name, value))
+                })
+                .collect::<HashMap<_, _>>();
+            daemon_variables.insert("id".into(), id.to_string());
+            if let Some(name) = daemon_name {
+                daemon_variables.insert("name".into(), name);
+            }
+
+            let (query, values) = Query::select()
+                .columns([
+                    Nodes::Id,
+                    Nodes::Config,
+                    Nodes::WorkspaceId,
+                    Nodes::Restarts,
+                ])
                 .from(Nodes::Table)
                 .and_where(Expr::col(Nodes::DaemonId).eq(id))
                 .build_any_sqlx(&*self.query_builder);
-            let nodes = sqlx::query_with(&query, values)
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?;
+            let workspace_ids = rows
+                .iter()
+                .map(|row| row.get::<i64, _>(2))
+                .collect::<Vec<_>>();
+
+            // variables of every workspace, daemon nodes belong to
+            let (query, values) = Query::select()
+                .columns([
+                    WorkspaceVariables::WorkspaceId,
+                    WorkspaceVariables::Name,
+                    WorkspaceVariables::Value,
+                ])
+                .from(WorkspaceVariables::Table)
+                .and_where(
+                    Expr::col(WorkspaceVariables::WorkspaceId).is_in(workspace_ids.iter().copied()),
+                )
+                .build_any_sqlx(&*self.query_builder);
+            let mut workspace_variables: HashMap<i64, HashMap<String, String>> = HashMap::new();
+            for row in sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+            {
+                let workspace_id: i64 = row.get(0);
+                let name: String = row.get(1);
+                let aad = encryption::workspace_variable_aad(workspace_id, &name);
+                if let Some(value) = decrypt(&aad, &name, &row.get::<String, _>(2)) {
+                    workspace_variables
+                        .entry(workspace_id)
+                        .or_default()
+                        .insert(name, value);
+                }
+            }
+
+            // nodes, which config can't be resolved, are skipped, so the rest of graph still runs
+            let mut errors = BTreeMap::new();
+            let mut nodes = Vec::with_capacity(rows.len());
+            for row in rows {
+                let node_id: Uuid = row.get(0);
+                let workspace_id: i64 = row.get(2);
+                let lookup = |name: &str| match name.strip_prefix(config::template::DAEMON_PREFIX) {
+                    Some(name) => daemon_variables.get(name).cloned(),
+                    None => workspace_variables
+                        .get(&workspace_id)
+                        .and_then(|variables| variables.get(name))
+                        .cloned(),
+                };
+                let node = resolve_daemon_node(
+                    config_registry,
+                    self.master_key.as_ref(),
+                    node_id,
+                    row.get::<Json<_>, _>(1).0,
+                    row.get(3),
+                    lookup,
+                );
+                match node {
+                    Ok(node) => nodes.push(node),
+                    Err(e) => {
+                        let error = format!("{:#}", e.err);
+                        tracing::error!("skipping node {node_id} in graph of daemon {id}: {error}");
+                        errors.insert(node_id, error);
+                    }
+                }
+            }
+
+            // paused node stops whole pipeline, including nodes on other daemons
+            // pipeline of skipped node is stopped too, so upstream nodes don't drop messages
+            let (query, values) = Query::select()
+                .columns([Nodes::Id])
+                .from(Nodes::Table)
+                .and_where(Expr::col(Nodes::WorkspaceId).is_in(workspace_ids.iter().copied()))
+                .and_where(Expr::col(Nodes::Paused).eq(true))
+                .build_any_sqlx(&*self.query_builder);
+            let stopped_nodes = sqlx::query_with(&query, values)
                 .fetch_all(&self.pool)
                 .await?
                 .into_iter()
-                .map(|row| {
-                    let config = row.get::<Json<_>, _>(1).0;
-                    Ok(DaemonNode {
-                        id: row.get(0),
-                        config: serde_json::from_value(config)?,
-                    })
-                })
-                .collect::<Result<Vec<_>>>()?;
+                .map(|row| row.get::<Uuid, _>(0))
+                .chain(errors.keys().copied())
+                .collect::<Vec<_>>();
+            if !stopped_nodes.is_empty() {
+                let (query, values) = Query::select()
+                    .columns([Edges::FromId, Edges::ToId])
+                    .from(Edges::Table)
+                    .and_where(
+                        Expr::col(Edges::FromId).in_subquery(
+                            Query::select()
+                                .column(Nodes::Id)
+                                .from(Nodes::Table)
+                                .and_where(
+                                    Expr::col(Nodes::WorkspaceId)
+                                        .is_in(workspace_ids.iter().copied()),
+                                )
+                                .take(),
+                        ),
+                    )
+                    .build_any_sqlx(&*self.query_builder);
+                let mut graph = graph::Graph::<Uuid, ()>::new();
+                for row in sqlx::query_with(&query, values)
+                    .fetch_all(&self.pool)
+                    .await?
+                {
+                    graph.add_edge_unchecked(row.get(0), row.get(1));
+                }
+                // pipeline consists of its sink and everything upstream of it
+                let mut stopped = std::collections::BTreeSet::new();
+                for node_id in stopped_nodes {
+                    let sink = graph
+                        .downstream(node_id)
+                        .into_iter()
+                        .find(|&id| graph.get_edge(id).is_none());
+                    let sink = sink.unwrap_or(node_id);
+                    stopped.insert(sink);
+                    stopped.extend(graph.upstream(sink));
+                }
+                nodes.retain(|node| !stopped.contains(&node.id));
+            }
 
             let node_ids = nodes.iter().map(|node| node.id);
             let (query, values) = Query::select()
@@ -751,19 +1736,1946 @@ where
                     to_id: row.get(1),
                 })
                 .collect::<Vec<_>>();
-            Ok(DaemonGraph { nodes, edges })
+            Ok((DaemonGraph { nodes, edges }, errors))
         })
     }
 
-    fn set_daemon_name<'a>(&'a self, id: Uuid, name: Option<&'a str>) -> BoxFuture<'a, Result<()>> {
+    // relay API
+
+    // queue message, relayed by daemon, returns daemon of destination node
+    // message is accepted only from daemon, which runs node with edge to destination node
+    fn store_relay_message<'a>(
+        &'a self,
+        daemon_id: Uuid,
+        message: &'a RelayMessage,
+    ) -> BoxFuture<'a, Result<Option<Uuid>>> {
         Box::pin(async move {
-            let (query, values) = Query::update()
-                .table(Daemons::Table)
-                .values([(Daemons::DisplayName, name.into())])
-                .and_where(Expr::col(Daemons::Id).eq(id))
+            let size: usize = message.chunks.iter().map(|chunk| chunk.len()).sum();
+            if size > MAX_RELAY_MESSAGE_SIZE {
+                Err(AppError::bad_request(anyhow::anyhow!(
+                    "message is too large: encoded size exceeds {MAX_RELAY_MESSAGE_SIZE} bytes"
+                )))?
+            }
+            let chunks = message
+                .chunks
+                .iter()
+                .map(|chunk| base64::engine::general_purpose::STANDARD.decode(chunk))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| AppError::bad_request(e.into()))?;
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::select()
+                .columns([Nodes::Id])
+                .from(Nodes::Table)
+                .and_where(Expr::col(Nodes::DaemonId).eq(daemon_id))
+                .and_where(
+                    Expr::col(Nodes::Id).in_subquery(
+                        Query::select()
+                            .column(Edges::FromId)
+                            .from(Edges::Table)
+                            .and_where(Expr::col(Edges::ToId).eq(message.to_node))
+                            .take(),
+                    ),
+                )
                 .build_any_sqlx(&*self.query_builder);
-            sqlx::query_with(&query, values).execute(&self.pool).await?;
-            Ok(())
+            if sqlx::query_with(&query, values)
+                .fetch_optional(&mut *transaction)
+                .await?
+                .is_none()
+            {
+                Err(AppError::not_found(anyhow::anyhow!(
+                    "daemon {daemon_id} has no edge to node {}",
+                    message.to_node
+                )))?
+            }
+            let (query, values) = Query::select()
+                .columns([Nodes::DaemonId])
+                .from(Nodes::Table)
+                .and_where(Expr::col(Nodes::Id).eq(message.to_node))
+                .build_any_sqlx(&*self.query_builder);
+            let destination: Option<Uuid> = sqlx::query_with(&query, values)
+                .fetch_optional(&mut *transaction)
+                .await?
+                .and_then(|row| row.get(0));
+
+            // origin daemon resends messages, which were not acked before reconnect
+            let select_message_id = || {
+                Query::select()
+                    .columns([Messages::Id])
+                    .from(Messages::Table)
+                    .and_where(Expr::col(Messages::FromDaemonId).eq(daemon_id))
+                    .and_where(Expr::col(Messages::FromMessageId).eq(message.id))
+                    .build_any_sqlx(&*self.query_builder)
+            };
+            let (query, values) = select_message_id();
+            if sqlx::query_with(&query, values)
+                .fetch_optional(&mut *transaction)
+                .await?
+                .is_some()
+            {
+                return Ok(destination);
+            }
+            let (query, values) = Query::insert()
+                .into_table(Messages::Table)
+                .columns([
+                    Messages::Topic,
+                    Messages::Origin,
+                    Messages::FromDaemonId,
+                    Messages::FromMessageId,
+                    Messages::CreatedAt,
+                ])
+                .values_panic([
+                    message.to_node.to_string().into(),
+                    message.origin.as_str().into(),
+                    daemon_id.into(),
+                    message.id.into(),
+                    Utc::now().into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = select_message_id();
+            let message_id: i64 = sqlx::query_with(&query, values)
+                .fetch_one(&mut *transaction)
+                .await?
+                .get(0);
+            if !chunks.is_empty() {
+                let mut query = Query::insert();
+                query.into_table(MessageChunks::Table).columns([
+                    MessageChunks::MessageId,
+                    MessageChunks::ChunkId,
+                    MessageChunks::Data,
+                ]);
+                for (chunk_id, chunk) in chunks.into_iter().enumerate() {
+                    query.values_panic([message_id.into(), (chunk_id as i32).into(), chunk.into()]);
+                }
+                let (query, values) = query.build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            transaction.commit().await?;
+            Ok(destination)
+        })
+    }
+
+    // messages, queued for nodes of daemon, ordered by id
+    fn list_relayed_messages(
+        &self,
+        daemon_id: Uuid,
+        after: i64,
+        limit: u64,
+    ) -> BoxFuture<'_, Result<Vec<(i64, RelayMessage)>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Nodes::Id])
+                .from(Nodes::Table)
+                .and_where(Expr::col(Nodes::DaemonId).eq(daemon_id))
+                .build_any_sqlx(&*self.query_builder);
+            let topics = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| row.get::<Uuid, _>(0).to_string())
+                .collect::<Vec<_>>();
+            if topics.is_empty() {
+                return Ok(vec![]);
+            }
+            let (query, values) = Query::select()
+                .columns([
+                    Messages::Id,
+                    Messages::Topic,
+                    Messages::Origin,
+                    Messages::FromMessageId,
+                ])
+                .from(Messages::Table)
+                .and_where(Expr::col(Messages::Topic).is_in(topics))
+                .and_where(Expr::col(Messages::Id).gt(after))
+                .order_by(Messages::Id, Order::Asc)
+                .limit(limit)
+                .build_any_sqlx(&*self.query_builder);
+            let mut messages = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| {
+                    let to_node = row.get::<String, _>(1).parse()?;
+                    let message = RelayMessage {
+                        id: row.get(3),
+                        to_node,
+                        origin: row.get::<Option<String>, _>(2).unwrap_or_default(),
+                        chunks: vec![],
+                    };
+                    Ok((row.get::<i64, _>(0), message))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let (query, values) = Query::select()
+                .columns([MessageChunks::MessageId, MessageChunks::Data])
+                .from(MessageChunks::Table)
+                .and_where(
+                    Expr::col(MessageChunks::MessageId).is_in(messages.iter().map(|(id, _)| *id)),
+                )
+                .order_by(MessageChunks::MessageId, Order::Asc)
+                .order_by(MessageChunks::ChunkId, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            let mut chunks: HashMap<i64, Vec<String>> = HashMap::new();
+            for row in sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+            {
+                chunks.entry(row.get(0)).or_default().push(
+                    base64::engine::general_purpose::STANDARD.encode(row.get::<Vec<u8>, _>(1)),
+                );
+            }
+            for (id, message) in messages.iter_mut() {
+                message.chunks = chunks.remove(id).unwrap_or_default();
+            }
+            Ok(messages)
+        })
+    }
+
+    // remove message, acked by daemon, returns origin daemon and message id
+    // messages, queued for nodes of other daemons, are left as is
+    fn delete_relayed_message(
+        &self,
+        daemon_id: Uuid,
+        id: i64,
+    ) -> BoxFuture<'_, Result<Option<(Uuid, Uuid)>>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::select()
+                .columns([
+                    Messages::Topic,
+                    Messages::FromDaemonId,
+                    Messages::FromMessageId,
+                ])
+                .from(Messages::Table)
+                .and_where(Expr::col(Messages::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            let (topic, origin) = match sqlx::query_with(&query, values)
+                .fetch_optional(&mut *transaction)
+                .await?
+            {
+                Some(row) => (row.get::<String, _>(0), (row.get(1), row.get(2))),
+                None => return Ok(None),
+            };
+            let to_node: Uuid = topic.parse()?;
+            let (query, values) = Query::select()
+                .columns([Nodes::Id])
+                .from(Nodes::Table)
+                .and_where(Expr::col(Nodes::Id).eq(to_node))
+                .and_where(Expr::col(Nodes::DaemonId).eq(daemon_id))
+                .build_any_sqlx(&*self.query_builder);
+            if sqlx::query_with(&query, values)
+                .fetch_optional(&mut *transaction)
+                .await?
+                .is_none()
+            {
+                return Ok(None);
+            }
+            let (query, values) = Query::delete()
+                .from_table(MessageChunks::Table)
+                .and_where(Expr::col(MessageChunks::MessageId).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = Query::delete()
+                .from_table(Messages::Table)
+                .and_where(Expr::col(Messages::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(Some(origin))
         })
     }
+
+    // remove messages, queued before `before`, returns origin daemons and message ids
+    fn delete_expired_relay_messages(
+        &self,
+        before: DateTime<Utc>,
+    ) -> BoxFuture<'_, Result<Vec<(Uuid, Uuid)>>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::select()
+                .columns([
+                    Messages::Id,
+                    Messages::FromDaemonId,
+                    Messages::FromMessageId,
+                ])
+                .from(Messages::Table)
+                .and_where(Expr::col(Messages::CreatedAt).lt(before))
+                .build_any_sqlx(&*self.query_builder);
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?;
+            if rows.is_empty() {
+                return Ok(vec![]);
+            }
+            let ids = rows.iter().map(|row| row.get::<i64, _>(0)).collect::<Vec<_>>();
+            let (query, values) = Query::delete()
+                .from_table(MessageChunks::Table)
+                .and_where(Expr::col(MessageChunks::MessageId).is_in(ids.clone()))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = Query::delete()
+                .from_table(Messages::Table)
+                .and_where(Expr::col(Messages::Id).is_in(ids))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            // messages, queued before relay, don't have origin daemon
+            let origins = rows
+                .into_iter()
+                .filter_map(|row| Some((row.get::<Option<Uuid>, _>(1)?, row.get::<Option<Uuid>, _>(2)?)))
+                .collect();
+            Ok(origins)
+        })
+    }
+
+    // users api
+    fn create_user<'a>(
+        &'a self,
+        user: &'a User,
+        password_hash: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::insert()
+                .into_table(Users::Table)
+                .columns([
+                    Users::Id,
+                    Users::Email,
+                    Users::PasswordHash,
+                    Users::CreatedAt,
+                    Users::IsAdmin,
+                ])
+                .values_panic([
+                    user.id.into(),
+                    user.email.as_str().into(),
+                    password_hash.into(),
+                    user.created_at.into(),
+                    user.is_admin.into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // user and password hash
+    fn get_user_credentials<'a>(
+        &'a self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(User, String)>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([
+                    Users::Id,
+                    Users::Email,
+                    Users::CreatedAt,
+                    Users::IsAdmin,
+                    Users::PasswordHash,
+                ])
+                .from(Users::Table)
+                .and_where(Expr::col(Users::Email).eq(email))
+                .build_any_sqlx(&*self.query_builder);
+            let credentials = sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| {
+                    let user = User {
+                        id: row.get(0),
+                        email: row.get(1),
+                        created_at: row.get(2),
+                        is_admin: row.get(3),
+                        address: None,
+                    };
+                    (user, row.get(4))
+                });
+            Ok(credentials)
+        })
+    }
+
+    fn get_user(&self, id: Uuid) -> BoxFuture<'_, Result<Option<User>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Users::Id, Users::Email, Users::CreatedAt, Users::IsAdmin])
+                .from(Users::Table)
+                .and_where(Expr::col(Users::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            let user = sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| User {
+                    id: row.get(0),
+                    email: row.get(1),
+                    created_at: row.get(2),
+                    is_admin: row.get(3),
+                    address: None,
+                });
+            Ok(user)
+        })
+    }
+
+    fn list_users(&self) -> BoxFuture<'_, Result<Vec<User>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Users::Id, Users::Email, Users::CreatedAt, Users::IsAdmin])
+                .from(Users::Table)
+                .order_by(Users::Email, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            let users = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| User {
+                    id: row.get(0),
+                    email: row.get(1),
+                    created_at: row.get(2),
+                    is_admin: row.get(3),
+                    address: None,
+                })
+                .collect();
+            Ok(users)
+        })
+    }
+
+    fn set_user_admin(&self, id: Uuid, is_admin: bool) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::update()
+                .table(Users::Table)
+                .values([(Users::IsAdmin, is_admin.into())])
+                .and_where(Expr::col(Users::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // sessions, api tokens and memberships are removed with user
+    fn delete_user(&self, id: Uuid) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::delete()
+                .from_table(Users::Table)
+                .and_where(Expr::col(Users::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // sessions api
+    fn create_session<'a>(
+        &'a self,
+        secret_hash: &'a str,
+        user_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            // expired sessions of user are cleaned up on login
+            let (query, values) = Query::delete()
+                .from_table(UserSessions::Table)
+                .and_where(Expr::col(UserSessions::UserId).eq(user_id))
+                .and_where(Expr::col(UserSessions::ExpiresAt).lt(Utc::now()))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            let (query, values) = Query::insert()
+                .into_table(UserSessions::Table)
+                .columns([
+                    UserSessions::Id,
+                    UserSessions::UserId,
+                    UserSessions::CreatedAt,
+                    UserSessions::ExpiresAt,
+                ])
+                .values_panic([
+                    secret_hash.into(),
+                    user_id.into(),
+                    Utc::now().into(),
+                    expires_at.into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // owner of session, which is not expired
+    fn get_session_user_id<'a>(
+        &'a self,
+        secret_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Uuid>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([UserSessions::UserId])
+                .from(UserSessions::Table)
+                .and_where(Expr::col(UserSessions::Id).eq(secret_hash))
+                .and_where(Expr::col(UserSessions::ExpiresAt).gt(Utc::now()))
+                .build_any_sqlx(&*self.query_builder);
+            let user_id = sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| row.get(0));
+            Ok(user_id)
+        })
+    }
+
+    fn delete_session<'a>(&'a self, secret_hash: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::delete()
+                .from_table(UserSessions::Table)
+                .and_where(Expr::col(UserSessions::Id).eq(secret_hash))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // api tokens api
+    fn create_api_token<'a>(
+        &'a self,
+        user_id: Uuid,
+        token: &'a ApiToken,
+        secret_hash: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::insert()
+                .into_table(ApiTokens::Table)
+                .columns([
+                    ApiTokens::Id,
+                    ApiTokens::UserId,
+                    ApiTokens::Name,
+                    ApiTokens::SecretHash,
+                    ApiTokens::CreatedAt,
+                ])
+                .values_panic([
+                    token.id.into(),
+                    user_id.into(),
+                    token.name.as_str().into(),
+                    secret_hash.into(),
+                    token.created_at.into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    fn list_api_tokens(&self, user_id: Uuid) -> BoxFuture<'_, Result<Vec<ApiToken>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([ApiTokens::Id, ApiTokens::Name, ApiTokens::CreatedAt])
+                .from(ApiTokens::Table)
+                .and_where(Expr::col(ApiTokens::UserId).eq(user_id))
+                .order_by(ApiTokens::CreatedAt, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            let tokens = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| ApiToken {
+                    id: row.get(0),
+                    name: row.get(1),
+                    created_at: row.get(2),
+                })
+                .collect();
+            Ok(tokens)
+        })
+    }
+
+    fn delete_api_token(&self, user_id: Uuid, id: Uuid) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            // tokens of other users are not visible
+            let (query, values) = Query::select()
+                .columns([ApiTokens::Id])
+                .from(ApiTokens::Table)
+                .and_where(Expr::col(ApiTokens::Id).eq(id))
+                .and_where(Expr::col(ApiTokens::UserId).eq(user_id))
+                .build_any_sqlx(&*self.query_builder);
+            if sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_none()
+            {
+                Err(AppError::not_found(anyhow::anyhow!("api token {id}")))?
+            }
+            let (query, values) = Query::delete()
+                .from_table(ApiTokens::Table)
+                .and_where(Expr::col(ApiTokens::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // owner of api token
+    fn get_api_token_user_id<'a>(
+        &'a self,
+        secret_hash: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Uuid>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([ApiTokens::UserId])
+                .from(ApiTokens::Table)
+                .and_where(Expr::col(ApiTokens::SecretHash).eq(secret_hash))
+                .build_any_sqlx(&*self.query_builder);
+            let user_id = sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| row.get(0));
+            Ok(user_id)
+        })
+    }
+
+    // organizations api
+    fn create_organization<'a>(
+        &'a self,
+        organization: &'a Organization,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::insert()
+                .into_table(Organizations::Table)
+                .columns([
+                    Organizations::Id,
+                    Organizations::Name,
+                    Organizations::CreatedAt,
+                ])
+                .values_panic([
+                    organization.id.into(),
+                    organization.name.as_str().into(),
+                    organization.created_at.unwrap_or_else(Utc::now).into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    fn list_organizations(&self) -> BoxFuture<'_, Result<Vec<Organization>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([
+                    Organizations::Id,
+                    Organizations::Name,
+                    Organizations::CreatedAt,
+                ])
+                .from(Organizations::Table)
+                .order_by(Organizations::Name, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            let organizations = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| Organization {
+                    id: row.get(0),
+                    name: row.get(1),
+                    created_at: Some(row.get(2)),
+                })
+                .collect();
+            Ok(organizations)
+        })
+    }
+
+    // members are removed with organization
+    fn delete_organization(&self, id: Uuid) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::delete()
+                .from_table(Organizations::Table)
+                .and_where(Expr::col(Organizations::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // assign workspaces, daemons and daemon tokens without organization to given organization
+    fn assign_default_organization(&self, id: Uuid) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let queries = [
+                Query::update()
+                    .table(Workspaces::Table)
+                    .values([(Workspaces::OrganizationId, id.into())])
+                    .and_where(Expr::col(Workspaces::OrganizationId).is_null())
+                    .build_any_sqlx(&*self.query_builder),
+                Query::update()
+                    .table(Daemons::Table)
+                    .values([(Daemons::OrganizationId, id.into())])
+                    .and_where(Expr::col(Daemons::OrganizationId).is_null())
+                    .build_any_sqlx(&*self.query_builder),
+                Query::update()
+                    .table(DaemonTokens::Table)
+                    .values([(DaemonTokens::OrganizationId, id.into())])
+                    .and_where(Expr::col(DaemonTokens::OrganizationId).is_null())
+                    .build_any_sqlx(&*self.query_builder),
+            ];
+            for (query, values) in queries {
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn list_organization_members(
+        &self,
+        organization_id: Uuid,
+    ) -> BoxFuture<'_, Result<Vec<OrganizationMember>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([OrganizationMembers::UserId, OrganizationMembers::Role])
+                .from(OrganizationMembers::Table)
+                .and_where(Expr::col(OrganizationMembers::OrganizationId).eq(organization_id))
+                .build_any_sqlx(&*self.query_builder);
+            let roles = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| Ok((row.get::<Uuid, _>(0), row.get::<String, _>(1).parse()?)))
+                .collect::<Result<HashMap<Uuid, OrganizationRole>>>()?;
+            let (query, values) = Query::select()
+                .columns([Users::Id, Users::Email])
+                .from(Users::Table)
+                .and_where(Expr::col(Users::Id).is_in(roles.keys().copied()))
+                .order_by(Users::Email, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            let members = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| OrganizationMember {
+                    user_id: row.get(0),
+                    email: row.get(1),
+                    role: roles[&row.get::<Uuid, _>(0)],
+                })
+                .collect();
+            Ok(members)
+        })
+    }
+
+    // roles of user in organizations, keyed by organization id
+    fn list_user_organizations(
+        &self,
+        user_id: Uuid,
+    ) -> BoxFuture<'_, Result<HashMap<Uuid, OrganizationRole>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([
+                    OrganizationMembers::OrganizationId,
+                    OrganizationMembers::Role,
+                ])
+                .from(OrganizationMembers::Table)
+                .and_where(Expr::col(OrganizationMembers::UserId).eq(user_id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| Ok((row.get(0), row.get::<String, _>(1).parse()?)))
+                .collect()
+        })
+    }
+
+    // add member or replace role of existing one
+    fn set_organization_member(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+        role: OrganizationRole,
+    ) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::delete()
+                .from_table(OrganizationMembers::Table)
+                .and_where(Expr::col(OrganizationMembers::OrganizationId).eq(organization_id))
+                .and_where(Expr::col(OrganizationMembers::UserId).eq(user_id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = Query::insert()
+                .into_table(OrganizationMembers::Table)
+                .columns([
+                    OrganizationMembers::OrganizationId,
+                    OrganizationMembers::UserId,
+                    OrganizationMembers::Role,
+                ])
+                .values_panic([organization_id.into(), user_id.into(), role.as_str().into()])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn remove_organization_member(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::delete()
+                .from_table(OrganizationMembers::Table)
+                .and_where(Expr::col(OrganizationMembers::OrganizationId).eq(organization_id))
+                .and_where(Expr::col(OrganizationMembers::UserId).eq(user_id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // workspace members api
+    // organizations of workspaces with given name
+    fn list_workspace_organizations<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Option<Uuid>>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Workspaces::OrganizationId])
+                .from(Workspaces::Table)
+                .and_where(Expr::col(Workspaces::Name).eq(name))
+                .build_any_sqlx(&*self.query_builder);
+            Ok(sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect())
+        })
+    }
+
+    fn list_workspace_members<'a>(
+        &'a self,
+        workspace: &'a WorkspaceKey,
+    ) -> BoxFuture<'a, Result<Vec<WorkspaceMember>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Workspaces::Id])
+                .from(Workspaces::Table)
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
+                .build_any_sqlx(&*self.query_builder);
+            let workspace_id: i64 = match sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+            {
+                Some(row) => row.get(0),
+                None => Err(AppError::workspace_not_found(&workspace.name))?,
+            };
+            let (query, values) = Query::select()
+                .columns([WorkspaceMembers::UserId, WorkspaceMembers::Role])
+                .from(WorkspaceMembers::Table)
+                .and_where(Expr::col(WorkspaceMembers::WorkspaceId).eq(workspace_id))
+                .build_any_sqlx(&*self.query_builder);
+            let roles = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| Ok((row.get::<Uuid, _>(0), row.get::<String, _>(1).parse()?)))
+                .collect::<Result<HashMap<Uuid, Role>>>()?;
+            let (query, values) = Query::select()
+                .columns([Users::Id, Users::Email])
+                .from(Users::Table)
+                .and_where(Expr::col(Users::Id).is_in(roles.keys().copied()))
+                .order_by(Users::Email, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            let members = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| WorkspaceMember {
+                    user_id: row.get(0),
+                    email: row.get(1),
+                    role: roles[&row.get::<Uuid, _>(0)],
+                })
+                .collect();
+            Ok(members)
+        })
+    }
+
+    // roles of user in workspaces, keyed by workspace name
+    fn list_user_workspace_roles(
+        &self,
+        user_id: Uuid,
+    ) -> BoxFuture<'_, Result<HashMap<WorkspaceKey, Role>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([WorkspaceMembers::WorkspaceId, WorkspaceMembers::Role])
+                .from(WorkspaceMembers::Table)
+                .and_where(Expr::col(WorkspaceMembers::UserId).eq(user_id))
+                .build_any_sqlx(&*self.query_builder);
+            let roles = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| Ok((row.get::<i64, _>(0), row.get::<String, _>(1).parse()?)))
+                .collect::<Result<HashMap<i64, Role>>>()?;
+            let (query, values) = Query::select()
+                .columns([
+                    Workspaces::Id,
+                    Workspaces::Name,
+                    Workspaces::OrganizationId,
+                ])
+                .from(Workspaces::Table)
+                .and_where(Expr::col(Workspaces::Id).is_in(roles.keys().copied()))
+                .build_any_sqlx(&*self.query_builder);
+            let roles = sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| {
+                    let workspace = WorkspaceKey {
+                        organization_id: row.get(2),
+                        name: row.get(1),
+                    };
+                    (workspace, roles[&row.get::<i64, _>(0)])
+                })
+                .collect();
+            Ok(roles)
+        })
+    }
+
+    // add member or replace role of existing one
+    fn set_workspace_member<'a>(
+        &'a self,
+        workspace: &'a WorkspaceKey,
+        user_id: Uuid,
+        role: Role,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Workspaces::Id])
+                .from(Workspaces::Table)
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
+                .build_any_sqlx(&*self.query_builder);
+            let workspace_id: i64 = match sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+            {
+                Some(row) => row.get(0),
+                None => Err(AppError::workspace_not_found(&workspace.name))?,
+            };
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::delete()
+                .from_table(WorkspaceMembers::Table)
+                .and_where(Expr::col(WorkspaceMembers::WorkspaceId).eq(workspace_id))
+                .and_where(Expr::col(WorkspaceMembers::UserId).eq(user_id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let (query, values) = Query::insert()
+                .into_table(WorkspaceMembers::Table)
+                .columns([
+                    WorkspaceMembers::WorkspaceId,
+                    WorkspaceMembers::UserId,
+                    WorkspaceMembers::Role,
+                ])
+                .values_panic([workspace_id.into(), user_id.into(), role.as_str().into()])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn remove_workspace_member<'a>(
+        &'a self,
+        workspace: &'a WorkspaceKey,
+        user_id: Uuid,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Workspaces::Id])
+                .from(Workspaces::Table)
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
+                .build_any_sqlx(&*self.query_builder);
+            let workspace_id: i64 = match sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+            {
+                Some(row) => row.get(0),
+                None => Err(AppError::workspace_not_found(&workspace.name))?,
+            };
+            let (query, values) = Query::delete()
+                .from_table(WorkspaceMembers::Table)
+                .and_where(Expr::col(WorkspaceMembers::WorkspaceId).eq(workspace_id))
+                .and_where(Expr::col(WorkspaceMembers::UserId).eq(user_id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    fn master_key(&self) -> Option<&MasterKey> {
+        self.master_key.as_ref()
+    }
+
+    // re-encrypt secret fields of all node configs, workspace revisions and variable values with new master key
+    // plaintext values are encrypted, returns number of updated nodes
+    // revisions, which can't be decrypted with current master key, are left as is
+    fn reencrypt_secrets<'a>(
+        &'a self,
+        config_registry: &'a ConfigRegistry,
+        new_master_key: Option<&'a MasterKey>,
+    ) -> BoxFuture<'a, Result<usize>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::select()
+                .columns([Nodes::Id, Nodes::Config])
+                .from(Nodes::Table)
+                .lock_exclusive()
+                .build_any_sqlx(&*self.query_builder);
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?;
+            let mut updated = 0;
+            for row in rows {
+                let id: Uuid = row.get(0);
+                let config = row.get::<Json<_>, _>(1).0;
+                let mut config: Box<dyn config_registry::Config> = serde_json::from_value(config)?;
+                let changed = encryption::reencrypt_config(
+                    config_registry,
+                    id,
+                    &mut config,
+                    self.master_key.as_ref(),
+                    new_master_key,
+                )
+                .map_err(|e| {
+                    e.err
+                        .context(format!("failed to re-encrypt config of node {id}"))
+                })?;
+                if !changed {
+                    continue;
+                }
+                let json = serde_json::to_string(&*config)?;
+                let (query, values) = Query::update()
+                    .table(Nodes::Table)
+                    .values([(Nodes::Config, json.into())])
+                    .and_where(Expr::col(Nodes::Id).eq(id))
+                    .build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+                updated += 1;
+            }
+            let (query, values) = Query::select()
+                .columns([
+                    WorkspaceRevisions::WorkspaceId,
+                    WorkspaceRevisions::Revision,
+                    WorkspaceRevisions::Operations,
+                    WorkspaceRevisions::Inverse,
+                ])
+                .from(WorkspaceRevisions::Table)
+                .lock_exclusive()
+                .build_any_sqlx(&*self.query_builder);
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?;
+            for row in rows {
+                let workspace_id: i64 = row.get(0);
+                let revision: i64 = row.get(1);
+                let mut operations: Vec<WorkspaceOperation> =
+                    serde_json::from_value(row.get::<Json<_>, _>(2).0)?;
+                let mut inverse: Vec<WorkspaceOperation> =
+                    serde_json::from_value(row.get::<Json<_>, _>(3).0)?;
+                let changed = [&mut operations, &mut inverse]
+                    .into_iter()
+                    .try_fold(false, |changed, operations| {
+                        reencrypt_operations(
+                            config_registry,
+                            operations,
+                            self.master_key.as_ref(),
+                            new_master_key,
+                        )
+                        .map(|c| changed | c)
+                    });
+                match changed {
+                    Ok(true) => (),
+                    Ok(false) => continue,
+                    Err(e) => {
+                        tracing::warn!(
+                            "failed to re-encrypt revision {revision} of workspace {workspace_id}: {:#}",
+                            e.err
+                        );
+                        continue;
+                    }
+                }
+                let (query, values) = Query::update()
+                    .table(WorkspaceRevisions::Table)
+                    .values([
+                        (
+                            WorkspaceRevisions::Operations,
+                            serde_json::to_string(&operations)?.into(),
+                        ),
+                        (
+                            WorkspaceRevisions::Inverse,
+                            serde_json::to_string(&inverse)?.into(),
+                        ),
+                    ])
+                    .and_where(Expr::col(WorkspaceRevisions::WorkspaceId).eq(workspace_id))
+                    .and_where(Expr::col(WorkspaceRevisions::Revision).eq(revision))
+                    .build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            let (query, values) = Query::select()
+                .columns([
+                    WorkspaceVariables::WorkspaceId,
+                    WorkspaceVariables::Name,
+                    WorkspaceVariables::Value,
+                ])
+                .from(WorkspaceVariables::Table)
+                .lock_exclusive()
+                .build_any_sqlx(&*self.query_builder);
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?;
+            for row in rows {
+                let workspace_id: i64 = row.get(0);
+                let name: String = row.get(1);
+                let value = encryption::reencrypt_value(
+                    &encryption::workspace_variable_aad(workspace_id, &name),
+                    &row.get::<String, _>(2),
+                    self.master_key.as_ref(),
+                    new_master_key,
+                )
+                .map_err(|e| {
+                    e.err.context(format!(
+                        "failed to re-encrypt variable '{name}' of workspace {workspace_id}"
+                    ))
+                })?;
+                let Some(value) = value else { continue };
+                let (query, values) = Query::update()
+                    .table(WorkspaceVariables::Table)
+                    .values([(WorkspaceVariables::Value, value.into())])
+                    .and_where(Expr::col(WorkspaceVariables::WorkspaceId).eq(workspace_id))
+                    .and_where(Expr::col(WorkspaceVariables::Name).eq(name))
+                    .build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            let (query, values) = Query::select()
+                .columns([
+                    DaemonVariables::DaemonId,
+                    DaemonVariables::Name,
+                    DaemonVariables::Value,
+                ])
+                .from(DaemonVariables::Table)
+                .lock_exclusive()
+                .build_any_sqlx(&*self.query_builder);
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?;
+            for row in rows {
+                let daemon_id: Uuid = row.get(0);
+                let name: String = row.get(1);
+                let value = encryption::reencrypt_value(
+                    &encryption::daemon_variable_aad(daemon_id, &name),
+                    &row.get::<String, _>(2),
+                    self.master_key.as_ref(),
+                    new_master_key,
+                )
+                .map_err(|e| {
+                    e.err.context(format!(
+                        "failed to re-encrypt variable '{name}' of daemon {daemon_id}"
+                    ))
+                })?;
+                let Some(value) = value else { continue };
+                let (query, values) = Query::update()
+                    .table(DaemonVariables::Table)
+                    .values([(DaemonVariables::Value, value.into())])
+                    .and_where(Expr::col(DaemonVariables::DaemonId).eq(daemon_id))
+                    .and_where(Expr::col(DaemonVariables::Name).eq(name))
+                    .build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            transaction.commit().await?;
+            Ok(updated)
+        })
+    }
+
+    // upgrade node configs, stored with older config version, to current version
+    // configs unknown to registry or with newer version are left as is, returns number of updated nodes
+    fn upgrade_configs<'a>(
+        &'a self,
+        config_registry: &'a ConfigRegistry,
+    ) -> BoxFuture<'a, Result<usize>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::select()
+                .columns([Nodes::Id, Nodes::Config])
+                .from(Nodes::Table)
+                .lock_exclusive()
+                .build_any_sqlx(&*self.query_builder);
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?;
+            let mut updated = 0;
+            for row in rows {
+                let id: Uuid = row.get(0);
+                let config = row.get::<Json<_>, _>(1).0;
+                let config = match upgrade_node_config(
+                    config_registry,
+                    self.master_key.as_ref(),
+                    id,
+                    config,
+                )? {
+                    Some(config) => config,
+                    None => continue,
+                };
+                let json = serde_json::to_string(&*config)?;
+                let (query, values) = Query::update()
+                    .table(Nodes::Table)
+                    .values([(Nodes::Config, json.into())])
+                    .and_where(Expr::col(Nodes::Id).eq(id))
+                    .build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+                updated += 1;
+            }
+            transaction.commit().await?;
+            Ok(updated)
+        })
+    }
+
+    // escape template fields of node configs, stored before variable references were introduced
+    fn escape_templates<'a>(
+        &'a self,
+        config_registry: &'a ConfigRegistry,
+    ) -> BoxFuture<'a, Result<usize>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::select()
+                .columns([Nodes::Id, Nodes::Config])
+                .from(Nodes::Table)
+                .and_where(Expr::col(Nodes::EscapeTemplates).eq(true))
+                .lock_exclusive()
+                .build_any_sqlx(&*self.query_builder);
+            let rows = sqlx::query_with(&query, values)
+                .fetch_all(&mut *transaction)
+                .await?;
+            let mut updated = 0;
+            for row in rows {
+                let id: Uuid = row.get(0);
+                let config = row.get::<Json<_>, _>(1).0;
+                let config =
+                    match escape_node_config(config_registry, self.master_key.as_ref(), id, config)? {
+                        Some(config) => config,
+                        None => continue,
+                    };
+                let json = serde_json::to_string(&*config)?;
+                let (query, values) = Query::update()
+                    .table(Nodes::Table)
+                    .values([(Nodes::Config, json.into())])
+                    .and_where(Expr::col(Nodes::Id).eq(id))
+                    .build_any_sqlx(&*self.query_builder);
+                sqlx::query_with(&query, values)
+                    .execute(&mut *transaction)
+                    .await?;
+                updated += 1;
+            }
+            let (query, values) = Query::update()
+                .table(Nodes::Table)
+                .values([(Nodes::EscapeTemplates, Option::<bool>::None.into())])
+                .and_where(Expr::col(Nodes::EscapeTemplates).eq(true))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(updated)
+        })
+    }
+
+    fn set_daemon_name<'a>(&'a self, id: Uuid, name: Option<&'a str>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::update()
+                .table(Daemons::Table)
+                .values([(Daemons::DisplayName, name.into())])
+                .and_where(Expr::col(Daemons::Id).eq(id))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // variables API
+    fn list_workspace_variables<'a>(
+        &'a self,
+        workspace: &'a WorkspaceKey,
+    ) -> BoxFuture<'a, Result<Vec<Variable>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Workspaces::Id])
+                .from(Workspaces::Table)
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
+                .build_any_sqlx(&*self.query_builder);
+            let workspace_id: i64 = match sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+            {
+                Some(row) => row.get(0),
+                None => Err(AppError::workspace_not_found(&workspace.name))?,
+            };
+            let (query, values) = Query::select()
+                .columns([WorkspaceVariables::Name, WorkspaceVariables::Value])
+                .from(WorkspaceVariables::Table)
+                .and_where(Expr::col(WorkspaceVariables::WorkspaceId).eq(workspace_id))
+                .order_by(WorkspaceVariables::Name, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| {
+                    let name: String = row.get(0);
+                    let aad = encryption::workspace_variable_aad(workspace_id, &name);
+                    let value = encryption::decrypt_value(
+                        self.master_key.as_ref(),
+                        &aad,
+                        &row.get::<String, _>(1),
+                    )?;
+                    Ok(Variable { name, value })
+                })
+                .collect()
+        })
+    }
+
+    fn set_workspace_variable<'a>(
+        &'a self,
+        workspace: &'a WorkspaceKey,
+        variable: &'a Variable,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::select()
+                .columns([Workspaces::Id])
+                .from(Workspaces::Table)
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
+                .build_any_sqlx(&*self.query_builder);
+            let workspace_id: i64 = match sqlx::query_with(&query, values)
+                .fetch_optional(&mut *transaction)
+                .await?
+            {
+                Some(row) => row.get(0),
+                None => Err(AppError::workspace_not_found(&workspace.name))?,
+            };
+            let (query, values) = Query::delete()
+                .from_table(WorkspaceVariables::Table)
+                .and_where(Expr::col(WorkspaceVariables::WorkspaceId).eq(workspace_id))
+                .and_where(Expr::col(WorkspaceVariables::Name).eq(variable.name.as_str()))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let value = encryption::encrypt_value(
+                self.master_key.as_ref(),
+                &encryption::workspace_variable_aad(workspace_id, &variable.name),
+                &variable.value,
+            )?;
+            let (query, values) = Query::insert()
+                .into_table(WorkspaceVariables::Table)
+                .columns([
+                    WorkspaceVariables::WorkspaceId,
+                    WorkspaceVariables::Name,
+                    WorkspaceVariables::Value,
+                ])
+                .values_panic([
+                    workspace_id.into(),
+                    variable.name.as_str().into(),
+                    value.into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn delete_workspace_variable<'a>(
+        &'a self,
+        workspace: &'a WorkspaceKey,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([Workspaces::Id])
+                .from(Workspaces::Table)
+                .cond_where(workspace_condition(workspace.organization_id, &workspace.name))
+                .build_any_sqlx(&*self.query_builder);
+            let workspace_id: i64 = match sqlx::query_with(&query, values)
+                .fetch_optional(&self.pool)
+                .await?
+            {
+                Some(row) => row.get(0),
+                None => Err(AppError::workspace_not_found(&workspace.name))?,
+            };
+            let (query, values) = Query::delete()
+                .from_table(WorkspaceVariables::Table)
+                .and_where(Expr::col(WorkspaceVariables::WorkspaceId).eq(workspace_id))
+                .and_where(Expr::col(WorkspaceVariables::Name).eq(name))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    fn list_daemon_variables(&self, id: Uuid) -> BoxFuture<'_, Result<Vec<Variable>>> {
+        Box::pin(async move {
+            let (query, values) = Query::select()
+                .columns([DaemonVariables::Name, DaemonVariables::Value])
+                .from(DaemonVariables::Table)
+                .and_where(Expr::col(DaemonVariables::DaemonId).eq(id))
+                .order_by(DaemonVariables::Name, Order::Asc)
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| {
+                    let name: String = row.get(0);
+                    let aad = encryption::daemon_variable_aad(id, &name);
+                    let value = encryption::decrypt_value(
+                        self.master_key.as_ref(),
+                        &aad,
+                        &row.get::<String, _>(1),
+                    )?;
+                    Ok(Variable { name, value })
+                })
+                .collect()
+        })
+    }
+
+    fn set_daemon_variable<'a>(
+        &'a self,
+        id: Uuid,
+        variable: &'a Variable,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let conn = &mut *self.pool.acquire().await?;
+            let mut transaction = conn.begin().await?;
+            let (query, values) = Query::delete()
+                .from_table(DaemonVariables::Table)
+                .and_where(Expr::col(DaemonVariables::DaemonId).eq(id))
+                .and_where(Expr::col(DaemonVariables::Name).eq(variable.name.as_str()))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            let value = encryption::encrypt_value(
+                self.master_key.as_ref(),
+                &encryption::daemon_variable_aad(id, &variable.name),
+                &variable.value,
+            )?;
+            let (query, values) = Query::insert()
+                .into_table(DaemonVariables::Table)
+                .columns([
+                    DaemonVariables::DaemonId,
+                    DaemonVariables::Name,
+                    DaemonVariables::Value,
+                ])
+                .values_panic([id.into(), variable.name.as_str().into(), value.into()])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn delete_daemon_variable<'a>(&'a self, id: Uuid, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::delete()
+                .from_table(DaemonVariables::Table)
+                .and_where(Expr::col(DaemonVariables::DaemonId).eq(id))
+                .and_where(Expr::col(DaemonVariables::Name).eq(name))
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // audit log is append-only, so there are no update and delete functions
+    fn append_audit_entry<'a>(&'a self, entry: &'a AuditEntry) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (query, values) = Query::insert()
+                .into_table(AuditLog::Table)
+                .columns([
+                    AuditLog::CreatedAt,
+                    AuditLog::UserId,
+                    AuditLog::Actor,
+                    AuditLog::Address,
+                    AuditLog::OrganizationId,
+                    AuditLog::Action,
+                    AuditLog::Target,
+                    AuditLog::Payload,
+                ])
+                .values_panic([
+                    entry.created_at.into(),
+                    entry.user_id.into(),
+                    entry.actor.as_str().into(),
+                    entry.address.clone().into(),
+                    entry.organization_id.into(),
+                    entry.action.as_str().into(),
+                    entry.target.as_str().into(),
+                    serde_json::to_string(&entry.payload)?.into(),
+                ])
+                .build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values).execute(&self.pool).await?;
+            Ok(())
+        })
+    }
+
+    // entries, newest first, organizations limit visible entries, if set
+    fn list_audit_entries<'a>(
+        &'a self,
+        filter: &'a AuditFilter,
+        organizations: Option<&'a [Uuid]>,
+    ) -> BoxFuture<'a, Result<Vec<AuditEntry>>> {
+        Box::pin(async move {
+            let mut select = Query::select();
+            select
+                .columns([
+                    AuditLog::Id,
+                    AuditLog::CreatedAt,
+                    AuditLog::UserId,
+                    AuditLog::Actor,
+                    AuditLog::Address,
+                    AuditLog::OrganizationId,
+                    AuditLog::Action,
+                    AuditLog::Target,
+                    AuditLog::Payload,
+                ])
+                .from(AuditLog::Table)
+                .order_by(AuditLog::Id, Order::Desc)
+                .limit(filter.page_size());
+            if let Some(organizations) = organizations {
+                select.and_where(
+                    Expr::col(AuditLog::OrganizationId).is_in(organizations.iter().copied()),
+                );
+            }
+            if let Some(actor) = filter.actor.as_deref() {
+                select.and_where(Expr::col(AuditLog::Actor).eq(actor));
+            }
+            if let Some(action) = filter.action.as_deref() {
+                select.and_where(Expr::col(AuditLog::Action).eq(action));
+            }
+            if let Some(target) = filter.target.as_deref() {
+                select.and_where(Expr::col(AuditLog::Target).eq(target));
+            }
+            if let Some(organization_id) = filter.organization_id {
+                select.and_where(Expr::col(AuditLog::OrganizationId).eq(organization_id));
+            }
+            if let Some(since) = filter.since {
+                select.and_where(Expr::col(AuditLog::CreatedAt).gte(since));
+            }
+            if let Some(until) = filter.until {
+                select.and_where(Expr::col(AuditLog::CreatedAt).lt(until));
+            }
+            if let Some(before) = filter.before {
+                select.and_where(Expr::col(AuditLog::Id).lt(before));
+            }
+            let (query, values) = select.build_any_sqlx(&*self.query_builder);
+            sqlx::query_with(&query, values)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| {
+                    Ok(AuditEntry {
+                        id: row.get(0),
+                        created_at: row.get(1),
+                        user_id: row.get(2),
+                        actor: row.get(3),
+                        address: row.get(4),
+                        organization_id: row.get(5),
+                        action: row.get(6),
+                        target: row.get(7),
+                        payload: row.get::<Json<_>, _>(8).0,
+                    })
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppErrorKind;
+    use base64::engine::general_purpose::STANDARD;
+    use config::prelude::{Configuration, FieldValue, RawConfig};
+
+    #[derive(Debug, Clone, Default, Configuration)]
+    #[section(output = dataframe, version = 2)]
+    #[migrate(from = 1, with = rename_pass)]
+    struct Conf {
+        #[field_type(password)]
+        password: String,
+    }
+
+    // configs of registry are sections, if registry is built with `section` feature
+    macro_rules! impl_section {
+        ($($ty:ty),*) => {$(
+            impl<Input, Output, SectionChan> section::prelude::Section<Input, Output, SectionChan>
+                for $ty
+            {
+                type Error = section::SectionError;
+                type Future = section::SectionFuture;
+
+                fn start(self, _: Input, _: Output, _: SectionChan) -> Self::Future {
+                    Box::pin(async { Err("test config is not runnable")? })
+                }
+            }
+        )*};
+    }
+
+    impl_section!(Conf, Templated);
+
+    fn rename_pass(
+        raw: &mut RawConfig,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        raw.rename_field("pass", "password");
+        Ok(())
+    }
+
+    #[derive(Debug, Clone, Default, Configuration)]
+    #[section(output = dataframe)]
+    struct Templated {
+        #[field(template)]
+        host: String,
+        #[field(template)]
+        #[field_type(password)]
+        password: String,
+        query: String,
+    }
+
+    fn password(config: &dyn config_registry::Config) -> String {
+        match config.get_field_value("password").unwrap() {
+            FieldValue::String(value) => value.to_string(),
+            value => panic!("unexpected value: {value:?}"),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_renamed_password_field() {
+        let mut config_registry = ConfigRegistry::new();
+        config_registry
+            .add_config(|| Box::new(Conf::default()))
+            .unwrap();
+        let master_key = MasterKey::from_base64(&STANDARD.encode([1; 32])).unwrap();
+        let node_id = Uuid::from_u128(1);
+
+        // version 1 config, value is encrypted under its stored field name
+        let encrypted = master_key
+            .encrypt("secret", &format!("node:{node_id}:pass"))
+            .unwrap();
+        let stored = serde_json::json!({
+            "config_name": "Conf",
+            "fields": [{"name": "pass", "value": encrypted}],
+        });
+
+        let upgraded =
+            upgrade_node_config(&config_registry, Some(&master_key), node_id, stored.clone())
+                .unwrap()
+                .unwrap();
+        assert_eq!(upgraded.version(), 2);
+        assert!(encryption::is_encrypted(&password(&*upgraded)));
+
+        // upgraded config is up to date
+        let upgraded = serde_json::to_value(&*upgraded).unwrap();
+        assert!(upgrade_node_config(
+            &config_registry,
+            Some(&master_key),
+            node_id,
+            upgraded.clone()
+        )
+        .unwrap()
+        .is_none());
+
+        // both stored and upgraded configs resolve into plaintext
+        for config in [stored.clone(), upgraded] {
+            let node = resolve_daemon_node(
+                &config_registry,
+                Some(&master_key),
+                node_id,
+                config,
+                0,
+                |_| None,
+            )
+            .unwrap();
+            assert_eq!(password(&*node.config), "secret");
+        }
+
+        // outdated config is re-encrypted under upgraded field name on key rotation
+        let new_key = MasterKey::from_base64(&STANDARD.encode([2; 32])).unwrap();
+        let mut config: Box<dyn config_registry::Config> = serde_json::from_value(stored).unwrap();
+        assert!(encryption::reencrypt_config(
+            &config_registry,
+            node_id,
+            &mut config,
+            Some(&master_key),
+            Some(&new_key)
+        )
+        .unwrap());
+        let node = resolve_daemon_node(
+            &config_registry,
+            Some(&new_key),
+            node_id,
+            serde_json::to_value(&*config).unwrap(),
+            0,
+            |_| None,
+        )
+        .unwrap();
+        assert_eq!(password(&*node.config), "secret");
+    }
+
+    #[test]
+    fn test_escape_templates() {
+        let mut config_registry = ConfigRegistry::new();
+        config_registry
+            .add_config(|| Box::new(Templated::default()))
+            .unwrap();
+        let master_key = MasterKey::from_base64(&STANDARD.encode([1; 32])).unwrap();
+        let node_id = Uuid::from_u128(1);
+        let mut config: Box<dyn config_registry::Config> = Box::new(Templated {
+            host: "${host}".into(),
+            password: "pa${ss".into(),
+            query: "select '${x}'".into(),
+        });
+        encryption::encrypt_config(&config_registry, node_id, &mut config, Some(&master_key))
+            .unwrap();
+        let stored = serde_json::to_value(&*config).unwrap();
+
+        let escaped =
+            escape_node_config(&config_registry, Some(&master_key), node_id, stored.clone())
+                .unwrap()
+                .unwrap();
+        assert!(encryption::is_encrypted(&password(&*escaped)));
+        let escaped = serde_json::to_value(&*escaped).unwrap();
+
+        // stored values are resolved literally
+        let node = resolve_daemon_node(
+            &config_registry,
+            Some(&master_key),
+            node_id,
+            escaped,
+            0,
+            |_| Some("substituted".into()),
+        )
+        .unwrap();
+        let value = |name| match node.config.get_field_value(name).unwrap() {
+            FieldValue::String(value) => value.to_string(),
+            value => panic!("unexpected value: {value:?}"),
+        };
+        assert_eq!(value("host"), "${host}");
+        assert_eq!(value("password"), "pa${ss");
+        assert_eq!(value("query"), "select '${x}'");
+
+        // configs without template references are left as is
+        let plain: Box<dyn config_registry::Config> = Box::new(Templated {
+            host: "localhost".into(),
+            ..Default::default()
+        });
+        let plain = serde_json::to_value(&*plain).unwrap();
+        assert!(escape_node_config(&config_registry, None, node_id, plain)
+            .unwrap()
+            .is_none());
+    }
+    // migrated in-memory database, shared by connections of pool
+    async fn test_db() -> Db<Sqlite> {
+        let url = format!("sqlite:file:{}?mode=memory&cache=shared", Uuid::now_v7());
+        let db = Db::<Sqlite>::new(
+            &url,
+            Box::new(SqliteQueryBuilder),
+            Box::new(SqliteQueryBuilder),
+            None,
+        )
+        .await
+        .unwrap();
+        db.migrate().await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_relay_message_limits() {
+        let db = test_db().await;
+        let daemon_id = Uuid::now_v7();
+        let message = RelayMessage {
+            id: Uuid::now_v7(),
+            to_node: Uuid::now_v7(),
+            origin: "test".into(),
+            chunks: vec![STANDARD.encode(vec![0; MAX_RELAY_MESSAGE_SIZE])],
+        };
+        let err = db.store_relay_message(daemon_id, &message).await.unwrap_err();
+        assert_eq!(err.kind, AppErrorKind::BadRequest);
+
+        // messages, which were not acked in time, are removed with chunks
+        let queued_at = |created_at: DateTime<Utc>, message_id: Uuid| {
+            Query::insert()
+                .into_table(Messages::Table)
+                .columns([
+                    Messages::Topic,
+                    Messages::FromDaemonId,
+                    Messages::FromMessageId,
+                    Messages::CreatedAt,
+                ])
+                .values_panic([
+                    message.to_node.to_string().into(),
+                    daemon_id.into(),
+                    message_id.into(),
+                    created_at.into(),
+                ])
+                .build_any_sqlx(&*db.query_builder)
+        };
+        let (expired, queued) = (Uuid::now_v7(), Uuid::now_v7());
+        for (created_at, message_id) in [
+            (Utc::now() - chrono::Duration::days(8), expired),
+            (Utc::now(), queued),
+        ] {
+            let (query, values) = queued_at(created_at, message_id);
+            sqlx::query_with(&query, values)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+        // chunk of expired message
+        let (query, values) = Query::insert()
+            .into_table(MessageChunks::Table)
+            .columns([
+                MessageChunks::MessageId,
+                MessageChunks::ChunkId,
+                MessageChunks::Data,
+            ])
+            .values_panic([1.into(), 0.into(), b"chunk".to_vec().into()])
+            .build_any_sqlx(&*db.query_builder);
+        sqlx::query_with(&query, values)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let before = Utc::now() - chrono::Duration::days(7);
+        assert_eq!(
+            db.delete_expired_relay_messages(before).await.unwrap(),
+            vec![(daemon_id, expired)]
+        );
+        assert!(db
+            .delete_expired_relay_messages(before)
+            .await
+            .unwrap()
+            .is_empty());
+        let count = |query: String| {
+            let pool = &db.pool;
+            async move {
+                sqlx::query(&query)
+                    .fetch_one(pool)
+                    .await
+                    .unwrap()
+                    .get::<i64, _>(0)
+            }
+        };
+        assert_eq!(count("SELECT COUNT(*) FROM messages".into()).await, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM message_chunks".into()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_workspace_names_unique_within_organization() {
+        let db = test_db().await;
+        let user = User {
+            id: Uuid::now_v7(),
+            email: "user@example.com".into(),
+            created_at: Utc::now(),
+            is_admin: false,
+            address: None,
+        };
+        db.create_user(&user, "hash").await.unwrap();
+        let (first, second) = (Some(Uuid::now_v7()), Some(Uuid::now_v7()));
+        let workspace = |organization_id| Workspace {
+            name: "prod".into(),
+            created_at: None,
+            organization_id,
+        };
+        db.create_workspace(&workspace(first), user.id).await.unwrap();
+        db.create_workspace(&workspace(second), user.id).await.unwrap();
+        let err = db
+            .create_workspace(&workspace(first), user.id)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, AppErrorKind::BadRequest);
+
+        let mut organizations = db.list_workspace_organizations("prod").await.unwrap();
+        organizations.sort();
+        assert_eq!(organizations, [first.min(second), first.max(second)]);
+        let roles = db.list_user_workspace_roles(user.id).await.unwrap();
+        let key = |organization_id| WorkspaceKey {
+            organization_id,
+            name: "prod".into(),
+        };
+        assert_eq!(roles.get(&key(first)), Some(&Role::Admin));
+        assert_eq!(roles.get(&key(second)), Some(&Role::Admin));
+
+        // workspace is removed only in own organization
+        db.delete_workspace(&key(first)).await.unwrap();
+        assert_eq!(
+            db.list_workspace_organizations("prod").await.unwrap(),
+            [second]
+        );
+        assert!(db.get_workspace(&key(first)).await.is_err());
+        assert!(db.get_workspace(&key(second)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_daemon_without_recorded_certificate() {
+        let db = test_db().await;
+        // daemon, joined before serial numbers were tracked
+        let legacy = Uuid::now_v7();
+        db.add_daemon(legacy, None).await.unwrap();
+        db.revoke_daemon_certificates(legacy).await.unwrap();
+        let daemon = db.get_daemon(legacy).await.unwrap().unwrap();
+        assert!(daemon.revoked_at.is_some());
+        assert!(!db.is_daemon_certificate_valid(legacy, "01").await.unwrap());
+
+        let daemon_id = Uuid::now_v7();
+        db.add_daemon(daemon_id, None).await.unwrap();
+        db.add_daemon_certificate(daemon_id, "02").await.unwrap();
+        assert!(db.is_daemon_certificate_valid(daemon_id, "02").await.unwrap());
+        // unrecorded and foreign certificates are rejected
+        assert!(!db.is_daemon_certificate_valid(daemon_id, "03").await.unwrap());
+        assert!(!db.is_daemon_certificate_valid(legacy, "02").await.unwrap());
+
+        db.revoke_daemon_certificates(daemon_id).await.unwrap();
+        assert!(!db.is_daemon_certificate_valid(daemon_id, "02").await.unwrap());
+        let revoked = db.list_revoked_certificates().await.unwrap();
+        assert_eq!(revoked.len(), 1);
+        assert_eq!(revoked[0].serial_number, "02");
+    }
 }
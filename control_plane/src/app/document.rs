@@ -0,0 +1,651 @@
+//! Declarative workspace documents
+//!
+//! Workspace is exported as a document with nodes, configs, positions, daemon assignments and edges.
+//! Applying a document computes workspace operations, which converge stored workspace to the document.
+//!
+//! Secrets never leave control plane: secret fields are exported only if they consist of variable
+//! references, other values are emptied. Document can set only template secret fields and only to
+//! variable references, empty secret fields of existing nodes keep stored value.
+use std::collections::{BTreeMap, BTreeSet};
+
+use config::{prelude::RawConfig, template, FieldValue};
+use config_registry::ConfigRegistry;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    encryption::{self, MasterKey},
+    AppError, Daemon, Edge, Result, WorkspaceGraph, WorkspaceOperation, WorkspaceUpdateResult,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkspaceDocument {
+    #[serde(default)]
+    pub nodes: Vec<DocumentNode>,
+    #[serde(default)]
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentNode {
+    pub id: Uuid,
+    pub x: f64,
+    pub y: f64,
+    /// name of assigned daemon, daemons without name are referenced by id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daemon: Option<String>,
+    pub config: Box<dyn config_registry::Config>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceApplyResult {
+    /// operations, which converge workspace to document
+    pub plan: Vec<WorkspaceOperation>,
+    /// result of applied plan, not set on dry run or if workspace already matches document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<WorkspaceUpdateResult>,
+}
+
+// secret value should consist only of variable references
+fn is_reference(value: &str) -> bool {
+    matches!(template::has_references(value), Ok(true))
+        && matches!(template::substitute(value, |_| Some(String::new())), Ok(rest) if rest.is_empty())
+}
+
+// names of secret fields, paired with template flag
+fn secret_fields(config: &dyn config_registry::Config) -> Vec<(String, bool)> {
+    config
+        .fields()
+        .into_iter()
+        .filter(|field| field.metadata.is_password)
+        .map(|field| (field.name.to_string(), field.metadata.is_template))
+        .collect()
+}
+
+// decrypt stored config and build it through registry to get field metadata
+fn build_stored_config(
+    config_registry: &ConfigRegistry,
+    master_key: Option<&MasterKey>,
+    id: Uuid,
+    mut config: Box<dyn config_registry::Config>,
+) -> Result<Box<dyn config_registry::Config>> {
+    encryption::decrypt_config(config_registry, id, &mut config, master_key)?;
+    config_registry
+        .deserialize_config(&*config)
+        .map_err(|e| AppError::invalid_node_config(id, anyhow::anyhow!("{e}")))
+}
+
+/// export workspace graph as document
+pub fn export(
+    config_registry: &ConfigRegistry,
+    master_key: Option<&MasterKey>,
+    graph: WorkspaceGraph,
+    daemons: &[Daemon],
+) -> Result<WorkspaceDocument> {
+    let daemon_names = daemons
+        .iter()
+        .map(|daemon| {
+            let name = daemon.name.clone().unwrap_or_else(|| daemon.id.to_string());
+            (daemon.id, name)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let nodes = graph
+        .nodes
+        .into_iter()
+        .map(|node| {
+            let mut config =
+                build_stored_config(config_registry, master_key, node.id, node.config)?;
+            for (name, is_template) in secret_fields(&*config) {
+                let keep = is_template
+                    && matches!(
+                        config.get_field_value(&name),
+                        Ok(FieldValue::String(value)) if is_reference(value)
+                    );
+                if !keep {
+                    config
+                        .set_field_value(&name, FieldValue::String(""))
+                        .map_err(|e| anyhow::anyhow!("failed to set field '{name}': {e}"))?;
+                }
+            }
+            let daemon = node.daemon_id.map(|id| {
+                daemon_names
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| id.to_string())
+            });
+            Ok(DocumentNode {
+                id: node.id,
+                x: node.x,
+                y: node.y,
+                daemon,
+                config,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(WorkspaceDocument {
+        nodes,
+        edges: graph.edges,
+    })
+}
+
+fn resolve_daemon(daemons: &[Daemon], name: &str) -> Result<Uuid> {
+    let mut matched = daemons
+        .iter()
+        .filter(|daemon| daemon.name.as_deref() == Some(name));
+    match (matched.next(), matched.next()) {
+        (Some(daemon), None) => Ok(daemon.id),
+        (Some(_), Some(_)) => Err(AppError::bad_request(anyhow::anyhow!(
+            "daemon name '{name}' is ambiguous"
+        ))),
+        (None, _) => name
+            .parse::<Uuid>()
+            .ok()
+            .filter(|id| daemons.iter().any(|daemon| daemon.id == *id))
+            .ok_or_else(|| AppError::bad_request(anyhow::anyhow!("daemon '{name}' not found"))),
+    }
+}
+
+// names of document config fields, which differ from stored config
+// empty secret fields keep stored value
+fn changed_fields(
+    stored: &dyn config_registry::Config,
+    document: &dyn config_registry::Config,
+) -> Vec<String> {
+    document
+        .fields()
+        .into_iter()
+        .filter(|field| !(field.metadata.is_password && field.value == FieldValue::String("")))
+        .filter(|field| {
+            stored
+                .get_field_value(field.name)
+                .map_or(true, |value| value != field.value)
+        })
+        .map(|field| field.name.to_string())
+        .collect()
+}
+
+// partial config with given fields, node config update keeps other fields as is
+fn partial_config(
+    config: &dyn config_registry::Config,
+    fields: &[String],
+) -> Result<Box<dyn config_registry::Config>> {
+    let raw = RawConfig::new(config.name())
+        .with_fields(
+            config
+                .fields()
+                .into_iter()
+                .filter(|field| fields.iter().any(|name| name == field.name)),
+        )
+        .with_version(config.version());
+    Ok(serde_json::from_value(serde_json::to_value(&raw)?)?)
+}
+
+/// compute operations, which converge workspace graph to document
+///
+/// Operations are ordered, so edges are removed before nodes and added after all nodes are in place.
+pub fn plan(
+    config_registry: &ConfigRegistry,
+    master_key: Option<&MasterKey>,
+    graph: WorkspaceGraph,
+    daemons: &[Daemon],
+    document: WorkspaceDocument,
+) -> Result<Vec<WorkspaceOperation>> {
+    let mut document_nodes = BTreeSet::new();
+    for node in document.nodes.iter() {
+        if !document_nodes.insert(node.id) {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "node {} is defined more than once",
+                node.id
+            )))?
+        }
+    }
+    let mut document_edges = BTreeMap::new();
+    for edge in document.edges.iter() {
+        let (from, to) = (edge.from_id, edge.to_id);
+        if !document_nodes.contains(&from) || !document_nodes.contains(&to) {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "edge from node {from} to node {to} references unknown node"
+            )))?
+        }
+        if document_edges.insert(from, to).is_some() {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "node {from} has more than one outgoing edge"
+            )))?
+        }
+    }
+
+    let mut stored_nodes = graph
+        .nodes
+        .into_iter()
+        .map(|node| (node.id, node))
+        .collect::<BTreeMap<_, _>>();
+    let stored_edges = graph
+        .edges
+        .into_iter()
+        .map(|edge| (edge.from_id, edge.to_id))
+        .collect::<BTreeMap<_, _>>();
+    // nodes, which are removed or replaced, their edges are removed together with them
+    let mut removed_nodes = BTreeSet::new();
+    let mut added_nodes = vec![];
+    let mut updated_nodes = vec![];
+    for node in document.nodes {
+        let DocumentNode {
+            id,
+            x,
+            y,
+            daemon,
+            config,
+        } = node;
+        let config_name = config.name().to_string();
+        let config = config_registry
+            .deserialize_config(&*config)
+            .map_err(|_| AppError::invalid_config(&config_name))?;
+        config::template::validate(&*config)
+            .map_err(|e| AppError::invalid_config_fields(&config_name, e))?;
+        for (name, is_template) in secret_fields(&*config) {
+            match config.get_field_value(&name) {
+                Ok(FieldValue::String(value))
+                    if value.is_empty() || (is_template && is_reference(value)) => {}
+                _ if is_template => Err(AppError::bad_request(anyhow::anyhow!(
+                    "secret field '{name}' of node {id} should reference variable"
+                )))?,
+                _ => Err(AppError::bad_request(anyhow::anyhow!(
+                    "secret field '{name}' of node {id} should be empty"
+                )))?,
+            }
+        }
+        let daemon_id = daemon
+            .map(|name| resolve_daemon(daemons, &name))
+            .transpose()?;
+        match stored_nodes.remove(&id) {
+            Some(stored) if stored.config.name() == config_name => {
+                if (stored.x, stored.y) != (x, y) {
+                    updated_nodes.push(WorkspaceOperation::UpdateNodePosition { uuid: id, x, y });
+                }
+                let stored_config =
+                    build_stored_config(config_registry, master_key, id, stored.config)?;
+                let changed = changed_fields(&*stored_config, &*config);
+                if !changed.is_empty() {
+                    updated_nodes.push(WorkspaceOperation::UpdateNodeConfig {
+                        id,
+                        config: partial_config(&*config, &changed)?,
+                    });
+                }
+                match (stored.daemon_id, daemon_id) {
+                    (stored, daemon_id) if stored == daemon_id => (),
+                    (_, Some(daemon_id)) => {
+                        updated_nodes.push(WorkspaceOperation::AssignNodeToDaemon {
+                            node_id: id,
+                            daemon_id,
+                        })
+                    }
+                    (_, None) => updated_nodes
+                        .push(WorkspaceOperation::UnassignNodeFromDaemon { node_id: id }),
+                }
+            }
+            stored => {
+                // section of existing node can't be changed in place, so node is replaced
+                if stored.is_some() {
+                    removed_nodes.insert(id);
+                }
+                added_nodes.push(WorkspaceOperation::AddNode { id, x, y, config });
+                if let Some(daemon_id) = daemon_id {
+                    added_nodes.push(WorkspaceOperation::AssignNodeToDaemon {
+                        node_id: id,
+                        daemon_id,
+                    });
+                }
+            }
+        }
+    }
+    removed_nodes.extend(stored_nodes.into_keys());
+
+    let is_removed =
+        |from: &Uuid, to: &Uuid| removed_nodes.contains(from) || removed_nodes.contains(to);
+    let removed_edges = stored_edges
+        .iter()
+        .filter(|(from, to)| !is_removed(from, to) && document_edges.get(from) != Some(to))
+        .map(|(&from, _)| WorkspaceOperation::RemoveEdge { from });
+    let added_edges = document_edges
+        .iter()
+        .filter(|(from, to)| is_removed(from, to) || stored_edges.get(from) != Some(to))
+        .map(|(&from, &to)| WorkspaceOperation::AddEdge { from, to });
+    let plan = removed_edges
+        .chain(
+            removed_nodes
+                .iter()
+                .map(|&id| WorkspaceOperation::RemoveNode(id)),
+        )
+        .chain(added_nodes)
+        .chain(updated_nodes)
+        .chain(added_edges)
+        .collect();
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{AppErrorKind, DaemonStatus, WorkspaceNode};
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    fn id(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    fn config(
+        config_registry: &ConfigRegistry,
+        name: &str,
+        fields: &[(&str, &str)],
+    ) -> Box<dyn config_registry::Config> {
+        let mut config = config_registry.build_config(name).unwrap();
+        for (field, value) in fields {
+            config
+                .set_field_value(field, FieldValue::String(value))
+                .unwrap();
+        }
+        config
+    }
+
+    fn dir_source(config_registry: &ConfigRegistry, path: &str) -> Box<dyn config_registry::Config> {
+        config(config_registry, "DirSource", &[("path", path)])
+    }
+
+    fn inspect(config_registry: &ConfigRegistry) -> Box<dyn config_registry::Config> {
+        config(config_registry, "Inspect", &[])
+    }
+
+    fn daemon(n: u128, name: &str) -> Daemon {
+        Daemon {
+            id: id(n),
+            name: Some(name.into()),
+            address: None,
+            last_seen: None,
+            joined_at: None,
+            version: None,
+            organization_id: None,
+            revoked_at: None,
+            status: DaemonStatus::default(),
+        }
+    }
+
+    fn stored_node(n: u128, config: Box<dyn config_registry::Config>) -> WorkspaceNode {
+        WorkspaceNode::new(id(n), String::new(), config, None, 0.0, 0.0)
+    }
+
+    fn document_node(
+        n: u128,
+        daemon: Option<&str>,
+        config: Box<dyn config_registry::Config>,
+    ) -> DocumentNode {
+        DocumentNode {
+            id: id(n),
+            x: 0.0,
+            y: 0.0,
+            daemon: daemon.map(Into::into),
+            config,
+        }
+    }
+
+    fn edge(from: u128, to: u128) -> Edge {
+        Edge {
+            from_id: id(from),
+            to_id: id(to),
+        }
+    }
+
+    // short form of operations, node ids are printed as numbers
+    fn summary(plan: &[WorkspaceOperation]) -> Vec<String> {
+        let n = |id: &Uuid| id.as_u128();
+        plan.iter()
+            .map(|op| match op {
+                WorkspaceOperation::AddNode { id, config, .. } => {
+                    format!("add {} {}", n(id), config.name())
+                }
+                WorkspaceOperation::UpdateNodeConfig { id, config } => {
+                    let fields = config
+                        .fields()
+                        .into_iter()
+                        .map(|field| field.name)
+                        .collect::<Vec<_>>();
+                    format!("update {} {}", n(id), fields.join(","))
+                }
+                WorkspaceOperation::UpdateNodePosition { uuid, x, y } => {
+                    format!("move {} {x} {y}", n(uuid))
+                }
+                WorkspaceOperation::RemoveNode(id) => format!("remove {}", n(id)),
+                WorkspaceOperation::AddEdge { from, to } => {
+                    format!("add edge {} {}", n(from), n(to))
+                }
+                WorkspaceOperation::RemoveEdge { from } => format!("remove edge {}", n(from)),
+                WorkspaceOperation::AssignNodeToDaemon { node_id, daemon_id } => {
+                    format!("assign {} {}", n(node_id), n(daemon_id))
+                }
+                WorkspaceOperation::UnassignNodeFromDaemon { node_id } => {
+                    format!("unassign {}", n(node_id))
+                }
+                op => format!("{op:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_add() {
+        let config_registry = config_registry::new().unwrap();
+        let graph = WorkspaceGraph {
+            nodes: vec![],
+            edges: vec![],
+        };
+        let document = WorkspaceDocument {
+            nodes: vec![
+                document_node(1, Some("daemon"), dir_source(&config_registry, "/tmp")),
+                document_node(2, None, inspect(&config_registry)),
+            ],
+            edges: vec![edge(1, 2)],
+        };
+        let daemons = [daemon(10, "daemon")];
+        let plan = plan(&config_registry, None, graph, &daemons, document).unwrap();
+        assert_eq!(
+            summary(&plan),
+            [
+                "add 1 DirSource",
+                "assign 1 10",
+                "add 2 Inspect",
+                "add edge 1 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_unchanged() {
+        let config_registry = config_registry::new().unwrap();
+        let graph = WorkspaceGraph {
+            nodes: vec![
+                stored_node(1, dir_source(&config_registry, "/tmp")),
+                stored_node(2, inspect(&config_registry)),
+            ],
+            edges: vec![edge(1, 2)],
+        };
+        let document = WorkspaceDocument {
+            nodes: vec![
+                document_node(1, None, dir_source(&config_registry, "/tmp")),
+                document_node(2, None, inspect(&config_registry)),
+            ],
+            edges: vec![edge(1, 2)],
+        };
+        let plan = plan(&config_registry, None, graph, &[], document).unwrap();
+        assert!(plan.is_empty(), "{:?}", summary(&plan));
+    }
+
+    #[test]
+    fn test_plan_update() {
+        let config_registry = config_registry::new().unwrap();
+        let graph = WorkspaceGraph {
+            nodes: vec![stored_node(1, dir_source(&config_registry, "/tmp"))],
+            edges: vec![],
+        };
+        let mut node = document_node(1, Some("daemon"), dir_source(&config_registry, "/var"));
+        node.x = 1.0;
+        let document = WorkspaceDocument {
+            nodes: vec![node],
+            edges: vec![],
+        };
+        let daemons = [daemon(10, "daemon")];
+        let plan = plan(&config_registry, None, graph, &daemons, document).unwrap();
+        assert_eq!(
+            summary(&plan),
+            ["move 1 1 0", "update 1 path", "assign 1 10"]
+        );
+    }
+
+    #[test]
+    fn test_plan_remove() {
+        let config_registry = config_registry::new().unwrap();
+        let graph = WorkspaceGraph {
+            nodes: vec![
+                stored_node(1, dir_source(&config_registry, "/tmp")),
+                stored_node(2, inspect(&config_registry)),
+            ],
+            edges: vec![edge(1, 2)],
+        };
+        let document = WorkspaceDocument {
+            nodes: vec![document_node(1, None, dir_source(&config_registry, "/tmp"))],
+            edges: vec![],
+        };
+        // edge is removed together with node
+        let plan = plan(&config_registry, None, graph, &[], document).unwrap();
+        assert_eq!(summary(&plan), ["remove 2"]);
+    }
+
+    #[test]
+    fn test_plan_replace() {
+        let config_registry = config_registry::new().unwrap();
+        let graph = WorkspaceGraph {
+            nodes: vec![
+                stored_node(1, dir_source(&config_registry, "/tmp")),
+                stored_node(2, inspect(&config_registry)),
+            ],
+            edges: vec![edge(1, 2)],
+        };
+        // section of node 2 is changed, node is replaced and its edge is added again
+        let document = WorkspaceDocument {
+            nodes: vec![
+                document_node(1, None, dir_source(&config_registry, "/tmp")),
+                document_node(2, None, config(&config_registry, "ToCsv", &[])),
+            ],
+            edges: vec![edge(1, 2)],
+        };
+        let plan = plan(&config_registry, None, graph, &[], document).unwrap();
+        assert_eq!(summary(&plan), ["remove 2", "add 2 ToCsv", "add edge 1 2"]);
+    }
+
+    #[test]
+    fn test_plan_rewire() {
+        let config_registry = config_registry::new().unwrap();
+        let graph = WorkspaceGraph {
+            nodes: vec![
+                stored_node(1, dir_source(&config_registry, "/tmp")),
+                stored_node(2, inspect(&config_registry)),
+                stored_node(3, inspect(&config_registry)),
+            ],
+            edges: vec![edge(1, 2)],
+        };
+        let document = WorkspaceDocument {
+            nodes: vec![
+                document_node(1, None, dir_source(&config_registry, "/tmp")),
+                document_node(2, None, inspect(&config_registry)),
+                document_node(3, None, inspect(&config_registry)),
+                document_node(4, None, inspect(&config_registry)),
+            ],
+            edges: vec![edge(1, 3)],
+        };
+        // stale edge is removed before nodes are added and new edge is added after them
+        let plan = plan(&config_registry, None, graph, &[], document).unwrap();
+        assert_eq!(
+            summary(&plan),
+            ["remove edge 1", "add 4 Inspect", "add edge 1 3"]
+        );
+    }
+
+    #[test]
+    fn test_plan_invalid_document() {
+        let config_registry = config_registry::new().unwrap();
+        let graph = || WorkspaceGraph {
+            nodes: vec![],
+            edges: vec![],
+        };
+        let documents = [
+            WorkspaceDocument {
+                nodes: vec![
+                    document_node(1, None, inspect(&config_registry)),
+                    document_node(1, None, inspect(&config_registry)),
+                ],
+                edges: vec![],
+            },
+            WorkspaceDocument {
+                nodes: vec![document_node(1, None, inspect(&config_registry))],
+                edges: vec![edge(1, 2)],
+            },
+            WorkspaceDocument {
+                nodes: vec![
+                    document_node(1, None, dir_source(&config_registry, "/tmp")),
+                    document_node(2, None, inspect(&config_registry)),
+                    document_node(3, None, inspect(&config_registry)),
+                ],
+                edges: vec![edge(1, 2), edge(1, 3)],
+            },
+            WorkspaceDocument {
+                nodes: vec![document_node(1, Some("missing"), inspect(&config_registry))],
+                edges: vec![],
+            },
+        ];
+        for document in documents {
+            let err = plan(&config_registry, None, graph(), &[], document).unwrap_err();
+            assert_eq!(err.kind, AppErrorKind::BadRequest);
+        }
+    }
+
+    #[test]
+    fn test_plan_secret_fields() {
+        let config_registry = config_registry::new().unwrap();
+        let master_key = MasterKey::from_base64(&STANDARD.encode([1; 32])).unwrap();
+        let postgres_source = |password: &str| {
+            config(&config_registry, "PostgresSource", &[("password", password)])
+        };
+        let graph = || {
+            let mut config = postgres_source("secret");
+            encryption::encrypt_config(&config_registry, id(1), &mut config, Some(&master_key))
+                .unwrap();
+            WorkspaceGraph {
+                nodes: vec![stored_node(1, config)],
+                edges: vec![],
+            }
+        };
+        let document = |password: &str| WorkspaceDocument {
+            nodes: vec![document_node(1, None, postgres_source(password))],
+            edges: vec![],
+        };
+
+        // empty secret field keeps stored value
+        let plan_for = |password: &str| {
+            plan(
+                &config_registry,
+                Some(&master_key),
+                graph(),
+                &[],
+                document(password),
+            )
+        };
+        assert!(plan_for("").unwrap().is_empty());
+
+        // secret field can be set to variable reference
+        let plan = plan_for("${PG_PASSWORD}").unwrap();
+        assert_eq!(summary(&plan), ["update 1 password"]);
+
+        // plaintext secret values are rejected
+        for password in ["plaintext", "prefix-${PG_PASSWORD}"] {
+            let err = plan_for(password).unwrap_err();
+            assert_eq!(err.kind, AppErrorKind::BadRequest);
+        }
+    }
+}
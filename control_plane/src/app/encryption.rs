@@ -0,0 +1,568 @@
+//! Envelope encryption of secret config fields and variable values
+//!
+//! Every value of `is_password` field and every variable value is encrypted with freshly generated data key,
+//! data key is encrypted (wrapped) with master key and stored alongside the value:
+//! `enc:v1:<master key id>:<wrapped data key>:<encrypted value>`
+//!
+//! Master key id is derived from key itself, which allows to detect values encrypted with
+//! different key and to re-encrypt values on key rotation.
+//!
+//! Values are bound to their location (node id and field name, or variable owner and name) with
+//! associated data, so encrypted value, copied into another node, field or variable, can't be decrypted.
+
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use config::prelude::RawConfig;
+use config_registry::ConfigRegistry;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use sha2::Digest;
+use uuid::Uuid;
+
+use crate::app::{AppError, Result};
+
+const PREFIX: &str = "enc:v1:";
+const KEY_LEN: usize = 32;
+
+pub struct MasterKey {
+    id: String,
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterKey").field("id", &self.id).finish()
+    }
+}
+
+impl MasterKey {
+    /// build master key from base64 encoded 32 bytes
+    pub fn from_base64(key: &str) -> Result<Self> {
+        let key = STANDARD
+            .decode(key.trim())
+            .map_err(|e| anyhow::anyhow!("failed to decode master key: {e}"))?;
+        if key.len() != KEY_LEN {
+            Err(anyhow::anyhow!(
+                "master key should be {KEY_LEN} bytes long, got {}",
+                key.len()
+            ))?
+        }
+        let id = sha2::Sha256::digest(&key)
+            .iter()
+            .take(4)
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        Ok(Self {
+            id,
+            key: LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &key).unwrap()),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let key = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read master key from {path:?}: {e}"))?;
+        Self::from_base64(&key)
+    }
+
+    /// load master key from env var value or file, value takes precedence
+    pub fn load(key: Option<&str>, key_file: Option<&Path>) -> Result<Option<Self>> {
+        match (key, key_file) {
+            (Some(key), _) => Self::from_base64(key).map(Some),
+            (None, Some(path)) => Self::from_file(path).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// encrypt value, `aad` identifies location of value and should be provided to decrypt it
+    pub fn encrypt(&self, value: &str, aad: &str) -> Result<String> {
+        let mut data_key = [0; KEY_LEN];
+        self.fill(&mut data_key)?;
+        let data_key_cipher = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &data_key).unwrap());
+        let wrapped_key = self.seal(&self.key, data_key.to_vec(), aad)?;
+        let payload = self.seal(&data_key_cipher, value.as_bytes().to_vec(), aad)?;
+        Ok(format!(
+            "{PREFIX}{}:{}:{}",
+            self.id,
+            STANDARD.encode(wrapped_key),
+            STANDARD.encode(payload)
+        ))
+    }
+
+    pub fn decrypt(&self, value: &str, aad: &str) -> Result<String> {
+        let (key_id, wrapped_key, payload) = match value
+            .strip_prefix(PREFIX)
+            .map(|value| value.splitn(3, ':').collect::<Vec<_>>())
+            .as_deref()
+        {
+            Some(&[key_id, wrapped_key, payload]) => (key_id, wrapped_key, payload),
+            _ => Err(anyhow::anyhow!("malformed encrypted value"))?,
+        };
+        if key_id != self.id {
+            Err(anyhow::anyhow!(
+                "value is encrypted with master key '{key_id}', current master key is '{}'",
+                self.id
+            ))?
+        }
+        let data_key = open(&self.key, wrapped_key, aad)?;
+        let data_key_cipher = LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, &data_key)
+                .map_err(|_| anyhow::anyhow!("malformed data key"))?,
+        );
+        let value = open(&data_key_cipher, payload, aad)?;
+        Ok(String::from_utf8(value)?)
+    }
+
+    fn fill(&self, buf: &mut [u8]) -> Result<()> {
+        self.rng
+            .fill(buf)
+            .map_err(|_| anyhow::anyhow!("failed to generate random bytes"))?;
+        Ok(())
+    }
+
+    // encrypt data, output is nonce followed by ciphertext and tag
+    fn seal(&self, key: &LessSafeKey, mut data: Vec<u8>, aad: &str) -> Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        self.fill(&mut nonce)?;
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad.as_bytes()),
+            &mut data,
+        )
+        .map_err(|_| anyhow::anyhow!("failed to encrypt value"))?;
+        let mut output = nonce.to_vec();
+        output.extend(data);
+        Ok(output)
+    }
+}
+
+fn open(key: &LessSafeKey, data: &str, aad: &str) -> Result<Vec<u8>> {
+    let mut data = STANDARD
+        .decode(data)
+        .map_err(|e| anyhow::anyhow!("failed to decode encrypted value: {e}"))?;
+    if data.len() < NONCE_LEN {
+        Err(anyhow::anyhow!("malformed encrypted value"))?
+    }
+    let mut payload = data.split_off(NONCE_LEN);
+    let nonce =
+        Nonce::try_assume_unique_for_key(&data).map_err(|_| anyhow::anyhow!("malformed nonce"))?;
+    let len = key
+        .open_in_place(nonce, Aad::from(aad.as_bytes()), &mut payload)
+        .map_err(|_| anyhow::anyhow!("failed to decrypt value"))?
+        .len();
+    payload.truncate(len);
+    Ok(payload)
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+fn key_id(value: &str) -> Option<&str> {
+    value
+        .strip_prefix(PREFIX)
+        .and_then(|value| value.split(':').next())
+}
+
+// associated data of config field value
+fn field_aad(node_id: Uuid, field: &str) -> String {
+    format!("node:{node_id}:{field}")
+}
+
+/// associated data of workspace variable value
+pub fn workspace_variable_aad(workspace_id: i64, name: &str) -> String {
+    format!("workspace:{workspace_id}:variable:{name}")
+}
+
+/// associated data of daemon variable value
+pub fn daemon_variable_aad(daemon_id: Uuid, name: &str) -> String {
+    format!("daemon:{daemon_id}:variable:{name}")
+}
+
+// apply function to every non-empty password field of config, function receives field aad and value
+//
+// stored configs are raw and don't carry field metadata, so config is rebuilt through registry
+// configs, unknown to registry, are left as is
+fn map_secrets(
+    config_registry: &ConfigRegistry,
+    node_id: Uuid,
+    config: &mut Box<dyn config_registry::Config>,
+    mut f: impl FnMut(&str, &str) -> Result<Option<String>>,
+) -> Result<bool> {
+    if config_registry.build_config(config.name()).is_err() {
+        return Ok(false);
+    }
+    let mut built = config_registry
+        .deserialize_config(&**config)
+        .map_err(|e| anyhow::anyhow!("failed to build config '{}': {e}", config.name()))?;
+    let password_fields = built
+        .fields()
+        .into_iter()
+        .filter(|field| field.metadata.is_password)
+        .map(|field| field.name.to_string())
+        .collect::<Vec<_>>();
+    let mut changed = false;
+    for name in password_fields {
+        let value = match built.get_field_value(&name) {
+            Ok(config::FieldValue::String(value)) if !value.is_empty() => value,
+            _ => continue,
+        };
+        if let Some(value) = f(&field_aad(node_id, &name), value)? {
+            built
+                .set_field_value(&name, config::FieldValue::String(&value))
+                .map_err(|e| anyhow::anyhow!("failed to set field '{name}': {e}"))?;
+            changed = true;
+        }
+    }
+    if changed {
+        *config = built;
+    }
+    Ok(changed)
+}
+
+// apply function to every encrypted string field of config of older version, function receives field aad and
+// value and returns plaintext
+//
+// values are bound to field names, which config migrations can rename, and password metadata is known only for
+// current config version, so outdated config is decrypted under stored field names before it's upgraded
+// fields, which current config version declares as non-password, are left as is
+fn decrypt_outdated(
+    config_registry: &ConfigRegistry,
+    node_id: Uuid,
+    config: &mut Box<dyn config_registry::Config>,
+    mut f: impl FnMut(&str, &str) -> Result<String>,
+) -> Result<bool> {
+    let current = match config_registry.build_config(config.name()) {
+        Ok(current) if config.version() < current.version() => current,
+        _ => return Ok(false),
+    };
+    let plain_fields = current
+        .fields()
+        .into_iter()
+        .filter(|field| !field.metadata.is_password)
+        .map(|field| field.name.to_string())
+        .collect::<Vec<_>>();
+    let mut decrypted = vec![];
+    for field in config.fields() {
+        match field.value {
+            config::FieldValue::String(value)
+                if is_encrypted(value) && !plain_fields.iter().any(|name| name == field.name) =>
+            {
+                let value = f(&field_aad(node_id, field.name), value)?;
+                decrypted.push((field.name.to_string(), value));
+            }
+            _ => (),
+        }
+    }
+    if decrypted.is_empty() {
+        return Ok(false);
+    }
+    let mut raw = RawConfig::new(config.name())
+        .with_version(config.version())
+        .with_fields(config.fields().into_iter());
+    for (name, value) in decrypted {
+        raw.set_field(&name, config::FieldValue::String(&value));
+    }
+    *config = serde_json::from_value(serde_json::to_value(&raw)?)?;
+    Ok(true)
+}
+
+/// encrypt password fields of config of node `node_id`, plaintext is stored if master key is not set
+///
+/// Already encrypted values are kept only if they were encrypted for the same node and field with current
+/// master key, such values come from stored configs, e.g. on revision rollback.
+/// Any other encrypted value is rejected.
+pub fn encrypt_config(
+    config_registry: &ConfigRegistry,
+    node_id: Uuid,
+    config: &mut Box<dyn config_registry::Config>,
+    master_key: Option<&MasterKey>,
+) -> Result<()> {
+    let name = config.name().to_string();
+    decrypt_outdated(config_registry, node_id, config, |aad, value| {
+        match master_key.map(|master_key| master_key.decrypt(value, aad)) {
+            Some(Ok(value)) => Ok(value),
+            _ => Err(AppError::bad_request(anyhow::anyhow!(
+                "config '{name}' of node {node_id} contains encrypted value, which can't be decrypted"
+            )))?,
+        }
+    })?;
+    map_secrets(config_registry, node_id, config, |aad, value| {
+        match (is_encrypted(value), master_key) {
+            (false, None) => Ok(None),
+            (false, Some(master_key)) => master_key.encrypt(value, aad).map(Some),
+            (true, Some(master_key)) if master_key.decrypt(value, aad).is_ok() => Ok(None),
+            (true, _) => Err(AppError::bad_request(anyhow::anyhow!(
+                "config '{name}' of node {node_id} contains encrypted value, which can't be decrypted"
+            )))?,
+        }
+    })?;
+    Ok(())
+}
+
+/// decrypt password fields of config of node `node_id`
+pub fn decrypt_config(
+    config_registry: &ConfigRegistry,
+    node_id: Uuid,
+    config: &mut Box<dyn config_registry::Config>,
+    master_key: Option<&MasterKey>,
+) -> Result<()> {
+    decrypt_outdated(config_registry, node_id, config, |aad, value| {
+        decrypt_value(master_key, aad, value)
+    })?;
+    let name = config.name().to_string();
+    map_secrets(config_registry, node_id, config, |aad, value| {
+        match (is_encrypted(value), master_key) {
+            (false, _) => Ok(None),
+            (true, Some(master_key)) => master_key.decrypt(value, aad).map(Some),
+            (true, None) => Err(anyhow::anyhow!(
+                "config '{name}' contains encrypted values, but master key is not set"
+            ))?,
+        }
+    })?;
+    Ok(())
+}
+
+/// re-encrypt password fields of config from `old_key` to `new_key`
+///
+/// plaintext values are encrypted, values already encrypted with `new_key` are left as is
+/// returns true if config was modified
+pub fn reencrypt_config(
+    config_registry: &ConfigRegistry,
+    node_id: Uuid,
+    config: &mut Box<dyn config_registry::Config>,
+    old_key: Option<&MasterKey>,
+    new_key: Option<&MasterKey>,
+) -> Result<bool> {
+    let decrypted = decrypt_outdated(config_registry, node_id, config, |aad, value| {
+        match key_id(value) == new_key.map(MasterKey::id) {
+            true => decrypt_value(new_key, aad, value),
+            false => decrypt_value(old_key, aad, value),
+        }
+    })?;
+    let reencrypted = map_secrets(config_registry, node_id, config, |aad, value| {
+        reencrypt_value(aad, value, old_key, new_key)
+    })?;
+    Ok(decrypted || reencrypted)
+}
+
+/// encrypt variable value, plaintext is stored if master key is not set
+pub fn encrypt_value(master_key: Option<&MasterKey>, aad: &str, value: &str) -> Result<String> {
+    match master_key {
+        Some(master_key) => master_key.encrypt(value, aad),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// decrypt variable value, plaintext values are returned as is
+pub fn decrypt_value(master_key: Option<&MasterKey>, aad: &str, value: &str) -> Result<String> {
+    match (is_encrypted(value), master_key) {
+        (false, _) => Ok(value.to_string()),
+        (true, Some(master_key)) => master_key.decrypt(value, aad),
+        (true, None) => Err(anyhow::anyhow!(
+            "value is encrypted with master key '{}', but master key is not set",
+            key_id(value).unwrap_or("")
+        ))?,
+    }
+}
+
+/// re-encrypt value from `old_key` to `new_key`, returns new value if it was changed
+///
+/// plaintext value is encrypted, value already encrypted with `new_key` is left as is
+pub fn reencrypt_value(
+    aad: &str,
+    value: &str,
+    old_key: Option<&MasterKey>,
+    new_key: Option<&MasterKey>,
+) -> Result<Option<String>> {
+    if key_id(value).is_some() && key_id(value) == new_key.map(MasterKey::id) {
+        return Ok(None);
+    }
+    let plaintext = decrypt_value(old_key, aad, value)?;
+    match new_key {
+        Some(new_key) => new_key.encrypt(&plaintext, aad).map(Some),
+        None if plaintext != value => Ok(Some(plaintext)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> MasterKey {
+        MasterKey::from_base64(&STANDARD.encode([byte; KEY_LEN])).unwrap()
+    }
+
+    fn postgres_source(
+        config_registry: &ConfigRegistry,
+        password: &str,
+    ) -> Box<dyn config_registry::Config> {
+        let mut config = config_registry.build_config("PostgresSource").unwrap();
+        config
+            .set_field_value("password", config::FieldValue::String(password))
+            .unwrap();
+        config
+    }
+
+    fn password(config: &dyn config_registry::Config) -> String {
+        match config.get_field_value("password").unwrap() {
+            config::FieldValue::String(value) => value.to_string(),
+            value => panic!("unexpected value: {value:?}"),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let key = key(1);
+        let encrypted = key.encrypt("secret", "aad").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(key_id(&encrypted), Some(key.id()));
+        assert!(!encrypted.contains("secret"));
+        assert_eq!(key.decrypt(&encrypted, "aad").unwrap(), "secret");
+        // data key and nonce are random
+        assert_ne!(encrypted, key.encrypt("secret", "aad").unwrap());
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let encrypted = key(1).encrypt("secret", "aad").unwrap();
+        assert!(key(2).decrypt(&encrypted, "aad").is_err());
+        // key id mismatch is detected before decryption, forged key id still doesn't decrypt
+        let forged = encrypted.replacen(key(1).id(), key(2).id(), 1);
+        assert!(key(2).decrypt(&forged, "aad").is_err());
+    }
+
+    #[test]
+    fn test_wrong_aad() {
+        let key = key(1);
+        let encrypted = key.encrypt("secret", "aad").unwrap();
+        assert!(key.decrypt(&encrypted, "other").is_err());
+        assert!(key.decrypt("enc:v1:malformed", "aad").is_err());
+    }
+
+    #[test]
+    fn test_config_roundtrip() {
+        let config_registry = config_registry::new().unwrap();
+        let key = key(1);
+        let node_id = Uuid::from_u128(1);
+        let mut config = postgres_source(&config_registry, "secret");
+        encrypt_config(&config_registry, node_id, &mut config, Some(&key)).unwrap();
+        let encrypted = password(&*config);
+        assert!(is_encrypted(&encrypted));
+
+        // stored value of the same node and field is kept as is
+        encrypt_config(&config_registry, node_id, &mut config, Some(&key)).unwrap();
+        assert_eq!(password(&*config), encrypted);
+
+        // value can't be decrypted in context of another node
+        let mut copied = config.clone();
+        assert!(decrypt_config(
+            &config_registry,
+            Uuid::from_u128(2),
+            &mut copied,
+            Some(&key)
+        )
+        .is_err());
+
+        decrypt_config(&config_registry, node_id, &mut config, Some(&key)).unwrap();
+        assert_eq!(password(&*config), "secret");
+    }
+
+    #[test]
+    fn test_config_rejects_foreign_ciphertext() {
+        let config_registry = config_registry::new().unwrap();
+        let (key, other_key) = (key(1), key(2));
+        let node_id = Uuid::from_u128(1);
+        let foreign = [
+            // encrypted for another node
+            key.encrypt("secret", &field_aad(Uuid::from_u128(2), "password"))
+                .unwrap(),
+            // encrypted for another field
+            key.encrypt("secret", &field_aad(node_id, "user")).unwrap(),
+            // encrypted with another key
+            other_key
+                .encrypt("secret", &field_aad(node_id, "password"))
+                .unwrap(),
+            "enc:v1:malformed".to_string(),
+        ];
+        for value in foreign {
+            let mut config = postgres_source(&config_registry, &value);
+            assert!(encrypt_config(&config_registry, node_id, &mut config, Some(&key)).is_err());
+            let mut config = postgres_source(&config_registry, &value);
+            assert!(encrypt_config(&config_registry, node_id, &mut config, None).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reencrypt_config() {
+        let config_registry = config_registry::new().unwrap();
+        let (old_key, new_key) = (key(1), key(2));
+        let node_id = Uuid::from_u128(1);
+        let mut config = postgres_source(&config_registry, "secret");
+        encrypt_config(&config_registry, node_id, &mut config, Some(&old_key)).unwrap();
+        assert!(reencrypt_config(
+            &config_registry,
+            node_id,
+            &mut config,
+            Some(&old_key),
+            Some(&new_key)
+        )
+        .unwrap());
+        assert_eq!(key_id(&password(&*config)), Some(new_key.id()));
+        assert!(decrypt_config(
+            &config_registry,
+            node_id,
+            &mut config.clone(),
+            Some(&old_key)
+        )
+        .is_err());
+        decrypt_config(&config_registry, node_id, &mut config, Some(&new_key)).unwrap();
+        assert_eq!(password(&*config), "secret");
+    }
+
+    #[test]
+    fn test_variable_value() {
+        let (old_key, new_key) = (key(1), key(2));
+        let aad = workspace_variable_aad(1, "PASSWORD");
+
+        // plaintext is stored and read as is without master key
+        assert_eq!(encrypt_value(None, &aad, "secret").unwrap(), "secret");
+        assert_eq!(decrypt_value(None, &aad, "secret").unwrap(), "secret");
+
+        let encrypted = encrypt_value(Some(&old_key), &aad, "secret").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(decrypt_value(Some(&old_key), &aad, &encrypted).unwrap(), "secret");
+        assert!(decrypt_value(None, &aad, &encrypted).is_err());
+        for aad in [
+            workspace_variable_aad(1, "OTHER"),
+            workspace_variable_aad(2, "PASSWORD"),
+            daemon_variable_aad(Uuid::from_u128(1), "PASSWORD"),
+        ] {
+            assert!(decrypt_value(Some(&old_key), &aad, &encrypted).is_err());
+        }
+
+        let reencrypted = reencrypt_value(&aad, &encrypted, Some(&old_key), Some(&new_key))
+            .unwrap()
+            .unwrap();
+        assert_eq!(key_id(&reencrypted), Some(new_key.id()));
+        assert_eq!(decrypt_value(Some(&new_key), &aad, &reencrypted).unwrap(), "secret");
+        // value, encrypted with new key, is left as is
+        assert!(reencrypt_value(&aad, &reencrypted, Some(&old_key), Some(&new_key))
+            .unwrap()
+            .is_none());
+        // plaintext value is encrypted
+        let encrypted = reencrypt_value(&aad, "secret", None, Some(&new_key))
+            .unwrap()
+            .unwrap();
+        assert_eq!(decrypt_value(Some(&new_key), &aad, &encrypted).unwrap(), "secret");
+    }
+}
@@ -0,0 +1,78 @@
+use super::m0001;
+use sea_query::{ColumnDef, ForeignKey, ForeignKeyAction, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Variables, referenced in node configs
+
+#[derive(Iden)]
+enum WorkspaceVariables {
+    Table,
+    WorkspaceId,
+    Name,
+    Value,
+}
+
+impl WorkspaceVariables {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(WorkspaceVariables::Table)
+            .col(
+                ColumnDef::new(WorkspaceVariables::WorkspaceId)
+                    .big_integer()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(WorkspaceVariables::Name).string().not_null())
+            .col(
+                ColumnDef::new(WorkspaceVariables::Value)
+                    .string()
+                    .not_null(),
+            )
+            .primary_key(
+                Index::create()
+                    .col(WorkspaceVariables::WorkspaceId)
+                    .col(WorkspaceVariables::Name),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(WorkspaceVariables::Table, WorkspaceVariables::WorkspaceId)
+                    .to(m0001::Workspaces::Table, m0001::Workspaces::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+// daemon variables are removed with daemon
+#[derive(Iden)]
+enum DaemonVariables {
+    Table,
+    DaemonId,
+    Name,
+    Value,
+}
+
+impl DaemonVariables {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(DaemonVariables::Table)
+            .col(ColumnDef::new(DaemonVariables::DaemonId).uuid().not_null())
+            .col(ColumnDef::new(DaemonVariables::Name).string().not_null())
+            .col(ColumnDef::new(DaemonVariables::Value).string().not_null())
+            .primary_key(
+                Index::create()
+                    .col(DaemonVariables::DaemonId)
+                    .col(DaemonVariables::Name),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [
+        WorkspaceVariables::into_query(schema_builder),
+        DaemonVariables::into_query(schema_builder),
+    ]
+    .join(";\n");
+    Migration::new(3, "variables".into(), MigrationType::Simple, sql.into())
+}
@@ -0,0 +1,59 @@
+use sea_query::{ColumnDef, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Daemon capabilities, reported by daemon on connect
+
+#[derive(Iden)]
+enum Daemons {
+    Table,
+    Version,
+}
+
+impl Daemons {
+    // version is not set until daemon reports its capabilities
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::alter()
+            .table(Daemons::Table)
+            .add_column(ColumnDef::new(Daemons::Version).string())
+            .build_any(schema_builder)
+    }
+}
+
+// section types, available on daemon, removed with daemon
+#[derive(Iden)]
+enum DaemonSections {
+    Table,
+    DaemonId,
+    Name,
+    Version,
+}
+
+impl DaemonSections {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(DaemonSections::Table)
+            .col(ColumnDef::new(DaemonSections::DaemonId).uuid().not_null())
+            .col(ColumnDef::new(DaemonSections::Name).string().not_null())
+            .col(ColumnDef::new(DaemonSections::Version).integer().not_null())
+            .primary_key(
+                Index::create()
+                    .col(DaemonSections::DaemonId)
+                    .col(DaemonSections::Name),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [
+        Daemons::into_query(schema_builder),
+        DaemonSections::into_query(schema_builder),
+    ]
+    .join(";\n");
+    Migration::new(
+        4,
+        "daemon_capabilities".into(),
+        MigrationType::Simple,
+        sql.into(),
+    )
+}
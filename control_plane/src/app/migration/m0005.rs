@@ -0,0 +1,50 @@
+use sea_query::{ColumnDef, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Messages, relayed between daemons over boundary edges
+//
+// sqlite doesn't support multiple alter options in single statement, so each column is added separately
+
+#[derive(Iden)]
+enum Messages {
+    Table,
+    FromDaemonId,
+    FromMessageId,
+}
+
+impl Messages {
+    fn add_column(schema_builder: &dyn SchemaBuilder, column: Messages) -> String {
+        Table::alter()
+            .table(Messages::Table)
+            .add_column(ColumnDef::new(column).uuid())
+            .build_any(schema_builder)
+    }
+}
+
+struct MessagesFromIndex;
+
+impl MessagesFromIndex {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Index::create()
+            .name("messages_from_idx")
+            .table(Messages::Table)
+            .col(Messages::FromDaemonId)
+            .col(Messages::FromMessageId)
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [
+        Messages::add_column(schema_builder, Messages::FromDaemonId),
+        Messages::add_column(schema_builder, Messages::FromMessageId),
+        MessagesFromIndex::into_query(schema_builder),
+    ]
+    .join(";\n");
+    Migration::new(
+        5,
+        "relay_messages".into(),
+        MigrationType::Simple,
+        sql.into(),
+    )
+}
@@ -0,0 +1,74 @@
+use super::m0001;
+use sea_query::{ColumnDef, ForeignKey, ForeignKeyAction, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Workspace revisions
+//
+// Each applied workspace update is stored with operations, which revert it, to support rollback
+
+#[derive(Iden)]
+enum WorkspaceRevisions {
+    Table,
+    WorkspaceId,
+    Revision,
+    Operations,
+    Inverse,
+    Author,
+    CreatedAt,
+}
+
+impl WorkspaceRevisions {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(WorkspaceRevisions::Table)
+            .col(
+                ColumnDef::new(WorkspaceRevisions::WorkspaceId)
+                    .big_integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(WorkspaceRevisions::Revision)
+                    .big_integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(WorkspaceRevisions::Operations)
+                    .json()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(WorkspaceRevisions::Inverse)
+                    .json()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(WorkspaceRevisions::Author).string())
+            .col(
+                ColumnDef::new(WorkspaceRevisions::CreatedAt)
+                    .timestamp()
+                    .not_null(),
+            )
+            .primary_key(
+                Index::create()
+                    .col(WorkspaceRevisions::WorkspaceId)
+                    .col(WorkspaceRevisions::Revision),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(WorkspaceRevisions::Table, WorkspaceRevisions::WorkspaceId)
+                    .to(m0001::Workspaces::Table, m0001::Workspaces::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [WorkspaceRevisions::into_query(schema_builder)].join(";\n");
+    Migration::new(
+        6,
+        "workspace_revisions".into(),
+        MigrationType::Simple,
+        sql.into(),
+    )
+}
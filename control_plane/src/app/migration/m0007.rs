@@ -0,0 +1,132 @@
+use sea_query::{ColumnDef, ForeignKey, ForeignKeyAction, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Users, their UI sessions and API tokens
+//
+// session and token secrets are stored as hashes
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+    Email,
+    PasswordHash,
+    CreatedAt,
+}
+
+impl Users {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(Users::Table)
+            .col(ColumnDef::new(Users::Id).uuid().primary_key().not_null())
+            .col(
+                ColumnDef::new(Users::Email)
+                    .string()
+                    .unique_key()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(Users::PasswordHash).string().not_null())
+            .col(ColumnDef::new(Users::CreatedAt).timestamp().not_null())
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum UserSessions {
+    Table,
+    Id,
+    UserId,
+    CreatedAt,
+    ExpiresAt,
+}
+
+impl UserSessions {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(UserSessions::Table)
+            .col(
+                ColumnDef::new(UserSessions::Id)
+                    .string()
+                    .primary_key()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(UserSessions::UserId).uuid().not_null())
+            .col(
+                ColumnDef::new(UserSessions::CreatedAt)
+                    .timestamp()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(UserSessions::ExpiresAt)
+                    .timestamp()
+                    .not_null(),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(UserSessions::Table, UserSessions::UserId)
+                    .to(Users::Table, Users::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum ApiTokens {
+    Table,
+    Id,
+    UserId,
+    Name,
+    SecretHash,
+    CreatedAt,
+}
+
+impl ApiTokens {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(ApiTokens::Table)
+            .col(
+                ColumnDef::new(ApiTokens::Id)
+                    .uuid()
+                    .primary_key()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(ApiTokens::UserId).uuid().not_null())
+            .col(ColumnDef::new(ApiTokens::Name).string().not_null())
+            .col(ColumnDef::new(ApiTokens::SecretHash).string().not_null())
+            .col(ColumnDef::new(ApiTokens::CreatedAt).timestamp().not_null())
+            .foreign_key(
+                ForeignKey::create()
+                    .from(ApiTokens::Table, ApiTokens::UserId)
+                    .to(Users::Table, Users::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+struct ApiTokensSecretHashIndex;
+
+impl ApiTokensSecretHashIndex {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Index::create()
+            .name("api_tokens_secret_hash_idx")
+            .table(ApiTokens::Table)
+            .col(ApiTokens::SecretHash)
+            .unique()
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [
+        Users::into_query(schema_builder),
+        UserSessions::into_query(schema_builder),
+        ApiTokens::into_query(schema_builder),
+        ApiTokensSecretHashIndex::into_query(schema_builder),
+    ]
+    .join(";\n");
+    Migration::new(7, "users".into(), MigrationType::Simple, sql.into())
+}
@@ -0,0 +1,252 @@
+use sea_query::{ColumnDef, ForeignKey, ForeignKeyAction, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Organizations, workspace roles and node run state
+//
+// workspaces, daemons and daemon tokens belong to organization
+// existing rows are assigned to default organization on control plane start
+
+#[derive(Iden)]
+enum Organizations {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+impl Organizations {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(Organizations::Table)
+            .col(
+                ColumnDef::new(Organizations::Id)
+                    .uuid()
+                    .primary_key()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(Organizations::Name)
+                    .string()
+                    .unique_key()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(Organizations::CreatedAt)
+                    .timestamp()
+                    .not_null(),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+    IsAdmin,
+}
+
+impl Users {
+    // instance admins manage users and organizations and have access to every workspace
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::alter()
+            .table(Users::Table)
+            .add_column(
+                ColumnDef::new(Users::IsAdmin)
+                    .boolean()
+                    .not_null()
+                    .default(false),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum OrganizationMembers {
+    Table,
+    OrganizationId,
+    UserId,
+    Role,
+}
+
+impl OrganizationMembers {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(OrganizationMembers::Table)
+            .col(
+                ColumnDef::new(OrganizationMembers::OrganizationId)
+                    .uuid()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(OrganizationMembers::UserId)
+                    .uuid()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(OrganizationMembers::Role)
+                    .string()
+                    .not_null(),
+            )
+            .primary_key(
+                Index::create()
+                    .col(OrganizationMembers::OrganizationId)
+                    .col(OrganizationMembers::UserId),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(
+                        OrganizationMembers::Table,
+                        OrganizationMembers::OrganizationId,
+                    )
+                    .to(Organizations::Table, Organizations::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(OrganizationMembers::Table, OrganizationMembers::UserId)
+                    .to(Users::Table, Users::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum Workspaces {
+    Table,
+    Id,
+    OrganizationId,
+}
+
+impl Workspaces {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::alter()
+            .table(Workspaces::Table)
+            .add_column(ColumnDef::new(Workspaces::OrganizationId).uuid())
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum WorkspaceMembers {
+    Table,
+    WorkspaceId,
+    UserId,
+    Role,
+}
+
+impl WorkspaceMembers {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(WorkspaceMembers::Table)
+            .col(
+                ColumnDef::new(WorkspaceMembers::WorkspaceId)
+                    .big_integer()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(WorkspaceMembers::UserId).uuid().not_null())
+            .col(ColumnDef::new(WorkspaceMembers::Role).string().not_null())
+            .primary_key(
+                Index::create()
+                    .col(WorkspaceMembers::WorkspaceId)
+                    .col(WorkspaceMembers::UserId),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(WorkspaceMembers::Table, WorkspaceMembers::WorkspaceId)
+                    .to(Workspaces::Table, Workspaces::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(WorkspaceMembers::Table, WorkspaceMembers::UserId)
+                    .to(Users::Table, Users::Id)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum Daemons {
+    Table,
+    OrganizationId,
+}
+
+impl Daemons {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::alter()
+            .table(Daemons::Table)
+            .add_column(ColumnDef::new(Daemons::OrganizationId).uuid())
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum DaemonTokens {
+    Table,
+    OrganizationId,
+}
+
+impl DaemonTokens {
+    // daemon joins organization of token
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::alter()
+            .table(DaemonTokens::Table)
+            .add_column(ColumnDef::new(DaemonTokens::OrganizationId).uuid())
+            .build_any(schema_builder)
+    }
+}
+
+#[derive(Iden)]
+enum Nodes {
+    Table,
+    Paused,
+    Restarts,
+}
+
+// paused nodes stop their pipelines, restart counter is sent to daemon to restart pipeline
+// sqlite doesn't support multiple alter options in single statement, so each column is added separately
+impl Nodes {
+    fn add_column(schema_builder: &dyn SchemaBuilder, mut column: ColumnDef) -> String {
+        Table::alter()
+            .table(Nodes::Table)
+            .add_column(&mut column)
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [
+        Organizations::into_query(schema_builder),
+        Users::into_query(schema_builder),
+        OrganizationMembers::into_query(schema_builder),
+        Workspaces::into_query(schema_builder),
+        WorkspaceMembers::into_query(schema_builder),
+        Daemons::into_query(schema_builder),
+        DaemonTokens::into_query(schema_builder),
+        Nodes::add_column(
+            schema_builder,
+            ColumnDef::new(Nodes::Paused)
+                .boolean()
+                .not_null()
+                .default(false)
+                .to_owned(),
+        ),
+        Nodes::add_column(
+            schema_builder,
+            ColumnDef::new(Nodes::Restarts)
+                .big_integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+        ),
+    ]
+    .join(";\n");
+    Migration::new(8, "organizations".into(), MigrationType::Simple, sql.into())
+}
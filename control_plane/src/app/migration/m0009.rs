@@ -0,0 +1,64 @@
+use sea_query::{ColumnDef, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Audit log of control plane mutations
+//
+// log is append-only, entries are never updated or deleted
+
+#[derive(Iden)]
+enum AuditLog {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    Actor,
+    Address,
+    OrganizationId,
+    Action,
+    Target,
+    Payload,
+}
+
+impl AuditLog {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(AuditLog::Table)
+            .col(
+                ColumnDef::new(AuditLog::Id)
+                    .big_integer()
+                    .primary_key()
+                    .auto_increment(),
+            )
+            .col(ColumnDef::new(AuditLog::CreatedAt).timestamp().not_null())
+            // users can be deleted, so actor email is stored too
+            .col(ColumnDef::new(AuditLog::UserId).uuid())
+            .col(ColumnDef::new(AuditLog::Actor).string().not_null())
+            .col(ColumnDef::new(AuditLog::Address).string())
+            .col(ColumnDef::new(AuditLog::OrganizationId).uuid())
+            .col(ColumnDef::new(AuditLog::Action).string().not_null())
+            .col(ColumnDef::new(AuditLog::Target).string().not_null())
+            .col(ColumnDef::new(AuditLog::Payload).json().not_null())
+            .build_any(schema_builder)
+    }
+}
+
+struct AuditLogCreatedAtIndex;
+
+impl AuditLogCreatedAtIndex {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Index::create()
+            .name("audit_log_created_at_idx")
+            .table(AuditLog::Table)
+            .col(AuditLog::CreatedAt)
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [
+        AuditLog::into_query(schema_builder),
+        AuditLogCreatedAtIndex::into_query(schema_builder),
+    ]
+    .join(";\n");
+    Migration::new(9, "audit log".into(), MigrationType::Simple, sql.into())
+}
@@ -0,0 +1,68 @@
+use sea_query::{ColumnDef, Iden, Index, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Issued daemon certificates
+//
+// revoked certificates are listed in CRL, which is checked on daemon TLS handshake
+// rows are kept after daemon deletion, so certificate stays revoked
+
+#[derive(Iden)]
+enum DaemonCertificates {
+    Table,
+    SerialNumber,
+    DaemonId,
+    IssuedAt,
+    RevokedAt,
+}
+
+impl DaemonCertificates {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::create()
+            .table(DaemonCertificates::Table)
+            // hex encoded
+            .col(
+                ColumnDef::new(DaemonCertificates::SerialNumber)
+                    .string()
+                    .primary_key()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(DaemonCertificates::DaemonId)
+                    .uuid()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(DaemonCertificates::IssuedAt)
+                    .timestamp()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(DaemonCertificates::RevokedAt).timestamp())
+            .build_any(schema_builder)
+    }
+}
+
+struct DaemonCertificatesDaemonIdIndex;
+
+impl DaemonCertificatesDaemonIdIndex {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Index::create()
+            .name("daemon_certificates_daemon_id_idx")
+            .table(DaemonCertificates::Table)
+            .col(DaemonCertificates::DaemonId)
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [
+        DaemonCertificates::into_query(schema_builder),
+        DaemonCertificatesDaemonIdIndex::into_query(schema_builder),
+    ]
+    .join(";\n");
+    Migration::new(
+        10,
+        "daemon certificates".into(),
+        MigrationType::Simple,
+        sql.into(),
+    )
+}
@@ -0,0 +1,41 @@
+use sea_query::{ColumnDef, Iden, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Nodes, stored before variable references were introduced
+//
+// literal `${` in template fields of flagged node configs is escaped on control plane start,
+// nodes, created afterwards, are not flagged
+
+#[derive(Iden)]
+enum Nodes {
+    Table,
+    EscapeTemplates,
+}
+
+impl Nodes {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::alter()
+            .table(Nodes::Table)
+            .add_column(ColumnDef::new(Nodes::EscapeTemplates).boolean())
+            .build_any(schema_builder)
+    }
+
+    // migrations are built only with schema builder, statement is portable across supported databases
+    fn flag_query() -> String {
+        format!(
+            "UPDATE {} SET {} = TRUE",
+            Nodes::Table.to_string(),
+            Nodes::EscapeTemplates.to_string()
+        )
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    let sql = [Nodes::into_query(schema_builder), Nodes::flag_query()].join(";\n");
+    Migration::new(
+        11,
+        "escape templates".into(),
+        MigrationType::Simple,
+        sql.into(),
+    )
+}
@@ -0,0 +1,35 @@
+use sea_query::{Iden, Index, SchemaBuilder};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Workspace names are unique within organization
+//
+// migration fails if organization already has several workspaces with same name,
+// such workspaces were not addressable by name anyway and should be renamed before upgrade
+
+#[derive(Iden)]
+enum Workspaces {
+    Table,
+    OrganizationId,
+    Name,
+}
+
+impl Workspaces {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Index::create()
+            .name("workspaces_organization_id_name_idx")
+            .table(Workspaces::Table)
+            .col(Workspaces::OrganizationId)
+            .col(Workspaces::Name)
+            .unique()
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    Migration::new(
+        12,
+        "unique workspace names".into(),
+        MigrationType::Simple,
+        Workspaces::into_query(schema_builder).into(),
+    )
+}
@@ -0,0 +1,31 @@
+use sea_query::{ColumnDef, Iden, SchemaBuilder, Table};
+use sqlx::migrate::{Migration, MigrationType};
+
+// Daemon revocation
+//
+// revocation is stored per daemon, so daemon, which certificate serial number was never recorded,
+// can be revoked too, such daemon is rejected on connection regardless of presented certificate
+
+#[derive(Iden)]
+enum Daemons {
+    Table,
+    RevokedAt,
+}
+
+impl Daemons {
+    fn into_query(schema_builder: &dyn SchemaBuilder) -> String {
+        Table::alter()
+            .table(Daemons::Table)
+            .add_column(ColumnDef::new(Daemons::RevokedAt).timestamp())
+            .build_any(schema_builder)
+    }
+}
+
+pub fn into_migration(schema_builder: &dyn SchemaBuilder) -> Migration {
+    Migration::new(
+        13,
+        "daemon revocation".into(),
+        MigrationType::Simple,
+        Daemons::into_query(schema_builder).into(),
+    )
+}
@@ -1,5 +1,16 @@
 mod m0001;
 mod m0002;
+mod m0003;
+mod m0004;
+mod m0005;
+mod m0006;
+mod m0007;
+mod m0008;
+mod m0009;
+mod m0010;
+mod m0011;
+mod m0012;
+mod m0013;
 
 use futures::future::BoxFuture;
 use sea_query::SchemaBuilder;
@@ -31,6 +42,17 @@ impl<'s> MigrationSource<'s> for SQSource<'s> {
             Ok(vec![
                 m0001::into_migration(self.schema_builder),
                 m0002::into_migration(self.schema_builder),
+                m0003::into_migration(self.schema_builder),
+                m0004::into_migration(self.schema_builder),
+                m0005::into_migration(self.schema_builder),
+                m0006::into_migration(self.schema_builder),
+                m0007::into_migration(self.schema_builder),
+                m0008::into_migration(self.schema_builder),
+                m0009::into_migration(self.schema_builder),
+                m0010::into_migration(self.schema_builder),
+                m0011::into_migration(self.schema_builder),
+                m0012::into_migration(self.schema_builder),
+                m0013::into_migration(self.schema_builder),
             ])
         })
     }
@@ -1,15 +1,36 @@
+pub mod access;
+pub mod audit;
+pub mod auth;
 pub mod daemon_tracker;
 pub mod db;
+pub mod document;
+pub mod encryption;
 pub mod migration;
 pub mod tables;
 
+use access::{
+    MemberRequest, Organization, OrganizationMember, OrganizationRole, Role, WorkspaceMember,
+};
+use audit::{AuditAction, AuditEntry, AuditFilter, AuditPage};
+use auth::{ApiToken, Credentials, NewApiToken, User};
 use chrono::{DateTime, Utc};
-use config_registry::{self, ConfigRegistry};
+use config::SectionIO;
+use config_registry::{
+    self,
+    plugin::{PluginConfig, SectionDescriptor},
+    ConfigRegistry,
+};
 use daemon_tracker::DaemonMessage;
+use document::{WorkspaceApplyResult, WorkspaceDocument};
+use encryption::MasterKey;
 use pki::{CertificateDer, CertifiedKey, KeyPair};
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    net::IpAddr,
+    sync::{Arc, RwLock},
+};
 use tokio::sync::mpsc::UnboundedReceiver;
 use uuid::Uuid;
 
@@ -18,6 +39,7 @@ pub type Result<T, E = AppError> = std::result::Result<T, E>;
 #[derive(Debug, PartialEq, Eq)]
 pub enum AppErrorKind {
     Unauthorized,
+    Forbidden,
     BadRequest,
     NotFound,
     Internal,
@@ -27,6 +49,9 @@ pub enum AppErrorKind {
     ConfigNotFound,
     ConfigIsInvalid,
     DaemonNotFound,
+    SectionNotSupported,
+    InvalidEdge,
+    CyclicEdge,
 }
 
 #[derive(Debug)]
@@ -96,12 +121,72 @@ impl AppError {
         }
     }
 
+    pub fn invalid_config_fields(name: &str, errors: config::ValidationErrors) -> Self {
+        Self {
+            kind: AppErrorKind::ConfigIsInvalid,
+            err: anyhow::Error::new(errors).context(format!("configuration for {name} is invalid")),
+        }
+    }
+
     pub fn daemon_not_found(id: Uuid) -> Self {
         Self {
             kind: AppErrorKind::DaemonNotFound,
             err: anyhow::anyhow!("daemon with {id} not found"),
         }
     }
+
+    pub fn section_not_supported(node_id: Uuid, daemon_id: Uuid, reason: &str) -> Self {
+        Self {
+            kind: AppErrorKind::SectionNotSupported,
+            err: anyhow::anyhow!(
+                "node {node_id} can't be assigned to daemon {daemon_id}: {reason}"
+            ),
+        }
+    }
+
+    pub fn invalid_edge(edge: InvalidEdge) -> Self {
+        Self {
+            kind: AppErrorKind::InvalidEdge,
+            err: anyhow::Error::new(edge),
+        }
+    }
+
+    pub fn cyclic_edge(from: Uuid, to: Uuid) -> Self {
+        Self {
+            kind: AppErrorKind::CyclicEdge,
+            err: anyhow::anyhow!("edge from node {from} to node {to} forms a cycle"),
+        }
+    }
+
+    pub fn unauthorized(err: anyhow::Error) -> Self {
+        Self {
+            kind: AppErrorKind::Unauthorized,
+            err,
+        }
+    }
+
+    pub fn forbidden(err: anyhow::Error) -> Self {
+        Self {
+            kind: AppErrorKind::Forbidden,
+            err,
+        }
+    }
+
+    pub fn bad_request(err: anyhow::Error) -> Self {
+        Self {
+            kind: AppErrorKind::BadRequest,
+            err,
+        }
+    }
+
+    pub fn invalid_node_config(id: Uuid, err: impl Into<anyhow::Error>) -> Self {
+        Self {
+            kind: AppErrorKind::ConfigIsInvalid,
+            err: err
+                .into()
+                .context(format!("configuration of node {id} is invalid")),
+        }
+    }
 }
 
 impl<E: Into<anyhow::Error>> From<E> for AppError {
@@ -122,6 +207,16 @@ pub struct Workspace {
     pub name: String,
     #[serde(default)]
     pub created_at: Option<DateTime<Utc>>,
+    /// organization, workspace belongs to, can be omitted if user is member of single organization
+    #[serde(default)]
+    pub organization_id: Option<Uuid>,
+}
+
+/// Workspace, resolved from name, names are unique within organization
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorkspaceKey {
+    pub organization_id: Option<Uuid>,
+    pub name: String,
 }
 
 /// Workspace State
@@ -149,6 +244,14 @@ pub struct WorkspaceNode {
     pub daemon_id: Option<Uuid>,
     pub x: f64,
     pub y: f64,
+    /// reason why assigned daemon can't run node section, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incompatible: Option<String>,
+    /// reason why node was skipped in graph of assigned daemon, e.g. undefined variable, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// pipeline of paused node is not scheduled on daemons
+    pub paused: bool,
 }
 
 impl WorkspaceNode {
@@ -167,21 +270,18 @@ impl WorkspaceNode {
             daemon_id,
             x,
             y,
+            incompatible: None,
+            error: None,
+            paused: false,
         }
     }
 
     pub fn strip_secrets(&mut self, config_registry: &ConfigRegistry) -> Result<()> {
-        match config_registry.deserialize_config(&*self.config) {
-            Ok(mut config) => {
-                config.strip_secrets();
-                std::mem::swap(&mut self.config, &mut config);
-                Ok(())
-            }
-            Err(e) => Err(anyhow::anyhow!(
-                "failed to build config from config registry for {}: {e}",
-                self.config.name()
-            ))?,
-        }
+        strip_config_secrets(config_registry, &mut self.config)
+    }
+
+    pub fn hide_restricted_config(&mut self, config_registry: &ConfigRegistry) -> Result<()> {
+        access::hide_restricted_config(config_registry, &mut self.config)
     }
 }
 
@@ -191,10 +291,75 @@ pub struct DaemonGraph {
     pub edges: Vec<Edge>,
 }
 
+/// Daemon capabilities, reported by daemon on connect
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// daemon version
+    #[serde(default)]
+    pub version: String,
+    /// section types, available on daemon, including plugin sections
+    #[serde(default)]
+    pub sections: Vec<SectionVersion>,
+    /// sections, provided by plugins loaded on daemon
+    #[serde(default)]
+    pub plugin_sections: Vec<SectionDescriptor>,
+}
+
+/// Section type with latest config version, supported by daemon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionVersion {
+    pub name: String,
+    pub version: u32,
+}
+
+/// Returns reason why config can't be run by daemon with given sections
+///
+/// Daemon migrates configs with older versions, so only newer config versions are incompatible.
+fn check_compatibility(
+    config: &dyn config_registry::Config,
+    sections: &[SectionVersion],
+) -> Option<String> {
+    let name = config.name();
+    match sections.iter().find(|section| section.name == name) {
+        None => Some(format!("section '{name}' is not available on daemon")),
+        Some(section) if section.version < config.version() => Some(format!(
+            "daemon supports '{name}' config up to version {}, node config has version {}",
+            section.version,
+            config.version()
+        )),
+        Some(_) => None,
+    }
+}
+
+/// Message, relayed between daemons over boundary edge
+///
+/// Chunks are base64 encoded and opaque to control plane.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayMessage {
+    /// message id, assigned by origin daemon
+    pub id: Uuid,
+    /// destination node
+    pub to_node: Uuid,
+    pub origin: String,
+    pub chunks: Vec<String>,
+}
+
+/// max amount of relayed messages, sent to daemon at once
+const RELAY_BATCH_SIZE: u64 = 64;
+
+/// max size of encoded chunks of relayed message, same as daemon limit
+const MAX_RELAY_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+
+/// relayed messages, not acked by destination daemon in time, are dropped
+const RELAY_MESSAGE_RETENTION_DAYS: i64 = 7;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DaemonNode {
     id: uuid::Uuid,
     config: Box<dyn config_registry::Config>,
+    /// restart counter, daemon restarts pipeline of node when counter changes
+    #[serde(default)]
+    restarts: i64,
 }
 
 impl DaemonNode {
@@ -207,15 +372,99 @@ impl DaemonNode {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+}
+
+/// listed variable, value is omitted for users, who can't edit variables
+#[derive(Debug, Serialize)]
+pub struct VariableEntry {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl VariableEntry {
+    fn new(variable: Variable, with_value: bool) -> Self {
+        Self {
+            name: variable.name,
+            value: with_value.then_some(variable.value),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Edge {
     pub from_id: uuid::Uuid,
     pub to_id: uuid::Uuid,
 }
 
+/// Edge, which connects section output to section input of incompatible type
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidEdge {
+    pub from: Uuid,
+    pub to: Uuid,
+    /// output type of `from` section
+    pub output: String,
+    /// input type of `to` section
+    pub input: String,
+}
+
+impl InvalidEdge {
+    fn new(from: Uuid, to: Uuid, output: SectionIO, input: SectionIO) -> Self {
+        Self {
+            from,
+            to,
+            output: format!("{output:?}"),
+            input: format!("{input:?}"),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "output of node {} ({}) can't be connected to input of node {} ({})",
+            self.from, self.output, self.to, self.input
+        )
+    }
+}
+
+impl std::error::Error for InvalidEdge {}
+
+/// Validate edge between nodes with given configs
+///
+/// Configs, unknown to registry, are not checked.
+fn validate_edge(
+    config_registry: &ConfigRegistry,
+    (from, from_config): (Uuid, &str),
+    (to, to_config): (Uuid, &str),
+) -> Result<()> {
+    let (from_config, to_config) = match (
+        config_registry.build_config(from_config),
+        config_registry.build_config(to_config),
+    ) {
+        (Ok(from_config), Ok(to_config)) => (from_config, to_config),
+        _ => return Ok(()),
+    };
+    let (output, input) = (from_config.output(), to_config.input());
+    match output.can_connect_to(input) {
+        true => Ok(()),
+        false => Err(AppError::invalid_edge(InvalidEdge::new(
+            from, to, output, input,
+        ))),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct WorkspaceUpdate {
     name: String,
+    /// organization of workspace, can be omitted if user has access to single workspace with the name
+    #[serde(default)]
+    organization_id: Option<Uuid>,
     operations: Vec<WorkspaceOperation>,
 }
 
@@ -223,7 +472,16 @@ pub struct WorkspaceUpdate {
 #[serde(tag = "result")]
 pub enum WorkspaceUpdateResult {
     Success,
-    Error { kind: String, description: String },
+    Error {
+        kind: String,
+        description: String,
+        // per-field errors of invalid config
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        fields: Vec<config::ValidationError>,
+        // edge with incompatible output and input
+        #[serde(skip_serializing_if = "Option::is_none")]
+        edge: Option<InvalidEdge>,
+    },
 }
 
 impl WorkspaceUpdateResult {
@@ -232,14 +490,22 @@ impl WorkspaceUpdateResult {
     }
 
     fn from_app_error(err: AppError) -> Self {
+        let fields = err
+            .err
+            .downcast_ref::<config::ValidationErrors>()
+            .map(|errors| errors.errors().to_vec())
+            .unwrap_or_default();
+        let edge = err.err.downcast_ref::<InvalidEdge>().cloned();
         Self::Error {
             kind: format!("{:?}", err.kind),
-            description: err.err.to_string(),
+            description: format!("{:#}", err.err),
+            fields,
+            edge,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum WorkspaceOperation {
     AddNode {
         id: Uuid,
@@ -271,6 +537,9 @@ pub enum WorkspaceOperation {
     UnassignNodeFromDaemon {
         node_id: Uuid,
     },
+    PauseNode(Uuid),
+    ResumeNode(Uuid),
+    RestartNode(Uuid),
 }
 
 #[allow(clippy::match_like_matches_macro)]
@@ -284,9 +553,296 @@ impl WorkspaceOperation {
             WorkspaceOperation::UpdateNodeConfig { .. } => true,
             WorkspaceOperation::AssignNodeToDaemon { .. } => true,
             WorkspaceOperation::UnassignNodeFromDaemon { .. } => true,
+            WorkspaceOperation::PauseNode(_) => true,
+            WorkspaceOperation::ResumeNode(_) => true,
+            WorkspaceOperation::RestartNode(_) => true,
             _ => false,
         }
     }
+
+    /// role, required to apply operation
+    fn required_role(&self) -> Role {
+        match self {
+            WorkspaceOperation::PauseNode(_)
+            | WorkspaceOperation::ResumeNode(_)
+            | WorkspaceOperation::RestartNode(_) => Role::Operator,
+            _ => Role::Editor,
+        }
+    }
+
+    /// existing nodes, referenced by operation
+    fn node_ids(&self) -> Vec<Uuid> {
+        match *self {
+            WorkspaceOperation::AddNode { .. } => vec![],
+            WorkspaceOperation::UpdateNodeConfig { id, .. } => vec![id],
+            WorkspaceOperation::UpdateNodePosition { uuid, .. } => vec![uuid],
+            WorkspaceOperation::RemoveNode(id) => vec![id],
+            WorkspaceOperation::AddEdge { from, to } => vec![from, to],
+            WorkspaceOperation::RemoveEdge { from } => vec![from],
+            WorkspaceOperation::AssignNodeToDaemon { node_id, .. } => vec![node_id],
+            WorkspaceOperation::UnassignNodeFromDaemon { node_id } => vec![node_id],
+            WorkspaceOperation::PauseNode(id)
+            | WorkspaceOperation::ResumeNode(id)
+            | WorkspaceOperation::RestartNode(id) => vec![id],
+        }
+    }
+
+    fn strip_secrets(&mut self, config_registry: &ConfigRegistry) -> Result<()> {
+        match self {
+            WorkspaceOperation::AddNode { config, .. }
+            | WorkspaceOperation::UpdateNodeConfig { config, .. } => {
+                strip_config_secrets(config_registry, config)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn hide_restricted_config(&mut self, config_registry: &ConfigRegistry) -> Result<()> {
+        match self {
+            WorkspaceOperation::AddNode { config, .. }
+            | WorkspaceOperation::UpdateNodeConfig { config, .. } => {
+                access::hide_restricted_config(config_registry, config)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn strip_config_secrets(
+    config_registry: &ConfigRegistry,
+    config: &mut Box<dyn config_registry::Config>,
+) -> Result<()> {
+    match config_registry.deserialize_config(&**config) {
+        Ok(mut stripped) => {
+            stripped.strip_secrets();
+            std::mem::swap(config, &mut stripped);
+            Ok(())
+        }
+        Err(e) => Err(anyhow::anyhow!(
+            "failed to build config from config registry for {}: {e}",
+            config.name()
+        ))?,
+    }
+}
+
+// revision 0 is workspace state before first recorded update
+fn check_revision(name: &str, revisions: &[WorkspaceRevision], revision: i64) -> Result<()> {
+    let latest = revisions.last().map(|r| r.revision).unwrap_or(0);
+    match (0..=latest).contains(&revision) {
+        true => Ok(()),
+        false => Err(AppError::not_found(anyhow::anyhow!(
+            "revision {revision} of workspace '{name}' not found"
+        ))),
+    }
+}
+
+/// Applied workspace update
+#[derive(Debug, Serialize)]
+pub struct WorkspaceRevision {
+    pub revision: i64,
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub operations: Vec<WorkspaceOperation>,
+    /// operations, which revert revision, in order of application
+    #[serde(skip)]
+    pub inverse: Vec<WorkspaceOperation>,
+}
+
+/// Difference between two workspace revisions
+#[derive(Debug, Serialize)]
+pub struct WorkspaceDiff {
+    pub from: i64,
+    pub to: i64,
+    pub added_nodes: Vec<Uuid>,
+    pub removed_nodes: Vec<Uuid>,
+    pub changed_nodes: Vec<NodeDiff>,
+    pub added_edges: Vec<Edge>,
+    pub removed_edges: Vec<Edge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeDiff {
+    pub id: Uuid,
+    /// changed node properties: `position`, `daemon_id`, `paused`, `config` or `config.<field name>`
+    pub changes: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct SnapshotNode {
+    x: f64,
+    y: f64,
+    config: Box<dyn config_registry::Config>,
+    daemon_id: Option<Uuid>,
+    paused: bool,
+}
+
+impl SnapshotNode {
+    fn changes(&self, other: &Self) -> Vec<String> {
+        let mut changes = vec![];
+        if (self.x, self.y) != (other.x, other.y) {
+            changes.push("position".into());
+        }
+        if self.daemon_id != other.daemon_id {
+            changes.push("daemon_id".into());
+        }
+        if self.paused != other.paused {
+            changes.push("paused".into());
+        }
+        if self.config.name() != other.config.name() {
+            changes.push("config".into());
+            return changes;
+        }
+        let other_fields = other.config.fields();
+        for field in self.config.fields() {
+            match other_fields.iter().find(|other| other.name == field.name) {
+                Some(other) if other.value == field.value => (),
+                _ => changes.push(format!("config.{}", field.name)),
+            }
+        }
+        changes
+    }
+}
+
+/// In-memory workspace graph, earlier revisions are restored by reverting later ones
+#[derive(Debug, Clone)]
+struct WorkspaceSnapshot {
+    nodes: BTreeMap<Uuid, SnapshotNode>,
+    // node has at most one outgoing edge
+    edges: BTreeMap<Uuid, Uuid>,
+}
+
+impl WorkspaceSnapshot {
+    fn new(graph: WorkspaceGraph) -> Self {
+        let nodes = graph
+            .nodes
+            .into_iter()
+            .map(|node| {
+                let snapshot = SnapshotNode {
+                    x: node.x,
+                    y: node.y,
+                    config: node.config,
+                    daemon_id: node.daemon_id,
+                    paused: node.paused,
+                };
+                (node.id, snapshot)
+            })
+            .collect();
+        let edges = graph
+            .edges
+            .into_iter()
+            .map(|edge| (edge.from_id, edge.to_id))
+            .collect();
+        Self { nodes, edges }
+    }
+
+    fn apply(&mut self, op: &WorkspaceOperation) {
+        match *op {
+            WorkspaceOperation::AddNode {
+                id,
+                x,
+                y,
+                ref config,
+            } => {
+                let node = SnapshotNode {
+                    x,
+                    y,
+                    config: config.clone(),
+                    daemon_id: None,
+                    paused: false,
+                };
+                self.nodes.insert(id, node);
+            }
+            WorkspaceOperation::UpdateNodeConfig { id, ref config } => {
+                // revisions store full configs
+                if let Some(node) = self.nodes.get_mut(&id) {
+                    node.config = config.clone();
+                }
+            }
+            WorkspaceOperation::UpdateNodePosition { uuid, x, y } => {
+                if let Some(node) = self.nodes.get_mut(&uuid) {
+                    (node.x, node.y) = (x, y);
+                }
+            }
+            WorkspaceOperation::RemoveNode(id) => {
+                // edges are removed together with node
+                self.nodes.remove(&id);
+                self.edges.retain(|from, to| *from != id && *to != id);
+            }
+            WorkspaceOperation::AddEdge { from, to } => {
+                self.edges.insert(from, to);
+            }
+            WorkspaceOperation::RemoveEdge { from } => {
+                self.edges.remove(&from);
+            }
+            WorkspaceOperation::AssignNodeToDaemon { node_id, daemon_id } => {
+                if let Some(node) = self.nodes.get_mut(&node_id) {
+                    node.daemon_id = Some(daemon_id);
+                }
+            }
+            WorkspaceOperation::UnassignNodeFromDaemon { node_id } => {
+                if let Some(node) = self.nodes.get_mut(&node_id) {
+                    node.daemon_id = None;
+                }
+            }
+            WorkspaceOperation::PauseNode(id) | WorkspaceOperation::ResumeNode(id) => {
+                if let Some(node) = self.nodes.get_mut(&id) {
+                    node.paused = matches!(op, WorkspaceOperation::PauseNode(_));
+                }
+            }
+            // restart doesn't change graph
+            WorkspaceOperation::RestartNode(_) => (),
+        }
+    }
+
+    fn revert(&mut self, revision: &WorkspaceRevision) {
+        revision.inverse.iter().for_each(|op| self.apply(op));
+    }
+
+    fn diff(&self, other: &Self, from: i64, to: i64) -> WorkspaceDiff {
+        let added_nodes = other
+            .nodes
+            .keys()
+            .filter(|id| !self.nodes.contains_key(id))
+            .copied()
+            .collect();
+        let removed_nodes = self
+            .nodes
+            .keys()
+            .filter(|id| !other.nodes.contains_key(id))
+            .copied()
+            .collect();
+        let changed_nodes = self
+            .nodes
+            .iter()
+            .filter_map(|(id, node)| {
+                let changes = node.changes(other.nodes.get(id)?);
+                (!changes.is_empty()).then_some(NodeDiff { id: *id, changes })
+            })
+            .collect();
+        let edges_diff = |left: &Self, right: &Self| {
+            left.edges
+                .iter()
+                .filter(|(from, to)| right.edges.get(from) != Some(to))
+                .map(|(&from_id, &to_id)| Edge { from_id, to_id })
+                .collect()
+        };
+        WorkspaceDiff {
+            from,
+            to,
+            added_nodes,
+            removed_nodes,
+            changed_nodes,
+            added_edges: edges_diff(other, self),
+            removed_edges: edges_diff(self, other),
+        }
+    }
+}
+
+/// daemon certificate, listed in CRL, serial number is hex encoded
+#[derive(Debug)]
+pub struct RevokedDaemonCertificate {
+    pub serial_number: String,
+    pub revoked_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize)]
@@ -295,6 +851,8 @@ pub struct DaemonToken {
     pub secret: String,
     pub issued_at: chrono::DateTime<Utc>,
     pub used_at: Option<chrono::DateTime<Utc>>,
+    /// daemon, joined with token, belongs to token organization
+    pub organization_id: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -317,6 +875,11 @@ pub struct Daemon {
     pub address: Option<String>,
     pub last_seen: Option<DateTime<Utc>>,
     pub joined_at: Option<DateTime<Utc>>,
+    /// version, reported by daemon on connect
+    pub version: Option<String>,
+    pub organization_id: Option<Uuid>,
+    /// revoked daemon can't connect, it needs new join token
+    pub revoked_at: Option<DateTime<Utc>>,
     pub status: DaemonStatus,
 }
 
@@ -334,25 +897,138 @@ impl Default for DaemonStatus {
 
 pub struct AppBuilder {
     db: Box<dyn db::DbTrait>,
+    config_registry: ConfigRegistry,
 }
 
 impl AppBuilder {
-    pub async fn new(database_url: &str) -> Result<Self> {
-        let db = db::new(database_url).await?;
+    pub async fn new(database_url: &str, master_key: Option<MasterKey>) -> Result<Self> {
+        let encrypt_secrets = master_key.is_some();
+        let db = db::new(database_url, master_key).await?;
         db.migrate().await?;
-        Ok(Self { db })
+        let config_registry = config_registry::new()
+            .map_err(|e| anyhow::anyhow!("failed to build config registry: {e}"))?;
+        let builder = Self {
+            db,
+            config_registry,
+        };
+        let upgraded = builder.db.upgrade_configs(&builder.config_registry).await?;
+        if upgraded > 0 {
+            tracing::info!("upgraded configs of {upgraded} nodes");
+        }
+        let escaped = builder.db.escape_templates(&builder.config_registry).await?;
+        if escaped > 0 {
+            tracing::info!("escaped literal `${{` in template fields of {escaped} nodes");
+        }
+        builder.ensure_default_organization().await?;
+        match encrypt_secrets {
+            true => builder.encrypt_plaintext_secrets().await?,
+            false => tracing::warn!(
+                "master key is not set, secret config fields are stored in plaintext"
+            ),
+        };
+        Ok(builder)
+    }
+
+    // migrate secret fields, stored in plaintext, to encrypted form
+    async fn encrypt_plaintext_secrets(&self) -> Result<()> {
+        let master_key = self.db.master_key();
+        let updated = self
+            .db
+            .reencrypt_secrets(&self.config_registry, master_key)
+            .await?;
+        if updated > 0 {
+            tracing::info!("encrypted secret fields of {updated} nodes");
+        }
+        Ok(())
+    }
+
+    // workspaces, daemons and tokens, created before organizations were introduced, belong to default organization
+    // existing users become its members
+    async fn ensure_default_organization(&self) -> Result<()> {
+        let organizations = self.db.list_organizations().await?;
+        let organization = match organizations
+            .iter()
+            .find(|organization| organization.name == access::DEFAULT_ORGANIZATION)
+        {
+            Some(organization) => organization.id,
+            None if !organizations.is_empty() => return Ok(()),
+            None => {
+                let organization = Organization {
+                    id: Uuid::now_v7(),
+                    name: access::DEFAULT_ORGANIZATION.into(),
+                    created_at: Some(Utc::now()),
+                };
+                self.db.create_organization(&organization).await?;
+                for user in self.db.list_users().await? {
+                    self.db
+                        .set_organization_member(organization.id, user.id, OrganizationRole::Member)
+                        .await?;
+                }
+                tracing::info!("created '{}' organization", organization.name);
+                organization.id
+            }
+        };
+        self.db.assign_default_organization(organization).await
+    }
+
+    /// re-encrypt secret fields of all nodes and variable values with new master key
+    pub async fn rotate_key(&self, new_master_key: &MasterKey) -> Result<usize> {
+        self.db
+            .reencrypt_secrets(&self.config_registry, Some(new_master_key))
+            .await
+    }
+
+    /// create instance admin, existing user with given email is made admin
+    pub async fn bootstrap_admin(&self, email: Option<&str>, password: Option<&str>) -> Result<()> {
+        let (email, password) = match (email, password) {
+            (Some(email), Some(password)) => (auth::normalize_email(email)?, password),
+            (None, None) => {
+                if self.db.list_users().await?.is_empty() {
+                    tracing::warn!(
+                        "no users exist and admin is not configured, api is not accessible"
+                    );
+                }
+                return Ok(());
+            }
+            _ => Err(anyhow::anyhow!(
+                "both admin email and admin password should be set"
+            ))?,
+        };
+        if let Some((user, _)) = self.db.get_user_credentials(&email).await? {
+            if !user.is_admin {
+                self.db.set_user_admin(user.id, true).await?;
+                tracing::info!("made user '{}' admin", user.email);
+            }
+            return Ok(());
+        }
+        auth::validate_password(password)?;
+        let user = User {
+            id: Uuid::now_v7(),
+            email,
+            created_at: Utc::now(),
+            is_admin: true,
+            address: None,
+        };
+        let password_hash = auth::hash_password(password.into()).await?;
+        self.db.create_user(&user, &password_hash).await?;
+        tracing::info!("created admin user '{}'", user.email);
+        Ok(())
     }
 
     pub async fn build(self) -> Result<App> {
         let certificate_bundle = self.get_or_create_certificate_bundle().await?;
         let db = Arc::from(self.db);
-        Ok(App {
+        let app = App {
             db: Arc::clone(&db),
-            config_registry: config_registry::new()
-                .map_err(|e| anyhow::anyhow!("failed to build config registry: {e}"))?,
+            sections: RwLock::new(Sections {
+                config_registry: Arc::new(self.config_registry),
+                plugin_sections: BTreeMap::new(),
+            }),
             certificate_bundle,
             daemon_tracker: daemon_tracker::DaemonTracker::spawn(),
-        })
+        };
+        app.refresh_crl().await?;
+        Ok(app)
     }
 
     async fn get_or_create_certificate_bundle(&self) -> Result<CertificateBundle> {
@@ -360,10 +1036,14 @@ impl AppBuilder {
         let (cert, key) = self
             .get_or_create_control_plane_cert_key(&ca_cert_key)
             .await?;
+        let client_verifier = pki::Verifier::new(ca_cert_key.cert.der().clone())
+            .map_err(|e| anyhow::anyhow!("failed to create certificate verifier: {e}"))?;
         Ok(CertificateBundle {
             ca_cert_key,
             cert,
             key,
+            client_verifier: Arc::new(client_verifier),
+            crl_number: tokio::sync::Mutex::new(Utc::now().timestamp_millis() as u64),
         })
     }
 
@@ -405,15 +1085,32 @@ pub type AppState = Arc<App>;
 
 pub(crate) struct App {
     db: Arc<dyn db::DbTrait>,
-    config_registry: ConfigRegistry,
+    sections: RwLock<Sections>,
     certificate_bundle: CertificateBundle,
     daemon_tracker: daemon_tracker::DaemonTrackerHandle,
 }
 
+// config registry is replaced on plugin section registration, so readers keep consistent snapshot
+// plugin sections are registered per organization of reporting daemon
+struct Sections {
+    config_registry: Arc<ConfigRegistry>,
+    plugin_sections: BTreeMap<Option<Uuid>, PluginSections>,
+}
+
+// plugin sections of organization and registry of builtin configs, extended with them
+struct PluginSections {
+    config_registry: Arc<ConfigRegistry>,
+    descriptors: BTreeMap<String, SectionDescriptor>,
+}
+
 pub(crate) struct CertificateBundle {
     pub ca_cert_key: CertifiedKey,
     pub cert: CertificateDer<'static>,
     pub key: KeyPair,
+    /// verifier of daemon certificates, holds CRL, signed by CA
+    pub client_verifier: Arc<pki::Verifier>,
+    /// number of last issued CRL, lock serializes CRL updates
+    crl_number: tokio::sync::Mutex<u64>,
 }
 
 impl App {
@@ -421,27 +1118,224 @@ impl App {
         &self.certificate_bundle
     }
 
+    // registry of builtin configs and plugin configs of organization
+    fn config_registry(&self, organization_id: Option<Uuid>) -> Arc<ConfigRegistry> {
+        let sections = self.sections.read().unwrap();
+        match sections.plugin_sections.get(&organization_id) {
+            Some(plugin_sections) => Arc::clone(&plugin_sections.config_registry),
+            None => Arc::clone(&sections.config_registry),
+        }
+    }
+
+    // sections api
+
+    /// JSON schemas of builtin sections and plugin sections of organization
+    pub async fn section_schemas(
+        &self,
+        user: &User,
+        organization_id: Option<Uuid>,
+    ) -> Result<Vec<serde_json::Value>> {
+        let organization_id = self.member_organization(user, organization_id).await?;
+        Ok(self.config_registry(Some(organization_id)).json_schemas())
+    }
+
+    /// plugin sections, reported by daemons of organization
+    pub async fn plugin_sections(
+        &self,
+        user: &User,
+        organization_id: Option<Uuid>,
+    ) -> Result<Vec<SectionDescriptor>> {
+        let organization_id = self.member_organization(user, organization_id).await?;
+        let sections = self.sections.read().unwrap();
+        Ok(sections
+            .plugin_sections
+            .get(&Some(organization_id))
+            .map(|plugin_sections| plugin_sections.descriptors.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    /// store capabilities, reported by daemon, and register its plugin sections
+    pub async fn register_capabilities(
+        &self,
+        daemon_id: Uuid,
+        capabilities: Capabilities,
+    ) -> Result<()> {
+        // daemons without version predate section reporting, so their nodes are not checked
+        if !capabilities.version.is_empty() {
+            self.db
+                .set_daemon_capabilities(daemon_id, &capabilities.version, &capabilities.sections)
+                .await?;
+        }
+        if !capabilities.plugin_sections.is_empty() {
+            let organization_id = self
+                .db
+                .get_daemon(daemon_id)
+                .await?
+                .and_then(|daemon| daemon.organization_id);
+            self.register_plugin_sections(daemon_id, organization_id, capabilities.plugin_sections);
+        }
+        Ok(())
+    }
+
+    /// register plugin sections, reported by daemon, in organization of daemon
+    ///
+    /// First daemon of organization to report section defines it for organization, differing
+    /// descriptors from other daemons of organization are ignored.
+    fn register_plugin_sections(
+        &self,
+        daemon_id: Uuid,
+        organization_id: Option<Uuid>,
+        plugin_sections: Vec<SectionDescriptor>,
+    ) {
+        let mut sections = self.sections.write().unwrap();
+        let builtin = Arc::clone(&sections.config_registry);
+        let sections = sections
+            .plugin_sections
+            .entry(organization_id)
+            .or_insert_with(|| PluginSections {
+                config_registry: builtin,
+                descriptors: BTreeMap::new(),
+            });
+        let mut config_registry: Option<ConfigRegistry> = None;
+        for descriptor in plugin_sections {
+            let name = descriptor.name.as_str();
+            match sections.descriptors.get(name) {
+                Some(known) if known == &descriptor => continue,
+                Some(_) => {
+                    tracing::warn!(
+                        "daemon {daemon_id} reported plugin section '{name}', which differs from registered one"
+                    );
+                    continue;
+                }
+                None => (),
+            };
+            let config = match PluginConfig::new(&descriptor) {
+                Ok(config) => config,
+                Err(e) => {
+                    tracing::warn!(
+                        "daemon {daemon_id} reported invalid plugin section '{name}': {e}"
+                    );
+                    continue;
+                }
+            };
+            let registry =
+                config_registry.get_or_insert_with(|| (*sections.config_registry).clone());
+            if let Err(e) = registry.add_plugin_config(config) {
+                tracing::warn!(
+                    "failed to register plugin section '{name}' of daemon {daemon_id}: {e}"
+                );
+                continue;
+            }
+            tracing::info!("registered plugin section '{name}' of daemon {daemon_id}");
+            sections
+                .descriptors
+                .insert(descriptor.name.clone(), descriptor);
+        }
+        if let Some(config_registry) = config_registry {
+            sections.config_registry = Arc::new(config_registry);
+        }
+    }
+
     // workspaces api
-    pub async fn create_workspace(&self, workspace: &Workspace) -> Result<()> {
-        self.db.create_workspace(workspace).await
+
+    /// create workspace in organization, creator becomes workspace admin
+    pub async fn create_workspace(&self, user: &User, workspace: &Workspace) -> Result<()> {
+        let organization_id = self
+            .member_organization(user, workspace.organization_id)
+            .await?;
+        let workspace = Workspace {
+            name: workspace.name.clone(),
+            created_at: None,
+            organization_id: Some(organization_id),
+        };
+        self.db.create_workspace(&workspace, user.id).await?;
+        self.audit(
+            user,
+            AuditAction::WorkspaceCreate,
+            Some(organization_id),
+            &workspace.name,
+            serde_json::json!({}),
+        )
+        .await
     }
 
-    pub async fn read_workspaces(&self) -> Result<Vec<Workspace>> {
-        self.db.read_workspaces().await
+    /// workspaces, user has role in
+    pub async fn read_workspaces(&self, user: &User) -> Result<Vec<Workspace>> {
+        let mut workspaces = self.db.read_workspaces().await?;
+        if !user.is_admin {
+            let organizations = self.db.list_user_organizations(user.id).await?;
+            let roles = self.db.list_user_workspace_roles(user.id).await?;
+            workspaces.retain(|workspace| {
+                let organization_role = workspace
+                    .organization_id
+                    .and_then(|id| organizations.get(&id));
+                let key = WorkspaceKey {
+                    organization_id: workspace.organization_id,
+                    name: workspace.name.clone(),
+                };
+                organization_role == Some(&OrganizationRole::Admin) || roles.contains_key(&key)
+            });
+        }
+        Ok(workspaces)
     }
 
-    pub async fn delete_workspace(&self, name: &str) -> Result<()> {
-        self.db.delete_workspace(name).await
+    pub async fn delete_workspace(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+    ) -> Result<()> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Admin)
+            .await?;
+        self.db.delete_workspace(&workspace).await?;
+        self.audit(
+            user,
+            AuditAction::WorkspaceDelete,
+            workspace.organization_id,
+            name,
+            serde_json::json!({}),
+        )
+        .await
     }
 
     // workspace api
-    pub async fn get_workspace(&self, name: &str) -> Result<WorkspaceState> {
-        let daemons = self.db.list_daemons().await?;
-        let mut graph = self.db.get_workspace(name).await?;
+
+    /// workspace graph and daemons of workspace organization
+    ///
+    /// Configs of restricted sections are hidden from viewers.
+    pub async fn get_workspace(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+    ) -> Result<WorkspaceState> {
+        let (workspace, role) = self
+            .check_workspace_role(user, name, organization_id, Role::Viewer)
+            .await?;
+        let daemons = self.list_workspace_daemons(&workspace).await?;
+        let daemon_sections = self.db.list_daemon_sections().await?;
+        let node_errors = self.daemon_tracker.list_node_errors().await?;
+        let mut graph = self.db.get_workspace(&workspace).await?;
+        let config_registry = self.config_registry(workspace.organization_id);
         graph.nodes.iter_mut().for_each(|node| {
-            if let Err(e) = node.strip_secrets(&self.config_registry) {
+            node.incompatible = node
+                .daemon_id
+                .and_then(|id| daemon_sections.get(&id))
+                .and_then(|sections| check_compatibility(&*node.config, sections));
+            node.error = node
+                .daemon_id
+                .and_then(|id| node_errors.get(&id))
+                .and_then(|errors| errors.get(&node.id))
+                .cloned();
+            if let Err(e) = node.strip_secrets(&config_registry) {
                 tracing::error!("{e}");
             }
+            if role == Role::Viewer {
+                if let Err(e) = node.hide_restricted_config(&config_registry) {
+                    tracing::error!("{e}");
+                }
+            }
         });
         Ok(WorkspaceState {
             nodes: graph.nodes,
@@ -450,44 +1344,78 @@ impl App {
         })
     }
 
+    /// apply workspace updates, each applied update is recorded as workspace revision
+    ///
+    /// Updates of workspaces, where user role doesn't allow all update operations, are rejected.
     pub async fn update_workspace(
         &self,
+        user: &User,
         updates: &mut [WorkspaceUpdate],
     ) -> Result<Vec<WorkspaceUpdateResult>> {
-        let mut result = Vec::with_capacity(updates.len());
         // validate operation
-        let validate_operation = |operation: &mut WorkspaceOperation| -> Result<()> {
+        fn validate_operation(
+            config_registry: &ConfigRegistry,
+            operation: &mut WorkspaceOperation,
+        ) -> Result<()> {
             if let WorkspaceOperation::AddNode { config, .. } = operation {
                 let config_name = config.name();
-                let mut default_config = self
-                    .config_registry
+                let mut default_config = config_registry
                     .deserialize_config(&**config)
                     .map_err(|_| AppError::invalid_config(config_name))?;
+                // fields with variable references are validated on daemon graph build
+                config::template::validate(&*default_config)
+                    .map_err(|e| AppError::invalid_config_fields(config_name, e))?;
                 std::mem::swap(config, &mut default_config);
             }
             Ok(())
-        };
+        }
+        let mut result = Vec::with_capacity(updates.len());
         let mut notify_daemons = false;
         for update in updates.iter_mut() {
-            let update_result =
-                match update
+            let required_role = update
+                .operations
+                .iter()
+                .map(WorkspaceOperation::required_role)
+                .max()
+                .unwrap_or(Role::Viewer);
+            let update_result = self
+                .check_workspace_role(user, &update.name, update.organization_id, required_role)
+                .await
+                .map(|(workspace, _)| update.organization_id = workspace.organization_id);
+            // plugin sections are registered per organization
+            let config_registry = self.config_registry(update.organization_id);
+            let update_result = match update_result.and_then(|_| {
+                update
                     .operations
                     .iter_mut()
                     .try_fold(notify_daemons, |mut acc, op| {
                         acc |= op.needs_daemon_notification();
-                        validate_operation(op)?;
+                        validate_operation(&config_registry, op)?;
                         Ok(acc)
-                    }) {
-                    Err(e) => Err(e),
-                    Ok(nd) => {
-                        notify_daemons = nd;
-                        self.db
-                            .update_workspace(&self.config_registry, update)
-                            .await
-                    }
-                };
+                    })
+            }) {
+                Err(e) => Err(e),
+                Ok(nd) => {
+                    notify_daemons = nd;
+                    self.db
+                        .update_workspace(&config_registry, update, Some(&user.email))
+                        .await
+                }
+            };
             let update_result = match update_result {
-                Ok(()) => WorkspaceUpdateResult::success(),
+                Ok(()) if update.operations.is_empty() => WorkspaceUpdateResult::success(),
+                Ok(()) => {
+                    let operations = audit::redact_operations(&config_registry, &update.operations);
+                    self.audit(
+                        user,
+                        AuditAction::WorkspaceUpdate,
+                        update.organization_id,
+                        &update.name,
+                        serde_json::json!({ "operations": operations }),
+                    )
+                    .await?;
+                    WorkspaceUpdateResult::success()
+                }
                 Err(e) if e.is_internal() => Err(e)?,
                 Err(e) => WorkspaceUpdateResult::from_app_error(e),
             };
@@ -500,9 +1428,525 @@ impl App {
         Ok(result)
     }
 
+    pub async fn get_workspace_history(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+    ) -> Result<Vec<WorkspaceRevision>> {
+        let (workspace, role) = self
+            .check_workspace_role(user, name, organization_id, Role::Viewer)
+            .await?;
+        let config_registry = self.config_registry(workspace.organization_id);
+        let mut revisions = self.db.list_workspace_revisions(&workspace).await?;
+        revisions
+            .iter_mut()
+            .flat_map(|revision| revision.operations.iter_mut())
+            .for_each(|op| {
+                if let Err(e) = op.strip_secrets(&config_registry) {
+                    tracing::error!("{e}");
+                }
+                if role == Role::Viewer {
+                    if let Err(e) = op.hide_restricted_config(&config_registry) {
+                        tracing::error!("{e}");
+                    }
+                }
+            });
+        Ok(revisions)
+    }
+
+    pub async fn diff_workspace_revisions(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+        from: i64,
+        to: i64,
+    ) -> Result<WorkspaceDiff> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Viewer)
+            .await?;
+        let revisions = self.db.list_workspace_revisions(&workspace).await?;
+        check_revision(name, &revisions, from)?;
+        check_revision(name, &revisions, to)?;
+        let (low, high) = (from.min(to), from.max(to));
+        // revisions before current one are restored by reverting later revisions
+        let mut snapshot = WorkspaceSnapshot::new(self.db.get_workspace(&workspace).await?);
+        let mut revisions = revisions.iter().rev().peekable();
+        while let Some(revision) = revisions.next_if(|revision| revision.revision > high) {
+            snapshot.revert(revision);
+        }
+        let high_snapshot = snapshot.clone();
+        while let Some(revision) = revisions.next_if(|revision| revision.revision > low) {
+            snapshot.revert(revision);
+        }
+        let diff = match from <= to {
+            true => snapshot.diff(&high_snapshot, from, to),
+            false => high_snapshot.diff(&snapshot, from, to),
+        };
+        Ok(diff)
+    }
+
+    /// revert workspace to state at given revision
+    ///
+    /// Inverse operations of later revisions are applied as new revision, so rollback can be rolled back too.
+    pub async fn rollback_workspace(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+        revision: i64,
+    ) -> Result<WorkspaceUpdateResult> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Editor)
+            .await?;
+        let revisions = self.db.list_workspace_revisions(&workspace).await?;
+        check_revision(name, &revisions, revision)?;
+        let operations = revisions
+            .into_iter()
+            .rev()
+            .take_while(|r| r.revision > revision)
+            .flat_map(|r| r.inverse)
+            .collect();
+        let mut updates = [WorkspaceUpdate {
+            name: name.into(),
+            organization_id: workspace.organization_id,
+            operations,
+        }];
+        let mut result = self.update_workspace(user, &mut updates).await?;
+        Ok(result.remove(0))
+    }
+
+    pub async fn export_workspace(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+    ) -> Result<WorkspaceDocument> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Editor)
+            .await?;
+        let graph = self.db.get_workspace(&workspace).await?;
+        let daemons = self.list_workspace_daemons(&workspace).await?;
+        document::export(
+            &self.config_registry(workspace.organization_id),
+            self.db.master_key(),
+            graph,
+            &daemons,
+        )
+    }
+
+    /// converge workspace to document, on dry run only operations are computed
+    pub async fn apply_workspace_document(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+        document: WorkspaceDocument,
+        dry_run: bool,
+    ) -> Result<WorkspaceApplyResult> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Editor)
+            .await?;
+        let graph = self.db.get_workspace(&workspace).await?;
+        let daemons = self.list_workspace_daemons(&workspace).await?;
+        let plan = document::plan(
+            &self.config_registry(workspace.organization_id),
+            self.db.master_key(),
+            graph,
+            &daemons,
+            document,
+        )?;
+        if dry_run || plan.is_empty() {
+            return Ok(WorkspaceApplyResult { plan, result: None });
+        }
+        let mut updates = [WorkspaceUpdate {
+            name: name.into(),
+            organization_id: workspace.organization_id,
+            operations: plan.clone(),
+        }];
+        let mut result = self.update_workspace(user, &mut updates).await?;
+        Ok(WorkspaceApplyResult {
+            plan,
+            result: Some(result.remove(0)),
+        })
+    }
+
+    // workspace members API
+
+    pub async fn list_workspace_members(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+    ) -> Result<Vec<WorkspaceMember>> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Viewer)
+            .await?;
+        self.db.list_workspace_members(&workspace).await
+    }
+
+    /// add member or change role of existing one, member should belong to workspace organization
+    pub async fn set_workspace_member(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+        member: MemberRequest<Role>,
+    ) -> Result<()> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Admin)
+            .await?;
+        let member_user = self.get_user_by_email(&member.email).await?;
+        let is_organization_member = match workspace.organization_id {
+            Some(id) => self.organization_role(&member_user, id).await?.is_some(),
+            None => member_user.is_admin,
+        };
+        if !is_organization_member {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "user '{}' is not member of workspace organization",
+                member_user.email
+            )))?
+        }
+        self.db
+            .set_workspace_member(&workspace, member_user.id, member.role)
+            .await?;
+        self.audit(
+            user,
+            AuditAction::WorkspaceMemberSet,
+            workspace.organization_id,
+            name,
+            serde_json::json!({
+                "user_id": member_user.id,
+                "email": member_user.email,
+                "role": member.role,
+            }),
+        )
+        .await
+    }
+
+    pub async fn remove_workspace_member(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+        user_id: Uuid,
+    ) -> Result<()> {
+        let (workspace, _) = self
+            .check_workspace_role(user, name, organization_id, Role::Admin)
+            .await?;
+        self.db.remove_workspace_member(&workspace, user_id).await?;
+        self.audit(
+            user,
+            AuditAction::WorkspaceMemberRemove,
+            workspace.organization_id,
+            name,
+            serde_json::json!({ "user_id": user_id }),
+        )
+        .await
+    }
+
+    // organizations API
+
+    /// organizations, user is member of
+    pub async fn list_organizations(&self, user: &User) -> Result<Vec<Organization>> {
+        let mut organizations = self.db.list_organizations().await?;
+        if !user.is_admin {
+            let roles = self.db.list_user_organizations(user.id).await?;
+            organizations.retain(|organization| roles.contains_key(&organization.id));
+        }
+        Ok(organizations)
+    }
+
+    pub async fn create_organization(
+        &self,
+        user: &User,
+        organization: &Organization,
+    ) -> Result<Organization> {
+        check_instance_admin(user)?;
+        let name = organization.name.trim();
+        if name.is_empty() {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "organization name is empty"
+            )))?
+        }
+        if self.find_organization(name).await?.is_some() {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "organization '{name}' already exists"
+            )))?
+        }
+        let organization = Organization {
+            id: Uuid::now_v7(),
+            name: name.into(),
+            created_at: Some(Utc::now()),
+        };
+        self.db.create_organization(&organization).await?;
+        self.audit(
+            user,
+            AuditAction::OrganizationCreate,
+            Some(organization.id),
+            &organization.name,
+            serde_json::json!({}),
+        )
+        .await?;
+        Ok(organization)
+    }
+
+    /// delete organization, organization shouldn't have workspaces and daemons
+    pub async fn delete_organization(&self, user: &User, name: &str) -> Result<()> {
+        check_instance_admin(user)?;
+        let organization = self.get_organization(name).await?;
+        let has_workspaces = self
+            .db
+            .read_workspaces()
+            .await?
+            .iter()
+            .any(|workspace| workspace.organization_id == Some(organization.id));
+        let has_daemons = self
+            .db
+            .list_daemons()
+            .await?
+            .iter()
+            .any(|daemon| daemon.organization_id == Some(organization.id));
+        if has_workspaces || has_daemons {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "organization '{name}' has workspaces or daemons"
+            )))?
+        }
+        self.db.delete_organization(organization.id).await?;
+        self.audit(
+            user,
+            AuditAction::OrganizationDelete,
+            Some(organization.id),
+            name,
+            serde_json::json!({}),
+        )
+        .await
+    }
+
+    pub async fn list_organization_members(
+        &self,
+        user: &User,
+        name: &str,
+    ) -> Result<Vec<OrganizationMember>> {
+        let organization = self.get_organization(name).await?;
+        if self
+            .organization_role(user, organization.id)
+            .await?
+            .is_none()
+        {
+            Err(AppError::not_found(anyhow::anyhow!(
+                "organization '{name}'"
+            )))?
+        }
+        self.db.list_organization_members(organization.id).await
+    }
+
+    /// add member or change role of existing one
+    pub async fn set_organization_member(
+        &self,
+        user: &User,
+        name: &str,
+        member: MemberRequest<OrganizationRole>,
+    ) -> Result<()> {
+        let organization = self.get_organization(name).await?;
+        self.check_organization_admin(user, organization.id).await?;
+        let member_user = self.get_user_by_email(&member.email).await?;
+        self.db
+            .set_organization_member(organization.id, member_user.id, member.role)
+            .await?;
+        self.audit(
+            user,
+            AuditAction::OrganizationMemberSet,
+            Some(organization.id),
+            name,
+            serde_json::json!({
+                "user_id": member_user.id,
+                "email": member_user.email,
+                "role": member.role,
+            }),
+        )
+        .await
+    }
+
+    pub async fn remove_organization_member(
+        &self,
+        user: &User,
+        name: &str,
+        user_id: Uuid,
+    ) -> Result<()> {
+        let organization = self.get_organization(name).await?;
+        self.check_organization_admin(user, organization.id).await?;
+        self.db
+            .remove_organization_member(organization.id, user_id)
+            .await?;
+        self.audit(
+            user,
+            AuditAction::OrganizationMemberRemove,
+            Some(organization.id),
+            name,
+            serde_json::json!({ "user_id": user_id }),
+        )
+        .await
+    }
+
+    // users API
+
+    pub async fn create_user(&self, user: &User, credentials: Credentials) -> Result<User> {
+        check_instance_admin(user)?;
+        let email = auth::normalize_email(&credentials.email)?;
+        auth::validate_password(&credentials.password)?;
+        if self.db.get_user_credentials(&email).await?.is_some() {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "user '{email}' already exists"
+            )))?
+        }
+        let new_user = User {
+            id: Uuid::now_v7(),
+            email,
+            created_at: Utc::now(),
+            is_admin: false,
+            address: None,
+        };
+        let password_hash = auth::hash_password(credentials.password).await?;
+        self.db.create_user(&new_user, &password_hash).await?;
+        self.audit(
+            user,
+            AuditAction::UserCreate,
+            None,
+            &new_user.email,
+            serde_json::json!({ "user_id": new_user.id }),
+        )
+        .await?;
+        Ok(new_user)
+    }
+
+    pub async fn list_users(&self, user: &User) -> Result<Vec<User>> {
+        check_instance_admin(user)?;
+        self.db.list_users().await
+    }
+
+    pub async fn delete_user(&self, current_user: &User, id: Uuid) -> Result<()> {
+        check_instance_admin(current_user)?;
+        if current_user.id == id {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "user can't delete itself"
+            )))?
+        }
+        let user = self
+            .db
+            .get_user(id)
+            .await?
+            .ok_or_else(|| AppError::not_found(anyhow::anyhow!("user {id}")))?;
+        self.db.delete_user(id).await?;
+        self.audit(
+            current_user,
+            AuditAction::UserDelete,
+            None,
+            &user.email,
+            serde_json::json!({ "user_id": id }),
+        )
+        .await
+    }
+
+    /// verify credentials and create session, returns user and session secret
+    pub async fn login(&self, credentials: Credentials) -> Result<(User, String)> {
+        let invalid = || AppError::unauthorized(anyhow::anyhow!("invalid email or password"));
+        let email = auth::normalize_email(&credentials.email).map_err(|_| invalid())?;
+        let (user, password_hash) = self
+            .db
+            .get_user_credentials(&email)
+            .await?
+            .ok_or_else(invalid)?;
+        if !auth::verify_password(credentials.password, password_hash).await? {
+            Err(invalid())?
+        }
+        let secret = auth::generate_secret();
+        let expires_at = Utc::now() + chrono::Duration::seconds(auth::SESSION_TTL);
+        self.db
+            .create_session(&auth::hash_secret(&secret), user.id, expires_at)
+            .await?;
+        Ok((user, secret))
+    }
+
+    pub async fn logout(&self, session_secret: &str) -> Result<()> {
+        self.db
+            .delete_session(&auth::hash_secret(session_secret))
+            .await
+    }
+
+    pub async fn authenticate_session(&self, session_secret: &str) -> Result<User> {
+        let user_id = self
+            .db
+            .get_session_user_id(&auth::hash_secret(session_secret))
+            .await?
+            .ok_or_else(|| AppError::unauthorized(anyhow::anyhow!("invalid or expired session")))?;
+        self.get_user(user_id).await
+    }
+
+    pub async fn authenticate_token(&self, token_secret: &str) -> Result<User> {
+        let user_id = self
+            .db
+            .get_api_token_user_id(&auth::hash_secret(token_secret))
+            .await?
+            .ok_or_else(|| AppError::unauthorized(anyhow::anyhow!("invalid api token")))?;
+        self.get_user(user_id).await
+    }
+
+    async fn get_user(&self, id: Uuid) -> Result<User> {
+        self.db
+            .get_user(id)
+            .await?
+            .ok_or_else(|| AppError::unauthorized(anyhow::anyhow!("user {id} not found")))
+    }
+
+    pub async fn create_api_token(&self, user: &User, name: &str) -> Result<NewApiToken> {
+        let token = ApiToken {
+            id: Uuid::now_v7(),
+            name: name.into(),
+            created_at: Utc::now(),
+        };
+        let secret = auth::generate_secret();
+        self.db
+            .create_api_token(user.id, &token, &auth::hash_secret(&secret))
+            .await?;
+        self.audit(
+            user,
+            AuditAction::ApiTokenCreate,
+            None,
+            &token.id.to_string(),
+            serde_json::json!({ "name": token.name }),
+        )
+        .await?;
+        Ok(NewApiToken { token, secret })
+    }
+
+    pub async fn list_api_tokens(&self, user: &User) -> Result<Vec<ApiToken>> {
+        self.db.list_api_tokens(user.id).await
+    }
+
+    pub async fn delete_api_token(&self, user: &User, id: Uuid) -> Result<()> {
+        self.db.delete_api_token(user.id, id).await?;
+        self.audit(
+            user,
+            AuditAction::ApiTokenDelete,
+            None,
+            &id.to_string(),
+            serde_json::json!({}),
+        )
+        .await
+    }
+
     // daemon API
 
-    pub async fn create_daemon_token(&self) -> Result<DaemonToken> {
+    /// create join token, daemon joins organization of token
+    pub async fn create_daemon_token(
+        &self,
+        user: &User,
+        organization_id: Option<Uuid>,
+    ) -> Result<DaemonToken> {
+        let organization_id = self.resolve_organization(user, organization_id).await?;
+        self.check_organization_admin(user, organization_id).await?;
         let secret = rand::random::<[u8; 16]>()
             .into_iter()
             .map(|byte| format!("{byte:x}"))
@@ -513,22 +1957,58 @@ impl App {
             secret,
             issued_at: Utc::now(),
             used_at: None,
+            organization_id: Some(organization_id),
         };
         self.db.store_daemon_token(&token).await?;
+        self.audit(
+            user,
+            AuditAction::DaemonTokenCreate,
+            token.organization_id,
+            &token.id.to_string(),
+            serde_json::json!({}),
+        )
+        .await?;
         Ok(token)
     }
 
-    pub async fn list_daemon_tokens(&self) -> Result<Vec<DaemonToken>> {
-        self.db.list_daemon_tokens().await
+    /// tokens of organizations, administered by user
+    pub async fn list_daemon_tokens(&self, user: &User) -> Result<Vec<DaemonToken>> {
+        let mut tokens = self.db.list_daemon_tokens().await?;
+        if !user.is_admin {
+            let organizations = self.db.list_user_organizations(user.id).await?;
+            tokens.retain(|token| {
+                token.organization_id.and_then(|id| organizations.get(&id))
+                    == Some(&OrganizationRole::Admin)
+            });
+        }
+        Ok(tokens)
     }
 
-    pub async fn delete_daemon_token(&self, id: uuid::Uuid) -> Result<()> {
+    pub async fn delete_daemon_token(&self, user: &User, id: uuid::Uuid) -> Result<()> {
+        let token = self
+            .list_daemon_tokens(user)
+            .await?
+            .into_iter()
+            .find(|token| token.id == id);
+        let token = token.ok_or_else(|| AppError::not_found(anyhow::anyhow!("token {id}")))?;
         self.db.delete_daemon_token(id).await?;
         self.daemon_tracker.shutdown_daemon(id).await?;
-        Ok(())
+        self.audit(
+            user,
+            AuditAction::DaemonTokenDelete,
+            token.organization_id,
+            &id.to_string(),
+            serde_json::json!({}),
+        )
+        .await
     }
 
-    pub async fn daemon_join(&self, join_request: DaemonJoinRequest) -> Result<DaemonJoinResponse> {
+    /// exchange join token for daemon certificate, address is source address of join request
+    pub async fn daemon_join(
+        &self,
+        join_request: DaemonJoinRequest,
+        address: IpAddr,
+    ) -> Result<DaemonJoinResponse> {
         let token = match self.db.consume_token(join_request.id).await? {
             None => return Err(AppError::not_found(anyhow::anyhow!("token not found"))),
             Some(token) => token,
@@ -548,19 +2028,47 @@ impl App {
             &join_request.id.to_string(),
         )
         .map_err(|e| anyhow::anyhow!("failed to sign certificate request: {e}"))?;
-        self.db.add_daemon(join_request.id).await?;
+        let serial_number = pki::serial_number(certificate.der())
+            .map_err(|e| anyhow::anyhow!("failed to read certificate serial number: {e}"))?;
+        self.db
+            .add_daemon(join_request.id, token.organization_id)
+            .await?;
+        self.db
+            .add_daemon_certificate(join_request.id, &encode_serial_number(&serial_number))
+            .await?;
+        // daemon joins on its own, so daemon is actor
+        let entry = AuditEntry {
+            id: 0,
+            created_at: Utc::now(),
+            user_id: None,
+            actor: join_request.id.to_string(),
+            address: Some(address.to_string()),
+            organization_id: token.organization_id,
+            action: AuditAction::DaemonJoin.as_str().into(),
+            target: join_request.id.to_string(),
+            payload: serde_json::json!({}),
+        };
+        self.db.append_audit_entry(&entry).await?;
         Ok(DaemonJoinResponse {
             certificate: certificate.pem(),
             ca_certificate: self.certificate_bundle.ca_cert_key.cert.pem(),
         })
     }
 
-    pub async fn list_daemons(&self) -> Result<Vec<Daemon>> {
+    /// daemons of organizations, user is member of
+    pub async fn list_daemons(&self, user: &User) -> Result<Vec<Daemon>> {
+        let organizations = self.db.list_user_organizations(user.id).await?;
         let mut stored_daemons = BTreeMap::from_iter(
             self.db
                 .list_daemons()
                 .await?
                 .into_iter()
+                .filter(|daemon| {
+                    user.is_admin
+                        || daemon
+                            .organization_id
+                            .is_some_and(|id| organizations.contains_key(&id))
+                })
                 .map(|daemon| (daemon.id, daemon)),
         );
         self.get_online_daemons().await?.into_iter().for_each(|id| {
@@ -571,9 +2079,70 @@ impl App {
         Ok(stored_daemons.into_values().collect())
     }
 
-    pub async fn delete_daemon(&self, id: Uuid) -> Result<()> {
+    pub async fn delete_daemon(&self, user: &User, id: Uuid) -> Result<()> {
+        let daemon = self
+            .check_daemon_access(user, id, OrganizationRole::Admin)
+            .await?;
+        // certificates are revoked before shutdown, so daemon can't reconnect in between
+        self.db.delete_daemon(id).await?;
+        self.refresh_crl().await?;
         self.daemon_tracker.shutdown_daemon(id).await?;
-        self.db.delete_daemon(id).await
+        self.audit(
+            user,
+            AuditAction::DaemonDelete,
+            daemon.organization_id,
+            &id.to_string(),
+            serde_json::json!({}),
+        )
+        .await
+    }
+
+    /// revoke daemon certificates and disconnect daemon, daemon needs new join token to reconnect
+    pub async fn revoke_daemon_certificate(&self, user: &User, id: Uuid) -> Result<()> {
+        let daemon = self
+            .check_daemon_access(user, id, OrganizationRole::Admin)
+            .await?;
+        self.db.revoke_daemon_certificates(id).await?;
+        self.refresh_crl().await?;
+        self.daemon_tracker.shutdown_daemon(id).await?;
+        self.audit(
+            user,
+            AuditAction::DaemonRevoke,
+            daemon.organization_id,
+            &id.to_string(),
+            serde_json::json!({}),
+        )
+        .await
+    }
+
+    /// reissue CRL with all revoked daemon certificates, new CRL is applied to next TLS handshakes
+    async fn refresh_crl(&self) -> Result<()> {
+        let mut crl_number = self.certificate_bundle.crl_number.lock().await;
+        let revoked = self
+            .db
+            .list_revoked_certificates()
+            .await?
+            .into_iter()
+            .map(|certificate| {
+                Ok(pki::RevokedCertificate {
+                    serial_number: decode_serial_number(&certificate.serial_number)?,
+                    revoked_at: certificate.revoked_at.into(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        *crl_number += 1;
+        let crl = pki::generate_crl(
+            &self.certificate_bundle.ca_cert_key,
+            *crl_number,
+            &revoked,
+            CRL_VALIDITY,
+        )
+        .map_err(|e| anyhow::anyhow!("failed to generate CRL: {e}"))?;
+        self.certificate_bundle
+            .client_verifier
+            .set_crl(&crl)
+            .map_err(|e| anyhow::anyhow!("failed to set CRL: {e}"))?;
+        Ok(())
     }
 
     pub async fn daemon_set_last_seen(&self, id: Uuid, timestamp: DateTime<Utc>) -> Result<()> {
@@ -585,27 +2154,551 @@ impl App {
         self.daemon_tracker.list_daemons().await
     }
 
-    pub async fn daemon_connected(&self, id: Uuid) -> Result<UnboundedReceiver<DaemonMessage>> {
-        match self.db.get_daemon(id).await?.is_some() {
-            true => self.daemon_tracker.daemon_connected(id).await,
-            false => Err(AppError::daemon_not_found(id)),
+    /// register daemon connection
+    ///
+    /// Connection is accepted only with certificate, recorded on join, and only if daemon wasn't revoked.
+    /// Daemons, which joined before serial numbers were tracked, need to rejoin.
+    pub async fn daemon_connected(
+        &self,
+        id: Uuid,
+        serial_number: &[u8],
+    ) -> Result<UnboundedReceiver<DaemonMessage>> {
+        let daemon = match self.db.get_daemon(id).await? {
+            Some(daemon) => daemon,
+            None => Err(AppError::daemon_not_found(id))?,
+        };
+        if daemon.revoked_at.is_some() {
+            Err(AppError::forbidden(anyhow::anyhow!("daemon '{id}' is revoked")))?
+        }
+        let serial_number = encode_serial_number(serial_number);
+        if !self
+            .db
+            .is_daemon_certificate_valid(id, &serial_number)
+            .await?
+        {
+            Err(AppError::forbidden(anyhow::anyhow!(
+                "certificate '{serial_number}' is not issued to daemon '{id}'"
+            )))?
         }
+        self.daemon_tracker.daemon_connected(id).await
     }
 
     pub async fn daemon_disconnected(&self, id: Uuid) -> Result<()> {
         self.daemon_tracker.daemon_disconnected(id).await
     }
 
+    /// graph of nodes, assigned to daemon
+    ///
+    /// Nodes, which config can't be resolved, are skipped with their pipelines and reported in workspace state.
     pub async fn get_daemon_graph(&self, id: Uuid) -> Result<DaemonGraph> {
-        let mut daemon_graph = self.db.get_daemon_graph(id).await?;
-        daemon_graph
-            .nodes
-            .iter_mut()
-            .try_for_each(|node| node.build_config(&self.config_registry))?;
+        let organization_id = self
+            .db
+            .get_daemon(id)
+            .await?
+            .and_then(|daemon| daemon.organization_id);
+        let config_registry = self.config_registry(organization_id);
+        let (daemon_graph, errors) = self.db.get_daemon_graph(&config_registry, id).await?;
+        self.daemon_tracker.set_node_errors(id, errors).await?;
         Ok(daemon_graph)
     }
 
-    pub async fn set_daemon_name(&self, id: Uuid, name: Option<&str>) -> Result<()> {
-        self.db.set_daemon_name(id, name).await
+    pub async fn set_daemon_name(&self, user: &User, id: Uuid, name: Option<&str>) -> Result<()> {
+        let daemon = self
+            .check_daemon_access(user, id, OrganizationRole::Admin)
+            .await?;
+        self.db.set_daemon_name(id, name).await?;
+        self.audit(
+            user,
+            AuditAction::DaemonSetName,
+            daemon.organization_id,
+            &id.to_string(),
+            serde_json::json!({ "name": name }),
+        )
+        .await
+    }
+
+    // variables API
+
+    /// variable values can hold secrets, so they are listed only for editors
+    pub async fn list_workspace_variables(
+        &self,
+        user: &User,
+        workspace_name: &str,
+        organization_id: Option<Uuid>,
+    ) -> Result<Vec<VariableEntry>> {
+        let (workspace, role) = self
+            .check_workspace_role(user, workspace_name, organization_id, Role::Viewer)
+            .await?;
+        let variables = self.db.list_workspace_variables(&workspace).await?;
+        Ok(variables
+            .into_iter()
+            .map(|variable| VariableEntry::new(variable, role >= Role::Editor))
+            .collect())
+    }
+
+    pub async fn set_workspace_variable(
+        &self,
+        user: &User,
+        workspace_name: &str,
+        organization_id: Option<Uuid>,
+        variable: &Variable,
+    ) -> Result<()> {
+        let (workspace, _) = self
+            .check_workspace_role(user, workspace_name, organization_id, Role::Editor)
+            .await?;
+        validate_variable_name(&variable.name)?;
+        self.db
+            .set_workspace_variable(&workspace, variable)
+            .await?;
+        self.daemon_tracker.notify_graph_update().await?;
+        // variable values can hold secrets, so only name is recorded
+        self.audit(
+            user,
+            AuditAction::WorkspaceVariableSet,
+            workspace.organization_id,
+            workspace_name,
+            serde_json::json!({ "name": variable.name }),
+        )
+        .await
+    }
+
+    pub async fn delete_workspace_variable(
+        &self,
+        user: &User,
+        workspace_name: &str,
+        organization_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<()> {
+        let (workspace, _) = self
+            .check_workspace_role(user, workspace_name, organization_id, Role::Editor)
+            .await?;
+        self.db
+            .delete_workspace_variable(&workspace, name)
+            .await?;
+        self.daemon_tracker.notify_graph_update().await?;
+        self.audit(
+            user,
+            AuditAction::WorkspaceVariableDelete,
+            workspace.organization_id,
+            workspace_name,
+            serde_json::json!({ "name": name }),
+        )
+        .await
+    }
+
+    /// variable values can hold secrets, so they are listed only for organization admins
+    pub async fn list_daemon_variables(
+        &self,
+        user: &User,
+        id: Uuid,
+    ) -> Result<Vec<VariableEntry>> {
+        let daemon = self
+            .check_daemon_access(user, id, OrganizationRole::Member)
+            .await?;
+        let is_admin = match daemon.organization_id {
+            Some(organization_id) => {
+                self.organization_role(user, organization_id).await?
+                    == Some(OrganizationRole::Admin)
+            }
+            None => user.is_admin,
+        };
+        let variables = self.db.list_daemon_variables(id).await?;
+        Ok(variables
+            .into_iter()
+            .map(|variable| VariableEntry::new(variable, is_admin))
+            .collect())
+    }
+
+    pub async fn set_daemon_variable(
+        &self,
+        user: &User,
+        id: Uuid,
+        variable: &Variable,
+    ) -> Result<()> {
+        validate_variable_name(&variable.name)?;
+        if RESERVED_DAEMON_VARIABLES.contains(&variable.name.as_str()) {
+            Err(AppError::bad_request(anyhow::anyhow!(
+                "daemon variable '{}' is reserved",
+                variable.name
+            )))?
+        }
+        let daemon = self
+            .check_daemon_access(user, id, OrganizationRole::Admin)
+            .await?;
+        self.db.set_daemon_variable(id, variable).await?;
+        self.daemon_tracker.notify_graph_update().await?;
+        self.audit(
+            user,
+            AuditAction::DaemonVariableSet,
+            daemon.organization_id,
+            &id.to_string(),
+            serde_json::json!({ "name": variable.name }),
+        )
+        .await
+    }
+
+    pub async fn delete_daemon_variable(&self, user: &User, id: Uuid, name: &str) -> Result<()> {
+        let daemon = self
+            .check_daemon_access(user, id, OrganizationRole::Admin)
+            .await?;
+        self.db.delete_daemon_variable(id, name).await?;
+        self.daemon_tracker.notify_graph_update().await?;
+        self.audit(
+            user,
+            AuditAction::DaemonVariableDelete,
+            daemon.organization_id,
+            &id.to_string(),
+            serde_json::json!({ "name": name }),
+        )
+        .await
+    }
+
+    // audit API
+
+    /// audit log page, instance admins see all entries, organization admins see entries of their organizations
+    pub async fn list_audit_entries(&self, user: &User, filter: &AuditFilter) -> Result<AuditPage> {
+        let organizations = match user.is_admin {
+            true => None,
+            false => {
+                let organizations = self
+                    .db
+                    .list_user_organizations(user.id)
+                    .await?
+                    .into_iter()
+                    .filter(|(_, role)| *role == OrganizationRole::Admin)
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                if organizations.is_empty() {
+                    Err(AppError::forbidden(anyhow::anyhow!(
+                        "organization admin role is required"
+                    )))?
+                }
+                Some(organizations)
+            }
+        };
+        let entries = self
+            .db
+            .list_audit_entries(filter, organizations.as_deref())
+            .await?;
+        let next = match entries.len() as u64 == filter.page_size() {
+            true => entries.last().map(|entry| entry.id),
+            false => None,
+        };
+        Ok(AuditPage { entries, next })
+    }
+
+    /// append entry, made by user, to audit log
+    async fn audit(
+        &self,
+        user: &User,
+        action: AuditAction,
+        organization_id: Option<Uuid>,
+        target: &str,
+        payload: serde_json::Value,
+    ) -> Result<()> {
+        let entry = AuditEntry {
+            id: 0,
+            created_at: Utc::now(),
+            user_id: Some(user.id),
+            actor: user.email.clone(),
+            address: user.address.map(|address| address.to_string()),
+            organization_id,
+            action: action.as_str().into(),
+            target: target.into(),
+            payload,
+        };
+        self.db.append_audit_entry(&entry).await
+    }
+
+    // relay API
+
+    /// queue message, relayed by daemon over boundary edge, and notify destination daemon
+    ///
+    /// Returns false if daemon doesn't have edge to destination node anymore or message is invalid,
+    /// such messages are dropped.
+    /// Messages, which were not acked by destination daemon within retention period, are dropped
+    /// and acked to origin daemon, so daemons, which never come back, don't hold messages forever.
+    pub async fn relay_message(&self, daemon_id: Uuid, message: &RelayMessage) -> Result<bool> {
+        let expired_before = Utc::now() - chrono::Duration::days(RELAY_MESSAGE_RETENTION_DAYS);
+        for (origin_daemon_id, message_id) in
+            self.db.delete_expired_relay_messages(expired_before).await?
+        {
+            tracing::warn!(
+                "dropping message {message_id} relayed by daemon {origin_daemon_id}: not delivered within {RELAY_MESSAGE_RETENTION_DAYS} days"
+            );
+            self.daemon_tracker
+                .notify_daemon(origin_daemon_id, DaemonMessage::RelayAck(message_id))
+                .await?;
+        }
+        match self.db.store_relay_message(daemon_id, message).await {
+            Ok(destination) => {
+                if let Some(destination) = destination {
+                    self.daemon_tracker
+                        .notify_daemon(destination, DaemonMessage::RelayAvailable)
+                        .await?;
+                }
+                Ok(true)
+            }
+            Err(e) if matches!(e.kind, AppErrorKind::NotFound | AppErrorKind::BadRequest) => {
+                tracing::warn!(
+                    "dropping message {} relayed by daemon {daemon_id}: {}",
+                    message.id,
+                    e.err
+                );
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// messages, queued for nodes of daemon, with id greater than `after`
+    pub async fn list_relayed_messages(
+        &self,
+        daemon_id: Uuid,
+        after: i64,
+    ) -> Result<Vec<(i64, RelayMessage)>> {
+        self.db
+            .list_relayed_messages(daemon_id, after, RELAY_BATCH_SIZE)
+            .await
+    }
+
+    /// remove message, acked by destination daemon, and pass ack to origin daemon
+    pub async fn ack_relayed_message(&self, daemon_id: Uuid, id: i64) -> Result<()> {
+        if let Some((origin_daemon_id, message_id)) =
+            self.db.delete_relayed_message(daemon_id, id).await?
+        {
+            self.daemon_tracker
+                .notify_daemon(origin_daemon_id, DaemonMessage::RelayAck(message_id))
+                .await?;
+        }
+        Ok(())
+    }
+
+    // access helpers
+
+    /// workspace with given name and role of user in it, workspace is not found for users without role
+    ///
+    /// Names are unique within organization, so organization should be given if user has access to
+    /// workspaces with same name in several organizations.
+    async fn workspace_role(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+    ) -> Result<(WorkspaceKey, Role)> {
+        let mut organizations = self.db.list_workspace_organizations(name).await?;
+        if let Some(organization_id) = organization_id {
+            organizations.retain(|id| *id == Some(organization_id));
+        }
+        let (user_organizations, workspace_roles) = match user.is_admin {
+            true => Default::default(),
+            false => (
+                self.db.list_user_organizations(user.id).await?,
+                self.db.list_user_workspace_roles(user.id).await?,
+            ),
+        };
+        let mut workspaces = organizations
+            .into_iter()
+            .filter_map(|organization_id| {
+                let workspace = WorkspaceKey {
+                    organization_id,
+                    name: name.into(),
+                };
+                let organization_role =
+                    organization_id.and_then(|id| user_organizations.get(&id));
+                let role = match user.is_admin
+                    || organization_role == Some(&OrganizationRole::Admin)
+                {
+                    true => Role::Admin,
+                    false => *workspace_roles.get(&workspace)?,
+                };
+                Some((workspace, role))
+            })
+            .collect::<Vec<_>>();
+        match workspaces.len() {
+            0 => Err(AppError::workspace_not_found(name)),
+            1 => Ok(workspaces.remove(0)),
+            _ => Err(AppError::bad_request(anyhow::anyhow!(
+                "workspace '{name}' exists in several organizations, organization should be specified"
+            ))),
+        }
+    }
+
+    async fn check_workspace_role(
+        &self,
+        user: &User,
+        name: &str,
+        organization_id: Option<Uuid>,
+        required: Role,
+    ) -> Result<(WorkspaceKey, Role)> {
+        let (workspace, role) = self.workspace_role(user, name, organization_id).await?;
+        match role >= required {
+            true => Ok((workspace, role)),
+            false => Err(AppError::forbidden(anyhow::anyhow!(
+                "{required} role in workspace '{name}' is required, user has {role} role"
+            ))),
+        }
+    }
+
+    /// role of user in organization, instance admins are admins of every organization
+    async fn organization_role(
+        &self,
+        user: &User,
+        organization_id: Uuid,
+    ) -> Result<Option<OrganizationRole>> {
+        if user.is_admin {
+            return Ok(Some(OrganizationRole::Admin));
+        }
+        Ok(self
+            .db
+            .list_user_organizations(user.id)
+            .await?
+            .remove(&organization_id))
+    }
+
+    async fn check_organization_admin(&self, user: &User, organization_id: Uuid) -> Result<()> {
+        match self.organization_role(user, organization_id).await? {
+            Some(OrganizationRole::Admin) => Ok(()),
+            Some(_) => Err(AppError::forbidden(anyhow::anyhow!(
+                "admin role in organization {organization_id} is required"
+            ))),
+            None => Err(AppError::not_found(anyhow::anyhow!(
+                "organization {organization_id}"
+            ))),
+        }
+    }
+
+    /// organization, resource is created in, if not given, user should be member of single organization
+    async fn resolve_organization(
+        &self,
+        user: &User,
+        organization_id: Option<Uuid>,
+    ) -> Result<Uuid> {
+        if let Some(organization_id) = organization_id {
+            return Ok(organization_id);
+        }
+        let organizations = self.list_organizations(user).await?;
+        match organizations.as_slice() {
+            [organization] => Ok(organization.id),
+            [] => Err(AppError::bad_request(anyhow::anyhow!(
+                "user is not member of any organization"
+            ))),
+            _ => Err(AppError::bad_request(anyhow::anyhow!(
+                "user is member of several organizations, organization should be specified"
+            ))),
+        }
+    }
+
+    /// organization, user is member of, organization is not found for non-members
+    async fn member_organization(&self, user: &User, organization_id: Option<Uuid>) -> Result<Uuid> {
+        let organization_id = self.resolve_organization(user, organization_id).await?;
+        match self.organization_role(user, organization_id).await? {
+            Some(_) => Ok(organization_id),
+            None => Err(AppError::not_found(anyhow::anyhow!(
+                "organization {organization_id}"
+            ))),
+        }
+    }
+
+    async fn find_organization(&self, name: &str) -> Result<Option<Organization>> {
+        Ok(self
+            .db
+            .list_organizations()
+            .await?
+            .into_iter()
+            .find(|organization| organization.name == name))
+    }
+
+    async fn get_organization(&self, name: &str) -> Result<Organization> {
+        self.find_organization(name)
+            .await?
+            .ok_or_else(|| AppError::not_found(anyhow::anyhow!("organization '{name}'")))
+    }
+
+    /// daemons of other organizations are not found
+    async fn check_daemon_access(
+        &self,
+        user: &User,
+        id: Uuid,
+        required: OrganizationRole,
+    ) -> Result<Daemon> {
+        let daemon = self
+            .db
+            .get_daemon(id)
+            .await?
+            .ok_or_else(|| AppError::daemon_not_found(id))?;
+        let role = match (user.is_admin, daemon.organization_id) {
+            (true, _) => Some(OrganizationRole::Admin),
+            (false, Some(organization_id)) => self.organization_role(user, organization_id).await?,
+            (false, None) => None,
+        };
+        match role {
+            Some(role) if role >= required => Ok(daemon),
+            Some(_) => Err(AppError::forbidden(anyhow::anyhow!(
+                "admin role in daemon organization is required"
+            ))),
+            None => Err(AppError::daemon_not_found(id)),
+        }
+    }
+
+    /// daemons of workspace organization, nodes can be assigned only to them
+    async fn list_workspace_daemons(&self, workspace: &WorkspaceKey) -> Result<Vec<Daemon>> {
+        let mut daemons = self.db.list_daemons().await?;
+        daemons.retain(|daemon| daemon.organization_id == workspace.organization_id);
+        Ok(daemons)
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<User> {
+        let email = auth::normalize_email(email)?;
+        match self.db.get_user_credentials(&email).await? {
+            Some((user, _)) => Ok(user),
+            None => Err(AppError::not_found(anyhow::anyhow!("user '{email}'"))),
+        }
+    }
+}
+
+fn check_instance_admin(user: &User) -> Result<()> {
+    match user.is_admin {
+        true => Ok(()),
+        false => Err(AppError::forbidden(anyhow::anyhow!(
+            "instance admin role is required"
+        ))),
+    }
+}
+
+/// validity of CRL, CRL is reissued on each revocation and on start, so expiration isn't enforced
+const CRL_VALIDITY: std::time::Duration = std::time::Duration::from_secs(7 * 24 * 60 * 60);
+
+// certificate serial numbers are stored hex encoded
+fn encode_serial_number(serial_number: &[u8]) -> String {
+    serial_number
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn decode_serial_number(serial_number: &str) -> Result<Vec<u8>> {
+    serial_number
+        .as_bytes()
+        .chunks(2)
+        .map(|byte| {
+            std::str::from_utf8(byte)
+                .ok()
+                .filter(|byte| byte.len() == 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| anyhow::anyhow!("invalid serial number '{serial_number}'").into())
+        })
+        .collect()
+}
+
+/// daemon variables, provided by control plane
+const RESERVED_DAEMON_VARIABLES: &[&str] = &["id", "name"];
+
+fn validate_variable_name(name: &str) -> Result<()> {
+    match config::template::is_valid_name(name) {
+        true => Ok(()),
+        false => Err(AppError::bad_request(anyhow::anyhow!(
+            "invalid variable name '{name}', expected letters, digits and underscores"
+        ))),
     }
 }
@@ -21,6 +21,8 @@ pub enum Messages {
     Origin,
     StreamType,
     CreatedAt,
+    FromDaemonId,
+    FromMessageId,
 }
 
 #[derive(Iden)]
@@ -38,6 +40,71 @@ pub enum Workspaces {
     UserId,
     Name,
     CreatedAt,
+    OrganizationId,
+}
+
+#[derive(Iden)]
+pub enum WorkspaceMembers {
+    Table,
+    WorkspaceId,
+    UserId,
+    Role,
+}
+
+#[derive(Iden)]
+pub enum WorkspaceRevisions {
+    Table,
+    WorkspaceId,
+    Revision,
+    Operations,
+    Inverse,
+    Author,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum Users {
+    Table,
+    Id,
+    Email,
+    PasswordHash,
+    CreatedAt,
+    IsAdmin,
+}
+
+#[derive(Iden)]
+pub enum Organizations {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum OrganizationMembers {
+    Table,
+    OrganizationId,
+    UserId,
+    Role,
+}
+
+#[derive(Iden)]
+pub enum UserSessions {
+    Table,
+    Id,
+    UserId,
+    CreatedAt,
+    ExpiresAt,
+}
+
+#[derive(Iden)]
+pub enum ApiTokens {
+    Table,
+    Id,
+    UserId,
+    Name,
+    SecretHash,
+    CreatedAt,
 }
 
 #[derive(Iden)]
@@ -69,6 +136,9 @@ pub enum Nodes {
     Config,
     X,
     Y,
+    Paused,
+    Restarts,
+    EscapeTemplates,
 }
 
 #[derive(Iden)]
@@ -116,6 +186,9 @@ pub enum Daemons {
     Address,
     LastOnline,
     JoinedAt,
+    Version,
+    OrganizationId,
+    RevokedAt,
 }
 
 #[derive(Iden)]
@@ -125,4 +198,52 @@ pub enum DaemonTokens {
     Secret,
     IssuedAt,
     UsedAt,
+    OrganizationId,
+}
+
+#[derive(Iden)]
+pub enum WorkspaceVariables {
+    Table,
+    WorkspaceId,
+    Name,
+    Value,
+}
+
+#[derive(Iden)]
+pub enum DaemonVariables {
+    Table,
+    DaemonId,
+    Name,
+    Value,
+}
+
+#[derive(Iden)]
+pub enum DaemonSections {
+    Table,
+    DaemonId,
+    Name,
+    Version,
+}
+
+#[derive(Iden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    CreatedAt,
+    UserId,
+    Actor,
+    Address,
+    OrganizationId,
+    Action,
+    Target,
+    Payload,
+}
+
+#[derive(Iden)]
+pub enum DaemonCertificates {
+    Table,
+    SerialNumber,
+    DaemonId,
+    IssuedAt,
+    RevokedAt,
 }
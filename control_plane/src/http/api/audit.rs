@@ -0,0 +1,23 @@
+//! Audit log routes
+
+use crate::{
+    app::{
+        audit::{AuditFilter, AuditPage},
+        auth::User,
+        AppState,
+    },
+    http::Result,
+};
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+
+/// audit log page, newest entries first, pass `next` of response as `before` to fetch next page
+pub async fn list(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(filter): Query<AuditFilter>,
+) -> Result<Json<AuditPage>> {
+    app.list_audit_entries(&user, &filter).await.map(Json)
+}
@@ -0,0 +1,137 @@
+//! Authentication of HTTP API calls and user routes
+//!
+//! UI authenticates with session cookie, which is set on login.
+//! Automation authenticates with API token, passed as `Authorization: Bearer <secret>`.
+
+use crate::{
+    app::{
+        auth::{ApiToken, Credentials, NewApiToken, User, SESSION_COOKIE, SESSION_TTL},
+        AppError, AppState,
+    },
+    http::Result,
+};
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Path, State},
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization, Cookie, HeaderMapExt};
+use serde::Deserialize;
+use std::net::SocketAddr;
+
+// routes, which are accessible without authentication
+const PUBLIC_ROUTES: &[&str] = &["/api/daemon/join", "/api/auth/login"];
+
+fn session_cookie(secret: &str, max_age: i64) -> String {
+    format!("{SESSION_COOKIE}={secret}; Path=/; HttpOnly; SameSite=Strict; Max-Age={max_age}")
+}
+
+/// Authenticate API calls with session cookie or bearer token
+///
+/// Authenticated user with request source address is added to request extensions.
+pub async fn auth_middleware(
+    State(app): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    let path = request.uri().path();
+    if !path.starts_with("/api/") || PUBLIC_ROUTES.contains(&path) {
+        return Ok(next.run(request).await);
+    }
+    let headers = request.headers();
+    let mut user = match (
+        headers.typed_get::<Authorization<Bearer>>(),
+        headers.typed_get::<Cookie>(),
+    ) {
+        (Some(bearer), _) => app.authenticate_token(bearer.token()).await?,
+        (None, Some(cookie)) => match cookie.get(SESSION_COOKIE) {
+            Some(secret) => app.authenticate_session(secret).await?,
+            None => Err(AppError::unauthorized(anyhow::anyhow!("no credentials")))?,
+        },
+        (None, None) => Err(AppError::unauthorized(anyhow::anyhow!("no credentials")))?,
+    };
+    user.address = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+    request.extensions_mut().insert(user);
+    Ok(next.run(request).await)
+}
+
+pub async fn login(
+    State(app): State<AppState>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Response> {
+    let (user, secret) = app.login(credentials).await?;
+    let cookie = session_cookie(&secret, SESSION_TTL);
+    Ok(([(header::SET_COOKIE, cookie)], Json(user)).into_response())
+}
+
+pub async fn logout(State(app): State<AppState>, request: Request<Body>) -> Result<Response> {
+    let secret = request
+        .headers()
+        .typed_get::<Cookie>()
+        .and_then(|cookie| cookie.get(SESSION_COOKIE).map(String::from));
+    if let Some(secret) = secret {
+        app.logout(&secret).await?;
+    }
+    Ok([(header::SET_COOKIE, session_cookie("", 0))].into_response())
+}
+
+pub async fn me(Extension(user): Extension<User>) -> Json<User> {
+    Json(user)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewApiTokenRequest {
+    name: String,
+}
+
+pub async fn create_token(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Json(request): Json<NewApiTokenRequest>,
+) -> Result<Json<NewApiToken>> {
+    app.create_api_token(&user, &request.name).await.map(Json)
+}
+
+pub async fn list_tokens(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<ApiToken>>> {
+    app.list_api_tokens(&user).await.map(Json)
+}
+
+pub async fn delete_token(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> Result<()> {
+    app.delete_api_token(&user, id.parse()?).await
+}
+
+pub async fn list_users(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<User>>> {
+    app.list_users(&user).await.map(Json)
+}
+
+pub async fn create_user(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<User>> {
+    app.create_user(&user, credentials).await.map(Json)
+}
+
+pub async fn delete_user(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> Result<()> {
+    app.delete_user(&user, id.parse()?).await
+}
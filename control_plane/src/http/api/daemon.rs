@@ -1,35 +1,55 @@
-use axum::extract::{Path, State};
-use axum::{http::StatusCode, Json};
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::{http::StatusCode, Extension, Json};
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::net::SocketAddr;
 
 use crate::app::{
-    AppErrorKind, AppState, Daemon, DaemonJoinRequest, DaemonJoinResponse, DaemonToken,
+    auth::User, AppErrorKind, AppState, Daemon, DaemonJoinRequest, DaemonJoinResponse, DaemonToken,
 };
 use crate::http::Result;
 use crate::AppError;
 
-pub async fn create_token(State(app): State<AppState>) -> Result<Json<DaemonToken>> {
-    let token = app.create_daemon_token().await?;
+#[derive(Deserialize)]
+pub struct NewTokenParams {
+    organization_id: Option<uuid::Uuid>,
+}
+
+pub async fn create_token(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<NewTokenParams>,
+) -> Result<Json<DaemonToken>> {
+    let token = app
+        .create_daemon_token(&user, params.organization_id)
+        .await?;
     Ok(Json(token))
 }
 
-pub async fn list_tokens(State(app): State<AppState>) -> Result<Json<Vec<DaemonToken>>> {
-    let tokens = app.list_daemon_tokens().await?;
+pub async fn list_tokens(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<DaemonToken>>> {
+    let tokens = app.list_daemon_tokens(&user).await?;
     Ok(Json(tokens))
 }
 
-pub async fn delete_token(State(app): State<AppState>, Path(id): Path<String>) -> Result<()> {
+pub async fn delete_token(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> Result<()> {
     let id: uuid::Uuid = id.parse()?;
-    app.delete_daemon_token(id).await?;
+    app.delete_daemon_token(&user, id).await?;
     Ok(())
 }
 
 pub async fn join(
     State(app): State<AppState>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
     Json(join_request): Json<DaemonJoinRequest>,
 ) -> Result<Json<DaemonJoinResponse>, (StatusCode, Json<Value>)> {
-    match app.daemon_join(join_request).await {
+    match app.daemon_join(join_request, address.ip()).await {
         Ok(response) => Ok(Json(response)),
         Err(AppError {
             kind: AppErrorKind::TokenUsed,
@@ -62,12 +82,28 @@ pub async fn join(
     }
 }
 
-pub async fn list_daemons(State(app): State<AppState>) -> Result<Json<Vec<Daemon>>> {
-    app.list_daemons().await.map(Json)
+pub async fn list_daemons(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<Daemon>>> {
+    app.list_daemons(&user).await.map(Json)
 }
 
-pub async fn delete_daemon(State(app): State<AppState>, Path(id): Path<String>) -> Result<()> {
-    app.delete_daemon(id.parse()?).await
+pub async fn delete_daemon(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> Result<()> {
+    app.delete_daemon(&user, id.parse()?).await
+}
+
+/// revoke daemon certificate, daemon is disconnected and can't reconnect
+pub async fn revoke(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> Result<()> {
+    app.revoke_daemon_certificate(&user, id.parse()?).await
 }
 
 #[derive(Deserialize)]
@@ -77,10 +113,11 @@ pub struct SetName {
 
 pub async fn set_name(
     State(app): State<AppState>,
+    Extension(user): Extension<User>,
     Path(id): Path<String>,
     name: Json<SetName>,
 ) -> Result<()> {
-    app.set_daemon_name(id.parse()?, name.name.as_deref())
+    app.set_daemon_name(&user, id.parse()?, name.name.as_deref())
         .await?;
     Ok(())
 }
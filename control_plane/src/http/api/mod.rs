@@ -1,5 +1,10 @@
 pub mod assets;
+pub mod audit;
+pub mod auth;
 pub mod daemon;
+pub mod organizations;
+pub mod sections;
+pub mod variables;
 pub mod workspace;
 pub mod workspaces;
 
@@ -11,6 +16,34 @@ use axum::{
 
 pub fn new(app: crate::app::AppState) -> Router {
     Router::new()
+        // auth API
+        .route("/api/auth/login", post(auth::login))
+        .route("/api/auth/logout", post(auth::logout))
+        .route("/api/auth/me", get(auth::me))
+        .route(
+            "/api/auth/tokens",
+            get(auth::list_tokens).post(auth::create_token),
+        )
+        .route("/api/auth/tokens/:id", delete(auth::delete_token))
+        // users API
+        .route("/api/users", get(auth::list_users).post(auth::create_user))
+        .route("/api/users/:id", delete(auth::delete_user))
+        // organizations API
+        .route(
+            "/api/organizations",
+            get(organizations::list).post(organizations::create),
+        )
+        .route("/api/organizations/:name", delete(organizations::delete))
+        .route(
+            "/api/organizations/:name/members",
+            get(organizations::list_members).post(organizations::set_member),
+        )
+        .route(
+            "/api/organizations/:name/members/:user_id",
+            delete(organizations::remove_member),
+        )
+        // audit API
+        .route("/api/audit", get(audit::list))
         // workspaces API
         .route(
             "/api/workspaces",
@@ -20,6 +53,35 @@ pub fn new(app: crate::app::AppState) -> Router {
         // workspace API
         .route("/api/workspace", post(workspace::update))
         .route("/api/workspace/:name", get(workspace::read))
+        .route("/api/workspace/:name/history", get(workspace::history))
+        .route("/api/workspace/:name/diff/:from/:to", get(workspace::diff))
+        .route("/api/workspace/:name/export", get(workspace::export))
+        .route("/api/workspace/:name/apply", post(workspace::apply))
+        .route(
+            "/api/workspace/:name/rollback/:rev",
+            post(workspace::rollback),
+        )
+        // workspace members API
+        .route(
+            "/api/workspace/:name/members",
+            get(workspace::list_members).post(workspace::set_member),
+        )
+        .route(
+            "/api/workspace/:name/members/:user_id",
+            delete(workspace::remove_member),
+        )
+        // workspace variables API
+        .route(
+            "/api/workspace/:name/variables",
+            get(variables::list_workspace_variables).post(variables::set_workspace_variable),
+        )
+        .route(
+            "/api/workspace/:name/variables/:variable",
+            delete(variables::delete_workspace_variable),
+        )
+        // section catalog API
+        .route("/api/sections", get(sections::list))
+        .route("/api/sections/plugins", get(sections::list_plugins))
         // daemon join api
         .route("/api/daemon/join", post(daemon::join))
         // daemon tokens api
@@ -32,8 +94,22 @@ pub fn new(app: crate::app::AppState) -> Router {
         .route("/api/daemon", get(daemon::list_daemons))
         .route("/api/daemon/:id", delete(daemon::delete_daemon))
         .route("/api/daemon/set_name/:id", post(daemon::set_name))
+        .route("/api/daemon/revoke/:id", post(daemon::revoke))
+        // daemon variables API
+        .route(
+            "/api/daemon/:id/variables",
+            get(variables::list_daemon_variables).post(variables::set_daemon_variable),
+        )
+        .route(
+            "/api/daemon/:id/variables/:variable",
+            delete(variables::delete_daemon_variable),
+        )
         // assets
         .fallback(assets::assets)
+        .layer(middleware::from_fn_with_state(
+            app.clone(),
+            auth::auth_middleware,
+        ))
         .layer(middleware::from_fn(crate::http::log_middleware))
         .with_state(app)
 }
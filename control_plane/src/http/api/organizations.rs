@@ -0,0 +1,65 @@
+//! Organizations routes
+
+use crate::{
+    app::{
+        access::{MemberRequest, Organization, OrganizationMember, OrganizationRole},
+        auth::User,
+        AppState,
+    },
+    http::Result,
+};
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+
+pub async fn list(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<Organization>>> {
+    app.list_organizations(&user).await.map(Json)
+}
+
+pub async fn create(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Json(organization): Json<Organization>,
+) -> Result<Json<Organization>> {
+    app.create_organization(&user, &organization)
+        .await
+        .map(Json)
+}
+
+pub async fn delete(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(name): Path<String>,
+) -> Result<()> {
+    app.delete_organization(&user, &name).await
+}
+
+pub async fn list_members(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<OrganizationMember>>> {
+    app.list_organization_members(&user, &name).await.map(Json)
+}
+
+pub async fn set_member(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(name): Path<String>,
+    Json(member): Json<MemberRequest<OrganizationRole>>,
+) -> Result<()> {
+    app.set_organization_member(&user, &name, member).await
+}
+
+pub async fn remove_member(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((name, user_id)): Path<(String, String)>,
+) -> Result<()> {
+    app.remove_organization_member(&user, &name, user_id.parse()?)
+        .await
+}
@@ -0,0 +1,42 @@
+//! Section catalog routes
+
+use crate::{
+    app::{auth::User, AppState},
+    http::Result,
+};
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+use config_registry::plugin::SectionDescriptor;
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// plugin sections are registered per organization, organization can be omitted if user is member of single one
+#[derive(Debug, Deserialize)]
+pub struct SectionParams {
+    organization_id: Option<Uuid>,
+}
+
+/// JSON Schema of every section config, known to control plane in organization
+pub async fn list(
+    app: State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<SectionParams>,
+) -> Result<Json<Vec<Value>>> {
+    Ok(Json(
+        app.section_schemas(&user, params.organization_id).await?,
+    ))
+}
+
+/// Descriptors of plugin sections, reported by daemons of organization
+pub async fn list_plugins(
+    app: State<AppState>,
+    Extension(user): Extension<User>,
+    Query(params): Query<SectionParams>,
+) -> Result<Json<Vec<SectionDescriptor>>> {
+    Ok(Json(
+        app.plugin_sections(&user, params.organization_id).await?,
+    ))
+}
@@ -0,0 +1,71 @@
+//! Workspace and daemon variables routes
+
+use crate::{
+    app::{auth::User, AppState, Variable, VariableEntry},
+    http::Result,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+
+use super::workspace::WorkspaceParams;
+
+pub async fn list_workspace_variables(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(workspace_name): Path<String>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<Json<Vec<VariableEntry>>> {
+    app.list_workspace_variables(&user, &workspace_name, params.organization_id)
+        .await
+        .map(Json)
+}
+
+pub async fn set_workspace_variable(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(workspace_name): Path<String>,
+    Query(params): Query<WorkspaceParams>,
+    Json(variable): Json<Variable>,
+) -> Result<()> {
+    app.set_workspace_variable(&user, &workspace_name, params.organization_id, &variable)
+        .await
+}
+
+pub async fn delete_workspace_variable(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((workspace_name, name)): Path<(String, String)>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<()> {
+    app.delete_workspace_variable(&user, &workspace_name, params.organization_id, &name)
+        .await
+}
+
+pub async fn list_daemon_variables(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<VariableEntry>>> {
+    app.list_daemon_variables(&user, id.parse()?)
+        .await
+        .map(Json)
+}
+
+pub async fn set_daemon_variable(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+    Json(variable): Json<Variable>,
+) -> Result<()> {
+    app.set_daemon_variable(&user, id.parse()?, &variable).await
+}
+
+pub async fn delete_daemon_variable(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((id, name)): Path<(String, String)>,
+) -> Result<()> {
+    app.delete_daemon_variable(&user, id.parse()?, &name).await
+}
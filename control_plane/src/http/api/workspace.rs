@@ -1,24 +1,183 @@
 use crate::{
-    app::{AppState, WorkspaceState, WorkspaceUpdate, WorkspaceUpdateResult},
+    app::{
+        access::{MemberRequest, Role, WorkspaceMember},
+        auth::User,
+        document::{WorkspaceApplyResult, WorkspaceDocument},
+        AppError, AppState, WorkspaceDiff, WorkspaceRevision, WorkspaceState, WorkspaceUpdate,
+        WorkspaceUpdateResult,
+    },
     http::Result,
 };
 use axum::{
-    extract::{Path, State},
-    Json,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// organization of workspace, required if user has access to workspaces with same name in several organizations
+#[derive(Debug, Deserialize)]
+pub struct WorkspaceParams {
+    pub organization_id: Option<Uuid>,
+}
 
 // Return workspace state:
 // - nodes, edges
 pub async fn read(
     State(app): State<AppState>,
+    Extension(user): Extension<User>,
     Path(workspace_name): Path<String>,
+    Query(params): Query<WorkspaceParams>,
 ) -> Result<Json<WorkspaceState>> {
-    app.get_workspace(&workspace_name).await.map(Json)
+    app.get_workspace(&user, &workspace_name, params.organization_id)
+        .await
+        .map(Json)
 }
 
 pub async fn update(
     State(app): State<AppState>,
+    Extension(user): Extension<User>,
     Json(mut updates): Json<Vec<WorkspaceUpdate>>,
 ) -> Result<Json<Vec<WorkspaceUpdateResult>>> {
-    app.update_workspace(updates.as_mut_slice()).await.map(Json)
+    app.update_workspace(&user, updates.as_mut_slice())
+        .await
+        .map(Json)
+}
+
+// Return applied workspace updates, oldest first
+pub async fn history(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(workspace_name): Path<String>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<Json<Vec<WorkspaceRevision>>> {
+    app.get_workspace_history(&user, &workspace_name, params.organization_id)
+        .await
+        .map(Json)
+}
+
+pub async fn diff(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((workspace_name, from, to)): Path<(String, i64, i64)>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<Json<WorkspaceDiff>> {
+    app.diff_workspace_revisions(&user, &workspace_name, params.organization_id, from, to)
+        .await
+        .map(Json)
+}
+
+pub async fn rollback(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((workspace_name, revision)): Path<(String, i64)>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<Json<WorkspaceUpdateResult>> {
+    app.rollback_workspace(&user, &workspace_name, params.organization_id, revision)
+        .await
+        .map(Json)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    format: DocumentFormat,
+}
+
+// Return workspace document:
+// - nodes with configs, positions and daemon names, edges
+// - secret fields only if they reference variables
+pub async fn export(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(workspace_name): Path<String>,
+    Query(params): Query<ExportParams>,
+    Query(workspace): Query<WorkspaceParams>,
+) -> Result<Response> {
+    let document = app
+        .export_workspace(&user, &workspace_name, workspace.organization_id)
+        .await?;
+    let response = match params.format {
+        DocumentFormat::Json => Json(document).into_response(),
+        DocumentFormat::Yaml => {
+            let document = serde_yaml::to_string(&document)?;
+            ([(header::CONTENT_TYPE, "application/yaml")], document).into_response()
+        }
+    };
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+// Converge workspace to document, document can be YAML or JSON, since JSON is valid YAML
+pub async fn apply(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(workspace_name): Path<String>,
+    Query(params): Query<ApplyParams>,
+    Query(workspace): Query<WorkspaceParams>,
+    document: String,
+) -> Result<Json<WorkspaceApplyResult>> {
+    let document = serde_yaml::from_str::<WorkspaceDocument>(&document)
+        .map_err(|e| AppError::bad_request(e.into()))?;
+    app.apply_workspace_document(
+        &user,
+        &workspace_name,
+        workspace.organization_id,
+        document,
+        params.dry_run,
+    )
+        .await
+        .map(Json)
+}
+
+pub async fn list_members(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(workspace_name): Path<String>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<Json<Vec<WorkspaceMember>>> {
+    app.list_workspace_members(&user, &workspace_name, params.organization_id)
+        .await
+        .map(Json)
+}
+
+pub async fn set_member(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(workspace_name): Path<String>,
+    Query(params): Query<WorkspaceParams>,
+    Json(member): Json<MemberRequest<Role>>,
+) -> Result<()> {
+    app.set_workspace_member(&user, &workspace_name, params.organization_id, member)
+        .await
+}
+
+pub async fn remove_member(
+    State(app): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((workspace_name, user_id)): Path<(String, String)>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<()> {
+    app.remove_workspace_member(
+        &user,
+        &workspace_name,
+        params.organization_id,
+        user_id.parse()?,
+    )
+        .await
 }
@@ -1,28 +1,40 @@
 //! Workspaces routes
 
 use crate::{
-    app::{AppState, Workspace},
+    app::{auth::User, AppState, Workspace},
     http::Result,
 };
+
+use super::workspace::WorkspaceParams;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::IntoResponse,
-    Json,
+    Extension, Json,
 };
 
 pub async fn create(
     app: State<AppState>,
+    Extension(user): Extension<User>,
     Json(workspace): Json<Workspace>,
 ) -> Result<impl IntoResponse> {
-    app.create_workspace(&workspace).await?;
+    app.create_workspace(&user, &workspace).await?;
     Ok(Json("ok"))
 }
 
-pub async fn read(app: State<AppState>) -> Result<Json<Vec<Workspace>>> {
-    Ok(Json(app.read_workspaces().await?))
+pub async fn read(
+    app: State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<Workspace>>> {
+    Ok(Json(app.read_workspaces(&user).await?))
 }
 
-pub async fn delete(app: State<AppState>, Path(name): Path<String>) -> Result<impl IntoResponse> {
-    app.delete_workspace(&name).await?;
+pub async fn delete(
+    app: State<AppState>,
+    Extension(user): Extension<User>,
+    Path(name): Path<String>,
+    Query(params): Query<WorkspaceParams>,
+) -> Result<impl IntoResponse> {
+    app.delete_workspace(&user, &name, params.organization_id)
+        .await?;
     Ok(Json("ok"))
 }
@@ -18,7 +18,7 @@ use tokio::sync::mpsc::UnboundedReceiver;
 use uuid::Uuid;
 
 use crate::{
-    app::{AppState, DaemonGraph},
+    app::{AppState, Capabilities, DaemonGraph, RelayMessage},
     tls_server::PeerInfo,
     Result,
 };
@@ -26,11 +26,15 @@ use crate::{
 async fn ws_handler(
     State(app): State<AppState>,
     ws: WebSocketUpgrade,
-    Extension(PeerInfo { common_name, addr }): Extension<PeerInfo>,
+    Extension(PeerInfo {
+        common_name,
+        serial_number,
+        addr,
+    }): Extension<PeerInfo>,
 ) -> Result<impl IntoResponse> {
     let common_name: Uuid = common_name.parse()?;
     Ok(ws.on_upgrade(move |socket| async move {
-        if let Err(e) = handle_socket(app, socket, addr, common_name).await {
+        if let Err(e) = handle_socket(app, socket, addr, common_name, &serial_number).await {
             tracing::error!("socket handle error for daemon with id '{common_name}': {e}");
         }
     }))
@@ -43,8 +47,8 @@ struct Daemon {
 }
 
 impl Daemon {
-    async fn new(app: AppState, id: Uuid) -> Result<Self> {
-        let rx = app.daemon_connected(id).await?;
+    async fn new(app: AppState, id: Uuid, serial_number: &[u8]) -> Result<Self> {
+        let rx = app.daemon_connected(id, serial_number).await?;
         Ok(Self { app, rx, id })
     }
 }
@@ -63,9 +67,33 @@ impl Drop for Daemon {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "message")]
 pub enum Message {
+    Capabilities {
+        capabilities: Capabilities,
+    },
     GetGraph,
-    GetGraphResponse { graph: DaemonGraph },
+    GetGraphResponse {
+        graph: DaemonGraph,
+    },
     RefetchGraph,
+    /// message for node on another daemon
+    Relay {
+        relay: RelayMessage,
+    },
+    /// message from node on another daemon, id is assigned by control plane
+    Relayed {
+        id: i64,
+        relay: RelayMessage,
+    },
+    /// relayed message was acked by destination daemon
+    RelayedAck {
+        id: i64,
+    },
+    /// request all relayed messages, which were not acked yet
+    GetRelayed,
+    /// message, sent by daemon, was acked by destination daemon
+    RelayAck {
+        id: Uuid,
+    },
 }
 
 struct WebsocketInput<S> {
@@ -84,6 +112,25 @@ impl<S: Sink<WebsocketMessage> + Unpin> WebsocketInput<S> {
             .map_err(|_| anyhow::anyhow!("failed to send websocket message"))?;
         Ok(())
     }
+
+    // send messages, relayed to daemon nodes, returns id of last sent message
+    async fn send_relayed(
+        &mut self,
+        app: &AppState,
+        daemon_id: Uuid,
+        mut after: i64,
+    ) -> Result<i64> {
+        loop {
+            let messages = app.list_relayed_messages(daemon_id, after).await?;
+            if messages.is_empty() {
+                return Ok(after);
+            }
+            for (id, relay) in messages {
+                self.send_message(&Message::Relayed { id, relay }).await?;
+                after = id;
+            }
+        }
+    }
 }
 
 async fn handle_socket(
@@ -91,10 +138,13 @@ async fn handle_socket(
     socket: WebSocket,
     _addr: SocketAddr,
     daemon_id: Uuid,
+    serial_number: &[u8],
 ) -> Result<()> {
-    let daemon = &mut Daemon::new(Arc::clone(&app), daemon_id).await?;
+    let daemon = &mut Daemon::new(Arc::clone(&app), daemon_id, serial_number).await?;
     let (input, mut output) = socket.split();
     let input = &mut WebsocketInput::new(input);
+    // id of last relayed message, sent over this connection
+    let mut last_relayed = 0;
     loop {
         tokio::select! {
             msg = output.next() => {
@@ -114,11 +164,26 @@ async fn handle_socket(
                     _ => Err(anyhow::anyhow!("unexpected message: {msg:?}"))?,
                 };
                 match msg {
+                    Message::Capabilities { capabilities } => {
+                        app.register_capabilities(daemon_id, capabilities).await?;
+                    },
                     Message::GetGraph => {
                         input.send_message(
                             &Message::GetGraphResponse { graph: app.get_daemon_graph(daemon_id).await?}
                         ).await?;
                     },
+                    Message::Relay { relay } => {
+                        // dropped message is acked right away, so origin daemon doesn't resend it
+                        if !app.relay_message(daemon_id, &relay).await? {
+                            input.send_message(&Message::RelayAck { id: relay.id }).await?;
+                        }
+                    },
+                    Message::RelayedAck { id } => {
+                        app.ack_relayed_message(daemon_id, id).await?;
+                    },
+                    Message::GetRelayed => {
+                        last_relayed = input.send_relayed(&app, daemon_id, 0).await?;
+                    },
                     _ => {
                         tracing::info!("unexpected message: {msg:?}");
                     },
@@ -136,7 +201,13 @@ async fn handle_socket(
                     },
                     DaemonMessage::ShutdownConnection => {
                         return Ok(())
-                    }
+                    },
+                    DaemonMessage::RelayAvailable => {
+                        last_relayed = input.send_relayed(&app, daemon_id, last_relayed).await?;
+                    },
+                    DaemonMessage::RelayAck(id) => {
+                        input.send_message(&Message::RelayAck { id }).await?;
+                    },
                 }
             }
         }
@@ -17,10 +17,14 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let status_code = match self.kind {
             AppErrorKind::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppErrorKind::Forbidden => StatusCode::FORBIDDEN,
             AppErrorKind::BadRequest
             | AppErrorKind::JoinRequestHashMissmatch
             | AppErrorKind::TokenUsed => StatusCode::BAD_REQUEST,
-            AppErrorKind::NotFound => StatusCode::NOT_FOUND,
+            // workspaces and daemons, user has no access to, are not found
+            AppErrorKind::NotFound
+            | AppErrorKind::WorkspaceNotFound
+            | AppErrorKind::DaemonNotFound => StatusCode::NOT_FOUND,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
         let mut response = status_code.into_response();
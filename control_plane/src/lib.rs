@@ -4,7 +4,7 @@ pub(crate) mod tls_server;
 
 use std::{net::SocketAddr, sync::Arc};
 
-pub use app::{AppError, Result};
+pub use app::{encryption::MasterKey, AppError, Result};
 
 use futures::FutureExt;
 use tokio::net::TcpListener;
@@ -15,7 +15,12 @@ async fn run_http_api(http_api_addr: &str, app: app::AppState) -> Result<()> {
         "listening for http API calls at {}",
         http_api_listener.local_addr()?
     );
-    axum::serve(http_api_listener, http::api::new(app).into_make_service()).await?;
+    // peer address is recorded in audit log
+    axum::serve(
+        http_api_listener,
+        http::api::new(app).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
@@ -32,8 +37,19 @@ async fn run_daemon_api(daemon_api_addr: &str, app: app::AppState) -> Result<()>
     Ok(())
 }
 
-pub async fn run(http_api_addr: &str, daemon_api_addr: &str, database_url: &str) -> Result<()> {
-    let app = Arc::new(app::AppBuilder::new(database_url).await?.build().await?);
+pub async fn run(
+    http_api_addr: &str,
+    daemon_api_addr: &str,
+    database_url: &str,
+    master_key: Option<MasterKey>,
+    admin_email: Option<&str>,
+    admin_password: Option<&str>,
+) -> Result<()> {
+    let app_builder = app::AppBuilder::new(database_url, master_key).await?;
+    app_builder
+        .bootstrap_admin(admin_email, admin_password)
+        .await?;
+    let app = Arc::new(app_builder.build().await?);
     futures::select! {
         res = run_http_api(http_api_addr, Arc::clone(&app)).fuse() => {
             tracing::error!("http api exited");
@@ -45,3 +61,18 @@ pub async fn run(http_api_addr: &str, daemon_api_addr: &str, database_url: &str)
         }
     }
 }
+
+/// Re-encrypt secret config fields with new master key
+pub async fn rotate_key(
+    database_url: &str,
+    master_key: Option<MasterKey>,
+    new_master_key: MasterKey,
+) -> Result<()> {
+    let app_builder = app::AppBuilder::new(database_url, master_key).await?;
+    let updated = app_builder.rotate_key(&new_master_key).await?;
+    tracing::info!(
+        "re-encrypted secret fields of {updated} nodes with master key '{}'",
+        new_master_key.id()
+    );
+    Ok(())
+}
@@ -1,5 +1,7 @@
-use clap::Parser;
-use control_plane::Result;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use control_plane::{MasterKey, Result};
 
 #[derive(Debug, Parser)]
 pub struct Cli {
@@ -23,16 +25,64 @@ pub struct Cli {
         default_value = "sqlite://control_plane.db"
     )]
     database_url: String,
+
+    /// Base64 encoded 32 byte key for encryption of secret config fields
+    #[clap(long, env = "MYCELIAL_MASTER_KEY", hide_env_values = true)]
+    master_key: Option<String>,
+
+    /// File with base64 encoded master key
+    #[clap(long, env = "MYCELIAL_MASTER_KEY_FILE")]
+    master_key_file: Option<PathBuf>,
+
+    /// Email of admin user, created on start if it doesn't exist
+    #[clap(long, env = "MYCELIAL_ADMIN_EMAIL")]
+    admin_email: Option<String>,
+
+    /// Password of admin user
+    #[clap(long, env = "MYCELIAL_ADMIN_PASSWORD", hide_env_values = true)]
+    admin_password: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Re-encrypt secret config fields and variable values with new master key
+    RotateKey {
+        #[clap(long, env = "MYCELIAL_NEW_MASTER_KEY", hide_env_values = true)]
+        new_master_key: Option<String>,
+
+        #[clap(long, env = "MYCELIAL_NEW_MASTER_KEY_FILE")]
+        new_master_key_file: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::try_parse()?;
     tracing_subscriber::fmt().with_ansi(false).init();
-    control_plane::run(
-        cli.http_api_listen_addr.as_str(),
-        cli.daemon_api_listen_addr.as_str(),
-        cli.database_url.as_str(),
-    )
-    .await
+    let master_key = MasterKey::load(cli.master_key.as_deref(), cli.master_key_file.as_deref())?;
+    match cli.command {
+        Some(Commands::RotateKey {
+            new_master_key,
+            new_master_key_file,
+        }) => {
+            let new_master_key =
+                MasterKey::load(new_master_key.as_deref(), new_master_key_file.as_deref())?
+                    .ok_or_else(|| anyhow::anyhow!("new master key is not set"))?;
+            control_plane::rotate_key(cli.database_url.as_str(), master_key, new_master_key).await
+        }
+        None => {
+            control_plane::run(
+                cli.http_api_listen_addr.as_str(),
+                cli.daemon_api_listen_addr.as_str(),
+                cli.database_url.as_str(),
+                master_key,
+                cli.admin_email.as_deref(),
+                cli.admin_password.as_deref(),
+            )
+            .await
+        }
+    }
 }
@@ -17,6 +17,8 @@ use crate::app;
 #[derive(Debug, Clone)]
 pub struct PeerInfo {
     pub common_name: Arc<str>,
+    /// serial number of peer certificate
+    pub serial_number: Arc<[u8]>,
     pub addr: SocketAddr,
 }
 
@@ -27,10 +29,9 @@ pub async fn serve(
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let certificate_bundle = app.certificate_bundle();
     let tcp_listener = TcpListener::bind(listen_addr).await?;
+    // verifier rejects revoked daemon certificates
     let server_config = ServerConfig::builder()
-        .with_client_cert_verifier(Arc::new(pki::Verifier::new(
-            certificate_bundle.ca_cert_key.cert.der().clone(),
-        )?))
+        .with_client_cert_verifier(certificate_bundle.client_verifier.clone())
         .with_single_cert(
             vec![certificate_bundle.cert.clone()],
             certificate_bundle.key.serialize_der().try_into()?,
@@ -75,14 +76,24 @@ pub async fn serve(
             };
 
             let (_, server_connection) = stream.get_ref();
-            let common_name = match server_connection.peer_certificates() {
-                Some([cert, ..]) => match pki::extract_common_name(cert) {
-                    Ok(name) => name,
-                    Err(e) => {
-                        tracing::error!("failed to extract common name from peer certificate: {e}");
-                        return;
+            let (common_name, serial_number) = match server_connection.peer_certificates() {
+                Some([cert, ..]) => {
+                    match (pki::extract_common_name(cert), pki::serial_number(cert)) {
+                        (Ok(name), Ok(serial_number)) => (name, serial_number),
+                        (Err(e), _) => {
+                            tracing::error!(
+                                "failed to extract common name from peer certificate: {e}"
+                            );
+                            return;
+                        }
+                        (_, Err(e)) => {
+                            tracing::error!(
+                                "failed to extract serial number from peer certificate: {e}"
+                            );
+                            return;
+                        }
                     }
-                },
+                }
                 _ => {
                     tracing::error!("peer certificate missing");
                     return;
@@ -91,6 +102,7 @@ pub async fn serve(
 
             let peer_common_name = PeerInfo {
                 common_name: Arc::from(common_name),
+                serial_number: Arc::from(serial_number),
                 addr,
             };
             let hyper_service =
@@ -23,6 +23,7 @@ use arrow::{
         TimestampNanosecondType, TimestampSecondType, UInt16Type, UInt32Type, UInt64Type,
         UInt8Type, UnionFields, UnionMode, DECIMAL128_MAX_PRECISION, DECIMAL_DEFAULT_SCALE,
     },
+    ipc::{reader::StreamReader, writer::StreamWriter},
     record_batch::RecordBatch as ArrowRecordBatch,
 };
 use chrono::FixedOffset;
@@ -1268,3 +1269,18 @@ pub fn df_to_recordbatch(df: &dyn DataFrame) -> Result<ArrowRecordBatch, Section
         rb_columns,
     )?)
 }
+
+/// encode dataframe as Arrow IPC stream
+pub fn df_to_ipc(df: &dyn DataFrame) -> Result<Vec<u8>, SectionError> {
+    let rb = df_to_recordbatch(df)?;
+    let mut writer = StreamWriter::try_new(vec![], rb.schema().as_ref())?;
+    writer.write(&rb)?;
+    Ok(writer.into_inner()?)
+}
+
+/// decode record batches from Arrow IPC stream
+pub fn ipc_to_recordbatches(ipc: &[u8]) -> Result<Vec<RecordBatch>, SectionError> {
+    StreamReader::try_new(ipc, None)?
+        .map(|rb| Ok(RecordBatch::from(rb?)))
+        .collect()
+}
@@ -0,0 +1,46 @@
+// Check dataframe roundtrip through Arrow IPC stream
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{ArrayRef, Int64Array, StringArray},
+    datatypes::{DataType as ArrowDataType, Field, Schema},
+    record_batch::RecordBatch as ArrowRecordBatch,
+};
+use arrow_msg::{df_to_ipc, ipc_to_recordbatches, RecordBatch};
+use quickcheck::TestResult;
+use section::message::{DataFrame, ValueView};
+
+#[test]
+fn test_ipc_roundtrip() {
+    fn check(values: Vec<(i64, String)>) -> TestResult {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("int", ArrowDataType::Int64, true),
+            Field::new("str", ArrowDataType::Utf8, true),
+        ]));
+        let ints: ArrayRef = Arc::new(Int64Array::from_iter(values.iter().map(|(i, _)| Some(*i))));
+        let strs: ArrayRef = Arc::new(StringArray::from_iter(
+            values.iter().map(|(_, s)| Some(s.as_str())),
+        ));
+        let rb = ArrowRecordBatch::try_new(Arc::clone(&schema), vec![ints, strs]).unwrap();
+        let df: Box<dyn DataFrame> = Box::new(RecordBatch::new(rb));
+
+        let ipc = df_to_ipc(df.as_ref()).unwrap();
+        let batches = ipc_to_recordbatches(&ipc).unwrap();
+        assert_eq!(batches.len(), 1);
+        let columns = batches[0].columns();
+        assert_eq!(
+            columns.iter().map(|c| c.name()).collect::<Vec<_>>(),
+            vec!["int", "str"]
+        );
+        let mut columns = columns.into_iter();
+        let ints = columns.next().unwrap();
+        let strs = columns.next().unwrap();
+        for ((int, str), (expected_int, expected_str)) in ints.zip(strs).zip(values.iter()) {
+            assert_eq!(int, ValueView::I64(*expected_int));
+            assert_eq!(str, ValueView::Str(expected_str));
+        }
+        TestResult::passed()
+    }
+    quickcheck::quickcheck(check as fn(Vec<(i64, String)>) -> TestResult);
+}
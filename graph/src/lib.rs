@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::hash::Hash;
 
 pub trait GraphKey: std::fmt::Debug + Copy + Ord + Hash {}
@@ -15,6 +15,18 @@ pub enum GraphOperation<K: GraphKey, T: GraphValue> {
     RemoveEdge(K, K),
 }
 
+/// Cycle, found in graph, nodes are listed in edge order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<K: GraphKey>(pub Vec<K>);
+
+impl<K: GraphKey> std::fmt::Display for Cycle<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "graph contains cycle: {:?}", self.0)
+    }
+}
+
+impl<K: GraphKey> std::error::Error for Cycle<K> {}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Graph<K: GraphKey, V: GraphValue> {
     nodes: BTreeMap<K, V>,
@@ -82,7 +94,7 @@ impl<K: GraphKey, V: GraphValue> Graph<K, V> {
         if !(self.nodes.contains_key(&from_node) && self.nodes.contains_key(&to_node)) {
             return ops;
         };
-        if self.check_loop(from_node, to_node) {
+        if self.creates_cycle(from_node, to_node) {
             return ops;
         }
         if let Some(prev_node) = self.edges.insert(from_node, to_node) {
@@ -96,18 +108,6 @@ impl<K: GraphKey, V: GraphValue> Graph<K, V> {
         self.edges.get(&from_node).copied()
     }
 
-    fn check_loop(&self, from_node: K, to_node: K) -> bool {
-        let mut visited = BTreeSet::<K>::from_iter([from_node, to_node]);
-        let mut next = to_node;
-        while let Some(node) = self.edges.get(&next).copied() {
-            if !visited.insert(node) {
-                return true;
-            };
-            next = node;
-        }
-        false
-    }
-
     // Add edge partial edge
     //
     // Function doesn't generate graph operations since it's not supposed to be used by UI.
@@ -124,7 +124,7 @@ impl<K: GraphKey, V: GraphValue> Graph<K, V> {
             (true, true) => {
                 self.add_edge(from_node, to_node);
             }
-            (from, to) if from ^ to && !self.check_loop(from_node, to_node) => {
+            (from, to) if from ^ to && !self.creates_cycle(from_node, to_node) => {
                 self.edges.insert(from_node, to_node);
             }
             _ => (),
@@ -174,6 +174,136 @@ impl<K: GraphKey, V: GraphValue> Graph<K, V> {
         self.edges.iter().map(|(key, value)| (*key, *value))
     }
 
+    // DAG utilities
+    //
+    // Every node has at most one outgoing edge, so walk from any node either ends or runs into cycle.
+    // Dangling nodes of partial graph are treated as regular nodes.
+
+    /// check if edge from `from_node` to `to_node` would form a cycle
+    pub fn creates_cycle(&self, from_node: K, to_node: K) -> bool {
+        let mut visited = BTreeSet::<K>::new();
+        let mut next = to_node;
+        loop {
+            if next == from_node {
+                return true;
+            }
+            if !visited.insert(next) {
+                return false;
+            }
+            match self.edges.get(&next) {
+                Some(node) => next = *node,
+                None => return false,
+            }
+        }
+    }
+
+    pub fn find_cycle(&self) -> Option<Cycle<K>> {
+        let mut checked = BTreeSet::<K>::new();
+        for start in self.edges.keys().copied() {
+            let mut path = vec![];
+            let mut positions = BTreeMap::<K, usize>::new();
+            let mut key = start;
+            while !checked.contains(&key) {
+                if let Some(&position) = positions.get(&key) {
+                    return Some(Cycle(path.split_off(position)));
+                }
+                positions.insert(key, path.len());
+                path.push(key);
+                match self.edges.get(&key) {
+                    Some(to_node) => key = *to_node,
+                    None => break,
+                }
+            }
+            checked.extend(path);
+        }
+        None
+    }
+
+    pub fn has_cycle(&self) -> bool {
+        self.find_cycle().is_some()
+    }
+
+    /// all nodes in order, where every node goes before the node its edge points to
+    pub fn topological_sort(&self) -> Result<Vec<K>, Cycle<K>> {
+        let mut in_degree = self
+            .all_nodes()
+            .into_iter()
+            .map(|key| (key, 0_usize))
+            .collect::<BTreeMap<_, _>>();
+        for to_node in self.edges.values() {
+            *in_degree.get_mut(to_node).unwrap() += 1;
+        }
+        let mut queue = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(key, _)| *key)
+            .collect::<VecDeque<_>>();
+        let mut sorted = Vec::with_capacity(in_degree.len());
+        while let Some(key) = queue.pop_front() {
+            sorted.push(key);
+            if let Some(to_node) = self.edges.get(&key) {
+                let degree = in_degree.get_mut(to_node).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push_back(*to_node);
+                }
+            }
+        }
+        match sorted.len() == in_degree.len() {
+            true => Ok(sorted),
+            false => Err(self.find_cycle().unwrap()),
+        }
+    }
+
+    /// all nodes, from which given node can be reached, node itself is excluded
+    pub fn upstream(&self, node: K) -> BTreeSet<K> {
+        let mut parents = BTreeMap::<K, Vec<K>>::new();
+        for (from, to) in self.iter_edges() {
+            parents.entry(to).or_default().push(from);
+        }
+        let mut upstream = BTreeSet::new();
+        let mut stack = vec![node];
+        while let Some(key) = stack.pop() {
+            for parent in parents.get(&key).into_iter().flatten().copied() {
+                if parent != node && upstream.insert(parent) {
+                    stack.push(parent);
+                }
+            }
+        }
+        upstream
+    }
+
+    /// all nodes, reachable from given node, node itself is excluded
+    pub fn downstream(&self, node: K) -> BTreeSet<K> {
+        let mut downstream = BTreeSet::new();
+        let mut next = node;
+        while let Some(to_node) = self.edges.get(&next).copied() {
+            if to_node == node || !downstream.insert(to_node) {
+                break;
+            }
+            next = to_node;
+        }
+        downstream
+    }
+
+    /// nodes without incoming edges
+    pub fn sources(&self) -> BTreeSet<K> {
+        let mut sources = self.all_nodes();
+        for to_node in self.edges.values() {
+            sources.remove(to_node);
+        }
+        sources
+    }
+
+    /// nodes without outgoing edge
+    pub fn sinks(&self) -> BTreeSet<K> {
+        let mut sinks = self.all_nodes();
+        for from_node in self.edges.keys() {
+            sinks.remove(from_node);
+        }
+        sinks
+    }
+
     pub fn get_subgraphs(&self) -> Vec<Graph<K, V>> {
         let mut graphs = Vec::<Graph<K, V>>::new();
         let mut stack = Vec::<StackItem<K, V>>::new();
@@ -629,4 +759,142 @@ mod test {
         };
         quickcheck::quickcheck(check as fn(u64, BTreeMap<u8, u8>) -> TestResult);
     }
+
+    #[test]
+    fn dag_utilities() {
+        let mut graph = Graph::new();
+        (1..8).for_each(|node| {
+            graph.add_node(node, node);
+        });
+        [(1, 2), (2, 4), (3, 4), (4, 5), (6, 7)]
+            .into_iter()
+            .for_each(|(from, to)| {
+                graph.add_edge(from, to);
+            });
+        assert!(!graph.has_cycle());
+        assert!(graph.creates_cycle(5, 1));
+        assert!(graph.creates_cycle(5, 5));
+        assert!(!graph.creates_cycle(5, 6));
+        assert_eq!(graph.topological_sort(), Ok(vec![1, 3, 6, 2, 7, 4, 5]));
+        assert_eq!(graph.upstream(4), BTreeSet::from([1, 2, 3]));
+        assert_eq!(graph.downstream(2), BTreeSet::from([4, 5]));
+        assert_eq!(graph.sources(), BTreeSet::from([1, 3, 6]));
+        assert_eq!(graph.sinks(), BTreeSet::from([5, 7]));
+
+        graph.add_edge_unchecked(5, 2);
+        assert_eq!(graph.find_cycle(), Some(Cycle(vec![2, 4, 5])));
+        assert_eq!(graph.topological_sort(), Err(Cycle(vec![2, 4, 5])));
+        assert_eq!(graph.downstream(2), BTreeSet::from([4, 5]));
+        assert_eq!(graph.upstream(2), BTreeSet::from([1, 3, 4, 5]));
+    }
+
+    // random edges, cycles are possible since edges are added unchecked
+    fn random_graph(prng_state: u64, len: u8) -> Graph<u8, u8> {
+        let mut prng = XorShift::new(prng_state);
+        let mut graph = Graph::new();
+        for node in 0..len {
+            graph.add_node(node, node);
+        }
+        for node in 0..len {
+            match prng.next().unwrap() % 3 {
+                0 => (),
+                _ => graph.add_edge_unchecked(node, (prng.next().unwrap() % len as u64) as u8),
+            }
+        }
+        graph
+    }
+
+    #[test]
+    // checked edge insertion never forms cycle
+    fn prop_add_edge_is_acyclic() {
+        let check = |prng_state: u64, len: u8| -> TestResult {
+            let len = len % 64;
+            if len == 0 {
+                return TestResult::discard();
+            }
+            let mut prng = XorShift::new(prng_state);
+            let mut graph = Graph::new();
+            for node in 0..len {
+                graph.add_node(node, node);
+            }
+            for _ in 0..len as usize * 2 {
+                let from = (prng.next().unwrap() % len as u64) as u8;
+                let to = (prng.next().unwrap() % len as u64) as u8;
+                let creates_cycle = graph.creates_cycle(from, to);
+                let added = !graph.add_edge(from, to).is_empty();
+                assert_eq!(creates_cycle, !added);
+                assert!(!graph.has_cycle(), "graph: {graph:?}");
+            }
+            TestResult::from_bool(graph.topological_sort().is_ok())
+        };
+        quickcheck::quickcheck(check as fn(u64, u8) -> TestResult);
+    }
+
+    #[test]
+    // topological order is found only for acyclic graphs and respects every edge
+    fn prop_topological_sort() {
+        let check = |prng_state: u64, len: u8| -> TestResult {
+            let len = len % 64;
+            if len == 0 {
+                return TestResult::discard();
+            }
+            let graph = random_graph(prng_state, len);
+            match graph.topological_sort() {
+                Ok(sorted) => {
+                    assert!(!graph.has_cycle());
+                    assert_eq!(
+                        sorted.iter().copied().collect::<BTreeSet<_>>(),
+                        graph.all_nodes()
+                    );
+                    let positions = sorted
+                        .iter()
+                        .enumerate()
+                        .map(|(pos, node)| (*node, pos))
+                        .collect::<BTreeMap<_, _>>();
+                    for (from, to) in graph.iter_edges() {
+                        assert!(positions[&from] < positions[&to]);
+                    }
+                }
+                Err(Cycle(cycle)) => {
+                    assert!(!cycle.is_empty());
+                    for (pos, node) in cycle.iter().enumerate() {
+                        assert_eq!(graph.get_edge(*node), Some(cycle[(pos + 1) % cycle.len()]));
+                    }
+                }
+            }
+            TestResult::from_bool(true)
+        };
+        quickcheck::quickcheck(check as fn(u64, u8) -> TestResult);
+    }
+
+    #[test]
+    // upstream and downstream sets mirror each other
+    fn prop_reachability() {
+        let check = |prng_state: u64, len: u8| -> TestResult {
+            let len = len % 64;
+            if len == 0 {
+                return TestResult::discard();
+            }
+            let graph = random_graph(prng_state, len);
+            let nodes = graph.all_nodes();
+            for &node in nodes.iter() {
+                let downstream = graph.downstream(node);
+                for &other in nodes.iter() {
+                    assert_eq!(
+                        downstream.contains(&other),
+                        graph.upstream(other).contains(&node),
+                        "node: {node}, other: {other}, graph: {graph:?}"
+                    );
+                }
+                let has_parents = graph.iter_edges().any(|(_, to)| to == node);
+                assert_eq!(graph.sources().contains(&node), !has_parents);
+                assert_eq!(
+                    graph.sinks().contains(&node),
+                    graph.get_edge(node).is_none()
+                );
+            }
+            TestResult::from_bool(true)
+        };
+        quickcheck::quickcheck(check as fn(u64, u8) -> TestResult);
+    }
 }
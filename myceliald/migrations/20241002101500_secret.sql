@@ -0,0 +1,4 @@
+CREATE TABLE secret (
+    name text primary key,
+    value text not null
+);
@@ -19,7 +19,8 @@ use tokio_tungstenite::Connector;
 use tungstenite::Message as WebsocketMessage;
 
 use crate::{
-    runtime::{CertifiedKey, Graph, RuntimeHandle},
+    relay::{RelayHandle, RelayMessage, RelayRequest},
+    runtime::{Capabilities, CertifiedKey, Graph, RuntimeHandle},
     runtime_error::RuntimeError,
     Result,
 };
@@ -56,15 +57,23 @@ struct JoinResponse {
 #[derive(Debug)]
 struct ControlPlaneClient {
     runtime_handle: RuntimeHandle,
+    capabilities: Arc<Capabilities>,
+    relay: RelayHandle,
     socket: Option<JoinHandle<()>>,
     control_plane_tls_url: Option<Arc<Url>>,
     certifiedkey: Option<Arc<CertifiedKey>>,
 }
 
 impl ControlPlaneClient {
-    fn new(runtime_handle: RuntimeHandle) -> Self {
+    fn new(
+        runtime_handle: RuntimeHandle,
+        capabilities: Arc<Capabilities>,
+        relay: RelayHandle,
+    ) -> Self {
         Self {
             runtime_handle,
+            capabilities,
+            relay,
             socket: None,
             control_plane_tls_url: None,
             certifiedkey: None,
@@ -209,10 +218,18 @@ impl ControlPlaneClient {
             .ok_or(RuntimeError::ControlPlaneCertifiedNotSet)?;
 
         let runtime_handle = self.runtime_handle.clone();
+        let capabilities = Arc::clone(&self.capabilities);
+        let relay = self.relay.clone();
         self.socket = Some(tokio::spawn(async move {
             let tx = tx;
-            if let Err(e) =
-                websocket_client(runtime_handle, control_plane_tls_url, certifiedkey).await
+            if let Err(e) = websocket_client(
+                runtime_handle,
+                capabilities,
+                relay,
+                control_plane_tls_url,
+                certifiedkey,
+            )
+            .await
             {
                 tracing::error!("websocket connection closed: {e}");
             }
@@ -243,9 +260,33 @@ enum Message {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "message")]
 pub enum ControlPlaneMessage {
+    Capabilities {
+        capabilities: Capabilities,
+    },
     GetGraph,
-    GetGraphResponse { graph: Graph },
+    GetGraphResponse {
+        graph: Graph,
+    },
     RefetchGraph,
+    /// message for node on another daemon
+    Relay {
+        relay: RelayMessage,
+    },
+    /// message from node on another daemon, id is assigned by control plane
+    Relayed {
+        id: i64,
+        relay: RelayMessage,
+    },
+    /// relayed message was acked by destination daemon
+    RelayedAck {
+        id: i64,
+    },
+    /// request all relayed messages, which were not acked yet
+    GetRelayed,
+    /// message, sent by this daemon, was acked by destination daemon
+    RelayAck {
+        id: uuid::Uuid,
+    },
 }
 
 struct WebsocketInput<S> {
@@ -253,16 +294,33 @@ struct WebsocketInput<S> {
 }
 
 impl<S: Sink<WebsocketMessage> + Unpin> WebsocketInput<S> {
-    async fn get_graph(&mut self) -> Result<()> {
+    async fn send(&mut self, message: &ControlPlaneMessage) -> Result<()> {
         self.input
-            .send(WebsocketMessage::Text(serde_json::to_string(
-                &ControlPlaneMessage::GetGraph {},
-            )?))
+            .send(WebsocketMessage::Text(serde_json::to_string(message)?))
             .await
             .map_err(|_| RuntimeError::ControlPlaneWebsocketSendError)?;
         Ok(())
     }
 
+    async fn capabilities(&mut self, capabilities: &Capabilities) -> Result<()> {
+        let capabilities = capabilities.clone();
+        self.send(&ControlPlaneMessage::Capabilities { capabilities })
+            .await
+    }
+
+    async fn get_graph(&mut self) -> Result<()> {
+        self.send(&ControlPlaneMessage::GetGraph {}).await
+    }
+
+    async fn relay_request(&mut self, request: RelayRequest) -> Result<()> {
+        let message = match request {
+            RelayRequest::Relay(relay) => ControlPlaneMessage::Relay { relay },
+            RelayRequest::RelayedAck(id) => ControlPlaneMessage::RelayedAck { id },
+            RelayRequest::GetRelayed => ControlPlaneMessage::GetRelayed,
+        };
+        self.send(&message).await
+    }
+
     async fn ping(&mut self) -> Result<()> {
         self.input
             .send(WebsocketMessage::Ping(vec![]))
@@ -274,6 +332,8 @@ impl<S: Sink<WebsocketMessage> + Unpin> WebsocketInput<S> {
 
 async fn websocket_client(
     runtime_handle: RuntimeHandle,
+    capabilities: Arc<Capabilities>,
+    relay: RelayHandle,
     control_plane_url: Arc<Url>,
     certifiedkey: Arc<CertifiedKey>,
 ) -> Result<()> {
@@ -302,7 +362,22 @@ async fn websocket_client(
     .await?;
     let (input, mut output) = socket.split();
     let input = &mut WebsocketInput { input };
+    // capabilities are reported before graph request, so control plane can validate graph against them
+    input.capabilities(&capabilities).await?;
     input.get_graph().await?;
+    // messages, which were relayed over previous connection, but not acked, are resent
+    // control plane drops duplicates
+    let relay_requests = relay.requests();
+    let mut relay_requests = relay_requests.lock().await;
+    for message in relay.pending_messages() {
+        input
+            .send(&ControlPlaneMessage::Relay { relay: message })
+            .await?;
+    }
+    // inbound sections, registered later, request relayed messages themselves
+    if relay.has_inbound() {
+        input.send(&ControlPlaneMessage::GetRelayed).await?;
+    }
     let mut interval = tokio::time::interval(Duration::from_secs(30));
     loop {
         tokio::select! {
@@ -322,9 +397,17 @@ async fn websocket_client(
                 match message {
                     ControlPlaneMessage::GetGraphResponse{ graph } => runtime_handle.graph(graph)?,
                     ControlPlaneMessage::RefetchGraph => input.get_graph().await?,
+                    ControlPlaneMessage::Relayed{ id, relay: message } => relay.deliver(id, message),
+                    ControlPlaneMessage::RelayAck{ id } => relay.ack(id),
                     _ => (),
                 }
             },
+            request = relay_requests.recv() => {
+                match request {
+                    Some(request) => input.relay_request(request).await?,
+                    None => Err(RuntimeError::ChannelRecvError)?,
+                }
+            },
             _ = interval.tick() => {
                 input.ping().await?;
             }
@@ -372,7 +455,11 @@ impl ControlPlaneClientHandle {
     }
 }
 
-pub fn new(runtime_handle: RuntimeHandle) -> ControlPlaneClientHandle {
-    let client = ControlPlaneClient::new(runtime_handle);
+pub fn new(
+    runtime_handle: RuntimeHandle,
+    capabilities: Arc<Capabilities>,
+    relay: RelayHandle,
+) -> ControlPlaneClientHandle {
+    let client = ControlPlaneClient::new(runtime_handle, capabilities, relay);
     client.spawn()
 }
@@ -1,11 +1,15 @@
 mod control_plane_client;
+mod relay;
 mod runtime;
 mod runtime_error;
 mod runtime_storage;
 mod scheduler;
+mod secret_store;
 mod section_channel;
 mod sqlite_storage;
 
+use std::path::Path;
+
 use config_registry::{Config as _Config, ConfigRegistry as _ConfigRegistry};
 use uuid::Uuid;
 
@@ -15,6 +19,10 @@ pub(crate) type Config = Box<dyn _Config<SectionChannel>>;
 
 pub(crate) type Result<T, E = runtime_error::RuntimeError> = std::result::Result<T, E>;
 
-pub async fn new(database_path: &str) -> Result<runtime::Runtime> {
-    runtime::Runtime::new(database_path).await
+pub async fn new(
+    database_path: &str,
+    plugins_dir: Option<&Path>,
+    secrets_dir: Option<&Path>,
+) -> Result<runtime::Runtime> {
+    runtime::Runtime::new(database_path, plugins_dir, secrets_dir).await
 }
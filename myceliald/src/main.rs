@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::{error::ErrorKind, Parser, Subcommand};
 use tracing::level_filters::LevelFilter;
@@ -9,6 +11,16 @@ struct Cli {
     #[clap(env = "DATABASE_PATH", default_value = "myceliald.db")]
     database_path: String,
 
+    /// Directory with section plugin libraries
+    #[clap(long, env = "MYCELIAL_PLUGINS_DIR")]
+    plugins_dir: Option<PathBuf>,
+
+    /// Directory with secret files, referenced from section configs as `file:<path>`.
+    /// Files outside of this directory can't be referenced.
+    /// Environment variables can be referenced as `env:<name>` only if name starts with `MYCELIAL_SECRET_`
+    #[clap(long, env = "MYCELIAL_SECRETS_DIR")]
+    secrets_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -24,6 +36,24 @@ pub enum Commands {
         join_token: String,
     },
     Reset,
+    /// Manage local secrets, referenced from section configs as `secret:<name>`
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SecretCommands {
+    /// Store secret, value is read from stdin if not provided
+    Set {
+        name: String,
+        value: Option<String>,
+    },
+    Delete {
+        name: String,
+    },
+    List,
 }
 
 #[tokio::main]
@@ -44,7 +74,12 @@ async fn main() -> Result<()> {
         Err(e) => Err(e)?,
         Ok(cli) => cli,
     };
-    let mut runtime = myceliald::new(&cli.database_path).await?;
+    let mut runtime = myceliald::new(
+        &cli.database_path,
+        cli.plugins_dir.as_deref(),
+        cli.secrets_dir.as_deref(),
+    )
+    .await?;
     match cli.command {
         Some(Commands::Join {
             control_plane_url,
@@ -60,6 +95,29 @@ async fn main() -> Result<()> {
             runtime.reset().await?;
             tracing::info!("runtime state reset");
         }
+        Some(Commands::Secret { command }) => match command {
+            SecretCommands::Set { name, value } => {
+                let value = match value {
+                    Some(value) => value,
+                    None => {
+                        let mut value = String::new();
+                        std::io::stdin().read_line(&mut value)?;
+                        value.trim_end_matches(['\r', '\n']).to_string()
+                    }
+                };
+                runtime.set_secret(&name, &value).await?;
+                tracing::info!("secret '{name}' stored");
+            }
+            SecretCommands::Delete { name } => match runtime.delete_secret(&name).await? {
+                true => tracing::info!("secret '{name}' deleted"),
+                false => tracing::warn!("secret '{name}' not found"),
+            },
+            SecretCommands::List => {
+                for name in runtime.list_secrets().await? {
+                    println!("{name}");
+                }
+            }
+        },
         None => {
             runtime.run().await?;
         }
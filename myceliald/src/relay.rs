@@ -0,0 +1,590 @@
+//! Relay of messages over boundary edges
+//!
+//! Edge between nodes, assigned to different daemons, is split by scheduler into two sections:
+//! `Outbound` on daemon with `from` node and `Inbound` on daemon with `to` node.
+//! Messages are relayed through control plane, which stores them until destination daemon acks them.
+//!
+//! 1. outbound section reads message chunks, encodes them and sends `Relay` to control plane
+//! 2. control plane queues message and sends `Relayed` to daemon, which runs `to` node
+//! 3. inbound section emits decoded message, once it's acked `RelayedAck` is sent to control plane
+//! 4. control plane removes message from queue and sends `RelayAck` to origin daemon,
+//!    which acks original message
+//!
+//! Delivery is at-least-once: unacked messages are resent after reconnect.
+//!
+//! Relayed message is sent as a single websocket frame, so size of encoded message is limited,
+//! larger messages can't be relayed on retry either, so outbound section logs and acks them.
+use std::{
+    collections::{BTreeMap, VecDeque},
+    pin::pin,
+    sync::{Arc, Mutex},
+};
+
+use arrow_msg::{df_to_ipc, ipc_to_recordbatches};
+use base64::Engine as _;
+use section::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    Mutex as AsyncMutex, OwnedSemaphorePermit, Semaphore,
+};
+use uuid::Uuid;
+
+/// max amount of relayed messages, which wait for ack from control plane
+const MAX_PENDING_MESSAGES: usize = 64;
+
+/// amount of acked relayed message ids, remembered to drop redelivered messages
+const MAX_ACKED_MESSAGES: usize = 1024;
+
+/// max total size of encoded message chunks, websocket frames are limited to 16MiB
+const MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+
+// chunk tags
+const BYTE_CHUNK: u8 = 0;
+const DATAFRAME_CHUNK: u8 = 1;
+
+/// Message, relayed over boundary edge
+///
+/// Each chunk is tagged with chunk type and base64 encoded, dataframes are encoded as Arrow IPC stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayMessage {
+    /// message id, assigned by origin daemon
+    pub id: Uuid,
+    /// destination node
+    pub to_node: Uuid,
+    pub origin: String,
+    pub chunks: Vec<String>,
+}
+
+/// Requests to control plane, produced by relay sections
+#[derive(Debug)]
+pub enum RelayRequest {
+    Relay(RelayMessage),
+    RelayedAck(i64),
+    GetRelayed,
+}
+
+struct PendingMessage {
+    message: RelayMessage,
+    ack: Ack,
+    _permit: OwnedSemaphorePermit,
+}
+
+#[derive(Default)]
+struct RelayState {
+    // outbound messages, waiting for ack from destination daemon
+    pending: BTreeMap<Uuid, PendingMessage>,
+    // inbound sections by destination node
+    inbound: BTreeMap<Uuid, UnboundedSender<(i64, RelayMessage)>>,
+    // relayed messages, emitted by inbound sections and not yet acked
+    in_flight: BTreeMap<i64, Uuid>,
+    // recently acked relayed messages
+    // control plane can resend message, if it was requested before ack was received
+    acked: VecDeque<i64>,
+}
+
+#[derive(Clone)]
+pub struct RelayHandle {
+    state: Arc<Mutex<RelayState>>,
+    permits: Arc<Semaphore>,
+    tx: UnboundedSender<RelayRequest>,
+    rx: Arc<AsyncMutex<UnboundedReceiver<RelayRequest>>>,
+}
+
+impl std::fmt::Debug for RelayHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RelayHandle").finish_non_exhaustive()
+    }
+}
+
+impl RelayHandle {
+    /// requests to control plane
+    ///
+    /// Receiver is held by websocket client for the lifetime of connection.
+    pub fn requests(&self) -> Arc<AsyncMutex<UnboundedReceiver<RelayRequest>>> {
+        Arc::clone(&self.rx)
+    }
+
+    /// check if any inbound section was registered
+    pub fn has_inbound(&self) -> bool {
+        !self.state.lock().unwrap().inbound.is_empty()
+    }
+
+    /// outbound messages, which were not acked yet
+    pub fn pending_messages(&self) -> Vec<RelayMessage> {
+        let state = self.state.lock().unwrap();
+        state
+            .pending
+            .values()
+            .map(|pending| pending.message.clone())
+            .collect()
+    }
+
+    /// send message to control plane, waits if too many messages are not acked yet
+    async fn relay(&self, message: RelayMessage, ack: Ack) {
+        // semaphore is never closed
+        let permit = Arc::clone(&self.permits).acquire_owned().await.unwrap();
+        let mut state = self.state.lock().unwrap();
+        state.pending.insert(
+            message.id,
+            PendingMessage {
+                message: message.clone(),
+                ack,
+                _permit: permit,
+            },
+        );
+        self.tx.send(RelayRequest::Relay(message)).ok();
+    }
+
+    /// message was delivered to destination daemon and acked there
+    pub fn ack(&self, id: Uuid) {
+        let pending = self.state.lock().unwrap().pending.remove(&id);
+        match pending {
+            Some(pending) => {
+                tokio::spawn(pending.ack);
+            }
+            None => tracing::warn!("ack for unknown relayed message {id}"),
+        }
+    }
+
+    /// register inbound section for destination node
+    ///
+    /// Messages, which were emitted by previous section, are requested again.
+    fn register_inbound(&self, to_node: Uuid) -> UnboundedReceiver<(i64, RelayMessage)> {
+        let (tx, rx) = unbounded_channel();
+        let mut state = self.state.lock().unwrap();
+        state.inbound.insert(to_node, tx);
+        state.in_flight.retain(|_, node| *node != to_node);
+        self.tx.send(RelayRequest::GetRelayed).ok();
+        rx
+    }
+
+    /// deliver message, relayed by control plane, to inbound section
+    ///
+    /// Messages without running inbound section are dropped, control plane resends them once section is registered.
+    pub fn deliver(&self, id: i64, message: RelayMessage) {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight.contains_key(&id) || state.acked.contains(&id) {
+            return;
+        }
+        let to_node = message.to_node;
+        let delivered = match state.inbound.get(&to_node) {
+            Some(tx) => tx.send((id, message)).is_ok(),
+            None => false,
+        };
+        match delivered {
+            true => {
+                state.in_flight.insert(id, to_node);
+            }
+            false => {
+                tracing::debug!(
+                    "no inbound section for node {to_node}, relayed message {id} is not delivered"
+                )
+            }
+        }
+    }
+
+    fn ack_relayed(&self, id: i64) {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight.remove(&id).is_some() {
+            if state.acked.len() == MAX_ACKED_MESSAGES {
+                state.acked.pop_front();
+            }
+            state.acked.push_back(id);
+            self.tx.send(RelayRequest::RelayedAck(id)).ok();
+        }
+    }
+}
+
+pub fn new() -> RelayHandle {
+    let (tx, rx) = unbounded_channel();
+    RelayHandle {
+        state: Arc::new(Mutex::new(RelayState::default())),
+        permits: Arc::new(Semaphore::new(MAX_PENDING_MESSAGES)),
+        tx,
+        rx: Arc::new(AsyncMutex::new(rx)),
+    }
+}
+
+fn encode_chunk(chunk: Chunk) -> Result<String, SectionError> {
+    let (tag, payload) = match chunk {
+        Chunk::Byte(bin) => (BYTE_CHUNK, bin),
+        Chunk::DataFrame(df) => (DATAFRAME_CHUNK, df_to_ipc(&*df)?),
+    };
+    let mut buf = Vec::with_capacity(payload.len() + 1);
+    buf.push(tag);
+    buf.extend(payload);
+    Ok(base64::engine::general_purpose::STANDARD.encode(buf))
+}
+
+// encode all chunks of message, returns None if encoded message exceeds `MAX_MESSAGE_SIZE`
+async fn encode_message(msg: &mut SectionMessage) -> Result<Option<Vec<String>>, SectionError> {
+    let mut chunks = vec![];
+    let mut size = 0;
+    while let Some(chunk) = msg.next().await? {
+        let chunk = encode_chunk(chunk)?;
+        size += chunk.len();
+        if size > MAX_MESSAGE_SIZE {
+            return Ok(None);
+        }
+        chunks.push(chunk);
+    }
+    Ok(Some(chunks))
+}
+
+fn decode_chunks(chunks: &[String]) -> Result<VecDeque<Chunk>, SectionError> {
+    let mut decoded = VecDeque::new();
+    for chunk in chunks {
+        let buf = base64::engine::general_purpose::STANDARD.decode(chunk)?;
+        match buf.split_first() {
+            Some((&BYTE_CHUNK, bin)) => decoded.push_back(Chunk::Byte(bin.to_vec())),
+            Some((&DATAFRAME_CHUNK, ipc)) => decoded.extend(
+                ipc_to_recordbatches(ipc)?
+                    .into_iter()
+                    .map(|rb| Chunk::DataFrame(Box::new(rb))),
+            ),
+            Some((tag, _)) => Err(format!("unexpected chunk tag: {tag}"))?,
+            None => Err("empty chunk")?,
+        }
+    }
+    Ok(decoded)
+}
+
+/// Sending side of boundary edge, relays messages to destination node
+#[derive(Debug)]
+pub struct Outbound {
+    to_node: Uuid,
+    relay: RelayHandle,
+}
+
+impl Outbound {
+    pub fn new(to_node: Uuid, relay: RelayHandle) -> Self {
+        Self { to_node, relay }
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for Outbound
+where
+    Input: SectionStream,
+    Output: SectionSink,
+    SectionChan: SectionChannel,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(
+        self,
+        input: Input,
+        _output: Output,
+        mut section_channel: SectionChan,
+    ) -> Self::Future {
+        Box::pin(async move {
+            let mut input = pin!(input);
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = match msg {
+                            None => Err("input closed")?,
+                            Some(msg) => msg
+                        };
+                        let chunks = match encode_message(&mut msg).await? {
+                            Some(chunks) => chunks,
+                            None => {
+                                tracing::error!(
+                                    "dropping message from '{}' to node {}: encoded size exceeds {MAX_MESSAGE_SIZE} bytes",
+                                    msg.origin(),
+                                    self.to_node
+                                );
+                                msg.ack().await;
+                                continue
+                            }
+                        };
+                        let message = RelayMessage {
+                            id: Uuid::now_v7(),
+                            to_node: self.to_node,
+                            origin: msg.origin().into(),
+                            chunks,
+                        };
+                        self.relay.relay(message, msg.ack()).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Receiving side of boundary edge, emits messages relayed to destination node
+#[derive(Debug)]
+pub struct Inbound {
+    rx: UnboundedReceiver<(i64, RelayMessage)>,
+    relay: RelayHandle,
+}
+
+impl Inbound {
+    pub fn new(to_node: Uuid, relay: RelayHandle) -> Self {
+        let rx = relay.register_inbound(to_node);
+        Self { rx, relay }
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for Inbound
+where
+    Input: SectionStream,
+    Output: SectionSink,
+    SectionChan: SectionChannel,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(
+        self,
+        _input: Input,
+        output: Output,
+        mut section_channel: SectionChan,
+    ) -> Self::Future {
+        Box::pin(async move {
+            let Self { mut rx, relay } = self;
+            let mut output = pin!(output);
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    relayed = rx.recv().fuse() => {
+                        let (id, message) = match relayed {
+                            None => Err("relay closed")?,
+                            Some(relayed) => relayed,
+                        };
+                        let chunks = match decode_chunks(&message.chunks) {
+                            Ok(chunks) => chunks,
+                            Err(e) => {
+                                // message can't be decoded on redelivery either, so it's dropped
+                                tracing::error!("failed to decode relayed message {}: {e}", message.id);
+                                relay.ack_relayed(id);
+                                continue
+                            }
+                        };
+                        let message = RelayedMessage {
+                            id,
+                            origin: message.origin,
+                            chunks,
+                            relay: relay.clone(),
+                        };
+                        output.send(Box::new(message)).await.map_err(|_| "send error")?
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+struct RelayedMessage {
+    id: i64,
+    origin: String,
+    chunks: VecDeque<Chunk>,
+    relay: RelayHandle,
+}
+
+impl Message for RelayedMessage {
+    fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        let chunk = self.chunks.pop_front();
+        Box::pin(async move { Ok(chunk) })
+    }
+
+    fn ack(&mut self) -> Ack {
+        let relay = self.relay.clone();
+        let id = self.id;
+        Box::pin(async move { relay.ack_relayed(id) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow_msg::{
+        arrow::{
+            array::{ArrayRef, Int64Array},
+            datatypes::{DataType, Field, Schema},
+            record_batch::RecordBatch as ArrowRecordBatch,
+        },
+        RecordBatch,
+    };
+    use section::message::{Message, Next, ValueView};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    struct TestMsg {
+        chunks: VecDeque<Chunk>,
+        acks: Arc<AtomicUsize>,
+    }
+
+    fn test_msg(chunks: impl IntoIterator<Item = Chunk>, acks: &Arc<AtomicUsize>) -> SectionMessage {
+        Box::new(TestMsg {
+            chunks: chunks.into_iter().collect(),
+            acks: Arc::clone(acks),
+        })
+    }
+
+    impl Message for TestMsg {
+        fn origin(&self) -> &str {
+            "test"
+        }
+
+        fn next(&mut self) -> Next<'_> {
+            let chunk = self.chunks.pop_front();
+            Box::pin(async move { Ok(chunk) })
+        }
+
+        fn ack(&mut self) -> Ack {
+            let acks = Arc::clone(&self.acks);
+            Box::pin(async move {
+                acks.fetch_add(1, Ordering::SeqCst);
+            })
+        }
+    }
+
+    fn message(to_node: Uuid) -> RelayMessage {
+        RelayMessage {
+            id: Uuid::now_v7(),
+            to_node,
+            origin: "test".into(),
+            chunks: vec![],
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_chunks() {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+        let ids: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), None, Some(3)]));
+        let rb = ArrowRecordBatch::try_new(schema, vec![ids]).unwrap();
+        let chunks = vec![
+            encode_chunk(Chunk::Byte(b"bin".to_vec())).unwrap(),
+            encode_chunk(Chunk::DataFrame(Box::new(RecordBatch::new(rb)))).unwrap(),
+            encode_chunk(Chunk::Byte(vec![])).unwrap(),
+        ];
+        let mut decoded = decode_chunks(&chunks).unwrap();
+        assert_eq!(decoded.len(), 3);
+        match decoded.pop_front() {
+            Some(Chunk::Byte(bin)) => assert_eq!(bin, b"bin"),
+            chunk => panic!("unexpected chunk: {chunk:?}"),
+        }
+        match decoded.pop_front() {
+            Some(Chunk::DataFrame(df)) => {
+                let mut columns = df.columns();
+                assert_eq!(columns.len(), 1);
+                assert_eq!(columns[0].name(), "id");
+                assert_eq!(
+                    columns.pop().unwrap().collect::<Vec<_>>(),
+                    vec![ValueView::I64(1), ValueView::Null, ValueView::I64(3)]
+                );
+            }
+            chunk => panic!("unexpected chunk: {chunk:?}"),
+        }
+        match decoded.pop_front() {
+            Some(Chunk::Byte(bin)) => assert!(bin.is_empty()),
+            chunk => panic!("unexpected chunk: {chunk:?}"),
+        }
+
+        assert!(decode_chunks(&["".into()]).is_err());
+        assert!(decode_chunks(&["Ag==".into()]).is_err());
+        assert!(decode_chunks(&["not base64".into()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encode_message_size_limit() {
+        let acks = Arc::new(AtomicUsize::new(0));
+        let mut msg = test_msg([Chunk::Byte(vec![0; 1024]), Chunk::Byte(vec![1; 1024])], &acks);
+        let chunks = encode_message(&mut msg).await.unwrap().unwrap();
+        assert_eq!(chunks.len(), 2);
+
+        let mut msg = test_msg(
+            [
+                Chunk::Byte(vec![0; MAX_MESSAGE_SIZE / 2]),
+                Chunk::Byte(vec![0; MAX_MESSAGE_SIZE / 2]),
+            ],
+            &acks,
+        );
+        assert!(encode_message(&mut msg).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_outbound_drops_oversized_message() {
+        let relay = new();
+        let requests = relay.requests();
+        let mut requests = requests.lock().await;
+        let to_node = Uuid::now_v7();
+        let acks = Arc::new(AtomicUsize::new(0));
+        let input = futures::stream::iter([
+            test_msg([Chunk::Byte(vec![0; MAX_MESSAGE_SIZE])], &acks),
+            test_msg([Chunk::Byte(vec![0; 1024])], &acks),
+        ]);
+        let output = futures::sink::drain().sink_map_err(|e| match e {});
+        let section = Outbound::new(to_node, relay.clone());
+        let result = section
+            .start(input, output, section::dummy::DummySectionChannel::new())
+            .await;
+        assert_eq!(result.unwrap_err().to_string(), "input closed");
+
+        // oversized message is acked without relaying, next message is relayed as usual
+        assert_eq!(acks.load(Ordering::SeqCst), 1);
+        match requests.try_recv() {
+            Ok(RelayRequest::Relay(message)) => {
+                assert_eq!(message.to_node, to_node);
+                assert_eq!(message.chunks.len(), 1);
+            }
+            request => panic!("unexpected request: {request:?}"),
+        }
+        assert!(requests.try_recv().is_err());
+        assert_eq!(relay.pending_messages().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_dedup() {
+        let relay = new();
+        let requests = relay.requests();
+        let mut requests = requests.lock().await;
+        let to_node = Uuid::now_v7();
+
+        // message without inbound section is dropped
+        relay.deliver(1, message(to_node));
+        let mut rx = relay.register_inbound(to_node);
+        assert!(matches!(requests.try_recv(), Ok(RelayRequest::GetRelayed)));
+        assert!(rx.try_recv().is_err());
+
+        relay.deliver(1, message(to_node));
+        assert_eq!(rx.try_recv().unwrap().0, 1);
+        // in flight message is not delivered twice
+        relay.deliver(1, message(to_node));
+        assert!(rx.try_recv().is_err());
+
+        relay.ack_relayed(1);
+        assert!(matches!(
+            requests.try_recv(),
+            Ok(RelayRequest::RelayedAck(1))
+        ));
+        // acked message is not delivered again, repeated ack is ignored
+        relay.deliver(1, message(to_node));
+        assert!(rx.try_recv().is_err());
+        relay.ack_relayed(1);
+        assert!(requests.try_recv().is_err());
+
+        // re-registered section gets in flight messages again
+        relay.deliver(2, message(to_node));
+        assert_eq!(rx.try_recv().unwrap().0, 2);
+        let mut rx = relay.register_inbound(to_node);
+        relay.deliver(2, message(to_node));
+        assert_eq!(rx.try_recv().unwrap().0, 2);
+    }
+}
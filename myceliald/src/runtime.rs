@@ -1,13 +1,16 @@
 use crate::{
     control_plane_client::{self, ControlPlaneClientHandle},
+    relay,
     runtime_error::RuntimeError,
     runtime_storage::{self, RuntimeStorage},
     scheduler::{self, SchedulerHandle},
+    secret_store::{self, SecretStore},
     sqlite_storage::{self, SqliteStorageHandle},
     Config, ConfigRegistry, Result,
 };
+use config_registry::plugin::{host::LoadedPlugin, SectionDescriptor};
 use serde::{Deserialize, Serialize};
-use std::{path::Path, time::Duration};
+use std::{path::Path, sync::Arc, time::Duration};
 use tokio::sync::mpsc::{
     unbounded_channel, UnboundedReceiver, UnboundedSender, WeakUnboundedSender,
 };
@@ -17,6 +20,7 @@ pub struct Runtime {
     scheduler_handle: SchedulerHandle,
     runtime_storage: RuntimeStorage,
     section_storage_handle: SqliteStorageHandle,
+    secret_store: SecretStore,
     control_plane_client_handle: ControlPlaneClientHandle,
     config_registry: ConfigRegistry,
     rx: UnboundedReceiver<RuntimeMessage>,
@@ -41,10 +45,23 @@ impl Graph {
 pub struct Node {
     pub id: uuid::Uuid,
     pub config: Config,
+    /// restart counter, pipeline is restarted when counter changes
+    #[serde(default)]
+    pub restarts: i64,
 }
 
 impl Node {
     pub fn deserialize_config(&mut self, registry: &ConfigRegistry) -> Result<()> {
+        // older configs are upgraded by registry, newer can't be handled by this daemon
+        if let Ok(supported) = registry.build_config(self.config.name()) {
+            if self.config.version() > supported.version() {
+                Err(RuntimeError::UnsupportedConfigVersion {
+                    config_name: self.config.name().into(),
+                    version: self.config.version(),
+                    supported: supported.version(),
+                })?
+            }
+        }
         let mut config = registry.deserialize_config(&*self.config).map_err(|_| {
             RuntimeError::RawConfigDeserializeError {
                 config_name: self.config.name().into(),
@@ -62,6 +79,41 @@ pub struct Edge {
     pub to_id: uuid::Uuid,
 }
 
+/// Daemon capabilities, reported to control plane on connect
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// daemon version
+    pub version: String,
+    /// section types, available on daemon, including plugin sections
+    pub sections: Vec<SectionVersion>,
+    /// sections, provided by loaded plugins
+    pub plugin_sections: Vec<SectionDescriptor>,
+}
+
+/// Section type with latest config version, supported by daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionVersion {
+    pub name: String,
+    pub version: u32,
+}
+
+impl Capabilities {
+    fn new(config_registry: &ConfigRegistry, plugin_sections: Vec<SectionDescriptor>) -> Self {
+        let sections = config_registry
+            .iter_values()
+            .map(|metadata| SectionVersion {
+                name: metadata.ty.to_string(),
+                version: metadata.build_config().version(),
+            })
+            .collect();
+        Self {
+            version: env!("CARGO_PKG_VERSION").into(),
+            sections,
+            plugin_sections,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum RuntimeMessage {
     RetryControlPlaneClientInit,
@@ -92,20 +144,39 @@ impl RuntimeHandle {
 }
 
 impl Runtime {
-    pub async fn new(database_path: &str) -> Result<Self> {
+    pub async fn new(
+        database_path: &str,
+        plugins_dir: Option<&Path>,
+        secrets_dir: Option<&Path>,
+    ) -> Result<Self> {
         let (tx, rx) = unbounded_channel();
         let database_path = Path::new(database_path);
         let section_storage_handle = sqlite_storage::new(database_path).await?;
-        let scheduler_handle = scheduler::new(section_storage_handle.clone());
+        let secret_store = secret_store::new(database_path, secrets_dir).await?;
+        let relay = relay::new();
+        let scheduler_handle = scheduler::new(
+            section_storage_handle.clone(),
+            secret_store.clone(),
+            relay.clone(),
+        );
         let runtime_storage = runtime_storage::new(database_path).await?;
-        let control_plane_client_handle = control_plane_client::new(RuntimeHandle::new(&tx));
+        let mut config_registry =
+            config_registry::new().map_err(RuntimeError::ConfigRegistryInitError)?;
+        let plugin_sections = match plugins_dir {
+            Some(plugins_dir) => load_plugins(plugins_dir, &mut config_registry)
+                .map_err(RuntimeError::PluginsDirReadError)?,
+            None => vec![],
+        };
+        let capabilities = Capabilities::new(&config_registry, plugin_sections);
+        let control_plane_client_handle =
+            control_plane_client::new(RuntimeHandle::new(&tx), Arc::new(capabilities), relay);
         Ok(Self {
             scheduler_handle,
             runtime_storage,
             section_storage_handle,
+            secret_store,
             control_plane_client_handle,
-            config_registry: config_registry::new()
-                .map_err(RuntimeError::ConfigRegistryInitError)?,
+            config_registry,
             rx,
             weak_tx: tx.clone().downgrade(),
         })
@@ -164,6 +235,18 @@ impl Runtime {
         Ok(())
     }
 
+    pub async fn set_secret(&mut self, name: &str, value: &str) -> Result<()> {
+        self.secret_store.set(name, value).await
+    }
+
+    pub async fn delete_secret(&mut self, name: &str) -> Result<bool> {
+        self.secret_store.delete(name).await
+    }
+
+    pub async fn list_secrets(&mut self) -> Result<Vec<String>> {
+        self.secret_store.list().await
+    }
+
     pub async fn shutdown(&mut self) -> anyhow::Result<()> {
         self.scheduler_handle.shutdown().await.ok();
         self.section_storage_handle.shutdown().await.ok();
@@ -196,3 +279,48 @@ impl Runtime {
         Ok(())
     }
 }
+
+// load plugin libraries from directory and register their configs
+//
+// broken plugins and sections, which clash with already registered ones, are skipped
+fn load_plugins(
+    plugins_dir: &Path,
+    config_registry: &mut ConfigRegistry,
+) -> std::io::Result<Vec<SectionDescriptor>> {
+    let mut sections = vec![];
+    let paths = config_registry::plugin::host::plugin_paths(plugins_dir)?;
+    for path in paths {
+        let plugin = match unsafe { LoadedPlugin::load(&path) } {
+            Ok(plugin) => plugin,
+            Err(e) => {
+                tracing::error!("failed to load plugin {}: {e}", path.display());
+                continue;
+            }
+        };
+        let configs = match plugin.configs() {
+            Ok(configs) => configs,
+            Err(e) => {
+                tracing::error!("invalid sections in plugin {}: {e}", path.display());
+                continue;
+            }
+        };
+        for (config, descriptor) in configs.into_iter().zip(plugin.sections()) {
+            match config_registry.add_plugin_config(config) {
+                Ok(()) => {
+                    tracing::info!(
+                        "loaded section '{}' from {}",
+                        descriptor.name,
+                        path.display()
+                    );
+                    sections.push(descriptor.clone());
+                }
+                Err(e) => tracing::error!(
+                    "failed to register section '{}' from {}: {e}",
+                    descriptor.name,
+                    path.display()
+                ),
+            }
+        }
+    }
+    Ok(sections)
+}
@@ -8,12 +8,22 @@ pub enum RuntimeError {
     /// Failed to initalize config registry
     ConfigRegistryInitError(StdError),
 
+    /// Failed to read plugins directory
+    PluginsDirReadError(std::io::Error),
+
     /// Failed to deserialize RawConfig into Config
     RawConfigDeserializeError {
         config_name: String,
         raw_config: Box<dyn config::Config>,
     },
 
+    /// Stored config version is newer than daemon supports
+    UnsupportedConfigVersion {
+        config_name: String,
+        version: u32,
+        supported: u32,
+    },
+
     /// Storage sqlx errors
     SqlxError(SqlxError),
 
@@ -72,6 +82,20 @@ pub enum RuntimeError {
     // Section Storage Errors
     StorageError(StdError),
 
+    // Secret Errors
+    /// Secret reference can't be resolved
+    SecretNotFound(String),
+    /// Secret reference points outside of allowed environment variables or secrets directory
+    SecretRefNotAllowed {
+        reference: String,
+        reason: String,
+    },
+    SecretsDirError(StdError),
+    SecretResolveError {
+        field: String,
+        reason: StdError,
+    },
+
     // Graph Error
     DanglingEdge,
     GraphNodeOutputError,
@@ -1,6 +1,6 @@
 use std::{
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     pin::pin,
     time::Duration,
 };
@@ -8,7 +8,8 @@ use std::{
 use graph::Graph as GenericGraph;
 use section::{
     command_channel::ReplyTo as _,
-    prelude::{RootChannel as _, SinkExt}, SectionError, SectionMessage,
+    prelude::{futures, RootChannel as _, Section as _, SinkExt},
+    DynSink, DynStream, SectionError, SectionFuture, SectionMessage,
 };
 use sha2::{Digest, Sha256};
 use tokio::{
@@ -20,11 +21,13 @@ use tokio_util::sync::PollSender;
 use uuid::Uuid;
 
 use crate::{
+    relay::{Inbound, Outbound, RelayHandle},
     runtime::Graph as RawGraph,
     runtime_error::RuntimeError,
+    secret_store::SecretStore,
     section_channel::{RootChannel, SectionRequest},
     sqlite_storage::{SqliteState, SqliteStorageHandle},
-    Config, Result,
+    Config, Result, SectionChannel,
 };
 
 use tokio::sync::oneshot::{channel as oneshot_channel, Sender as OneshotSender};
@@ -46,6 +49,8 @@ pub struct Task {
     // FIXME: task id is a hash of whole graph and created locally
     id: String,
     storage_handle: SqliteStorageHandle,
+    secret_store: SecretStore,
+    relay: RelayHandle,
     graph: Graph,
     status: TaskStatus,
     root_channel: RootChannel<SqliteState>,
@@ -53,10 +58,18 @@ pub struct Task {
 }
 
 impl Task {
-    fn new(id: String, graph: Graph, storage_handle: SqliteStorageHandle) -> Self {
+    fn new(
+        id: String,
+        graph: Graph,
+        storage_handle: SqliteStorageHandle,
+        secret_store: SecretStore,
+        relay: RelayHandle,
+    ) -> Self {
         Self {
             id,
             storage_handle,
+            secret_store,
+            relay,
             graph,
             status: TaskStatus::New,
             root_channel: RootChannel::new(),
@@ -91,6 +104,8 @@ impl Task {
                             },
                             Err(e) => {
                                 tracing::error!("task with id {} failed to start: {e}", self.id);
+                                // sections, which were already started, are stopped before next attempt
+                                self.shutdown().await.ok();
                                 tokio::time::sleep(Duration::from_secs(3)).await;
                             }
                         }
@@ -223,7 +238,68 @@ impl Task {
                 from_node.set_output(input)?;
             }
         }
+        // secret references are resolved on every start attempt, so task picks up updated secrets on restart
+        for (&id, plan) in task_plan.iter_mut() {
+            if let Some(config) = self.graph.get_node(id) {
+                let mut config = config.clone();
+                self.secret_store.resolve_config(&mut config).await?;
+                plan.set_config(config);
+            }
+        }
         tracing::info!("task_plan: {task_plan:#?}");
+
+        // boundary edges, which end at the same local node, share single inbound section
+        let mut inbound_nodes = BTreeSet::new();
+        for (id, mut plan) in task_plan {
+            let section: Box<dyn FnOnce(DynStream, DynSink, SectionChannel) -> SectionFuture> =
+                match plan.ty {
+                    SectionType::Regular => match plan.config.take() {
+                        Some(config) => Box::new(move |input, output, section_channel| {
+                            config.dyn_start(input, output, section_channel)
+                        }),
+                        None => Err(RuntimeError::MalformedGraph)?,
+                    },
+                    SectionType::Outbound => {
+                        let outbound = Outbound::new(id, self.relay.clone());
+                        Box::new(move |input, output, section_channel| {
+                            outbound.start(input, output, section_channel)
+                        })
+                    }
+                    SectionType::Inbound => {
+                        let to_node = self.graph.get_edge(id).ok_or(RuntimeError::DanglingEdge)?;
+                        if !inbound_nodes.insert(to_node) {
+                            continue;
+                        }
+                        let inbound = Inbound::new(to_node, self.relay.clone());
+                        Box::new(move |input, output, section_channel| {
+                            inbound.start(input, output, section_channel)
+                        })
+                    }
+                };
+            let input: DynStream = match plan.section_input.take() {
+                Some(input) => Box::pin(input),
+                None => Box::pin(futures::stream::pending()),
+            };
+            let output: DynSink = match plan.section_output.take() {
+                Some(output) => {
+                    Box::pin(output.sink_map_err(|_| SectionError::from("output closed")))
+                }
+                // messages, which leave the graph, are acked
+                None => Box::pin(futures::sink::unfold(
+                    (),
+                    |(), mut message: SectionMessage| async move {
+                        message.ack().await;
+                        Ok::<_, SectionError>(())
+                    },
+                )),
+            };
+            let section_channel = self
+                .root_channel
+                .add_section(id)
+                .map_err(|_| RuntimeError::SectionChannelAllocationError)?;
+            self.section_handles
+                .insert(id, tokio::spawn(section(input, output, section_channel)));
+        }
         Ok(())
     }
 
@@ -273,7 +349,8 @@ struct SectionPlan {
     input: Option<PollSender<SectionMessage>>,
     section_input: Option<ReceiverStream<SectionMessage>>,
     section_output: Option<PollSender<SectionMessage>>,
-    //runner: Option<Box<dyn FnOnce(DynStream, DynSink, SectionChannel) -> SectionFuture>>,
+    // config with resolved secrets, should never be logged
+    config: Option<Config>,
 }
 
 impl std::fmt::Debug for SectionPlan {
@@ -284,6 +361,7 @@ impl std::fmt::Debug for SectionPlan {
             .field("input", &self.input.is_some())
             .field("section_input", &self.section_input.is_some())
             .field("section_output", &self.section_output.is_some())
+            .field("config", &self.config.as_ref().map(|config| config.name()))
             .finish()
     }
 }
@@ -296,9 +374,14 @@ impl SectionPlan {
             input: None,
             section_input: None,
             section_output: None,
+            config: None,
         }
     }
 
+    fn set_config(&mut self, config: Config) {
+        self.config = Some(config);
+    }
+
     // get section input
     // section can have multiple inputs
     fn get_input(&mut self) -> PollSender<SectionMessage> {
@@ -366,6 +449,8 @@ enum TaskMessage {
 struct Scheduler {
     tasks: BTreeMap<String, TaskHandle>,
     storage_handle: SqliteStorageHandle,
+    secret_store: SecretStore,
+    relay: RelayHandle,
 }
 
 impl Scheduler {
@@ -412,6 +497,11 @@ impl Scheduler {
     async fn schedule(&mut self, raw_graph: RawGraph) -> Result<()> {
         tracing::info!("raw graph: {:#?}", raw_graph);
         let mut graph = Graph::new();
+        let restarts = raw_graph
+            .nodes
+            .iter()
+            .map(|node| (node.id, node.restarts))
+            .collect::<BTreeMap<_, _>>();
         for node in raw_graph.nodes.into_iter() {
             graph.add_node(node.id, node.config);
         }
@@ -431,6 +521,11 @@ impl Scheduler {
                     hasher.update(field.name.as_bytes());
                     hasher.update(field.value.to_string().as_bytes());
                 }
+                // ids of never restarted pipelines don't depend on counter
+                match restarts.get(&id) {
+                    Some(&restarts) if restarts > 0 => hasher.update(restarts.to_le_bytes()),
+                    _ => (),
+                }
             }
             for (from, to) in graph.iter_edges() {
                 hasher.update(from.as_bytes());
@@ -478,7 +573,14 @@ impl Scheduler {
         for (id, graph) in to_add {
             self.tasks.insert(
                 id.clone(),
-                Task::new(id, graph, self.storage_handle.clone()).spawn(),
+                Task::new(
+                    id,
+                    graph,
+                    self.storage_handle.clone(),
+                    self.secret_store.clone(),
+                    self.relay.clone(),
+                )
+                .spawn(),
             );
         }
         Ok(())
@@ -526,10 +628,16 @@ impl SchedulerHandle {
     }
 }
 
-pub fn new(storage_handle: SqliteStorageHandle) -> SchedulerHandle {
+pub fn new(
+    storage_handle: SqliteStorageHandle,
+    secret_store: SecretStore,
+    relay: RelayHandle,
+) -> SchedulerHandle {
     Scheduler {
         tasks: BTreeMap::new(),
         storage_handle,
+        secret_store,
+        relay,
     }
     .spawn()
 }
@@ -0,0 +1,298 @@
+//! Local secret store and resolution of secret references
+//!
+//! Password fields of section configs can hold a reference instead of a plaintext value:
+//! - `env:NAME` - value of environment variable `NAME` on the daemon host, name should start with `MYCELIAL_SECRET_`,
+//!   e.g. `env:MYCELIAL_SECRET_PG_PASSWORD`
+//! - `file:path` - contents of the file, with trailing whitespace trimmed, file should be located in
+//!   secrets directory (`--secrets-dir`), path is relative to it, e.g. `file:pg.pass` or `file:/etc/mycelial/secrets/pg.pass`
+//!   with `--secrets-dir /etc/mycelial/secrets`
+//! - `secret:NAME` - secret stored with `myceliald secret set NAME`
+//!
+//! Values without known prefix are treated as plaintext.
+//! Configs are edited from control plane, so references are restricted to avoid exposure of arbitrary
+//! host environment and files.
+//! References are resolved right before sections are started, so credentials never leave the daemon host.
+
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Row, SqlitePool,
+};
+use std::path::{Path, PathBuf};
+
+use crate::{runtime_error::RuntimeError, Config, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecretRef<'a> {
+    Env(&'a str),
+    File(&'a str),
+    Store(&'a str),
+}
+
+impl<'a> SecretRef<'a> {
+    pub fn parse(value: &'a str) -> Option<Self> {
+        let (kind, name) = value.split_once(':')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        match kind {
+            "env" => Some(SecretRef::Env(name)),
+            "file" => Some(SecretRef::File(name)),
+            "secret" => Some(SecretRef::Store(name)),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SecretRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretRef::Env(name) => write!(f, "env:{name}"),
+            SecretRef::File(path) => write!(f, "file:{path}"),
+            SecretRef::Store(name) => write!(f, "secret:{name}"),
+        }
+    }
+}
+
+/// prefix of environment variables, which can be referenced with `env:`
+pub const ENV_PREFIX: &str = "MYCELIAL_SECRET_";
+
+#[derive(Debug, Clone)]
+pub struct SecretStore {
+    pool: SqlitePool,
+    /// canonical path of directory with secret files, `file:` references are rejected if not set
+    secrets_dir: Option<PathBuf>,
+}
+
+impl SecretStore {
+    pub async fn new(path: &Path, secrets_dir: Option<&Path>) -> Result<Self> {
+        let secrets_dir = match secrets_dir {
+            Some(dir) => Some(
+                tokio::fs::canonicalize(dir)
+                    .await
+                    .map_err(|e| RuntimeError::SecretsDirError(e.into()))?,
+            ),
+            None => None,
+        };
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        sqlx::migrate!().run(&pool).await?;
+        Ok(Self { pool, secrets_dir })
+    }
+
+    pub async fn set(&self, name: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO secret(name, value) VALUES(?, ?) ON CONFLICT(name) DO UPDATE SET value=excluded.value",
+        )
+        .bind(name)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(sqlx::query("SELECT value FROM secret WHERE name=?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .map(|maybe_row| maybe_row.map(|row| row.get(0)))?)
+    }
+
+    /// returns true if secret was present
+    pub async fn delete(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM secret WHERE name=?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn list(&self) -> Result<Vec<String>> {
+        Ok(sqlx::query("SELECT name FROM secret ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect())
+    }
+
+    /// resolve secret reference into its value
+    pub async fn resolve(&self, secret_ref: SecretRef<'_>) -> Result<String> {
+        let value = match secret_ref {
+            SecretRef::Env(name) => {
+                if !name.starts_with(ENV_PREFIX) {
+                    Err(RuntimeError::SecretRefNotAllowed {
+                        reference: secret_ref.to_string(),
+                        reason: format!("environment variable name should start with `{ENV_PREFIX}`"),
+                    })?
+                }
+                std::env::var(name).ok()
+            }
+            SecretRef::File(path) => {
+                let secrets_dir = self
+                    .secrets_dir
+                    .as_deref()
+                    .ok_or_else(|| RuntimeError::SecretRefNotAllowed {
+                        reference: secret_ref.to_string(),
+                        reason: "secrets directory is not set, use `--secrets-dir` or `MYCELIAL_SECRETS_DIR`".into(),
+                    })?;
+                // canonical path has no symlinks and `..` components, so it can be checked by prefix
+                match tokio::fs::canonicalize(secrets_dir.join(path)).await {
+                    Ok(path) if path.starts_with(secrets_dir) => tokio::fs::read_to_string(path)
+                        .await
+                        .ok()
+                        .map(|value| value.trim_end().to_string()),
+                    Ok(_) => Err(RuntimeError::SecretRefNotAllowed {
+                        reference: secret_ref.to_string(),
+                        reason: format!(
+                            "file should be located in secrets directory `{}`",
+                            secrets_dir.display()
+                        ),
+                    })?,
+                    Err(_) => None,
+                }
+            }
+            SecretRef::Store(name) => self.get(name).await?,
+        };
+        value.ok_or_else(|| RuntimeError::SecretNotFound(secret_ref.to_string()))
+    }
+
+    /// replace secret references in password fields of config with resolved values
+    pub async fn resolve_config(&self, config: &mut Config) -> Result<()> {
+        let references = config
+            .fields()
+            .into_iter()
+            .filter(|field| field.metadata.is_password)
+            .filter_map(|field| match field.value {
+                config::FieldValue::String(value) => {
+                    SecretRef::parse(value).map(|_| (field.name.to_string(), value.to_string()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (field, reference) in references {
+            // reference was parsed above
+            let secret_ref = SecretRef::parse(&reference).unwrap();
+            let value = self.resolve(secret_ref).await?;
+            config
+                .set_field_value(&field, config::FieldValue::String(&value))
+                .map_err(|reason| RuntimeError::SecretResolveError { field, reason })?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn new(database_path: &Path, secrets_dir: Option<&Path>) -> Result<SecretStore> {
+    SecretStore::new(database_path, secrets_dir).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_secret_ref_parse() {
+        assert_eq!(
+            Some(SecretRef::Env("PG_PASSWORD")),
+            SecretRef::parse("env:PG_PASSWORD")
+        );
+        assert_eq!(
+            Some(SecretRef::File("/etc/mycelial/pg.pass")),
+            SecretRef::parse("file:/etc/mycelial/pg.pass")
+        );
+        assert_eq!(Some(SecretRef::Store("pg")), SecretRef::parse("secret:pg"));
+        assert_eq!(None, SecretRef::parse("password"));
+        assert_eq!(None, SecretRef::parse("env:"));
+        assert_eq!(None, SecretRef::parse("p@ss:word"));
+    }
+
+    #[tokio::test]
+    async fn test_secret_store() {
+        let dir = std::env::temp_dir().join(format!("myceliald-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let secrets_dir = dir.join("secrets");
+        std::fs::create_dir_all(&secrets_dir).unwrap();
+        let store = SecretStore::new(&dir.join("myceliald.db"), Some(&secrets_dir))
+            .await
+            .unwrap();
+
+        store.set("pg", "secret").await.unwrap();
+        store.set("pg", "updated").await.unwrap();
+        assert_eq!(vec!["pg".to_string()], store.list().await.unwrap());
+        assert_eq!(
+            "updated",
+            store.resolve(SecretRef::Store("pg")).await.unwrap()
+        );
+
+        let path = secrets_dir.join("pg.pass");
+        std::fs::write(&path, "from file\n").unwrap();
+        assert_eq!(
+            "from file",
+            store
+                .resolve(SecretRef::File(path.to_str().unwrap()))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            "from file",
+            store.resolve(SecretRef::File("pg.pass")).await.unwrap()
+        );
+        assert!(store.resolve(SecretRef::File("missing")).await.is_err());
+
+        // files outside of secrets directory are rejected
+        std::fs::write(dir.join("host.pass"), "host secret").unwrap();
+        let outside = dir.join("host.pass");
+        for path in ["../host.pass", outside.to_str().unwrap(), "/etc/passwd"] {
+            assert!(matches!(
+                store.resolve(SecretRef::File(path)).await,
+                Err(RuntimeError::SecretRefNotAllowed { .. })
+            ));
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, secrets_dir.join("link.pass")).unwrap();
+            assert!(matches!(
+                store.resolve(SecretRef::File("link.pass")).await,
+                Err(RuntimeError::SecretRefNotAllowed { .. })
+            ));
+        }
+        let no_dir = SecretStore::new(&dir.join("myceliald.db"), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            no_dir
+                .resolve(SecretRef::File(path.to_str().unwrap()))
+                .await,
+            Err(RuntimeError::SecretRefNotAllowed { .. })
+        ));
+
+        // only prefixed environment variables can be referenced
+        std::env::set_var("MYCELIAL_SECRET_TEST_STORE", "from env");
+        assert_eq!(
+            "from env",
+            store
+                .resolve(SecretRef::Env("MYCELIAL_SECRET_TEST_STORE"))
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            store.resolve(SecretRef::Env("PATH")).await,
+            Err(RuntimeError::SecretRefNotAllowed { .. })
+        ));
+
+        assert!(store.delete("pg").await.unwrap());
+        assert!(!store.delete("pg").await.unwrap());
+        assert!(store.resolve(SecretRef::Store("pg")).await.is_err());
+        assert!(store
+            .resolve(SecretRef::Env("MYCELIAL_SECRET_UNSET"))
+            .await
+            .is_err());
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
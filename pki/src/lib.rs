@@ -1,9 +1,14 @@
-use std::{io::Cursor, sync::Arc};
+use std::{
+    io::Cursor,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
 
 use ::time::OffsetDateTime;
 use rcgen::{
-    BasicConstraints, CertificateParams, CertificateSigningRequest, DistinguishedName, DnType,
-    ExtendedKeyUsagePurpose, IsCa, KeyUsagePurpose,
+    BasicConstraints, CertificateParams, CertificateRevocationListParams,
+    CertificateSigningRequest, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyIdMethod, KeyUsagePurpose, RevokedCertParams, SerialNumber,
 };
 pub use rcgen::{Certificate, CertifiedKey, KeyPair};
 use rustls::{
@@ -16,8 +21,12 @@ use rustls::{
 };
 pub use rustls::{ClientConfig, ServerConfig};
 use rustls_pemfile::{certs, private_key};
-pub use webpki::types::CertificateDer;
-use webpki::types::{PrivateKeyDer, ServerName, TrustAnchor, UnixTime};
+pub use webpki::types::{CertificateDer, CertificateRevocationListDer};
+use webpki::{
+    types::{PrivateKeyDer, ServerName, TrustAnchor, UnixTime},
+    CertRevocationList, OwnedCertRevocationList, RevocationCheckDepth, RevocationOptionsBuilder,
+    UnknownStatusPolicy,
+};
 use x509_parser::prelude::*;
 
 pub type Result<T, E = StdError> = std::result::Result<T, E>;
@@ -125,9 +134,52 @@ pub fn sign_csr(ca: &CertifiedKey, csr: &str, id: &str) -> Result<Certificate> {
     Ok(csr_params.signed_by(&ca.cert, &ca.key_pair)?)
 }
 
+/// serial number of certificate, as encoded in certificate
+pub fn serial_number(certificate: &CertificateDer<'_>) -> Result<Vec<u8>> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(certificate)?;
+    Ok(certificate.raw_serial().to_vec())
+}
+
+/// Revoked certificate, listed in CRL
+#[derive(Debug, Clone)]
+pub struct RevokedCertificate {
+    pub serial_number: Vec<u8>,
+    pub revoked_at: SystemTime,
+}
+
+/// generate CRL, signed by ca
+///
+/// CRL number should increase with each generated CRL.
+pub fn generate_crl(
+    ca: &CertifiedKey,
+    crl_number: u64,
+    revoked: &[RevokedCertificate],
+    validity: Duration,
+) -> Result<CertificateRevocationListDer<'static>> {
+    let this_update = OffsetDateTime::now_utc();
+    let params = CertificateRevocationListParams {
+        this_update,
+        next_update: this_update + validity,
+        crl_number: SerialNumber::from(crl_number),
+        issuing_distribution_point: None,
+        revoked_certs: revoked
+            .iter()
+            .map(|revoked| RevokedCertParams {
+                serial_number: SerialNumber::from_slice(&revoked.serial_number),
+                revocation_time: revoked.revoked_at.into(),
+                reason_code: None,
+                invalidity_date: None,
+            })
+            .collect(),
+        key_identifier_method: KeyIdMethod::Sha256,
+    };
+    Ok(params.signed_by(&ca.cert, &ca.key_pair)?.der().clone())
+}
+
 #[derive(Debug)]
 pub struct Verifier {
     ca: Vec<TrustAnchor<'static>>,
+    crls: RwLock<Vec<CertRevocationList<'static>>>,
     signature_verification_algorithms: WebPkiSupportedAlgorithms,
 }
 
@@ -138,16 +190,28 @@ fn to_err(err: webpki::Error) -> rustls::Error {
 }
 
 // DNS name is not checked
-// CRL is not supported (yet)
+// client certificates are checked against CRL, if set, server certificates are not
+// CRL expiration is not enforced, since CRL is replaced by its issuer
 impl Verifier {
     pub fn new(cert: CertificateDer<'_>) -> Result<Self> {
-        let provider =
-            CryptoProvider::get_default().ok_or("failed to get default crypto provider")?;
+        let signature_verification_algorithms = match CryptoProvider::get_default() {
+            Some(provider) => provider.signature_verification_algorithms,
+            // default provider is installed on first config build, verifier can be created before
+            None => rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        };
         Ok(Self {
             ca: vec![webpki::anchor_from_trusted_cert(&cert)?.to_owned()],
-            signature_verification_algorithms: provider.signature_verification_algorithms,
+            crls: RwLock::new(vec![]),
+            signature_verification_algorithms,
         })
     }
+
+    /// replace CRL, new handshakes of revoked clients are rejected
+    pub fn set_crl(&self, crl: &CertificateRevocationListDer<'_>) -> Result<()> {
+        let crl = OwnedCertRevocationList::from_der(crl)?;
+        *self.crls.write().unwrap() = vec![crl.into()];
+        Ok(())
+    }
 }
 
 impl ServerCertVerifier for Verifier {
@@ -208,13 +272,22 @@ impl ClientCertVerifier for Verifier {
         now: UnixTime,
     ) -> std::result::Result<rustls::server::danger::ClientCertVerified, rustls::Error> {
         let cert = webpki::EndEntityCert::try_from(end_entity).map_err(to_err)?;
+        let crls = self.crls.read().unwrap();
+        let crls = crls.iter().collect::<Vec<_>>();
+        // builder fails only if list of CRLs is empty
+        let revocation = RevocationOptionsBuilder::new(&crls).ok().map(|builder| {
+            builder
+                .with_depth(RevocationCheckDepth::EndEntity)
+                .with_status_policy(UnknownStatusPolicy::Allow)
+                .build()
+        });
         cert.verify_for_usage(
             self.signature_verification_algorithms.all,
             self.ca.as_slice(),
             intermediates,
             now,
             webpki::KeyUsage::client_auth(),
-            None,
+            revocation,
             None,
         )
         .map_err(to_err)?;
@@ -264,3 +337,65 @@ pub fn extract_common_name<'a>(certificate: &'a CertificateDer<'a>) -> Result<&'
     }
     Err("common name not present")?
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revoked(certificate: &CertifiedKey) -> RevokedCertificate {
+        RevokedCertificate {
+            serial_number: serial_number(certificate.cert.der()).unwrap(),
+            revoked_at: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_crl_rejects_revoked_client_cert() {
+        let ca = generate_ca_certkey("ca").unwrap();
+        let revoked_client = generate_client_cert(&ca, "revoked").unwrap();
+        let client = generate_client_cert(&ca, "client").unwrap();
+        let verifier = Verifier::new(ca.cert.der().clone()).unwrap();
+        let verify = |certificate: &CertifiedKey| {
+            verifier.verify_client_cert(certificate.cert.der(), &[], UnixTime::now())
+        };
+
+        // without CRL every client certificate, issued by ca, is accepted
+        assert!(verify(&revoked_client).is_ok());
+        assert!(verify(&client).is_ok());
+
+        let crl = generate_crl(
+            &ca,
+            1,
+            &[revoked(&revoked_client)],
+            Duration::from_secs(3600),
+        )
+        .unwrap();
+        verifier.set_crl(&crl).unwrap();
+        assert!(verify(&revoked_client).is_err());
+        assert!(verify(&client).is_ok());
+
+        // new CRL replaces previous one
+        let crl = generate_crl(&ca, 2, &[], Duration::from_secs(3600)).unwrap();
+        verifier.set_crl(&crl).unwrap();
+        assert!(verify(&revoked_client).is_ok());
+    }
+
+    #[test]
+    fn test_crl_of_other_ca() {
+        let ca = generate_ca_certkey("ca").unwrap();
+        let other_ca = generate_ca_certkey("other").unwrap();
+        let client = generate_client_cert(&ca, "client").unwrap();
+        let verifier = Verifier::new(ca.cert.der().clone()).unwrap();
+        assert!(verifier
+            .set_crl(&CertificateRevocationListDer::from(vec![0; 16]))
+            .is_err());
+
+        // CRL of other issuer doesn't revoke certificates of ca
+        let crl =
+            generate_crl(&other_ca, 1, &[revoked(&client)], Duration::from_secs(3600)).unwrap();
+        verifier.set_crl(&crl).unwrap();
+        assert!(verifier
+            .verify_client_cert(client.cert.der(), &[], UnixTime::now())
+            .is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+//! Example plugin with single section, which passes input chunks to output as is
+//!
+//! Build with `cargo build -p plugin --example passthrough` and copy library into daemon plugins directory.
+
+use std::collections::VecDeque;
+
+use plugin::{
+    Chunk, Error, FieldDescriptor, FieldTypeDescriptor, Plugin, PluginSection, Poll,
+    SectionDescriptor, SectionIo,
+};
+
+#[derive(Debug, Default)]
+pub struct Passthrough;
+
+impl Plugin for Passthrough {
+    fn sections(&self) -> Vec<SectionDescriptor> {
+        vec![SectionDescriptor::new("plugin_passthrough")
+            .input(SectionIo::BinOrDataFrame)
+            .output(SectionIo::BinOrDataFrame)
+            .description("Passes input to output as is")
+            .field(
+                FieldDescriptor::new("max_chunks", FieldTypeDescriptor::U64)
+                    .default(0)
+                    .description("Section finishes after this many chunks, 0 means no limit")
+                    .advanced(),
+            )]
+    }
+
+    fn start(
+        &self,
+        name: &str,
+        config: serde_json::Value,
+    ) -> Result<Box<dyn PluginSection>, Error> {
+        if name != "plugin_passthrough" {
+            Err(format!("unknown section: {name}"))?
+        }
+        let max_chunks = config["max_chunks"].as_u64().unwrap_or(0);
+        Ok(Box::new(PassthroughSection {
+            max_chunks,
+            passed: 0,
+            queue: VecDeque::new(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct PassthroughSection {
+    max_chunks: u64,
+    passed: u64,
+    queue: VecDeque<Chunk>,
+}
+
+impl PluginSection for PassthroughSection {
+    fn push(&mut self, chunk: Chunk) -> Result<(), Error> {
+        self.queue.push_back(chunk);
+        Ok(())
+    }
+
+    fn pull(&mut self) -> Result<Poll, Error> {
+        if self.max_chunks != 0 && self.passed >= self.max_chunks {
+            return Ok(Poll::Done);
+        }
+        match self.queue.pop_front() {
+            Some(chunk) => {
+                self.passed += 1;
+                Ok(Poll::Ready(chunk))
+            }
+            None => Ok(Poll::Pending),
+        }
+    }
+}
+
+plugin::export_plugin!(Passthrough);
@@ -0,0 +1,150 @@
+//! C ABI between daemon and plugin libraries
+//!
+//! Plugin library exports `mycelial_plugin_api` function, which returns pointer to static [`PluginApi`].
+//! Data crosses the boundary only as bytes: section manifest and configs are JSON,
+//! binary chunks are passed as is, dataframes are encoded as Arrow IPC streams.
+//!
+//! Buffers, returned by plugin, are allocated by plugin and should be released with `free_buffer`.
+
+use std::ffi::c_void;
+
+/// version of the ABI, plugins with different version are rejected
+pub const ABI_VERSION: u32 = 1;
+
+/// name of the function, exported by plugin library
+pub const ENTRY_SYMBOL: &str = "mycelial_plugin_api";
+
+pub type EntryFn = unsafe extern "C" fn() -> *const PluginApi;
+
+pub type Status = u32;
+
+/// call succeeded
+pub const STATUS_OK: Status = 0;
+/// no output chunk is ready yet
+pub const STATUS_PENDING: Status = 1;
+/// section is done and will not produce any output
+pub const STATUS_DONE: Status = 2;
+/// call failed, error message is returned in buffer
+pub const STATUS_ERROR: Status = 3;
+
+pub type ChunkKind = u32;
+
+pub const CHUNK_BIN: ChunkKind = 0;
+pub const CHUNK_ARROW_IPC: ChunkKind = 1;
+
+/// bytes, borrowed for the duration of the call
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Slice {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl Slice {
+    pub fn new(bytes: &[u8]) -> Self {
+        Self {
+            ptr: bytes.as_ptr(),
+            len: bytes.len(),
+        }
+    }
+
+    /// # Safety
+    /// `ptr` should be valid for reads of `len` bytes for the lifetime `'a`
+    pub unsafe fn as_bytes<'a>(&self) -> &'a [u8] {
+        match self.ptr.is_null() {
+            true => &[],
+            false => std::slice::from_raw_parts(self.ptr, self.len),
+        }
+    }
+}
+
+/// bytes, owned by the side which allocated them
+#[repr(C)]
+#[derive(Debug)]
+pub struct Buffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+    pub cap: usize,
+}
+
+impl Buffer {
+    pub fn empty() -> Self {
+        Self {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        }
+    }
+
+    pub fn from_vec(vec: Vec<u8>) -> Self {
+        let mut vec = std::mem::ManuallyDrop::new(vec);
+        Self {
+            ptr: vec.as_mut_ptr(),
+            len: vec.len(),
+            cap: vec.capacity(),
+        }
+    }
+
+    /// # Safety
+    /// buffer should be created with [`Buffer::from_vec`] by the same library
+    pub unsafe fn into_vec(self) -> Vec<u8> {
+        match self.ptr.is_null() {
+            true => vec![],
+            false => Vec::from_raw_parts(self.ptr, self.len, self.cap),
+        }
+    }
+
+    /// # Safety
+    /// buffer should point to valid allocation, which is not released yet
+    pub unsafe fn as_bytes(&self) -> &[u8] {
+        match self.ptr.is_null() {
+            true => &[],
+            false => std::slice::from_raw_parts(self.ptr, self.len),
+        }
+    }
+}
+
+/// function table, exported by plugin
+///
+/// Instance pointer is opaque to the daemon, it's returned by `start` and released by `stop`.
+/// Calls on single instance are never made concurrently.
+#[repr(C)]
+pub struct PluginApi {
+    pub abi_version: u32,
+
+    /// JSON encoded list of section descriptors
+    pub manifest: unsafe extern "C" fn() -> Buffer,
+
+    /// start section by name with JSON encoded config
+    ///
+    /// on success `instance` is set, on error `error` contains message
+    pub start: unsafe extern "C" fn(
+        name: Slice,
+        config: Slice,
+        instance: *mut *mut c_void,
+        error: *mut Buffer,
+    ) -> Status,
+
+    /// pass input chunk to section
+    pub push: unsafe extern "C" fn(
+        instance: *mut c_void,
+        kind: ChunkKind,
+        data: Slice,
+        error: *mut Buffer,
+    ) -> Status,
+
+    /// poll output chunk
+    ///
+    /// on `STATUS_OK` `kind` and `data` are set, on `STATUS_ERROR` `data` contains message
+    pub pull: unsafe extern "C" fn(
+        instance: *mut c_void,
+        kind: *mut ChunkKind,
+        data: *mut Buffer,
+    ) -> Status,
+
+    /// stop section and release instance
+    pub stop: unsafe extern "C" fn(instance: *mut c_void),
+
+    /// release buffer, returned by plugin
+    pub free_buffer: unsafe extern "C" fn(buffer: Buffer),
+}
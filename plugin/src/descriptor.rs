@@ -0,0 +1,225 @@
+//! Section descriptors
+//!
+//! Plugins describe their sections with JSON manifest, which is enough to render config form,
+//! validate field values and store configs without plugin library being present.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionIo {
+    #[default]
+    None,
+    Bin,
+    DataFrame,
+    BinOrDataFrame,
+}
+
+impl From<SectionIo> for config::SectionIO {
+    fn from(value: SectionIo) -> Self {
+        match value {
+            SectionIo::None => config::SectionIO::None,
+            SectionIo::Bin => config::SectionIO::Bin,
+            SectionIo::DataFrame => config::SectionIO::DataFrame,
+            SectionIo::BinOrDataFrame => config::SectionIO::BinOrDataFrame,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionDescriptor {
+    pub name: String,
+    #[serde(default)]
+    pub input: SectionIo,
+    #[serde(default)]
+    pub output: SectionIo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub fields: Vec<FieldDescriptor>,
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+impl SectionDescriptor {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            input: SectionIo::None,
+            output: SectionIo::None,
+            description: None,
+            version: default_version(),
+            fields: vec![],
+        }
+    }
+
+    pub fn input(self, input: SectionIo) -> Self {
+        Self { input, ..self }
+    }
+
+    pub fn output(self, output: SectionIo) -> Self {
+        Self { output, ..self }
+    }
+
+    pub fn description(self, description: impl Into<String>) -> Self {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    pub fn version(self, version: u32) -> Self {
+        Self { version, ..self }
+    }
+
+    pub fn field(mut self, field: FieldDescriptor) -> Self {
+        self.fields.push(field);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldTypeDescriptor {
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F64,
+    String,
+    Bool,
+    Duration,
+    StringList,
+    Enum(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDescriptor {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: FieldTypeDescriptor,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub optional: bool,
+    /// default value, type default is used if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_password: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_text_area: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_read_only: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_advanced: bool,
+    /// string and string list values can reference control plane variables
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_template: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl FieldDescriptor {
+    pub fn new(name: impl Into<String>, ty: FieldTypeDescriptor) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+            optional: false,
+            default: None,
+            description: None,
+            placeholder: None,
+            group: None,
+            is_password: false,
+            is_text_area: false,
+            is_read_only: false,
+            is_advanced: false,
+            is_template: false,
+        }
+    }
+
+    pub fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+
+    pub fn default(self, default: impl Into<serde_json::Value>) -> Self {
+        Self {
+            default: Some(default.into()),
+            ..self
+        }
+    }
+
+    pub fn description(self, description: impl Into<String>) -> Self {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    pub fn placeholder(self, placeholder: impl Into<String>) -> Self {
+        Self {
+            placeholder: Some(placeholder.into()),
+            ..self
+        }
+    }
+
+    pub fn group(self, group: impl Into<String>) -> Self {
+        Self {
+            group: Some(group.into()),
+            ..self
+        }
+    }
+
+    pub fn password(self) -> Self {
+        Self {
+            is_password: true,
+            ..self
+        }
+    }
+
+    pub fn text_area(self) -> Self {
+        Self {
+            is_text_area: true,
+            ..self
+        }
+    }
+
+    pub fn read_only(self) -> Self {
+        Self {
+            is_read_only: true,
+            ..self
+        }
+    }
+
+    pub fn advanced(self) -> Self {
+        Self {
+            is_advanced: true,
+            ..self
+        }
+    }
+
+    pub fn template(self) -> Self {
+        Self {
+            is_template: true,
+            ..self
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Plugin side of the ABI
+//!
+//! Plugin library implements [`Plugin`] and exports it with [`export_plugin!`](crate::export_plugin):
+//!
+//! ```ignore
+//! #[derive(Default)]
+//! struct MyPlugin;
+//!
+//! impl plugin::Plugin for MyPlugin {
+//!     fn sections(&self) -> Vec<plugin::SectionDescriptor> { ... }
+//!     fn start(&self, name: &str, config: serde_json::Value) -> Result<Box<dyn plugin::PluginSection>, plugin::Error> { ... }
+//! }
+//!
+//! plugin::export_plugin!(MyPlugin);
+//! ```
+
+use std::{
+    ffi::c_void,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use crate::{
+    abi::{
+        Buffer, ChunkKind, Slice, Status, CHUNK_ARROW_IPC, CHUNK_BIN, STATUS_DONE, STATUS_ERROR,
+        STATUS_OK, STATUS_PENDING,
+    },
+    descriptor::SectionDescriptor,
+    Error,
+};
+
+/// chunk of data, exchanged between daemon and plugin section
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chunk {
+    Bin(Vec<u8>),
+    /// dataframe, encoded as Arrow IPC stream
+    ArrowIpc(Vec<u8>),
+}
+
+impl Chunk {
+    pub(crate) fn from_raw(kind: ChunkKind, data: Vec<u8>) -> Result<Self, Error> {
+        match kind {
+            CHUNK_BIN => Ok(Chunk::Bin(data)),
+            CHUNK_ARROW_IPC => Ok(Chunk::ArrowIpc(data)),
+            kind => Err(format!("unknown chunk kind: {kind}"))?,
+        }
+    }
+
+    pub(crate) fn into_raw(self) -> (ChunkKind, Vec<u8>) {
+        match self {
+            Chunk::Bin(data) => (CHUNK_BIN, data),
+            Chunk::ArrowIpc(data) => (CHUNK_ARROW_IPC, data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Poll {
+    Ready(Chunk),
+    /// nothing to output yet, daemon will poll again later
+    Pending,
+    /// section finished
+    Done,
+}
+
+/// running plugin section
+///
+/// Calls are blocking and made from daemon worker threads, never concurrently.
+pub trait PluginSection: Send + 'static {
+    /// process input chunk, called only for sections with input
+    fn push(&mut self, chunk: Chunk) -> Result<(), Error>;
+
+    /// poll next output chunk
+    fn pull(&mut self) -> Result<Poll, Error>;
+}
+
+pub trait Plugin: Send + Sync + 'static {
+    /// descriptors of every section, provided by plugin
+    fn sections(&self) -> Vec<SectionDescriptor>;
+
+    /// start section by name, config is JSON object with field values keyed by field name
+    fn start(&self, name: &str, config: serde_json::Value)
+        -> Result<Box<dyn PluginSection>, Error>;
+}
+
+/// Export plugin from library, plugin type should implement [`Plugin`] and [`Default`]
+///
+/// Library should be built with `crate-type = ["cdylib"]`.
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:ty) => {
+        #[no_mangle]
+        pub extern "C" fn mycelial_plugin_api() -> *const $crate::abi::PluginApi {
+            fn plugin() -> &'static dyn $crate::Plugin {
+                static PLUGIN: ::std::sync::OnceLock<$plugin> = ::std::sync::OnceLock::new();
+                PLUGIN.get_or_init(<$plugin as ::std::default::Default>::default)
+            }
+
+            unsafe extern "C" fn manifest() -> $crate::abi::Buffer {
+                $crate::export::manifest(plugin())
+            }
+
+            unsafe extern "C" fn start(
+                name: $crate::abi::Slice,
+                config: $crate::abi::Slice,
+                instance: *mut *mut ::std::ffi::c_void,
+                error: *mut $crate::abi::Buffer,
+            ) -> $crate::abi::Status {
+                $crate::export::start(plugin(), name, config, instance, error)
+            }
+
+            static API: $crate::abi::PluginApi = $crate::abi::PluginApi {
+                abi_version: $crate::abi::ABI_VERSION,
+                manifest,
+                start,
+                push: $crate::export::push,
+                pull: $crate::export::pull,
+                stop: $crate::export::stop,
+                free_buffer: $crate::export::free_buffer,
+            };
+            &API
+        }
+    };
+}
+
+type Instance = Box<dyn PluginSection>;
+
+// panics shouldn't cross ABI boundary
+fn guard<T>(f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(format!("plugin panicked: {message}"))?
+        }
+    }
+}
+
+fn error_buffer(error: Error) -> Buffer {
+    Buffer::from_vec(error.to_string().into_bytes())
+}
+
+#[doc(hidden)]
+pub fn manifest(plugin: &dyn Plugin) -> Buffer {
+    let manifest = guard(|| Ok(serde_json::to_vec(&plugin.sections())?));
+    // empty manifest is rejected by daemon
+    Buffer::from_vec(manifest.unwrap_or_default())
+}
+
+/// # Safety
+/// pointers should be valid, see [`PluginApi::start`](crate::abi::PluginApi::start)
+#[doc(hidden)]
+pub unsafe fn start(
+    plugin: &dyn Plugin,
+    name: Slice,
+    config: Slice,
+    instance: *mut *mut c_void,
+    error: *mut Buffer,
+) -> Status {
+    let result = guard(|| {
+        let name = std::str::from_utf8(name.as_bytes())?;
+        let config = serde_json::from_slice(config.as_bytes())?;
+        plugin.start(name, config)
+    });
+    match result {
+        Ok(section) => {
+            *instance = Box::into_raw(Box::new(section)) as *mut c_void;
+            STATUS_OK
+        }
+        Err(e) => {
+            *error = error_buffer(e);
+            STATUS_ERROR
+        }
+    }
+}
+
+/// # Safety
+/// instance should be returned by `start` and not stopped yet
+#[doc(hidden)]
+pub unsafe extern "C" fn push(
+    instance: *mut c_void,
+    kind: ChunkKind,
+    data: Slice,
+    error: *mut Buffer,
+) -> Status {
+    let instance = &mut *(instance as *mut Instance);
+    let result = guard(|| instance.push(Chunk::from_raw(kind, data.as_bytes().to_vec())?));
+    match result {
+        Ok(()) => STATUS_OK,
+        Err(e) => {
+            *error = error_buffer(e);
+            STATUS_ERROR
+        }
+    }
+}
+
+/// # Safety
+/// instance should be returned by `start` and not stopped yet
+#[doc(hidden)]
+pub unsafe extern "C" fn pull(
+    instance: *mut c_void,
+    kind: *mut ChunkKind,
+    data: *mut Buffer,
+) -> Status {
+    let instance = &mut *(instance as *mut Instance);
+    match guard(|| instance.pull()) {
+        Ok(Poll::Ready(chunk)) => {
+            let (chunk_kind, chunk_data) = chunk.into_raw();
+            *kind = chunk_kind;
+            *data = Buffer::from_vec(chunk_data);
+            STATUS_OK
+        }
+        Ok(Poll::Pending) => STATUS_PENDING,
+        Ok(Poll::Done) => STATUS_DONE,
+        Err(e) => {
+            *data = error_buffer(e);
+            STATUS_ERROR
+        }
+    }
+}
+
+/// # Safety
+/// instance should be returned by `start` and not stopped yet
+#[doc(hidden)]
+pub unsafe extern "C" fn stop(instance: *mut c_void) {
+    let instance = Box::from_raw(instance as *mut Instance);
+    guard(|| {
+        drop(instance);
+        Ok(())
+    })
+    .ok();
+}
+
+/// # Safety
+/// buffer should be allocated by this library
+#[doc(hidden)]
+pub unsafe extern "C" fn free_buffer(buffer: Buffer) {
+    drop(buffer.into_vec())
+}
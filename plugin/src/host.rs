@@ -0,0 +1,363 @@
+//! Daemon side of the ABI
+//!
+//! Plugin libraries are loaded once on daemon start and never unloaded,
+//! configs and running sections keep reference to the library they came from.
+
+use std::{
+    ffi::c_void,
+    path::{Path, PathBuf},
+    pin::pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use arrow_msg::{df_to_ipc, ipc_to_recordbatches, ArrowMsg};
+use config::Config as _;
+use section::{
+    message::{Ack, Next},
+    prelude::*,
+};
+
+use crate::{
+    abi::{
+        Buffer, ChunkKind, EntryFn, PluginApi, Slice, ABI_VERSION, ENTRY_SYMBOL, STATUS_DONE,
+        STATUS_ERROR, STATUS_OK, STATUS_PENDING,
+    },
+    descriptor::SectionDescriptor,
+    export::{Chunk as PluginChunk, Poll},
+    plugin_config::PluginConfig,
+    Error,
+};
+
+/// interval between polls of section, which has nothing to output
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct LoadedPlugin {
+    api: &'static PluginApi,
+    path: PathBuf,
+    sections: Vec<SectionDescriptor>,
+    _library: Option<libloading::Library>,
+}
+
+impl std::fmt::Debug for LoadedPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadedPlugin")
+            .field("path", &self.path)
+            .field(
+                "sections",
+                &self
+                    .sections
+                    .iter()
+                    .map(|section| section.name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// paths of plugin libraries in directory, sorted by name
+pub fn plugin_paths(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|path| {
+            path.is_file()
+                && path.extension().and_then(|ext| ext.to_str())
+                    == Some(std::env::consts::DLL_EXTENSION)
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+impl LoadedPlugin {
+    /// load plugin from shared library
+    ///
+    /// # Safety
+    /// library initialization code is executed, library should be built against this ABI
+    pub unsafe fn load(path: &Path) -> Result<Arc<Self>, Error> {
+        let library = libloading::Library::new(path)?;
+        let entry = library.get::<EntryFn>(ENTRY_SYMBOL.as_bytes())?;
+        let api = entry();
+        Self::init(api, path.into(), Some(library))
+    }
+
+    /// plugin, linked into current binary
+    ///
+    /// # Safety
+    /// pointer should be returned by `mycelial_plugin_api` function
+    pub unsafe fn from_api(api: *const PluginApi) -> Result<Arc<Self>, Error> {
+        Self::init(api, PathBuf::new(), None)
+    }
+
+    unsafe fn init(
+        api: *const PluginApi,
+        path: PathBuf,
+        library: Option<libloading::Library>,
+    ) -> Result<Arc<Self>, Error> {
+        let api = match api.as_ref() {
+            Some(api) => api,
+            None => Err("plugin returned null api pointer")?,
+        };
+        if api.abi_version != ABI_VERSION {
+            Err(format!(
+                "unsupported plugin ABI version {}, expected {ABI_VERSION}",
+                api.abi_version
+            ))?
+        }
+        let manifest = take_buffer(api, (api.manifest)());
+        let sections: Vec<SectionDescriptor> = serde_json::from_slice(&manifest)
+            .map_err(|e| format!("failed to parse plugin manifest: {e}"))?;
+        Ok(Arc::new(Self {
+            api,
+            path,
+            sections,
+            _library: library,
+        }))
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub fn sections(&self) -> &[SectionDescriptor] {
+        self.sections.as_slice()
+    }
+
+    /// configs of every plugin section, which can be started
+    pub fn configs(self: &Arc<Self>) -> Result<Vec<PluginConfig>, Error> {
+        self.sections
+            .iter()
+            .map(|descriptor| {
+                let mut config = PluginConfig::new(descriptor)?;
+                config.plugin = Some(Arc::clone(self));
+                Ok(config)
+            })
+            .collect()
+    }
+
+    pub fn start(self: &Arc<Self>, config: &PluginConfig) -> Result<Instance, Error> {
+        let name = config.name().as_bytes();
+        let config = serde_json::to_vec(&config.to_json())?;
+        let mut instance = std::ptr::null_mut();
+        let mut error = Buffer::empty();
+        let status = unsafe {
+            (self.api.start)(
+                Slice::new(name),
+                Slice::new(&config),
+                &mut instance,
+                &mut error,
+            )
+        };
+        match status {
+            STATUS_OK if !instance.is_null() => Ok(Instance {
+                plugin: Arc::clone(self),
+                ptr: instance,
+            }),
+            STATUS_OK => Err("plugin returned null instance")?,
+            _ => Err(self.error(error))?,
+        }
+    }
+
+    fn error(&self, buffer: Buffer) -> String {
+        let message = unsafe { take_buffer(self.api, buffer) };
+        String::from_utf8_lossy(&message).into_owned()
+    }
+}
+
+// copy buffer, allocated by plugin, and release it
+unsafe fn take_buffer(api: &PluginApi, buffer: Buffer) -> Vec<u8> {
+    let bytes = buffer.as_bytes().to_vec();
+    if !buffer.ptr.is_null() {
+        (api.free_buffer)(buffer);
+    }
+    bytes
+}
+
+/// running plugin section
+pub struct Instance {
+    plugin: Arc<LoadedPlugin>,
+    ptr: *mut c_void,
+}
+
+// instance is used by one thread at a time, plugins are required to support that
+unsafe impl Send for Instance {}
+
+impl std::fmt::Debug for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Instance")
+            .field("plugin", &self.plugin)
+            .finish()
+    }
+}
+
+impl Instance {
+    pub fn push(&mut self, chunk: PluginChunk) -> Result<(), Error> {
+        let (kind, data) = chunk.into_raw();
+        let mut error = Buffer::empty();
+        let status =
+            unsafe { (self.plugin.api.push)(self.ptr, kind, Slice::new(&data), &mut error) };
+        match status {
+            STATUS_OK => Ok(()),
+            _ => Err(self.plugin.error(error))?,
+        }
+    }
+
+    pub fn pull(&mut self) -> Result<Poll, Error> {
+        let mut kind: ChunkKind = 0;
+        let mut data = Buffer::empty();
+        let status = unsafe { (self.plugin.api.pull)(self.ptr, &mut kind, &mut data) };
+        match status {
+            STATUS_OK => {
+                let data = unsafe { take_buffer(self.plugin.api, data) };
+                Ok(Poll::Ready(PluginChunk::from_raw(kind, data)?))
+            }
+            STATUS_PENDING => Ok(Poll::Pending),
+            STATUS_DONE => Ok(Poll::Done),
+            STATUS_ERROR => Err(self.plugin.error(data))?,
+            status => Err(format!("unexpected status: {status}"))?,
+        }
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        unsafe { (self.plugin.api.stop)(self.ptr) }
+    }
+}
+
+// plugin calls are blocking, so they are moved off async runtime
+async fn blocking<T: Send + 'static>(
+    mut instance: Instance,
+    f: impl FnOnce(&mut Instance) -> Result<T, Error> + Send + 'static,
+) -> Result<(Instance, T), Error> {
+    tokio::task::spawn_blocking(move || f(&mut instance).map(|result| (instance, result))).await?
+}
+
+struct PluginMsg {
+    origin: Arc<str>,
+    chunk: Option<Chunk>,
+    ack: Option<Ack>,
+}
+
+impl std::fmt::Debug for PluginMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginMsg")
+            .field("origin", &self.origin)
+            .field("chunk", &self.chunk)
+            .finish()
+    }
+}
+
+impl Message for PluginMsg {
+    fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        let chunk = self.chunk.take();
+        Box::pin(async move { Ok(chunk) })
+    }
+
+    fn ack(&mut self) -> Ack {
+        self.ack.take().unwrap_or(Box::pin(async {}))
+    }
+}
+
+fn into_message(origin: &Arc<str>, chunk: PluginChunk, ack: Ack) -> Result<SectionMessage, Error> {
+    let message: SectionMessage = match chunk {
+        PluginChunk::Bin(data) => Box::new(PluginMsg {
+            origin: Arc::clone(origin),
+            chunk: Some(Chunk::Byte(data)),
+            ack: Some(ack),
+        }),
+        PluginChunk::ArrowIpc(data) => Box::new(ArrowMsg::new(
+            origin.as_ref(),
+            ipc_to_recordbatches(&data)?.into_iter().map(Some).collect(),
+            Some(ack),
+        )),
+    };
+    Ok(message)
+}
+
+// single ack, which acks all given acks
+fn join_acks(acks: Vec<Ack>) -> Ack {
+    Box::pin(async move {
+        for ack in acks {
+            ack.await;
+        }
+    })
+}
+
+impl<SectionChan: SectionChannel> Section<DynStream, DynSink, SectionChan> for PluginConfig {
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(
+        self,
+        input: DynStream,
+        output: DynSink,
+        mut section_chan: SectionChan,
+    ) -> Self::Future {
+        Box::pin(async move {
+            let plugin = match self.plugin.as_ref() {
+                Some(plugin) => Arc::clone(plugin),
+                None => Err(format!("plugin of section '{}' is not loaded", self.name()))?,
+            };
+            let origin: Arc<str> = Arc::from(self.name());
+            // sections without input are only polled for output
+            let input: DynStream = match self.input().is_none() {
+                true => Box::pin(futures::stream::pending()),
+                false => input,
+            };
+            // plugin output isn't correlated with input, so acks of consumed input messages are
+            // attached to the next output message, input of sections without output is acked once pushed
+            let has_output = !self.output().is_none();
+            let mut pending_acks = vec![];
+            let mut instance = tokio::task::spawn_blocking(move || plugin.start(&self)).await??;
+            let mut input = pin!(input.fuse());
+            let mut output = pin!(output);
+            loop {
+                // drain ready output
+                loop {
+                    let (returned, poll) = blocking(instance, Instance::pull).await?;
+                    instance = returned;
+                    match poll {
+                        Poll::Ready(chunk) => {
+                            let ack = join_acks(std::mem::take(&mut pending_acks));
+                            output.send(into_message(&origin, chunk, ack)?).await?
+                        }
+                        Poll::Pending => break,
+                        Poll::Done => return Ok(()),
+                    }
+                }
+                futures::select! {
+                    cmd = section_chan.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next() => {
+                        let mut msg = match msg {
+                            None => Err("input closed")?,
+                            Some(msg) => msg,
+                        };
+                        while let Some(chunk) = msg.next().await? {
+                            let chunk = match chunk {
+                                Chunk::Byte(data) => PluginChunk::Bin(data),
+                                Chunk::DataFrame(df) => PluginChunk::ArrowIpc(df_to_ipc(&*df)?),
+                            };
+                            instance = blocking(instance, move |instance| instance.push(chunk)).await?.0;
+                        }
+                        match has_output {
+                            true => pending_acks.push(msg.ack()),
+                            false => msg.ack().await,
+                        }
+                    },
+                    _ = tokio::time::sleep(POLL_INTERVAL).fuse() => {},
+                }
+            }
+        })
+    }
+}
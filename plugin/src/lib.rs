@@ -0,0 +1,19 @@
+//! Out-of-tree sections
+//!
+//! Plugin is a shared library, which implements sections behind stable C ABI (see [`abi`]).
+//! Plugin authors use [`export`] module and [`export_plugin!`] macro,
+//! daemon loads plugins and runs their sections with `host` feature enabled.
+
+pub mod abi;
+mod descriptor;
+pub mod export;
+mod plugin_config;
+
+#[cfg(feature = "host")]
+pub mod host;
+
+pub use descriptor::{FieldDescriptor, FieldTypeDescriptor, SectionDescriptor, SectionIo};
+pub use export::{Chunk, Plugin, PluginSection, Poll};
+pub use plugin_config::PluginConfig;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
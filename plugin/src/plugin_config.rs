@@ -0,0 +1,418 @@
+//! Config of plugin section, built from section descriptor
+
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use config::{Config, Field, FieldType, FieldValue, Metadata, SectionIO};
+
+use crate::{
+    descriptor::{FieldDescriptor, FieldTypeDescriptor, SectionDescriptor},
+    Error,
+};
+
+// field types and metadata of configs have static lifetime, while descriptors are loaded at runtime,
+// so strings are interned and never released
+fn intern(value: &str) -> &'static str {
+    static STRINGS: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let mut strings = STRINGS.lock().unwrap();
+    match strings.get(value) {
+        Some(value) => value,
+        None => {
+            let value: &'static str = Box::leak(value.to_owned().into_boxed_str());
+            strings.insert(value);
+            value
+        }
+    }
+}
+
+fn intern_variants(variants: &[String]) -> &'static [&'static str] {
+    static VARIANTS: Mutex<BTreeSet<&'static [&'static str]>> = Mutex::new(BTreeSet::new());
+    let variants = variants
+        .iter()
+        .map(|variant| intern(variant))
+        .collect::<Vec<_>>();
+    let mut interned = VARIANTS.lock().unwrap();
+    match interned.get(variants.as_slice()) {
+        Some(variants) => variants,
+        None => {
+            let variants: &'static [&'static str] = Box::leak(variants.into_boxed_slice());
+            interned.insert(variants);
+            variants
+        }
+    }
+}
+
+fn intern_type(ty: FieldType) -> &'static FieldType {
+    static TYPES: Mutex<Vec<&'static FieldType>> = Mutex::new(Vec::new());
+    let mut types = TYPES.lock().unwrap();
+    match types.iter().find(|interned| ***interned == ty) {
+        Some(ty) => ty,
+        None => {
+            let ty: &'static FieldType = Box::leak(Box::new(ty));
+            types.push(ty);
+            ty
+        }
+    }
+}
+
+impl FieldTypeDescriptor {
+    fn field_type(&self) -> Result<FieldType, Error> {
+        let ty = match self {
+            Self::Usize => FieldType::Usize,
+            Self::I8 => FieldType::I8,
+            Self::I16 => FieldType::I16,
+            Self::I32 => FieldType::I32,
+            Self::I64 => FieldType::I64,
+            Self::U8 => FieldType::U8,
+            Self::U16 => FieldType::U16,
+            Self::U32 => FieldType::U32,
+            Self::U64 => FieldType::U64,
+            Self::F64 => FieldType::F64,
+            Self::String => FieldType::String,
+            Self::Bool => FieldType::Bool,
+            Self::Duration => FieldType::Duration,
+            Self::StringList => FieldType::StringList,
+            Self::Enum(variants) if variants.is_empty() => Err("enum without variants")?,
+            Self::Enum(variants) => FieldType::Enum(intern_variants(variants)),
+        };
+        Ok(ty)
+    }
+}
+
+#[derive(Debug)]
+struct FieldSchema {
+    name: String,
+    ty: FieldType,
+    description: Option<&'static str>,
+    placeholder: Option<&'static str>,
+    group: Option<&'static str>,
+    is_password: bool,
+    is_text_area: bool,
+    is_read_only: bool,
+    is_advanced: bool,
+    is_template: bool,
+}
+
+impl FieldSchema {
+    fn new(field: &FieldDescriptor) -> Result<Self, Error> {
+        let ty = match field.ty.field_type()? {
+            ty if field.optional => FieldType::Option(intern_type(ty)),
+            ty => ty,
+        };
+        if field.is_template && !matches!(ty.inner(), FieldType::String | FieldType::StringList) {
+            Err(format!(
+                "field '{}': only string and string list fields can be templates",
+                field.name
+            ))?
+        }
+        Ok(Self {
+            name: field.name.clone(),
+            ty,
+            description: field.description.as_deref().map(intern),
+            placeholder: field.placeholder.as_deref().map(intern),
+            group: field.group.as_deref().map(intern),
+            is_password: field.is_password,
+            is_text_area: field.is_text_area,
+            is_read_only: field.is_read_only,
+            is_advanced: field.is_advanced,
+            is_template: field.is_template,
+        })
+    }
+
+    fn metadata(&self) -> Metadata {
+        Metadata {
+            is_password: self.is_password,
+            is_text_area: self.is_text_area,
+            is_read_only: self.is_read_only,
+            description: self.description,
+            placeholder: self.placeholder,
+            group: self.group,
+            is_advanced: self.is_advanced,
+            is_template: self.is_template,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Schema {
+    descriptor: SectionDescriptor,
+    description: Option<&'static str>,
+    fields: Vec<FieldSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Usize(usize),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F64(f64),
+    String(String),
+    Bool(bool),
+    Duration(Duration),
+    StringList(Vec<String>),
+    Null,
+}
+
+impl Value {
+    fn default_for(ty: FieldType) -> Self {
+        match ty {
+            FieldType::Usize => Self::Usize(0),
+            FieldType::I8 => Self::I8(0),
+            FieldType::I16 => Self::I16(0),
+            FieldType::I32 => Self::I32(0),
+            FieldType::I64 => Self::I64(0),
+            FieldType::U8 => Self::U8(0),
+            FieldType::U16 => Self::U16(0),
+            FieldType::U32 => Self::U32(0),
+            FieldType::U64 => Self::U64(0),
+            FieldType::F64 => Self::F64(0.0),
+            FieldType::String => Self::String(String::new()),
+            FieldType::Bool => Self::Bool(false),
+            FieldType::Duration => Self::Duration(Duration::ZERO),
+            FieldType::StringList => Self::StringList(vec![]),
+            FieldType::Enum(variants) => Self::String(variants[0].into()),
+            FieldType::Option(_) => Self::Null,
+        }
+    }
+
+    // convert value into declared field type
+    fn from_field_value(ty: FieldType, value: FieldValue<'_>) -> Result<Self, Error> {
+        let value = match ty {
+            FieldType::Option(_) if value.is_empty() => Self::Null,
+            FieldType::Option(ty) => Self::from_field_value(*ty, value)?,
+            FieldType::Usize => Self::Usize(value.try_into()?),
+            FieldType::I8 => Self::I8(value.try_into()?),
+            FieldType::I16 => Self::I16(value.try_into()?),
+            FieldType::I32 => Self::I32(value.try_into()?),
+            FieldType::I64 => Self::I64(value.try_into()?),
+            FieldType::U8 => Self::U8(value.try_into()?),
+            FieldType::U16 => Self::U16(value.try_into()?),
+            FieldType::U32 => Self::U32(value.try_into()?),
+            FieldType::U64 => Self::U64(value.try_into()?),
+            FieldType::F64 => Self::F64(value.try_into()?),
+            FieldType::Bool => Self::Bool(value.try_into()?),
+            FieldType::Duration => Self::Duration(value.try_into()?),
+            FieldType::StringList => Self::StringList(value.try_into()?),
+            FieldType::String => Self::String(value.to_string()),
+            FieldType::Enum(variants) => {
+                let value = value.to_string();
+                match variants
+                    .iter()
+                    .find(|variant| variant.eq_ignore_ascii_case(value.trim()))
+                {
+                    Some(variant) => Self::String(variant.to_string()),
+                    None => Err(format!("'{value}' is not one of {variants:?}"))?,
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    fn from_json(ty: FieldType, value: &serde_json::Value) -> Result<Self, Error> {
+        let list;
+        let value = match value {
+            serde_json::Value::Null => FieldValue::Null,
+            serde_json::Value::Bool(value) => FieldValue::Bool(*value),
+            serde_json::Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+                (Some(value), _) => FieldValue::U64(value),
+                (_, Some(value)) => FieldValue::I64(value),
+                _ => FieldValue::F64(number.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(value) => FieldValue::String(value),
+            serde_json::Value::Array(items) => {
+                list = items
+                    .iter()
+                    .map(|item| match item {
+                        serde_json::Value::String(item) => Ok(item.clone()),
+                        _ => Err("list items should be strings"),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                FieldValue::StringList(&list)
+            }
+            serde_json::Value::Object(_) => Err("objects are not supported as field values")?,
+        };
+        Self::from_field_value(ty, value)
+    }
+
+    fn as_field_value(&self) -> FieldValue<'_> {
+        match self {
+            Self::Usize(v) => FieldValue::Usize(*v),
+            Self::I8(v) => FieldValue::I8(*v),
+            Self::I16(v) => FieldValue::I16(*v),
+            Self::I32(v) => FieldValue::I32(*v),
+            Self::I64(v) => FieldValue::I64(*v),
+            Self::U8(v) => FieldValue::U8(*v),
+            Self::U16(v) => FieldValue::U16(*v),
+            Self::U32(v) => FieldValue::U32(*v),
+            Self::U64(v) => FieldValue::U64(*v),
+            Self::F64(v) => FieldValue::F64(*v),
+            Self::String(v) => FieldValue::String(v),
+            Self::Bool(v) => FieldValue::Bool(*v),
+            Self::Duration(v) => FieldValue::Duration(*v),
+            Self::StringList(v) => FieldValue::StringList(v),
+            Self::Null => FieldValue::Null,
+        }
+    }
+
+    // durations are passed to plugins as number of seconds
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Usize(v) => (*v).into(),
+            Self::I8(v) => (*v).into(),
+            Self::I16(v) => (*v).into(),
+            Self::I32(v) => (*v).into(),
+            Self::I64(v) => (*v).into(),
+            Self::U8(v) => (*v).into(),
+            Self::U16(v) => (*v).into(),
+            Self::U32(v) => (*v).into(),
+            Self::U64(v) => (*v).into(),
+            Self::F64(v) => (*v).into(),
+            Self::String(v) => v.as_str().into(),
+            Self::Bool(v) => (*v).into(),
+            Self::Duration(v) => v.as_secs_f64().into(),
+            Self::StringList(v) => v.as_slice().into(),
+            Self::Null => serde_json::Value::Null,
+        }
+    }
+}
+
+/// Config of section, provided by plugin
+///
+/// Field types and metadata are taken from section descriptor,
+/// so config can be rendered, validated and stored without plugin library.
+#[derive(Debug, Clone)]
+pub struct PluginConfig {
+    schema: Arc<Schema>,
+    values: Vec<Value>,
+    #[cfg(feature = "host")]
+    pub(crate) plugin: Option<Arc<crate::host::LoadedPlugin>>,
+}
+
+impl PluginConfig {
+    /// build config with default values from descriptor
+    pub fn new(descriptor: &SectionDescriptor) -> Result<Self, Error> {
+        if descriptor.name.is_empty() {
+            Err("section name is empty")?
+        }
+        let mut fields: Vec<FieldSchema> = Vec::with_capacity(descriptor.fields.len());
+        let mut values = Vec::with_capacity(descriptor.fields.len());
+        for field in descriptor.fields.iter() {
+            let with_name = |e: Error| format!("{}: field '{}': {e}", descriptor.name, field.name);
+            if fields.iter().any(|schema| schema.name == field.name) {
+                Err(with_name("duplicate field".into()))?
+            }
+            let schema = FieldSchema::new(field).map_err(with_name)?;
+            let value = match &field.default {
+                Some(default) => Value::from_json(schema.ty, default).map_err(with_name)?,
+                None => Value::default_for(schema.ty),
+            };
+            fields.push(schema);
+            values.push(value);
+        }
+        Ok(Self {
+            schema: Arc::new(Schema {
+                descriptor: descriptor.clone(),
+                description: descriptor.description.as_deref().map(intern),
+                fields,
+            }),
+            values,
+            #[cfg(feature = "host")]
+            plugin: None,
+        })
+    }
+
+    pub fn descriptor(&self) -> &SectionDescriptor {
+        &self.schema.descriptor
+    }
+
+    /// field values as JSON object, keyed by field name, plugin receives it on section start
+    pub fn to_json(&self) -> serde_json::Value {
+        self.schema
+            .fields
+            .iter()
+            .zip(self.values.iter())
+            .map(|(schema, value)| (schema.name.clone(), value.to_json()))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    fn position(&self, name: &str) -> Result<usize, Error> {
+        match self
+            .schema
+            .fields
+            .iter()
+            .position(|schema| schema.name == name)
+        {
+            Some(pos) => Ok(pos),
+            None => Err(format!("unmatched field name '{name}'"))?,
+        }
+    }
+}
+
+impl Config for PluginConfig {
+    fn name(&self) -> &str {
+        self.schema.descriptor.name.as_str()
+    }
+
+    fn input(&self) -> SectionIO {
+        self.schema.descriptor.input.into()
+    }
+
+    fn output(&self) -> SectionIO {
+        self.schema.descriptor.output.into()
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        self.schema.description
+    }
+
+    fn version(&self) -> u32 {
+        self.schema.descriptor.version
+    }
+
+    fn fields(&self) -> Vec<Field<'_>> {
+        self.schema
+            .fields
+            .iter()
+            .zip(self.values.iter())
+            .map(|(schema, value)| Field {
+                name: schema.name.as_str(),
+                ty: schema.ty,
+                metadata: schema.metadata(),
+                value: value.as_field_value(),
+            })
+            .collect()
+    }
+
+    fn get_field_value(&self, name: &str) -> Result<FieldValue<'_>, Error> {
+        let pos = self.position(name)?;
+        Ok(self.values[pos].as_field_value())
+    }
+
+    fn set_field_value(&mut self, name: &str, value: FieldValue<'_>) -> Result<(), Error> {
+        let pos = self.position(name)?;
+        self.values[pos] = Value::from_field_value(self.schema.fields[pos].ty, value)?;
+        Ok(())
+    }
+
+    fn strip_secrets(&mut self) {
+        for (schema, value) in self.schema.fields.iter().zip(self.values.iter_mut()) {
+            if schema.is_password {
+                *value = Value::default_for(schema.ty);
+            }
+        }
+    }
+
+    fn clone_config(&self) -> Box<dyn Config> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,175 @@
+// Run example plugin, linked into test binary, through the ABI
+
+#[path = "../examples/passthrough.rs"]
+mod passthrough;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use arrow_msg::arrow::{
+    array::{ArrayRef, Int64Array},
+    datatypes::{DataType as ArrowDataType, Field, Schema},
+    record_batch::RecordBatch as ArrowRecordBatch,
+};
+use arrow_msg::RecordBatch;
+use config::{Config, FieldValue};
+use plugin::{host::LoadedPlugin, Chunk as PluginChunk, Poll};
+use section::futures::{SinkExt, StreamExt};
+use section::message::{Ack, Chunk, Message, Next, ValueView};
+use section::section::Section as _;
+use section::{dummy::*, SectionMessage};
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::PollSender;
+
+type StdError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+fn channel<T>(buf_size: usize) -> (PollSender<T>, ReceiverStream<T>)
+where
+    T: Send + 'static,
+{
+    let (tx, rx): (Sender<T>, Receiver<T>) = tokio::sync::mpsc::channel(buf_size);
+    (PollSender::new(tx), ReceiverStream::new(rx))
+}
+
+#[derive(Debug)]
+struct TestMsg {
+    chunks: Vec<Chunk>,
+    acked: Arc<AtomicBool>,
+}
+
+impl TestMsg {
+    fn new(chunks: Vec<Chunk>, acked: &Arc<AtomicBool>) -> Self {
+        Self {
+            chunks: chunks.into_iter().rev().collect(),
+            acked: Arc::clone(acked),
+        }
+    }
+}
+
+impl Message for TestMsg {
+    fn origin(&self) -> &str {
+        "test"
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        let chunk = self.chunks.pop();
+        Box::pin(async move { Ok(chunk) })
+    }
+
+    fn ack(&mut self) -> Ack {
+        let acked = Arc::clone(&self.acked);
+        Box::pin(async move { acked.store(true, Ordering::SeqCst) })
+    }
+}
+
+fn load() -> Arc<LoadedPlugin> {
+    unsafe { LoadedPlugin::from_api(passthrough::mycelial_plugin_api()) }.unwrap()
+}
+
+#[test]
+fn test_manifest() {
+    let plugin = load();
+    let configs = plugin.configs().unwrap();
+    assert_eq!(configs.len(), 1);
+    let config = &configs[0];
+    assert_eq!(config.name(), "plugin_passthrough");
+    assert_eq!(config.input(), config::SectionIO::BinOrDataFrame);
+    assert_eq!(config.output(), config::SectionIO::BinOrDataFrame);
+    let fields = config.fields();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].name, "max_chunks");
+    assert_eq!(fields[0].value, FieldValue::U64(0));
+    assert!(fields[0].metadata.is_advanced);
+}
+
+#[test]
+fn test_instance() {
+    let plugin = load();
+    let config = plugin.configs().unwrap().pop().unwrap();
+    let mut instance = plugin.start(&config).unwrap();
+    assert_eq!(instance.pull().unwrap(), Poll::Pending);
+    instance.push(PluginChunk::Bin(b"hello".to_vec())).unwrap();
+    assert_eq!(
+        instance.pull().unwrap(),
+        Poll::Ready(PluginChunk::Bin(b"hello".to_vec()))
+    );
+    assert_eq!(instance.pull().unwrap(), Poll::Pending);
+}
+
+#[test]
+fn test_max_chunks() {
+    let plugin = load();
+    let mut config = plugin.configs().unwrap().pop().unwrap();
+    config
+        .set_field_value("max_chunks", FieldValue::U64(1))
+        .unwrap();
+    let mut instance = plugin.start(&config).unwrap();
+    instance.push(PluginChunk::Bin(vec![1])).unwrap();
+    instance.push(PluginChunk::Bin(vec![2])).unwrap();
+    assert_eq!(
+        instance.pull().unwrap(),
+        Poll::Ready(PluginChunk::Bin(vec![1]))
+    );
+    assert_eq!(instance.pull().unwrap(), Poll::Done);
+}
+
+#[tokio::test]
+async fn test_section() -> Result<(), StdError> {
+    let config = load().configs()?.pop().unwrap();
+    let (input_tx, input_rx) = channel::<SectionMessage>(1);
+    let (output_tx, mut output_rx) = channel::<SectionMessage>(1);
+    let mut input_tx = input_tx.sink_map_err(|_| "chan closed");
+    let output_tx = output_tx.sink_map_err(|_| "chan closed".into());
+    let handle = tokio::spawn(config.start(
+        Box::pin(input_rx),
+        Box::pin(output_tx),
+        DummySectionChannel::new(),
+    ));
+
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "id",
+        ArrowDataType::Int64,
+        true,
+    )]));
+    let ids: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3]));
+    let rb = ArrowRecordBatch::try_new(schema, vec![ids])?;
+    let chunks = vec![
+        Chunk::Byte(b"bin".to_vec()),
+        Chunk::DataFrame(Box::new(RecordBatch::new(rb))),
+    ];
+    let acked = Arc::new(AtomicBool::new(false));
+    input_tx
+        .send(Box::new(TestMsg::new(chunks, &acked)))
+        .await?;
+
+    let mut msg = output_rx.next().await.ok_or("output closed")?;
+    assert_eq!(msg.origin(), "plugin_passthrough");
+    match msg.next().await? {
+        Some(Chunk::Byte(bin)) => assert_eq!(bin, b"bin"),
+        chunk => panic!("unexpected chunk: {chunk:?}"),
+    }
+    // input is acked through output message
+    assert!(!acked.load(Ordering::SeqCst));
+    msg.ack().await;
+    assert!(acked.load(Ordering::SeqCst));
+
+    let mut msg = output_rx.next().await.ok_or("output closed")?;
+    match msg.next().await? {
+        Some(Chunk::DataFrame(df)) => {
+            let columns = df.columns();
+            assert_eq!(columns.len(), 1);
+            assert_eq!(columns[0].name(), "id");
+            let values = df.columns().pop().unwrap().collect::<Vec<_>>();
+            assert_eq!(
+                values,
+                vec![ValueView::I64(1), ValueView::I64(2), ValueView::I64(3)]
+            );
+        }
+        chunk => panic!("unexpected chunk: {chunk:?}"),
+    }
+    handle.abort();
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Blocking IO adapters for sections, which decode binary messages with synchronous readers
+
+use tokio::sync::mpsc::Receiver;
+
+/// Blocking reader over binary chunks of incoming message
+///
+/// `None` marks end of the message, reader must be used outside of async runtime, e.g. in
+/// `spawn_blocking`.
+pub struct ReceiverReader {
+    rx: Receiver<Option<Vec<u8>>>,
+    buf: Vec<u8>,
+    offset: usize,
+    closed: bool,
+}
+
+impl ReceiverReader {
+    pub fn new(rx: Receiver<Option<Vec<u8>>>) -> Self {
+        Self {
+            rx,
+            buf: vec![],
+            offset: 0,
+            closed: false,
+        }
+    }
+}
+
+impl std::io::Read for ReceiverReader {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        while self.offset == self.buf.len() {
+            if self.closed {
+                return Ok(0);
+            }
+            match self.rx.blocking_recv() {
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "receiver reader error: receiver closed",
+                ))?,
+                Some(None) => self.closed = true,
+                Some(Some(buf)) => {
+                    self.buf = buf;
+                    self.offset = 0;
+                }
+            }
+        }
+        let available = (self.buf.len() - self.offset).min(out.len());
+        out[..available].copy_from_slice(&self.buf[self.offset..self.offset + available]);
+        self.offset += available;
+        Ok(available)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_receiver_reader() {
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        for chunk in [b"hello".to_vec(), vec![], b", world".to_vec()] {
+            tx.try_send(Some(chunk)).unwrap();
+        }
+        tx.try_send(None).unwrap();
+        let mut out = String::new();
+        ReceiverReader::new(rx).read_to_string(&mut out).unwrap();
+        assert_eq!(out, "hello, world");
+    }
+
+    #[test]
+    fn test_receiver_reader_closed() {
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        tx.try_send(Some(b"partial".to_vec())).unwrap();
+        drop(tx);
+        let mut out = vec![];
+        let err = ReceiverReader::new(rx).read_to_end(&mut out).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
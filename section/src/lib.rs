@@ -1,5 +1,6 @@
 pub mod command_channel;
 pub mod dummy;
+pub mod io;
 pub mod message;
 pub mod pretty_print;
 pub mod section;
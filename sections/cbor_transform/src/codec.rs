@@ -0,0 +1,441 @@
+//! Column-wise CBOR encoding of dataframes
+//!
+//! Each dataframe is encoded as a single CBOR item, consecutive dataframes form a CBOR sequence (RFC 8742):
+//!
+//! ```text
+//! frame  = [version, [column, ...]]
+//! column = [name, type, [value, ...]]
+//! ```
+//!
+//! `type` is the `i8` code of column `DataType`, values are stored as native CBOR types:
+//! integers for integer, time, date and timestamp types (time unit is part of type code),
+//! floats, text, byte strings for binary and uuid values.
+//! Decimals are stored as text to preserve exact value and scale.
+//! Raw JSON columns hold JSON documents as string values and are stored as text.
+//! Columns of type `Any` store each value as `[type, value]` pair.
+//! Null is encoded as CBOR null in columns of any type.
+
+use ciborium::Value as Cbor;
+use section::{
+    message::{Column, DataFrame, DataType, Value, ValueView},
+    uuid::Uuid,
+    SectionError,
+};
+
+type Result<T, E = SectionError> = std::result::Result<T, E>;
+
+pub(crate) const VERSION: u8 = 1;
+
+pub(crate) fn encode(df: &dyn DataFrame) -> Result<Vec<u8>> {
+    let columns = df
+        .columns()
+        .into_iter()
+        .map(encode_column)
+        .collect::<Result<Vec<_>>>()?;
+    let frame = Cbor::Array(vec![Cbor::from(VERSION), Cbor::Array(columns)]);
+    let mut buf = vec![];
+    ciborium::into_writer(&frame, &mut buf)?;
+    Ok(buf)
+}
+
+fn encode_column(column: Column<'_>) -> Result<Cbor> {
+    let name = Cbor::Text(column.name().into());
+    let data_type = column.data_type();
+    let values = column
+        .map(|value| match data_type {
+            DataType::Any => encode_any(value),
+            data_type => encode_value(data_type, value),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Cbor::Array(vec![
+        name,
+        Cbor::from(i8::from(data_type)),
+        Cbor::Array(values),
+    ]))
+}
+
+fn encode_any(value: ValueView<'_>) -> Result<Cbor> {
+    match value {
+        ValueView::Null => Ok(Cbor::Null),
+        value => {
+            let data_type = value.data_type();
+            Ok(Cbor::Array(vec![
+                Cbor::from(i8::from(data_type)),
+                encode_value(data_type, value)?,
+            ]))
+        }
+    }
+}
+
+fn encode_value(data_type: DataType, value: ValueView<'_>) -> Result<Cbor> {
+    if value == ValueView::Null {
+        return Ok(Cbor::Null);
+    }
+    // raw json columns carry json documents as strings
+    let matches = match (data_type, value) {
+        (DataType::RawJson, ValueView::Str(_)) => true,
+        (data_type, value) => value.data_type() == data_type,
+    };
+    if !matches {
+        Err(format!(
+            "value of type {} in column of type {data_type}",
+            value.data_type()
+        ))?
+    }
+    let value = match value {
+        ValueView::Bool(v) => Cbor::Bool(v),
+        ValueView::I8(v) => Cbor::from(v),
+        ValueView::I16(v) => Cbor::from(v),
+        ValueView::I32(v) => Cbor::from(v),
+        ValueView::I64(v) => Cbor::from(v),
+        ValueView::U8(v) => Cbor::from(v),
+        ValueView::U16(v) => Cbor::from(v),
+        ValueView::U32(v) => Cbor::from(v),
+        ValueView::U64(v) => Cbor::from(v),
+        ValueView::F32(v) => Cbor::Float(v.into()),
+        ValueView::F64(v) => Cbor::Float(v),
+        ValueView::Str(v) => Cbor::Text(v.into()),
+        ValueView::Bin(v) => Cbor::Bytes(v.into()),
+        ValueView::Time(_, v)
+        | ValueView::Date(_, v)
+        | ValueView::TimeStamp(_, v)
+        | ValueView::TimeStampUTC(_, v) => Cbor::from(v),
+        ValueView::Decimal(v) => Cbor::Text(v.to_string()),
+        ValueView::Uuid(v) => Cbor::Bytes(v.as_bytes().to_vec()),
+        value => Err(format!("unsupported value: {value:?}"))?,
+    };
+    Ok(value)
+}
+
+#[derive(Debug, PartialEq)]
+struct CborColumn {
+    name: String,
+    data_type: DataType,
+    values: Vec<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct CborDataFrame {
+    columns: Vec<CborColumn>,
+}
+
+impl DataFrame for CborDataFrame {
+    fn columns(&self) -> Vec<Column<'_>> {
+        self.columns
+            .iter()
+            .map(|column| {
+                Column::new(
+                    column.name.as_str(),
+                    column.data_type,
+                    Box::new(column.values.iter().map(Into::into)),
+                )
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn decode(frame: Cbor) -> Result<CborDataFrame> {
+    let [version, columns]: [Cbor; 2] = into_array(frame, "frame")?
+        .try_into()
+        .map_err(|_| "malformed frame: expected [version, columns]")?;
+    match version.as_integer().map(i128::from) {
+        Some(version) if version == VERSION as i128 => (),
+        Some(version) => Err(format!("unsupported frame version: {version}"))?,
+        None => Err("malformed frame: version is expected to be integer")?,
+    };
+    let columns = into_array(columns, "columns")?
+        .into_iter()
+        .map(decode_column)
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(first) = columns.first() {
+        if columns.iter().any(|c| c.values.len() != first.values.len()) {
+            Err("malformed frame: columns have different lengths")?
+        }
+    }
+    Ok(CborDataFrame { columns })
+}
+
+fn decode_column(column: Cbor) -> Result<CborColumn> {
+    let [name, data_type, values]: [Cbor; 3] = into_array(column, "column")?
+        .try_into()
+        .map_err(|_| "malformed column: expected [name, type, values]")?;
+    let name = match name {
+        Cbor::Text(name) => name,
+        _ => Err("malformed column: name is expected to be text")?,
+    };
+    let data_type = decode_data_type(data_type)?;
+    let values = into_array(values, "column values")?
+        .into_iter()
+        .map(|value| match data_type {
+            DataType::Any => decode_any(value),
+            data_type => decode_value(data_type, value),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(CborColumn {
+        name,
+        data_type,
+        values,
+    })
+}
+
+fn decode_data_type(code: Cbor) -> Result<DataType> {
+    let code = code
+        .as_integer()
+        .and_then(|code| i8::try_from(code).ok())
+        .ok_or("malformed data type code")?;
+    // `From<i8> for DataType` panics on unknown codes
+    match code {
+        0..=32 | 127 => Ok(DataType::from(code)),
+        code => Err(format!("unknown data type code: {code}"))?,
+    }
+}
+
+fn decode_any(value: Cbor) -> Result<Value> {
+    match value {
+        Cbor::Null => Ok(Value::Null),
+        value => {
+            let [data_type, value]: [Cbor; 2] = into_array(value, "value")?
+                .try_into()
+                .map_err(|_| "malformed value: expected [type, value]")?;
+            decode_value(decode_data_type(data_type)?, value)
+        }
+    }
+}
+
+fn decode_value(data_type: DataType, value: Cbor) -> Result<Value> {
+    let value = match (data_type, value) {
+        (_, Cbor::Null) => Value::Null,
+        (DataType::Bool, Cbor::Bool(v)) => Value::Bool(v),
+        (DataType::I8, Cbor::Integer(v)) => Value::I8(v.try_into()?),
+        (DataType::I16, Cbor::Integer(v)) => Value::I16(v.try_into()?),
+        (DataType::I32, Cbor::Integer(v)) => Value::I32(v.try_into()?),
+        (DataType::I64, Cbor::Integer(v)) => Value::I64(v.try_into()?),
+        (DataType::U8, Cbor::Integer(v)) => Value::U8(v.try_into()?),
+        (DataType::U16, Cbor::Integer(v)) => Value::U16(v.try_into()?),
+        (DataType::U32, Cbor::Integer(v)) => Value::U32(v.try_into()?),
+        (DataType::U64, Cbor::Integer(v)) => Value::U64(v.try_into()?),
+        (DataType::F32, Cbor::Float(v)) => Value::F32(v as f32),
+        (DataType::F64, Cbor::Float(v)) => Value::F64(v),
+        (DataType::Str | DataType::RawJson, Cbor::Text(v)) => Value::Str(v.into()),
+        (DataType::Bin, Cbor::Bytes(v)) => Value::Bin(v.into()),
+        (DataType::Time(unit), Cbor::Integer(v)) => Value::Time(unit, v.try_into()?),
+        (DataType::Date(unit), Cbor::Integer(v)) => Value::Date(unit, v.try_into()?),
+        (DataType::TimeStamp(unit), Cbor::Integer(v)) => Value::TimeStamp(unit, v.try_into()?),
+        (DataType::TimeStampUTC(unit), Cbor::Integer(v)) => {
+            Value::TimeStampUTC(unit, v.try_into()?)
+        }
+        (DataType::Decimal, Cbor::Text(v)) => Value::Decimal(v.parse()?),
+        (DataType::Uuid, Cbor::Bytes(v)) => Value::Uuid(Uuid::from_slice(&v)?),
+        (data_type, _) => Err(format!("malformed value for column of type {data_type}"))?,
+    };
+    Ok(value)
+}
+
+fn into_array(value: Cbor, what: &str) -> Result<Vec<Cbor>> {
+    match value {
+        Cbor::Array(array) => Ok(array),
+        _ => Err(format!("malformed {what}: expected array"))?,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use section::{decimal::Decimal, message::TimeUnit};
+
+    fn column(name: &str, data_type: DataType, values: Vec<Value>) -> CborColumn {
+        CborColumn {
+            name: name.into(),
+            data_type,
+            values,
+        }
+    }
+
+    fn roundtrip(df: &CborDataFrame) -> Result<CborDataFrame> {
+        let buf = encode(df)?;
+        decode(ciborium::from_reader(buf.as_slice())?)
+    }
+
+    #[test]
+    fn test_roundtrip() -> Result<()> {
+        let df = CborDataFrame {
+            columns: vec![
+                column("bool", DataType::Bool, vec![Value::Bool(true), Value::Null]),
+                column("i8", DataType::I8, vec![Value::I8(i8::MIN), Value::I8(1)]),
+                column(
+                    "i16",
+                    DataType::I16,
+                    vec![Value::I16(i16::MIN), Value::Null],
+                ),
+                column(
+                    "i32",
+                    DataType::I32,
+                    vec![Value::I32(-1), Value::I32(i32::MAX)],
+                ),
+                column(
+                    "i64",
+                    DataType::I64,
+                    vec![Value::I64(i64::MIN), Value::I64(0)],
+                ),
+                column("u8", DataType::U8, vec![Value::U8(u8::MAX), Value::Null]),
+                column(
+                    "u16",
+                    DataType::U16,
+                    vec![Value::U16(u16::MAX), Value::U16(0)],
+                ),
+                column(
+                    "u32",
+                    DataType::U32,
+                    vec![Value::U32(u32::MAX), Value::U32(0)],
+                ),
+                column(
+                    "u64",
+                    DataType::U64,
+                    vec![Value::U64(u64::MAX), Value::U64(0)],
+                ),
+                column(
+                    "f32",
+                    DataType::F32,
+                    vec![Value::F32(0.1), Value::F32(-1e30)],
+                ),
+                column(
+                    "f64",
+                    DataType::F64,
+                    vec![Value::F64(0.1), Value::F64(f64::MAX)],
+                ),
+                column(
+                    "str",
+                    DataType::Str,
+                    vec![Value::from("".to_string()), Value::from("ü".to_string())],
+                ),
+                column(
+                    "bin",
+                    DataType::Bin,
+                    vec![Value::from(vec![0, 1, 2]), Value::Null],
+                ),
+                column(
+                    "time",
+                    DataType::Time(TimeUnit::Nanosecond),
+                    vec![Value::Time(TimeUnit::Nanosecond, 1), Value::Null],
+                ),
+                column(
+                    "date",
+                    DataType::Date(TimeUnit::Second),
+                    vec![Value::Date(TimeUnit::Second, 86400), Value::Null],
+                ),
+                column(
+                    "timestamp",
+                    DataType::TimeStamp(TimeUnit::Microsecond),
+                    vec![Value::TimeStamp(TimeUnit::Microsecond, -1), Value::Null],
+                ),
+                column(
+                    "timestamp_utc",
+                    DataType::TimeStampUTC(TimeUnit::Millisecond),
+                    vec![Value::TimeStampUTC(TimeUnit::Millisecond, 1), Value::Null],
+                ),
+                column(
+                    "decimal",
+                    DataType::Decimal,
+                    vec![
+                        Value::Decimal(Decimal::new(1000, 3)),
+                        Value::Decimal(Decimal::MAX),
+                    ],
+                ),
+                column(
+                    "uuid",
+                    DataType::Uuid,
+                    vec![Value::Uuid(Uuid::from_u128(u128::MAX)), Value::Null],
+                ),
+                column(
+                    "raw_json",
+                    DataType::RawJson,
+                    vec![Value::from(r#"{"a": [1, null]}"#.to_string()), Value::Null],
+                ),
+                column(
+                    "any",
+                    DataType::Any,
+                    vec![
+                        Value::TimeStamp(TimeUnit::Second, 1),
+                        Value::from("any".to_string()),
+                    ],
+                ),
+                column("null", DataType::Null, vec![Value::Null, Value::Null]),
+            ],
+        };
+        let decoded = roundtrip(&df)?;
+        assert_eq!(df, decoded);
+        // decimal scale is preserved
+        let decimal = match decoded.columns[17].values[0] {
+            Value::Decimal(v) => v,
+            _ => unreachable!(),
+        };
+        assert_eq!(decimal.to_string(), "1.000");
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty() -> Result<()> {
+        let df = CborDataFrame {
+            columns: vec![column("empty", DataType::I64, vec![])],
+        };
+        assert_eq!(df, roundtrip(&df)?);
+        let df = CborDataFrame { columns: vec![] };
+        assert_eq!(df, roundtrip(&df)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact() -> Result<()> {
+        let values = (0..100).map(|v| Value::I64(v % 24)).collect::<Vec<_>>();
+        let df = CborDataFrame {
+            columns: vec![column("id", DataType::I64, values)],
+        };
+        // integers below 24 are encoded in a single byte, regardless of column type width
+        assert!(encode(&df)?.len() <= 110);
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let df = CborDataFrame {
+            columns: vec![column("id", DataType::I64, vec![Value::I32(1)])],
+        };
+        assert!(encode(&df).is_err());
+        let df = CborDataFrame {
+            columns: vec![column("json", DataType::RawJson, vec![Value::I64(1)])],
+        };
+        assert!(encode(&df).is_err());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let decode = |frame: Cbor| decode(frame).map(|_| ());
+        let frame =
+            |columns: Vec<Cbor>| Cbor::Array(vec![Cbor::from(VERSION), Cbor::Array(columns)]);
+        let col = |ty: Cbor, values: Vec<Cbor>| {
+            Cbor::Array(vec![Cbor::Text("c".into()), ty, Cbor::Array(values)])
+        };
+
+        assert!(decode(Cbor::Null).is_err());
+        assert!(decode(Cbor::Array(vec![Cbor::from(2), Cbor::Array(vec![])])).is_err());
+        // unknown type code
+        assert!(decode(frame(vec![col(Cbor::from(100), vec![])])).is_err());
+        // value doesn't match column type
+        assert!(decode(frame(vec![col(
+            Cbor::from(5),
+            vec![Cbor::Text("1".into())]
+        )]))
+        .is_err());
+        // integer out of range
+        assert!(decode(frame(vec![col(Cbor::from(2), vec![Cbor::from(128)])])).is_err());
+        // nested any
+        let any = Cbor::Array(vec![Cbor::from(127), Cbor::from(1)]);
+        assert!(decode(frame(vec![col(Cbor::from(127), vec![any])])).is_err());
+        // column lengths differ
+        assert!(decode(frame(vec![
+            col(Cbor::from(5), vec![Cbor::from(1)]),
+            col(Cbor::from(5), vec![]),
+        ]))
+        .is_err());
+    }
+}
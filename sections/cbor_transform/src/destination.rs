@@ -0,0 +1,105 @@
+//! Encodes incoming dataframes into binary CBOR stream
+//!
+//! Each dataframe is sent as a separate binary chunk.
+
+use crate::{codec, ToCbor};
+use section::{
+    command_channel::{Command, SectionChannel},
+    futures::{self, FutureExt, Sink, SinkExt, Stream, StreamExt},
+    message::{Ack, Chunk, Message, Next},
+    section::Section,
+    SectionError, SectionFuture, SectionMessage,
+};
+use std::pin::pin;
+use tokio::sync::mpsc::{channel, Receiver};
+
+impl ToCbor {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+struct ToCborMsg {
+    origin: String,
+    ack: Option<Ack>,
+    rx: Receiver<Option<Chunk>>,
+}
+
+impl std::fmt::Debug for ToCborMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToCborMsg")
+            .field("origin", &self.origin)
+            .finish()
+    }
+}
+
+impl ToCborMsg {
+    fn new(origin: &str, ack: Ack, rx: Receiver<Option<Chunk>>) -> Self {
+        Self {
+            origin: origin.into(),
+            ack: Some(ack),
+            rx,
+        }
+    }
+}
+
+impl Message for ToCborMsg {
+    fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        Box::pin(async move {
+            match self.rx.recv().await {
+                Some(res) => Ok(res),
+                None => Err("stream closed".into()),
+            }
+        })
+    }
+
+    fn ack(&mut self) -> Ack {
+        self.ack.take().unwrap_or(Box::pin(async {}))
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for ToCbor
+where
+    Input: Stream<Item = SectionMessage> + Send + 'static,
+    Output: Sink<SectionMessage, Error = SectionError> + Send + 'static,
+    SectionChan: SectionChannel,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
+        Box::pin(async move {
+            let mut input = pin!(input);
+            let mut output = pin!(output);
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = msg.ok_or("input closed")?;
+                        let (tx, rx) = channel(1);
+                        let ack = msg.ack();
+                        let out_msg = ToCborMsg::new(msg.origin(), ack, rx);
+                        output.send(Box::new(out_msg)).await?;
+                        while let Some(chunk) = msg.next().await? {
+                            let df = match chunk {
+                                Chunk::DataFrame(df) => df,
+                                Chunk::Byte(_) => Err("ToCbor section expects dataframe input")?,
+                            };
+                            let buf = codec::encode(df.as_ref())?;
+                            tx.send(Some(Chunk::Byte(buf))).await.map_err(|_| "stream error")?;
+                        }
+                        tx.send(None).await.map_err(|_| "stream error")?;
+                    }
+                }
+            }
+        })
+    }
+}
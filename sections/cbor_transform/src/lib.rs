@@ -0,0 +1,18 @@
+//! Compact binary transport for dataframes
+//!
+//! `ToCbor` encodes dataframes column-wise into CBOR, `FromCbor` rebuilds them on the receiving side.
+//! Encoded stream carries column names and data types, so no external schema is required.
+#[cfg(feature = "section")]
+mod codec;
+#[cfg(feature = "section")]
+pub mod destination;
+#[cfg(feature = "section")]
+pub mod source;
+
+#[derive(Debug, Default, Clone, config::Configuration)]
+#[section(input=bin, output=dataframe)]
+pub struct FromCbor {}
+
+#[derive(Debug, Default, Clone, config::Configuration)]
+#[section(input=dataframe, output=bin)]
+pub struct ToCbor {}
@@ -0,0 +1,153 @@
+//! Decodes incoming binary CBOR stream into dataframes
+//!
+//! Frames can span arbitrary binary chunks, each decoded frame is sent as a separate dataframe.
+
+use crate::{codec, FromCbor};
+use section::{
+    command_channel::{Command, SectionChannel},
+    futures::{self, FutureExt, Sink, SinkExt, Stream, StreamExt},
+    io::ReceiverReader,
+    message::{Ack, Chunk, Message, Next},
+    section::Section,
+    SectionError, SectionFuture, SectionMessage,
+};
+use std::{
+    io::{BufRead, BufReader},
+    pin::pin,
+};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+type Result<T, E = SectionError> = std::result::Result<T, E>;
+
+impl FromCbor {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+struct FromCborMsg {
+    origin: String,
+    ack: Option<Ack>,
+    rx: Receiver<Result<Option<Chunk>>>,
+}
+
+impl std::fmt::Debug for FromCborMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FromCborMsg")
+            .field("origin", &self.origin)
+            .finish()
+    }
+}
+
+impl FromCborMsg {
+    fn new(origin: &str, ack: Ack, rx: Receiver<Result<Option<Chunk>>>) -> Self {
+        Self {
+            origin: origin.into(),
+            ack: Some(ack),
+            rx,
+        }
+    }
+}
+
+impl Message for FromCborMsg {
+    fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    fn ack(&mut self) -> Ack {
+        self.ack.take().unwrap_or(Box::pin(async {}))
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        Box::pin(async move {
+            match self.rx.recv().await {
+                None => Err("FromCborMsg error: receiver closed".into()),
+                Some(msg) => msg,
+            }
+        })
+    }
+}
+
+fn bin_to_dataframe(
+    rx: Receiver<Option<Vec<u8>>>,
+    tx: Sender<Result<Option<Chunk>>>,
+) -> Result<()> {
+    let mut reader = BufReader::new(ReceiverReader::new(rx));
+    // stream is a sequence of frames, stop once input is exhausted at frame boundary
+    while !reader.fill_buf()?.is_empty() {
+        let frame = ciborium::from_reader(&mut reader)?;
+        let df = codec::decode(frame)?;
+        tx.blocking_send(Ok(Some(Chunk::DataFrame(Box::new(df)))))
+            .map_err(|_| "send error")?;
+    }
+    tx.blocking_send(Ok(None)).map_err(|_| "send error")?;
+    Ok(())
+}
+
+async fn stream_in(mut msg: SectionMessage, tx: Sender<Option<Vec<u8>>>) -> Result<()> {
+    loop {
+        match msg.next().await {
+            Ok(Some(Chunk::Byte(bin))) => {
+                tx.send(Some(bin)).await?;
+            }
+            Ok(None) => {
+                tx.send(None).await?;
+                return Ok(());
+            }
+            Ok(Some(Chunk::DataFrame(_))) => Err("FromCbor section expects binary input")?,
+            Err(e) => Err(e)?,
+        }
+    }
+}
+
+async fn stream_out(
+    rx: Receiver<Option<Vec<u8>>>,
+    tx: Sender<Result<Option<Chunk>>>,
+) -> Result<()> {
+    match tokio::task::spawn_blocking(move || bin_to_dataframe(rx, tx)).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(e)?,
+        Err(e) => Err(format!("join error: {e}"))?,
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for FromCbor
+where
+    Input: Stream<Item = SectionMessage> + Send + 'static,
+    Output: Sink<SectionMessage, Error = SectionError> + Send + 'static,
+    SectionChan: SectionChannel + Send + 'static,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
+        Box::pin(async move {
+            let mut input = pin!(input);
+            let mut output = pin!(output);
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = match msg {
+                            None => Err("input closed")?,
+                            Some(msg) => msg,
+                        };
+                        let (tx_in, rx_in) = channel(1);
+                        let (tx_out, rx_out) = channel(1);
+                        let ack = msg.ack();
+                        let out = FromCborMsg::new(msg.origin(), ack, rx_out);
+                        output.send(Box::new(out)).await?;
+                        futures::try_join!(
+                            stream_in(msg, tx_in),
+                            stream_out(rx_in, tx_out)
+                        )?;
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,171 @@
+use cbor_transform::{FromCbor, ToCbor};
+use section::futures::{SinkExt, StreamExt};
+use section::message::{Ack, Chunk, Column, DataFrame, DataType, Message, Next, TimeUnit, Value};
+use section::section::Section as _;
+use section::{dummy::*, SectionMessage};
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::PollSender;
+
+type StdError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+pub fn channel<T>(buf_size: usize) -> (PollSender<T>, ReceiverStream<T>)
+where
+    T: Send + 'static,
+{
+    let (tx, rx): (Sender<T>, Receiver<T>) = tokio::sync::mpsc::channel(buf_size);
+    (PollSender::new(tx), ReceiverStream::new(rx))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TestDataFrame {
+    columns: Vec<(String, DataType, Vec<Value>)>,
+}
+
+impl DataFrame for TestDataFrame {
+    fn columns(&self) -> Vec<Column<'_>> {
+        self.columns
+            .iter()
+            .map(|(name, data_type, values)| {
+                Column::new(name, *data_type, Box::new(values.iter().map(Into::into)))
+            })
+            .collect()
+    }
+}
+
+impl From<&dyn DataFrame> for TestDataFrame {
+    fn from(df: &dyn DataFrame) -> Self {
+        let columns = df
+            .columns()
+            .into_iter()
+            .map(|column| {
+                let name = column.name().to_string();
+                let data_type = column.data_type();
+                (name, data_type, column.map(|v| Value::from(&v)).collect())
+            })
+            .collect();
+        Self { columns }
+    }
+}
+
+#[derive(Debug)]
+struct TestMsg {
+    chunks: Vec<Chunk>,
+}
+
+impl TestMsg {
+    fn new(chunks: Vec<Chunk>) -> Self {
+        Self {
+            chunks: chunks.into_iter().rev().collect(),
+        }
+    }
+}
+
+impl Message for TestMsg {
+    fn origin(&self) -> &str {
+        "test"
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        let chunk = self.chunks.pop();
+        Box::pin(async move { Ok(chunk) })
+    }
+
+    fn ack(&mut self) -> Ack {
+        Box::pin(async {})
+    }
+}
+
+async fn encode(dataframes: Vec<TestDataFrame>) -> Result<Vec<u8>, StdError> {
+    let (input_tx, input_rx) = channel::<SectionMessage>(1);
+    let (output_tx, mut output_rx) = channel::<SectionMessage>(1);
+    let mut input_tx = input_tx.sink_map_err(|_| "chan closed");
+    let output_tx = output_tx.sink_map_err(|_| "chan closed".into());
+    let handle = tokio::spawn(ToCbor::new().start(input_rx, output_tx, DummySectionChannel::new()));
+    let chunks = dataframes
+        .into_iter()
+        .map(|df| Chunk::DataFrame(Box::new(df)))
+        .collect();
+    input_tx.send(Box::new(TestMsg::new(chunks))).await?;
+    let mut msg = output_rx.next().await.ok_or("output closed")?;
+    let mut payload = vec![];
+    while let Some(chunk) = msg.next().await? {
+        match chunk {
+            Chunk::Byte(bin) => payload.extend(bin),
+            Chunk::DataFrame(_) => Err("unexpected dataframe")?,
+        }
+    }
+    handle.abort();
+    Ok(payload)
+}
+
+async fn decode(payload: Vec<u8>) -> Result<Vec<TestDataFrame>, StdError> {
+    let (input_tx, input_rx) = channel::<SectionMessage>(1);
+    let (output_tx, mut output_rx) = channel::<SectionMessage>(1);
+    let mut input_tx = input_tx.sink_map_err(|_| "chan closed");
+    let output_tx = output_tx.sink_map_err(|_| "chan closed".into());
+    let handle =
+        tokio::spawn(FromCbor::new().start(input_rx, output_tx, DummySectionChannel::new()));
+    // split payload into small chunks, so frames span chunk boundaries
+    let chunks = payload.chunks(3).map(|c| Chunk::Byte(c.to_vec())).collect();
+    input_tx.send(Box::new(TestMsg::new(chunks))).await?;
+    let mut msg = output_rx.next().await.ok_or("output closed")?;
+    let mut dataframes = vec![];
+    while let Some(chunk) = msg.next().await? {
+        match chunk {
+            Chunk::DataFrame(df) => dataframes.push(TestDataFrame::from(df.as_ref())),
+            Chunk::Byte(_) => Err("unexpected binary chunk")?,
+        }
+    }
+    handle.abort();
+    Ok(dataframes)
+}
+
+#[tokio::test]
+async fn cbor_roundtrip() -> Result<(), StdError> {
+    let dataframes = vec![
+        TestDataFrame {
+            columns: vec![
+                (
+                    "id".into(),
+                    DataType::U32,
+                    vec![Value::U32(1), Value::U32(2)],
+                ),
+                (
+                    "name".into(),
+                    DataType::Str,
+                    vec![Value::from("one".to_string()), Value::Null],
+                ),
+                (
+                    "created_at".into(),
+                    DataType::TimeStampUTC(TimeUnit::Millisecond),
+                    vec![
+                        Value::TimeStampUTC(TimeUnit::Millisecond, 1_700_000_000_000),
+                        Value::TimeStampUTC(TimeUnit::Millisecond, 0),
+                    ],
+                ),
+            ],
+        },
+        TestDataFrame {
+            columns: vec![(
+                "any".into(),
+                DataType::Any,
+                vec![Value::I16(-1), Value::Bool(false), Value::Null],
+            )],
+        },
+    ];
+    let payload = encode(dataframes.clone()).await?;
+    assert_eq!(decode(payload).await?, dataframes);
+    Ok(())
+}
+
+#[tokio::test]
+async fn cbor_truncated_payload() -> Result<(), StdError> {
+    let dataframes = vec![TestDataFrame {
+        columns: vec![("id".into(), DataType::I64, vec![Value::I64(1)])],
+    }];
+    let mut payload = encode(dataframes).await?;
+    payload.pop();
+    assert!(decode(payload).await.is_err());
+    Ok(())
+}
@@ -15,11 +15,14 @@ use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 impl ToCsv {
     pub fn new(buf_size: usize) -> Self {
-        Self { buf_size }
+        Self {
+            buf_size,
+            ..Default::default()
+        }
     }
 
-    fn get_writer(&self) -> csv::Writer<Vec<u8>> {
-        csv::Writer::from_writer(Vec::<u8>::with_capacity(self.buf_size))
+    fn get_writer(&self, builder: &csv::WriterBuilder) -> csv::Writer<Vec<u8>> {
+        builder.from_writer(Vec::<u8>::with_capacity(self.buf_size))
     }
 
     fn write_header(
@@ -38,13 +41,14 @@ impl ToCsv {
 
     async fn maybe_send_chunk(
         &self,
+        builder: &csv::WriterBuilder,
         writer: &mut csv::Writer<Vec<u8>>,
         tx: &Sender<Option<Chunk>>,
         last: bool,
     ) -> Result<(), SectionError> {
         let current_pos = writer.get_ref().len();
         if (current_pos != 0 && last) || (current_pos >= self.buf_size) {
-            let mut new_writer = self.get_writer();
+            let mut new_writer = self.get_writer(builder);
             std::mem::swap(&mut new_writer, writer);
             let mut buf = new_writer.into_inner()?;
             unsafe { buf.set_len(current_pos) };
@@ -115,6 +119,7 @@ where
         Box::pin(async move {
             let mut input = pin!(input);
             let mut output = pin!(output);
+            let builder = self.writer_builder()?;
             loop {
                 futures::select! {
                     cmd = section_channel.recv().fuse() => {
@@ -124,11 +129,11 @@ where
                     },
                     msg = input.next().fuse() => {
                         let mut msg = msg.ok_or("input closed")?;
-                        let mut header_written = false;
+                        let mut header_written = !self.has_header;
                         let (tx, rx) = channel(1);
                         let out_msg = ToCsvMsg::new(msg.origin().to_string(), msg.ack(), rx);
                         output.send(Box::new(out_msg)).await?;
-                        let mut writer = self.get_writer();
+                        let mut writer = self.get_writer(&builder);
                         while let Some(chunk) = msg.next().await? {
                             let df = match chunk {
                                 Chunk::DataFrame(df) => df,
@@ -138,7 +143,7 @@ where
                             if !header_written {
                                 header_written = true;
                                 self.write_header(&mut writer, columns.as_slice())?;
-                                self.maybe_send_chunk(&mut writer, &tx, false).await?;
+                                self.maybe_send_chunk(&builder, &mut writer, &tx, false).await?;
                             }
                             'outer: loop {
                                 for column in columns.iter_mut() {
@@ -176,10 +181,10 @@ where
                                 }
                                 writer.write_record(None::<&[u8]>)?;
                                 writer.flush()?;
-                                self.maybe_send_chunk(&mut writer, &tx, false).await?;
+                                self.maybe_send_chunk(&builder, &mut writer, &tx, false).await?;
                             }
                         }
-                        self.maybe_send_chunk(&mut writer, &tx, true).await?;
+                        self.maybe_send_chunk(&builder, &mut writer, &tx, true).await?;
                     },
                 }
             }
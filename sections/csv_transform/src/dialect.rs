@@ -0,0 +1,65 @@
+//! csv dialect options shared by reader and writer
+
+use crate::{FromCsv, ToCsv};
+use section::SectionError;
+
+// parse single byte dialect option, empty value means option is not set
+fn parse_byte(name: &str, value: &str) -> Result<Option<u8>, SectionError> {
+    match value {
+        "" => Ok(None),
+        "\\t" | "tab" => Ok(Some(b'\t')),
+        value if value.len() == 1 => Ok(Some(value.as_bytes()[0])),
+        value => Err(format!(
+            "{name} is expected to be a single ascii character, got: '{value}'"
+        ))?,
+    }
+}
+
+fn parse_required_byte(name: &str, value: &str) -> Result<u8, SectionError> {
+    Ok(parse_byte(name, value)?.ok_or(format!("{name} can't be empty"))?)
+}
+
+impl FromCsv {
+    pub(crate) fn reader_builder(&self) -> Result<csv::ReaderBuilder, SectionError> {
+        let mut builder = csv::ReaderBuilder::new();
+        builder
+            .delimiter(parse_required_byte("delimiter", &self.delimiter)?)
+            .quote(parse_required_byte("quote", &self.quote)?)
+            .comment(parse_byte("comment", &self.comment)?)
+            .has_headers(self.has_header);
+        if let Some(escape) = parse_byte("escape", &self.escape)? {
+            builder.escape(Some(escape)).double_quote(false);
+        }
+        Ok(builder)
+    }
+}
+
+impl ToCsv {
+    pub(crate) fn writer_builder(&self) -> Result<csv::WriterBuilder, SectionError> {
+        let mut builder = csv::WriterBuilder::new();
+        builder
+            .delimiter(parse_required_byte("delimiter", &self.delimiter)?)
+            .quote(parse_required_byte("quote", &self.quote)?)
+            .has_headers(false);
+        if let Some(escape) = parse_byte("escape", &self.escape)? {
+            builder.escape(escape).double_quote(false);
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_byte() {
+        assert_eq!(parse_byte("delimiter", "").unwrap(), None);
+        assert_eq!(parse_byte("delimiter", ";").unwrap(), Some(b';'));
+        assert_eq!(parse_byte("delimiter", "\\t").unwrap(), Some(b'\t'));
+        assert_eq!(parse_byte("delimiter", "tab").unwrap(), Some(b'\t'));
+        assert!(parse_byte("delimiter", ";;").is_err());
+        assert!(parse_byte("delimiter", "ä").is_err());
+        assert!(parse_required_byte("delimiter", "").is_err());
+    }
+}
@@ -1,23 +1,58 @@
 #[cfg(feature = "section")]
 pub mod destination;
 #[cfg(feature = "section")]
+mod dialect;
+#[cfg(feature = "section")]
 pub mod source;
+#[cfg(feature = "section")]
+mod types;
 
 #[derive(Debug, Clone, config::Configuration)]
 #[section(input=bin, output=dataframe)]
 pub struct FromCsv {
     batch_size: usize,
+    // single character, `\t` for tab
+    delimiter: String,
+    quote: String,
+    // empty value means quotes are escaped by doubling them
+    escape: String,
+    // when disabled, columns are named `column_1`, `column_2`, ...
+    has_header: bool,
+    // lines, starting with comment character are skipped, empty value disables comments
+    comment: String,
+    // infer column types over first `infer_sample_size` rows, columns are strings otherwise
+    // rows, which don't fit inferred or explicit column types, are skipped and logged
+    infer_types: bool,
+    infer_sample_size: usize,
+    // comma separated list of chrono formats, used to parse timestamps
+    timestamp_formats: String,
+    // comma separated list of `column:type` pairs, where type is one of str, i64, f64, bool, timestamp
+    column_types: String,
 }
 
 impl Default for FromCsv {
     fn default() -> Self {
-        Self { batch_size: 512 }
+        Self {
+            batch_size: 512,
+            delimiter: ",".into(),
+            quote: "\"".into(),
+            escape: "".into(),
+            has_header: true,
+            comment: "".into(),
+            infer_types: false,
+            infer_sample_size: 100,
+            timestamp_formats: "%Y-%m-%d %H:%M:%S%.f,%Y-%m-%dT%H:%M:%S%.f".into(),
+            column_types: "".into(),
+        }
     }
 }
 
 impl FromCsv {
     pub fn new(batch_size: usize) -> Self {
-        Self { batch_size }
+        Self {
+            batch_size,
+            ..Default::default()
+        }
     }
 }
 
@@ -25,10 +60,22 @@ impl FromCsv {
 #[section(input=dataframe, output=bin)]
 pub struct ToCsv {
     buf_size: usize,
+    // single character, `\t` for tab
+    delimiter: String,
+    quote: String,
+    // empty value means quotes are escaped by doubling them
+    escape: String,
+    has_header: bool,
 }
 
 impl Default for ToCsv {
     fn default() -> Self {
-        Self { buf_size: 4096 }
+        Self {
+            buf_size: 4096,
+            delimiter: ",".into(),
+            quote: "\"".into(),
+            escape: "".into(),
+            has_header: true,
+        }
     }
 }
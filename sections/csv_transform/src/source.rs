@@ -1,11 +1,15 @@
 //! Transforms incoming binary csv stream into dataframe stream
 
-use crate::FromCsv;
+use crate::{
+    types::{ColumnType, Schema},
+    FromCsv,
+};
 use csv::StringRecord;
 use section::{
     command_channel::{Command, SectionChannel},
     futures::{self, FutureExt, Sink, SinkExt, Stream, StreamExt},
-    message::{Ack, Chunk, Column, DataFrame, DataType, Message, Next, ValueView},
+    io::ReceiverReader,
+    message::{Ack, Chunk, Column, DataFrame, Message, Next, Value, ValueView},
     section::Section,
     SectionError, SectionFuture, SectionMessage,
 };
@@ -57,117 +61,144 @@ impl Message for FromCsvMsg {
     }
 }
 
-struct ReceiverReader {
-    rx: Receiver<Option<Vec<u8>>>,
-    buf: Option<Vec<u8>>,
-    closed: bool,
-    offset: u64,
-}
-
-impl ReceiverReader {
-    fn new(rx: Receiver<Option<Vec<u8>>>) -> Self {
-        Self {
-            rx,
-            buf: None,
-            closed: false,
-            offset: 0,
-        }
-    }
-}
-
-impl std::io::Read for ReceiverReader {
-    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
-        if self.closed {
-            return Ok(0);
-        }
-        if self.buf.is_none() {
-            self.offset = 0;
-            self.buf = match self.rx.blocking_recv() {
-                None => Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "receiver reader error: receiver closed",
-                ))?,
-                Some(buf) => buf,
-            }
-        }
-        let mut reader = match self.buf {
-            None => {
-                self.closed = true;
-                return Ok(0);
-            }
-            Some(ref buf) => {
-                let mut available = buf.len() as u64 - self.offset;
-                if available > out.len() as u64 {
-                    available = out.len() as u64
-                }
-                std::io::Cursor::new(
-                    &buf.as_slice()[self.offset as usize..(self.offset + available) as usize],
-                )
-            }
-        };
-        let read = std::io::copy(&mut reader, &mut std::io::Cursor::new(out))?;
-        self.offset += read;
-        if self.offset == self.buf.as_ref().unwrap().len() as u64 {
-            self.buf = None;
-        }
-        Ok(read as usize)
-    }
-}
-
 #[derive(Debug)]
 struct CsvDataFrame {
-    header: Arc<StringRecord>,
+    schema: Arc<Schema>,
     batch: Vec<StringRecord>,
+    // parsed values of typed columns, string columns are served directly from records
+    values: Vec<Option<Vec<Value>>>,
 }
 
 impl DataFrame for CsvDataFrame {
     fn columns(&self) -> Vec<section::message::Column<'_>> {
-        (&*self.header)
-            .into_iter()
+        self.schema
+            .names
+            .iter()
+            .zip(self.schema.types.iter())
+            .zip(self.values.iter())
             .enumerate()
-            .map(|(pos, name)| {
-                Column::new(
-                    name,
-                    DataType::Str,
-                    Box::new(
+            .map(|(pos, ((name, ty), values))| {
+                let iter: Box<dyn Iterator<Item = ValueView<'_>> + Send> = match values {
+                    Some(values) => Box::new(values.iter().map(Into::into)),
+                    None => Box::new(
                         self.batch
                             .iter()
                             .map(move |record| ValueView::Str(&record[pos])),
                     ),
-                )
+                };
+                Column::new(name, (*ty).into(), iter)
             })
             .collect()
     }
 }
 
 impl CsvDataFrame {
-    fn new(header: Arc<StringRecord>, batch: Vec<StringRecord>) -> Self {
-        Self { header, batch }
+    /// rows, which values don't fit schema, e.g. text in inferred integer column, are skipped and reported
+    fn new(schema: Arc<Schema>, batch: Vec<StringRecord>) -> Self {
+        let typed = schema
+            .types
+            .iter()
+            .enumerate()
+            .filter(|(_, ty)| **ty != ColumnType::Str)
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        let mut values = schema
+            .types
+            .iter()
+            .map(|ty| match ty {
+                ColumnType::Str => None,
+                _ => Some(Vec::with_capacity(batch.len())),
+            })
+            .collect::<Vec<_>>();
+        let mut records = Vec::with_capacity(batch.len());
+        for record in batch {
+            let parsed = typed
+                .iter()
+                .map(|&pos| schema.parse(pos, &record[pos]))
+                .collect::<Result<Vec<_>>>();
+            match parsed {
+                Ok(parsed) => {
+                    for (values, value) in values.iter_mut().flatten().zip(parsed) {
+                        values.push(value);
+                    }
+                    records.push(record);
+                }
+                Err(e) => {
+                    let line = record.position().map(|position| position.line());
+                    tracing::warn!("skipping csv row at line {}: {e}", line.unwrap_or(0));
+                }
+            }
+        }
+        Self {
+            schema,
+            batch: records,
+            values,
+        }
     }
 }
 
 fn bin_to_dataframe(
-    batch_size: usize,
+    config: FromCsv,
     rx: Receiver<Option<Vec<u8>>>,
     tx: Sender<Result<Option<Chunk>>>,
 ) -> Result<()> {
-    let mut reader = csv::Reader::from_reader(ReceiverReader::new(rx));
-    let header = Arc::new(reader.headers()?.clone());
+    let batch_size = config.batch_size.max(1);
+    let mut reader = config
+        .reader_builder()?
+        .from_reader(ReceiverReader::new(rx));
+    let header = match config.has_header {
+        true => Some(reader.headers()?.clone()),
+        false => None,
+    };
+    let mut records = reader.into_records();
+
+    // sample is needed to infer types, or to count columns if header is absent
+    let sample_size = match config.infer_types {
+        true => config.infer_sample_size.max(1),
+        false => 1,
+    };
     let mut batch = vec![];
-    for record in reader.records() {
+    for record in records.by_ref() {
         batch.push(record?);
-        if batch.len() >= batch_size {
-            let mut new_batch = vec![];
-            std::mem::swap(&mut new_batch, &mut batch);
-            let df = Box::new(CsvDataFrame::new(Arc::clone(&header), new_batch));
-            tx.blocking_send(Ok(Some(Chunk::DataFrame(df))))
-                .map_err(|_| "send error")?;
+        if batch.len() >= sample_size {
+            break;
         }
     }
-    if !batch.is_empty() {
-        let df = Box::new(CsvDataFrame::new(Arc::clone(&header), batch));
+    let names = match header {
+        Some(header) => header.iter().map(Into::into).collect(),
+        None => (1..=batch.first().map(|record| record.len()).unwrap_or(0))
+            .map(|pos| format!("column_{pos}"))
+            .collect(),
+    };
+    let mut schema = Schema::new(names, &config.timestamp_formats);
+    if config.infer_types {
+        schema.infer(&batch);
+    }
+    schema.apply_overrides(&config.column_types)?;
+    let schema = Arc::new(schema);
+
+    let send = |batch: Vec<StringRecord>| -> Result<()> {
+        let df = Box::new(CsvDataFrame::new(Arc::clone(&schema), batch));
+        if df.batch.is_empty() {
+            return Ok(());
+        }
         tx.blocking_send(Ok(Some(Chunk::DataFrame(df))))
             .map_err(|_| "send error")?;
+        Ok(())
+    };
+    // sample can be bigger than batch size
+    while batch.len() >= batch_size {
+        let rest = batch.split_off(batch_size);
+        send(std::mem::replace(&mut batch, rest))?;
+    }
+    for record in records {
+        batch.push(record?);
+        if batch.len() >= batch_size {
+            send(std::mem::take(&mut batch))?;
+        }
+    }
+    if !batch.is_empty() {
+        send(batch)?;
     }
     tx.blocking_send(Ok(None)).map_err(|_| "send error")?;
     Ok(())
@@ -190,11 +221,11 @@ async fn stream_in(mut msg: SectionMessage, tx: Sender<Option<Vec<u8>>>) -> Resu
 }
 
 async fn stream_out(
-    batch_size: usize,
+    config: FromCsv,
     rx: Receiver<Option<Vec<u8>>>,
     tx: Sender<Result<Option<Chunk>>>,
 ) -> Result<()> {
-    match tokio::task::spawn_blocking(move || bin_to_dataframe(batch_size, rx, tx)).await {
+    match tokio::task::spawn_blocking(move || bin_to_dataframe(config, rx, tx)).await {
         Ok(Ok(())) => Ok(()),
         Ok(Err(e)) => Err(e)?,
         Err(e) => Err(format!("join error: {e}"))?,
@@ -233,7 +264,7 @@ where
                         output.send(Box::new(out)).await?;
                         futures::try_join!(
                             stream_in(msg, tx_in),
-                            stream_out(self.batch_size, rx_in, tx_out)
+                            stream_out(self.clone(), rx_in, tx_out)
                         )?;
                     }
                 }
@@ -241,3 +272,140 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Config;
+    use section::message::DataType;
+
+    // columns of each emitted dataframe: name, type and values
+    type Batch = Vec<(String, DataType, Vec<Value>)>;
+
+    fn read_csv(config: FromCsv, payload: &str) -> Result<Vec<Batch>> {
+        let (tx_in, rx_in) = channel(2);
+        let (tx_out, mut rx_out) = channel(64);
+        tx_in.blocking_send(Some(payload.as_bytes().to_vec()))?;
+        tx_in.blocking_send(None)?;
+        bin_to_dataframe(config, rx_in, tx_out)?;
+        let mut batches = vec![];
+        while let Some(chunk) = rx_out.blocking_recv().ok_or("closed")?? {
+            let df = match chunk {
+                Chunk::DataFrame(df) => df,
+                Chunk::Byte(_) => Err("unexpected binary chunk")?,
+            };
+            let columns = df
+                .columns()
+                .into_iter()
+                .map(|column| {
+                    let name = column.name().to_string();
+                    let data_type = column.data_type();
+                    (name, data_type, column.map(|v| Value::from(&v)).collect())
+                })
+                .collect();
+            batches.push(columns);
+        }
+        Ok(batches)
+    }
+
+    #[test]
+    fn test_dialect() -> Result<()> {
+        let mut config = FromCsv::new(2);
+        config.set_field_value("delimiter", "|".into())?;
+        config.set_field_value("has_header", false.into())?;
+        config.set_field_value("comment", "#".into())?;
+        let batches = read_csv(config, "# comment\na|1\nb|2\n# comment\nc|3")?;
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            batches[1],
+            vec![
+                (
+                    "column_1".into(),
+                    DataType::Str,
+                    vec![Value::from("c".to_string())]
+                ),
+                (
+                    "column_2".into(),
+                    DataType::Str,
+                    vec![Value::from("3".to_string())]
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_columns() -> Result<()> {
+        let mut config = FromCsv::new(512);
+        config.set_field_value("delimiter", ";".into())?;
+        config.set_field_value("infer_types", true.into())?;
+        config.set_field_value("column_types", "zip:str".into())?;
+        let batches = read_csv(config, "zip;price;active\n01234;1,5;true\n99999;2;false")?;
+        assert_eq!(
+            batches,
+            vec![vec![
+                (
+                    "zip".into(),
+                    DataType::Str,
+                    vec![
+                        Value::from("01234".to_string()),
+                        Value::from("99999".to_string())
+                    ]
+                ),
+                (
+                    "price".into(),
+                    DataType::Str,
+                    vec![Value::from("1,5".to_string()), Value::from("2".to_string())]
+                ),
+                (
+                    "active".into(),
+                    DataType::Bool,
+                    vec![Value::Bool(true), Value::Bool(false)]
+                ),
+            ]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_column_parse_error() -> Result<()> {
+        let mut config = FromCsv::new(512);
+        config.set_field_value("column_types", "a:i64".into())?;
+        let batches = read_csv(config, "a\n1\nnot a number\n2")?;
+        assert_eq!(
+            batches,
+            vec![vec![(
+                "a".into(),
+                DataType::I64,
+                vec![Value::I64(1), Value::I64(2)]
+            )]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_mismatch_after_sample() -> Result<()> {
+        let mut config = FromCsv::new(2);
+        config.set_field_value("infer_types", true.into())?;
+        config.set_field_value("infer_sample_size", 2.into())?;
+        // `id` is inferred as integer from first two rows, mismatching row is skipped, stream continues
+        // batches are cut by read rows, so batch with skipped row is smaller
+        let batches = read_csv(config, "id,name\n1,a\n2,b\nthree,c\n4,d\n5,e")?;
+        let ids = batches
+            .iter()
+            .map(|batch| batch[0].2.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                vec![Value::I64(1), Value::I64(2)],
+                vec![Value::I64(4)],
+                vec![Value::I64(5)],
+            ]
+        );
+        assert_eq!(batches[1][0].1, DataType::I64);
+        // skipped row is dropped from all columns
+        assert_eq!(batches[1][1].2, vec![Value::from("d".to_string())]);
+        Ok(())
+    }
+}
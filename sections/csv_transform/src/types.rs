@@ -0,0 +1,224 @@
+//! Column typing: explicit overrides and inference over a sample of rows
+
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use csv::StringRecord;
+use section::{
+    message::{DataType, TimeUnit, Value},
+    SectionError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnType {
+    Str,
+    I64,
+    F64,
+    Bool,
+    TimeStamp,
+}
+
+impl FromStr for ColumnType {
+    type Err = SectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ty = match s.to_lowercase().as_str() {
+            "str" | "string" | "text" => Self::Str,
+            "i64" | "int" | "integer" => Self::I64,
+            "f64" | "float" | "double" => Self::F64,
+            "bool" | "boolean" => Self::Bool,
+            "timestamp" | "datetime" => Self::TimeStamp,
+            other => Err(format!("unsupported column type: '{other}'"))?,
+        };
+        Ok(ty)
+    }
+}
+
+impl From<ColumnType> for DataType {
+    fn from(value: ColumnType) -> Self {
+        match value {
+            ColumnType::Str => DataType::Str,
+            ColumnType::I64 => DataType::I64,
+            ColumnType::F64 => DataType::F64,
+            ColumnType::Bool => DataType::Bool,
+            ColumnType::TimeStamp => DataType::TimeStamp(TimeUnit::Microsecond),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Schema {
+    pub names: Vec<String>,
+    pub types: Vec<ColumnType>,
+    timestamp_formats: Vec<String>,
+}
+
+impl Schema {
+    pub fn new(names: Vec<String>, timestamp_formats: &str) -> Self {
+        let types = vec![ColumnType::Str; names.len()];
+        let timestamp_formats = timestamp_formats
+            .split(',')
+            .map(str::trim)
+            .filter(|format| !format.is_empty())
+            .map(Into::into)
+            .collect();
+        Self {
+            names,
+            types,
+            timestamp_formats,
+        }
+    }
+
+    /// infer column types, the most specific type which fits all non-empty values in sample wins
+    pub fn infer(&mut self, sample: &[StringRecord]) {
+        let candidates = [
+            ColumnType::Bool,
+            ColumnType::I64,
+            ColumnType::F64,
+            ColumnType::TimeStamp,
+        ];
+        for (pos, ty) in self.types.iter_mut().enumerate() {
+            let mut values = sample
+                .iter()
+                .filter_map(|record| record.get(pos))
+                .filter(|value| !value.is_empty())
+                .peekable();
+            if values.peek().is_none() {
+                continue;
+            }
+            let mut fits = candidates.map(|candidate| (candidate, true));
+            for value in values {
+                for (candidate, fit) in fits.iter_mut().filter(|(_, fit)| *fit) {
+                    *fit = parse(*candidate, value, &self.timestamp_formats).is_ok();
+                }
+            }
+            *ty = fits
+                .into_iter()
+                .find(|(_, fit)| *fit)
+                .map(|(candidate, _)| candidate)
+                .unwrap_or(ColumnType::Str);
+        }
+    }
+
+    /// apply explicit column types, given as comma separated list of `column:type` pairs
+    pub fn apply_overrides(&mut self, overrides: &str) -> Result<(), SectionError> {
+        for pair in overrides
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            let (name, ty) = pair
+                .rsplit_once(':')
+                .ok_or(format!("malformed column type override: '{pair}'"))?;
+            let pos = self
+                .names
+                .iter()
+                .position(|column| column == name.trim())
+                .ok_or(format!("column type override for unknown column: '{name}'"))?;
+            self.types[pos] = ty.trim().parse()?;
+        }
+        Ok(())
+    }
+
+    pub fn parse(&self, pos: usize, value: &str) -> Result<Value, SectionError> {
+        if value.is_empty() {
+            return Ok(Value::Null);
+        }
+        parse(self.types[pos], value, &self.timestamp_formats).map_err(|_| {
+            format!(
+                "failed to parse '{value}' in column '{}' as {:?}",
+                self.names[pos], self.types[pos]
+            )
+            .into()
+        })
+    }
+}
+
+fn parse(ty: ColumnType, value: &str, timestamp_formats: &[String]) -> Result<Value, ()> {
+    let value = match ty {
+        ColumnType::Str => Value::from(value.to_string()),
+        ColumnType::I64 => Value::I64(value.parse().map_err(|_| ())?),
+        ColumnType::F64 => Value::F64(value.parse().map_err(|_| ())?),
+        ColumnType::Bool => match value {
+            v if v.eq_ignore_ascii_case("true") => Value::Bool(true),
+            v if v.eq_ignore_ascii_case("false") => Value::Bool(false),
+            _ => Err(())?,
+        },
+        ColumnType::TimeStamp => timestamp_formats
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .map(|datetime| {
+                Value::TimeStamp(TimeUnit::Microsecond, datetime.and_utc().timestamp_micros())
+            })
+            .ok_or(())?,
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: &str = "%Y-%m-%d %H:%M:%S%.f,%d.%m.%Y %H:%M";
+
+    fn sample(rows: &[&[&str]]) -> Vec<StringRecord> {
+        rows.iter()
+            .map(|row| StringRecord::from(row.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_infer() {
+        let mut schema = Schema::new(
+            ["bool", "int", "float", "ts", "str", "empty"]
+                .map(String::from)
+                .to_vec(),
+            FORMATS,
+        );
+        schema.infer(&sample(&[
+            &["true", "1", "1", "2024-01-01 00:00:00", "a", ""],
+            &["FALSE", "-2", "2.5", "01.02.2024 10:30", "1", ""],
+            &["", "", "1e3", "", "", ""],
+        ]));
+        assert_eq!(
+            schema.types,
+            vec![
+                ColumnType::Bool,
+                ColumnType::I64,
+                ColumnType::F64,
+                ColumnType::TimeStamp,
+                ColumnType::Str,
+                ColumnType::Str,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut schema = Schema::new(["a", "b"].map(String::from).to_vec(), FORMATS);
+        schema.infer(&sample(&[&["1", "2"]]));
+        assert!(schema.apply_overrides("a:str, b : f64").is_ok());
+        assert_eq!(schema.types, vec![ColumnType::Str, ColumnType::F64]);
+        assert!(schema.apply_overrides("c:str").is_err());
+        assert!(schema.apply_overrides("a:unknown").is_err());
+        assert!(schema.apply_overrides("a").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut schema = Schema::new(["int", "ts"].map(String::from).to_vec(), FORMATS);
+        schema.apply_overrides("int:i64,ts:timestamp").unwrap();
+        assert_eq!(schema.parse(0, "42").unwrap(), Value::I64(42));
+        assert_eq!(schema.parse(0, "").unwrap(), Value::Null);
+        assert!(schema.parse(0, "4.2").is_err());
+        assert_eq!(
+            schema.parse(1, "1970-01-01 00:00:01.5").unwrap(),
+            Value::TimeStamp(TimeUnit::Microsecond, 1_500_000)
+        );
+        assert_eq!(
+            schema.parse(1, "01.01.1970 00:01").unwrap(),
+            Value::TimeStamp(TimeUnit::Microsecond, 60_000_000)
+        );
+        assert!(schema.parse(1, "yesterday").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 //! List directory with applied filters
 //! This example outputs binary streams
 
+use std::time::Duration;
+
 use clap::Parser;
 use dir::DirSource;
 use section::{
@@ -24,7 +26,13 @@ struct Cli {
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
-    let source = DirSource::new(cli.dir_path, cli.pattern, cli.start_after, 3, true);
+    let source = DirSource::new(
+        cli.dir_path,
+        cli.pattern,
+        cli.start_after,
+        Duration::from_secs(3),
+        true,
+    );
 
     let (tx, mut rx) = channel(1);
     let tx = PollSender::new(tx).sink_map_err(|_| "send error".into());
@@ -1,13 +1,17 @@
 #[cfg(feature = "section")]
 pub mod source;
 
+use std::time::Duration;
+
 #[derive(Debug, Clone, config::Configuration)]
 #[section(output=bin_or_dataframe)]
 pub struct DirSource {
+    #[field(template)]
     path: String,
     pattern: String,
     start_after: String,
-    interval: u64,
+    #[validate(min = 1)]
+    interval: Duration,
     stream_binary: bool,
 }
 
@@ -16,7 +20,7 @@ impl DirSource {
         path: String,
         pattern: String,
         start_after: String,
-        interval: u64,
+        interval: Duration,
         stream_binary: bool,
     ) -> Self {
         Self {
@@ -35,7 +39,7 @@ impl Default for DirSource {
             path: "".into(),
             pattern: "".into(),
             start_after: "".into(),
-            interval: 30,
+            interval: Duration::from_secs(30),
             stream_binary: false,
         }
     }
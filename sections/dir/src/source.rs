@@ -240,7 +240,7 @@ where
     ) -> Self::Future {
         Box::pin(async move {
             let mut output = pin!(output);
-            let mut interval = tokio::time::interval(Duration::from_secs(self.interval));
+            let mut interval = tokio::time::interval(self.interval);
             let pattern = match self.pattern.is_empty() {
                 true => None,
                 false => Some(Regex::try_from(self.pattern.as_str())?),
@@ -0,0 +1,83 @@
+//! Decrypt incoming age-encrypted binary stream
+//!
+//! Payload is authenticated chunk by chunk, plaintext is forwarded only after chunk passes authentication.
+//! Tampered or truncated payload fails outgoing message with an error; section keeps processing next messages.
+
+use std::{pin::pin, str::FromStr};
+
+use crate::{
+    stream::{keys, stream_in, stream_out, BinMsg, Result},
+    Decrypt,
+};
+use age::x25519;
+use section::{
+    command_channel::{Command, SectionChannel},
+    futures::{self, FutureExt, Sink, SinkExt, Stream, StreamExt},
+    section::Section,
+    SectionError, SectionFuture, SectionMessage,
+};
+use tokio::sync::mpsc::channel;
+
+impl Decrypt {
+    fn identities(&self) -> Result<Vec<x25519::Identity>> {
+        let identities = keys(&self.identities)
+            .map(|key| {
+                x25519::Identity::from_str(key)
+                    .map_err(|e| format!("failed to parse identity: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if identities.is_empty() {
+            Err("at least one identity is required")?
+        }
+        Ok(identities)
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for Decrypt
+where
+    Input: Stream<Item = SectionMessage> + Send + 'static,
+    Output: Sink<SectionMessage, Error = SectionError> + Send + 'static,
+    SectionChan: SectionChannel + Send + 'static,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
+        Box::pin(async move {
+            let mut input = pin!(input);
+            let mut output = pin!(output);
+            let identities = self.identities()?;
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = msg.ok_or("input closed")?;
+                        let (tx_in, rx_in) = channel(1);
+                        let (tx_out, rx_out) = channel(1);
+                        let ack = msg.ack();
+                        let out = BinMsg::new("DecryptMsg", msg.origin(), ack, rx_out);
+                        output.send(Box::new(out)).await?;
+                        let identities = identities.clone();
+                        futures::try_join!(
+                            stream_in(msg, tx_in),
+                            stream_out(rx_in, tx_out, move |reader, mut writer| {
+                                let decryptor = age::Decryptor::new(reader)?;
+                                if decryptor.is_scrypt() {
+                                    Err("passphrase encrypted payloads are not supported")?
+                                }
+                                let mut reader = decryptor
+                                    .decrypt(identities.iter().map(|i| i as &dyn age::Identity))?;
+                                std::io::copy(&mut reader, &mut writer)?;
+                                writer.finish()
+                            })
+                        )?;
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,78 @@
+//! Encrypt incoming binary stream for configured age recipients
+
+use std::{pin::pin, str::FromStr};
+
+use crate::{
+    stream::{keys, stream_in, stream_out, BinMsg, Result},
+    Encrypt,
+};
+use age::x25519;
+use section::{
+    command_channel::{Command, SectionChannel},
+    futures::{self, FutureExt, Sink, SinkExt, Stream, StreamExt},
+    section::Section,
+    SectionError, SectionFuture, SectionMessage,
+};
+use tokio::sync::mpsc::channel;
+
+impl Encrypt {
+    fn recipients(&self) -> Result<Vec<x25519::Recipient>> {
+        let recipients = keys(&self.recipients)
+            .map(|key| {
+                x25519::Recipient::from_str(key)
+                    .map_err(|e| format!("failed to parse recipient: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if recipients.is_empty() {
+            Err("at least one recipient is required")?
+        }
+        Ok(recipients)
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for Encrypt
+where
+    Input: Stream<Item = SectionMessage> + Send + 'static,
+    Output: Sink<SectionMessage, Error = SectionError> + Send + 'static,
+    SectionChan: SectionChannel + Send + 'static,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
+        Box::pin(async move {
+            let mut input = pin!(input);
+            let mut output = pin!(output);
+            let recipients = self.recipients()?;
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = msg.ok_or("input closed")?;
+                        let (tx_in, rx_in) = channel(1);
+                        let (tx_out, rx_out) = channel(1);
+                        let ack = msg.ack();
+                        let out = BinMsg::new("EncryptMsg", msg.origin(), ack, rx_out);
+                        output.send(Box::new(out)).await?;
+                        let recipients = recipients.clone();
+                        futures::try_join!(
+                            stream_in(msg, tx_in),
+                            stream_out(rx_in, tx_out, move |mut reader, writer| {
+                                let encryptor = age::Encryptor::with_recipients(
+                                    recipients.iter().map(|r| r as &dyn age::Recipient),
+                                )?;
+                                let mut writer = encryptor.wrap_output(writer)?;
+                                std::io::copy(&mut reader, &mut writer)?;
+                                writer.finish()?.finish()
+                            })
+                        )?;
+                    }
+                }
+            }
+        })
+    }
+}
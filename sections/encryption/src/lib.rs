@@ -0,0 +1,59 @@
+//! Encrypt / decrypt binary streams with [age](https://age-encryption.org/v1)
+//!
+//! age is an authenticated streaming format: payload is split into 64KiB chunks, each chunk is sealed with
+//! ChaCha20-Poly1305, so tampered or truncated streams are detected while decrypting.
+
+#[cfg(feature = "section")]
+pub mod decrypt;
+#[cfg(feature = "section")]
+pub mod encrypt;
+#[cfg(feature = "section")]
+mod stream;
+
+#[derive(Debug, Clone, config::Configuration)]
+#[section(input=bin, output=bin)]
+pub struct Encrypt {
+    // age x25519 public keys (`age1...`), separated by commas or newlines
+    #[field_type(password, text_area)]
+    recipients: String,
+}
+
+impl Default for Encrypt {
+    fn default() -> Self {
+        Self {
+            recipients: "".into(),
+        }
+    }
+}
+
+impl Encrypt {
+    pub fn new(recipients: impl Into<String>) -> Self {
+        Self {
+            recipients: recipients.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, config::Configuration)]
+#[section(input=bin, output=bin)]
+pub struct Decrypt {
+    // age x25519 secret keys (`AGE-SECRET-KEY-1...`), separated by commas or newlines
+    #[field_type(password, text_area)]
+    identities: String,
+}
+
+impl Default for Decrypt {
+    fn default() -> Self {
+        Self {
+            identities: "".into(),
+        }
+    }
+}
+
+impl Decrypt {
+    pub fn new(identities: impl Into<String>) -> Self {
+        Self {
+            identities: identities.into(),
+        }
+    }
+}
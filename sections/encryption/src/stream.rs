@@ -0,0 +1,151 @@
+//! Glue between async section messages and blocking age readers/writers
+
+use section::{
+    io::ReceiverReader,
+    message::{Ack, Chunk, Message, Next},
+    SectionError, SectionMessage,
+};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+pub(crate) type Result<T, E = SectionError> = std::result::Result<T, E>;
+
+const BUF_SIZE: usize = 64 * 1024;
+
+pub(crate) struct BinMsg {
+    name: &'static str,
+    origin: String,
+    ack: Option<Ack>,
+    rx: Receiver<Result<Option<Chunk>>>,
+}
+
+impl std::fmt::Debug for BinMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(self.name)
+            .field("origin", &self.origin)
+            .finish()
+    }
+}
+
+impl BinMsg {
+    pub(crate) fn new(
+        name: &'static str,
+        origin: &str,
+        ack: Ack,
+        rx: Receiver<Result<Option<Chunk>>>,
+    ) -> Self {
+        Self {
+            name,
+            origin: origin.into(),
+            ack: Some(ack),
+            rx,
+        }
+    }
+}
+
+impl Message for BinMsg {
+    fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    fn ack(&mut self) -> Ack {
+        self.ack.take().unwrap_or(Box::pin(async {}))
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        Box::pin(async move {
+            match self.rx.recv().await {
+                None => Err(format!("{} error: receiver closed", self.name).into()),
+                Some(msg) => msg,
+            }
+        })
+    }
+}
+
+/// Blocking writer, which turns written bytes into binary chunks of outgoing message
+pub(crate) struct SenderWriter {
+    tx: Sender<Result<Option<Chunk>>>,
+    buf: Vec<u8>,
+}
+
+impl SenderWriter {
+    pub(crate) fn new(tx: Sender<Result<Option<Chunk>>>) -> Self {
+        Self {
+            tx,
+            buf: Vec::with_capacity(BUF_SIZE),
+        }
+    }
+
+    fn send_buf(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let buf = std::mem::replace(&mut self.buf, Vec::with_capacity(BUF_SIZE));
+        self.tx
+            .blocking_send(Ok(Some(Chunk::Byte(buf))))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "send error"))
+    }
+
+    /// flush leftovers and mark end of the message
+    pub(crate) fn finish(mut self) -> Result<()> {
+        self.send_buf()?;
+        self.tx.blocking_send(Ok(None)).map_err(|_| "send error")?;
+        Ok(())
+    }
+}
+
+impl std::io::Write for SenderWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= BUF_SIZE {
+            self.send_buf()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) async fn stream_in(mut msg: SectionMessage, tx: Sender<Option<Vec<u8>>>) -> Result<()> {
+    loop {
+        let chunk = match msg.next().await {
+            Ok(Some(Chunk::Byte(bin))) => Some(bin),
+            Ok(None) => None,
+            Ok(Some(Chunk::DataFrame(_))) => Err("expected binary input")?,
+            Err(e) => Err(e)?,
+        };
+        let last = chunk.is_none();
+        // receiving side hangs up only on failure, which is already reported through outgoing message
+        if tx.send(chunk).await.is_err() || last {
+            return Ok(());
+        }
+    }
+}
+
+/// Run blocking transformation, reporting transformation failure through outgoing message
+pub(crate) async fn stream_out<F>(
+    rx: Receiver<Option<Vec<u8>>>,
+    tx: Sender<Result<Option<Chunk>>>,
+    f: F,
+) -> Result<()>
+where
+    F: FnOnce(ReceiverReader, SenderWriter) -> Result<()> + Send + 'static,
+{
+    let reader = ReceiverReader::new(rx);
+    let writer = SenderWriter::new(tx.clone());
+    match tokio::task::spawn_blocking(move || f(reader, writer)).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            tx.send(Err(e)).await.ok();
+            Ok(())
+        }
+        Err(e) => Err(format!("join error: {e}"))?,
+    }
+}
+
+/// split list of keys, separated by commas or whitespace
+pub(crate) fn keys(keys: &str) -> impl Iterator<Item = &str> {
+    keys.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|key| !key.is_empty())
+}
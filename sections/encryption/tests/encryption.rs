@@ -0,0 +1,148 @@
+use age::secrecy::ExposeSecret;
+use encryption::{Decrypt, Encrypt};
+use section::futures::{SinkExt, StreamExt};
+use section::message::{Ack, Chunk, Message, Next};
+use section::section::Section as _;
+use section::{dummy::*, SectionMessage};
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::PollSender;
+
+type StdError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+pub fn channel<T>(buf_size: usize) -> (PollSender<T>, ReceiverStream<T>)
+where
+    T: Send + 'static,
+{
+    let (tx, rx): (Sender<T>, Receiver<T>) = tokio::sync::mpsc::channel(buf_size);
+    (PollSender::new(tx), ReceiverStream::new(rx))
+}
+
+#[derive(Debug)]
+struct BinMsg {
+    chunks: Vec<Vec<u8>>,
+}
+
+impl BinMsg {
+    fn new(chunks: Vec<Vec<u8>>) -> Self {
+        Self {
+            chunks: chunks.into_iter().rev().collect(),
+        }
+    }
+}
+
+impl Message for BinMsg {
+    fn origin(&self) -> &str {
+        "test"
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        let chunk = self.chunks.pop().map(Chunk::Byte);
+        Box::pin(async move { Ok(chunk) })
+    }
+
+    fn ack(&mut self) -> Ack {
+        Box::pin(async {})
+    }
+}
+
+async fn collect(msg: &mut SectionMessage) -> Result<Vec<u8>, StdError> {
+    let mut payload = vec![];
+    while let Some(chunk) = msg.next().await? {
+        match chunk {
+            Chunk::Byte(bin) => payload.extend(bin),
+            Chunk::DataFrame(_) => Err("unexpected dataframe")?,
+        }
+    }
+    Ok(payload)
+}
+
+// encrypt payload and return encrypted stream
+async fn encrypt(recipient: &str, chunks: Vec<Vec<u8>>) -> Result<Vec<u8>, StdError> {
+    let (input_tx, input_rx) = channel::<SectionMessage>(1);
+    let (output_tx, mut output_rx) = channel::<SectionMessage>(1);
+    let mut input_tx = input_tx.sink_map_err(|_| "chan closed");
+    let output_tx = output_tx.sink_map_err(|_| "chan closed".into());
+    let handle = tokio::spawn(Encrypt::new(recipient).start(
+        input_rx,
+        output_tx,
+        DummySectionChannel::new(),
+    ));
+    input_tx.send(Box::new(BinMsg::new(chunks))).await?;
+    let mut msg = output_rx.next().await.ok_or("output closed")?;
+    let payload = collect(&mut msg).await?;
+    handle.abort();
+    Ok(payload)
+}
+
+// decrypt each payload, return result per message
+async fn decrypt(
+    identity: &str,
+    payloads: Vec<Vec<u8>>,
+) -> Result<Vec<Result<Vec<u8>, StdError>>, StdError> {
+    let (input_tx, input_rx) = channel::<SectionMessage>(1);
+    let (output_tx, mut output_rx) = channel::<SectionMessage>(1);
+    let mut input_tx = input_tx.sink_map_err(|_| "chan closed");
+    let output_tx = output_tx.sink_map_err(|_| "chan closed".into());
+    let handle =
+        tokio::spawn(Decrypt::new(identity).start(input_rx, output_tx, DummySectionChannel::new()));
+    let mut results = vec![];
+    for payload in payloads {
+        // split payload into small chunks to exercise streaming
+        let chunks = payload.chunks(7).map(|c| c.to_vec()).collect();
+        input_tx.send(Box::new(BinMsg::new(chunks))).await?;
+        let mut msg = output_rx.next().await.ok_or("output closed")?;
+        results.push(collect(&mut msg).await);
+    }
+    handle.abort();
+    Ok(results)
+}
+
+#[tokio::test]
+async fn encrypt_decrypt_roundtrip() -> Result<(), StdError> {
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public().to_string();
+    let plaintext = (0..200_000).map(|x| (x % 251) as u8).collect::<Vec<u8>>();
+    let chunks = plaintext.chunks(1000).map(|c| c.to_vec()).collect();
+
+    let encrypted = encrypt(&recipient, chunks).await?;
+    assert_ne!(encrypted, plaintext);
+
+    let mut results = decrypt(identity.to_string().expose_secret(), vec![encrypted]).await?;
+    assert_eq!(results.pop().unwrap()?, plaintext);
+    Ok(())
+}
+
+#[tokio::test]
+async fn decrypt_fails_message_on_tampered_payload() -> Result<(), StdError> {
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public().to_string();
+    let plaintext = b"some secret payload".to_vec();
+
+    let encrypted = encrypt(&recipient, vec![plaintext.clone()]).await?;
+    let mut tampered = encrypted.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let truncated = encrypted[..encrypted.len() - 1].to_vec();
+
+    let results = decrypt(
+        identity.to_string().expose_secret(),
+        vec![tampered, truncated, encrypted],
+    )
+    .await?;
+    assert!(results[0].is_err());
+    assert!(results[1].is_err());
+    // section keeps working after failed messages
+    assert_eq!(results[2].as_ref().unwrap(), &plaintext);
+    Ok(())
+}
+
+#[tokio::test]
+async fn decrypt_fails_message_with_wrong_identity() -> Result<(), StdError> {
+    let recipient = age::x25519::Identity::generate().to_public().to_string();
+    let other_identity = age::x25519::Identity::generate();
+
+    let encrypted = encrypt(&recipient, vec![b"payload".to_vec()]).await?;
+    let results = decrypt(other_identity.to_string().expose_secret(), vec![encrypted]).await?;
+    assert!(results[0].is_err());
+    Ok(())
+}
@@ -0,0 +1,450 @@
+//! Convert incoming dataframe stream into xlsx workbook
+//!
+//! # Details
+//! - Each message is written into a separate workbook, with single sheet named after message origin.
+//! - Values are written as native excel cells: numbers, booleans, strings and dates (with number format applied).
+//! - Workbook is assembled in memory and emitted as a single binary chunk once input message is exhausted.
+
+use crate::ToXlsx;
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook, Worksheet};
+use section::decimal::prelude::ToPrimitive;
+use section::message::TimeUnit;
+use section::prelude::*;
+use std::pin::pin;
+
+// max integer which can be represented in excel (f64) without precision loss
+const MAX_SAFE_INT: u64 = 1 << 53;
+// 1970-01-01 in excel serial date
+const UNIX_EPOCH_SERIAL: f64 = 25569.0;
+const SECONDS_IN_DAY: f64 = 86400.0;
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+struct ToXlsxMsg {
+    origin: String,
+    ack: Option<Ack>,
+    payload: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for ToXlsxMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToXlsxMsg")
+            .field("origin", &self.origin)
+            .finish()
+    }
+}
+
+impl ToXlsxMsg {
+    fn new(origin: String, ack: Ack, payload: Vec<u8>) -> Self {
+        Self {
+            origin,
+            ack: Some(ack),
+            payload: Some(payload),
+        }
+    }
+}
+
+impl Message for ToXlsxMsg {
+    fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        let payload = self.payload.take().map(Chunk::Byte);
+        Box::pin(async move { Ok(payload) })
+    }
+
+    fn ack(&mut self) -> Ack {
+        self.ack.take().unwrap_or(Box::pin(async {}))
+    }
+}
+
+struct Formats {
+    header: Format,
+    date: Format,
+    time: Format,
+    timestamp: Format,
+}
+
+impl Formats {
+    fn new() -> Self {
+        Self {
+            header: Format::new()
+                .set_bold()
+                .set_background_color(Color::Theme(0, 2))
+                .set_border_bottom(FormatBorder::Thin),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            time: Format::new().set_num_format("hh:mm:ss"),
+            timestamp: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+        }
+    }
+}
+
+impl ToXlsx {
+    fn write_header(
+        &self,
+        worksheet: &mut Worksheet,
+        formats: &Formats,
+        columns: &[Column<'_>],
+    ) -> Result<(), SectionError> {
+        for (pos, column) in columns.iter().enumerate() {
+            let col = to_col_num(pos)?;
+            match self.style_header {
+                true => {
+                    worksheet.write_string_with_format(0, col, column.name(), &formats.header)?
+                }
+                false => worksheet.write_string(0, col, column.name())?,
+            };
+        }
+        if self.style_header {
+            worksheet.set_freeze_panes(1, 0)?;
+        }
+        Ok(())
+    }
+
+    fn write_dataframe(
+        &self,
+        worksheet: &mut Worksheet,
+        formats: &Formats,
+        row: &mut u32,
+        df: &dyn DataFrame,
+    ) -> Result<(), SectionError> {
+        let mut columns = df.columns();
+        // rows are terminated by exhausted column, dataframe without columns has no rows
+        if columns.is_empty() {
+            return Ok(());
+        }
+        'outer: loop {
+            for (pos, column) in columns.iter_mut().enumerate() {
+                let value = match column.next() {
+                    Some(value) => value,
+                    None => break 'outer,
+                };
+                let col = to_col_num(pos)?;
+                let row = *row;
+                match value {
+                    ValueView::Null => (),
+                    ValueView::Bool(v) => {
+                        worksheet.write_boolean(row, col, v)?;
+                    }
+                    ValueView::I8(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::I16(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::I32(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::I64(v) if v.unsigned_abs() <= MAX_SAFE_INT => {
+                        worksheet.write_number(row, col, v as f64)?;
+                    }
+                    ValueView::U8(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::U16(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::U32(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::U64(v) if v <= MAX_SAFE_INT => {
+                        worksheet.write_number(row, col, v as f64)?;
+                    }
+                    ValueView::F32(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::F64(v) => {
+                        worksheet.write_number(row, col, v)?;
+                    }
+                    ValueView::Decimal(v) => {
+                        match v.to_f64() {
+                            Some(v) => worksheet.write_number(row, col, v)?,
+                            None => worksheet.write_string(row, col, v.to_string())?,
+                        };
+                    }
+                    ValueView::Str(v) => {
+                        worksheet.write_string(row, col, v)?;
+                    }
+                    ValueView::Uuid(v) => {
+                        worksheet.write_string(row, col, v.to_string())?;
+                    }
+                    ValueView::Date(tu, t) => {
+                        let date = to_excel_datetime(tu, t).floor();
+                        worksheet.write_number_with_format(row, col, date, &formats.date)?;
+                    }
+                    ValueView::Time(tu, t) => {
+                        let time = to_excel_datetime(tu, t).fract();
+                        worksheet.write_number_with_format(row, col, time, &formats.time)?;
+                    }
+                    ValueView::TimeStamp(tu, t) | ValueView::TimeStampUTC(tu, t) => {
+                        let timestamp = to_excel_datetime(tu, t);
+                        worksheet.write_number_with_format(
+                            row,
+                            col,
+                            timestamp,
+                            &formats.timestamp,
+                        )?;
+                    }
+                    // integers which don't fit into f64 are written as strings to avoid precision loss
+                    ValueView::I64(_) | ValueView::U64(_) => {
+                        worksheet.write_string(row, col, value.to_string())?;
+                    }
+                    _ => Err(format!(
+                        "'{}' has unsupported value type: {:?}",
+                        column.name(),
+                        column.data_type()
+                    ))?,
+                }
+            }
+            *row += 1;
+        }
+        Ok(())
+    }
+}
+
+fn to_col_num(pos: usize) -> Result<u16, SectionError> {
+    Ok(u16::try_from(pos).map_err(|_| "too many columns")?)
+}
+
+// convert unix time into excel serial datetime
+fn to_excel_datetime(tu: TimeUnit, t: i64) -> f64 {
+    let seconds = match tu {
+        TimeUnit::Second => t as f64,
+        TimeUnit::Millisecond => t as f64 / 1_000.0,
+        TimeUnit::Microsecond => t as f64 / 1_000_000.0,
+        TimeUnit::Nanosecond => t as f64 / 1_000_000_000.0,
+    };
+    UNIX_EPOCH_SERIAL + seconds / SECONDS_IN_DAY
+}
+
+// excel sheet names are limited to 31 chars and can't contain any of []:*?/\
+fn to_sheet_name(origin: &str) -> String {
+    let name = origin
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let name = name.trim_matches('\'');
+    // keep tail of origin, since it's usually the most specific part (file name, table name)
+    let skip = name.chars().count().saturating_sub(MAX_SHEET_NAME_LEN);
+    let name = name.chars().skip(skip).collect::<String>();
+    match name.trim_matches('\'') {
+        "" => "Sheet1".into(),
+        name => name.into(),
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for ToXlsx
+where
+    Input: SectionStream,
+    Output: SectionSink,
+    SectionChan: SectionChannel,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
+        Box::pin(async move {
+            let mut input = pin!(input);
+            let mut output = pin!(output);
+            let formats = Formats::new();
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = msg.ok_or("input closed")?;
+                        let mut worksheet = Worksheet::new();
+                        worksheet.set_name(to_sheet_name(msg.origin()))?;
+                        let mut header_written = false;
+                        let mut row = 1;
+                        while let Some(chunk) = msg.next().await? {
+                            let df = match chunk {
+                                Chunk::DataFrame(df) => df,
+                                _ => Err("xlsx destination expects dataframe input")?
+                            };
+                            if !header_written {
+                                header_written = true;
+                                self.write_header(&mut worksheet, &formats, df.columns().as_slice())?;
+                            }
+                            self.write_dataframe(&mut worksheet, &formats, &mut row, &*df)?;
+                        }
+                        if self.autofit {
+                            worksheet.autofit();
+                        }
+                        let payload = tokio::task::spawn_blocking(move || {
+                            let mut workbook = Workbook::new();
+                            workbook.push_worksheet(worksheet);
+                            workbook.save_to_buffer()
+                        })
+                        .await??;
+                        let out = ToXlsxMsg::new(msg.origin().to_string(), msg.ack(), payload);
+                        output.send(Box::new(out)).await?;
+                    },
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_sheet_name() {
+        assert_eq!(to_sheet_name("report"), "report");
+        assert_eq!(
+            to_sheet_name("path/to/file.xlsx:Sheet1"),
+            "path_to_file.xlsx_Sheet1"
+        );
+        assert_eq!(
+            to_sheet_name("s3://bucket/some/very/long/path/orders.csv"),
+            "_some_very_long_path_orders.csv"
+        );
+        assert_eq!(to_sheet_name("''"), "Sheet1");
+        assert_eq!(to_sheet_name(""), "Sheet1");
+    }
+
+    #[derive(Debug)]
+    struct TestDf {
+        columns: Vec<(&'static str, DataType, Vec<Value>)>,
+    }
+
+    impl DataFrame for TestDf {
+        fn columns(&self) -> Vec<Column<'_>> {
+            self.columns
+                .iter()
+                .map(|(name, dt, values)| {
+                    Column::new(name, *dt, Box::new(values.iter().map(Into::into)))
+                })
+                .collect()
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestMsg {
+        df: Option<Box<dyn DataFrame>>,
+    }
+
+    impl Message for TestMsg {
+        fn origin(&self) -> &str {
+            "test:origin"
+        }
+
+        fn next(&mut self) -> Next<'_> {
+            let df = self.df.take().map(Chunk::DataFrame);
+            Box::pin(async move { Ok(df) })
+        }
+
+        fn ack(&mut self) -> Ack {
+            Box::pin(async {})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_to_xlsx() -> Result<(), SectionError> {
+        use calamine::{DataType as ExcelDataType, Reader, Xlsx};
+        use section::dummy::DummySectionChannel;
+        use tokio_stream::wrappers::ReceiverStream;
+        use tokio_util::sync::PollSender;
+
+        let (tx_in, rx_in) = tokio::sync::mpsc::channel::<SectionMessage>(1);
+        let (tx_out, mut rx_out) = tokio::sync::mpsc::channel::<SectionMessage>(1);
+        let tx_out = PollSender::new(tx_out).sink_map_err(|_| "send error".into());
+        let handle = tokio::spawn(ToXlsx::default().start(
+            ReceiverStream::new(rx_in),
+            tx_out,
+            DummySectionChannel::new(),
+        ));
+
+        let df = TestDf {
+            columns: vec![
+                (
+                    "id",
+                    DataType::I64,
+                    vec![Value::I64(1), Value::I64(i64::MAX)],
+                ),
+                (
+                    "name",
+                    DataType::Str,
+                    vec![Value::from("foo".to_string()), Value::Null],
+                ),
+                (
+                    "flag",
+                    DataType::Bool,
+                    vec![Value::Bool(true), Value::Bool(false)],
+                ),
+                (
+                    "ts",
+                    DataType::TimeStamp(TimeUnit::Second),
+                    vec![
+                        Value::TimeStamp(TimeUnit::Second, 1_704_110_400),
+                        Value::TimeStamp(TimeUnit::Second, 0),
+                    ],
+                ),
+            ],
+        };
+        tx_in
+            .send(Box::new(TestMsg {
+                df: Some(Box::new(df)),
+            }))
+            .await
+            .map_err(|_| "send error")?;
+        let mut msg = rx_out.recv().await.ok_or("output closed")?;
+        assert_eq!(msg.origin(), "test:origin");
+        let payload = match msg.next().await? {
+            Some(Chunk::Byte(payload)) => payload,
+            other => Err(format!("unexpected chunk: {other:?}"))?,
+        };
+        assert!(msg.next().await?.is_none());
+        handle.abort();
+
+        let mut workbook = Xlsx::new(std::io::Cursor::new(payload))?;
+        assert_eq!(workbook.sheet_names(), vec!["test_origin".to_string()]);
+        let range = workbook
+            .worksheet_range("test_origin")
+            .ok_or("sheet not found")??;
+        let rows = range.rows().collect::<Vec<_>>();
+        assert_eq!(
+            rows[0],
+            ["id", "name", "flag", "ts"].map(|s| ExcelDataType::String(s.into()))
+        );
+        assert_eq!(rows[1][0], ExcelDataType::Float(1.0));
+        assert_eq!(rows[1][1], ExcelDataType::String("foo".into()));
+        assert_eq!(rows[1][2], ExcelDataType::Bool(true));
+        assert_eq!(
+            rows[1][3].as_datetime().map(|d| d.to_string()),
+            Some("2024-01-01 12:00:00".into())
+        );
+        assert_eq!(rows[2][0], ExcelDataType::String(i64::MAX.to_string()));
+        assert_eq!(rows[2][1], ExcelDataType::Empty);
+        assert_eq!(rows[2][2], ExcelDataType::Bool(false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_dataframe_without_columns() -> Result<(), SectionError> {
+        let mut worksheet = Worksheet::new();
+        let mut row = 1;
+        let df = TestDf { columns: vec![] };
+        ToXlsx::default().write_dataframe(&mut worksheet, &Formats::new(), &mut row, &df)?;
+        assert_eq!(row, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_excel_datetime() {
+        assert_eq!(to_excel_datetime(TimeUnit::Second, 0), 25569.0);
+        // 2024-01-01 12:00:00
+        assert_eq!(
+            to_excel_datetime(TimeUnit::Millisecond, 1_704_110_400_000),
+            45292.5
+        );
+    }
+}
@@ -1,11 +1,15 @@
 #[cfg(feature = "section")]
+pub mod destination;
+#[cfg(feature = "section")]
 pub mod source;
 
 #[derive(Debug, Clone, config::Configuration)]
 #[section(output=dataframe)]
 pub struct Excel {
+    #[field(template)]
     path: String,
-    sheets: String,
+    #[validate(required)]
+    sheets: Vec<String>,
     stringify: bool,
 }
 
@@ -13,8 +17,33 @@ impl Default for Excel {
     fn default() -> Self {
         Self {
             path: "".into(),
-            sheets: "*".into(),
+            sheets: vec!["*".into()],
             stringify: false,
         }
     }
 }
+
+#[derive(Debug, Clone, config::Configuration)]
+#[section(input=dataframe, output=bin)]
+pub struct ToXlsx {
+    style_header: bool,
+    autofit: bool,
+}
+
+impl Default for ToXlsx {
+    fn default() -> Self {
+        Self {
+            style_header: true,
+            autofit: true,
+        }
+    }
+}
+
+impl ToXlsx {
+    pub fn new(style_header: bool, autofit: bool) -> Self {
+        Self {
+            style_header,
+            autofit,
+        }
+    }
+}
@@ -272,7 +272,11 @@ where
     fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
         Box::pin(async move {
             let path = &self.path;
-            let sheets = self.sheets.split(',').collect::<Vec<&str>>();
+            let sheets = self
+                .sheets
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<&str>>();
             let (tx, rx) = tokio::sync::mpsc::channel(4);
 
             // on init, sync all the files.
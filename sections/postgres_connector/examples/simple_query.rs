@@ -17,7 +17,7 @@ async fn main() -> Result<(), SectionError> {
         "password",
         "postgres",
         "public",
-        5,
+        std::time::Duration::from_secs(5),
         "select * from test",
     );
     let (tx, mut rx) = futures::channel::mpsc::channel(1);
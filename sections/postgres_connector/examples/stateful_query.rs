@@ -20,7 +20,7 @@ async fn main() -> Result<(), SectionError> {
         "password",
         "postgres",
         "public",
-        5,
+        std::time::Duration::from_secs(5),
         "select * from test where id > $id::i64 order by id limit 10000",
     );
     let (tx, mut rx) = futures::channel::mpsc::channel(1);
@@ -20,7 +20,7 @@ async fn main() -> Result<(), SectionError> {
         "password",
         "postgres",
         "public",
-        5,
+        std::time::Duration::from_secs(5),
         "select * from test where id between $id::i64 + 1 and $id::i64 + 1000000 order by id",
     );
     let (tx, mut rx) = futures::channel::mpsc::channel(1);
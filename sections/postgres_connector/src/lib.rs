@@ -9,17 +9,31 @@ pub(crate) mod stateful_query;
 #[cfg(feature = "section")]
 pub(crate) type Result<T, E = section::SectionError> = std::result::Result<T, E>;
 
+use std::time::Duration;
+
+/// Periodically runs query against Postgres database and streams result
 #[derive(Debug, Clone, config::Configuration)]
 #[section(output=dataframe)]
 pub struct PostgresSource {
+    #[field(group = "Connection", placeholder = "localhost", template)]
     host: String,
+    #[field(group = "Connection")]
+    #[validate(min = 1)]
     port: u16,
+    #[field(group = "Connection", template)]
     user: String,
+    #[field(group = "Connection", template)]
     #[field_type(password)]
     password: String,
+    #[field(group = "Connection", template)]
     database: String,
+    /// Name of the stream origin, attached to every message
+    #[field(template)]
     origin: String,
-    poll_interval: u64,
+    /// Delay between query runs
+    #[field(advanced)]
+    #[validate(min = 1)]
+    poll_interval: Duration,
     #[field_type(text_area)]
     query: String,
 }
@@ -33,7 +47,7 @@ impl PostgresSource {
         password: impl Into<String>,
         database: impl Into<String>,
         origin: impl Into<String>,
-        poll_interval: u64,
+        poll_interval: Duration,
         query: impl Into<String>,
     ) -> Self {
         Self {
@@ -58,23 +72,36 @@ impl Default for PostgresSource {
             "",
             "postgres",
             "test",
-            30,
+            Duration::from_secs(30),
             "select * from test",
         )
     }
 }
 
+/// Writes incoming dataframes into Postgres tables
 #[derive(Debug, Clone, config::Configuration)]
 #[section(input=dataframe)]
 pub struct PostgresDestination {
+    #[field(group = "Connection", placeholder = "localhost", template)]
     host: String,
+    #[field(group = "Connection")]
+    #[validate(min = 1)]
     port: u16,
+    #[field(group = "Connection", template)]
     user: String,
+    #[field(group = "Connection", template)]
     #[field_type(password)]
     password: String,
+    #[field(group = "Connection", template)]
     database: String,
+    /// Schema, where tables are created
+    #[field(template)]
     schema: String,
+    /// Truncate table before the first insert
     truncate: bool,
+    /// Maximum number of bind parameters in single insert statement,
+    /// batches with more parameters are split
+    #[field(advanced)]
     max_parameters: u32,
 }
 
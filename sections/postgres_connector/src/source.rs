@@ -53,7 +53,7 @@ impl PostgresSourceInner {
         url: impl Into<String>,
         origin: impl AsRef<str>,
         query: impl AsRef<str>,
-        poll_interval: u64,
+        poll_interval: Duration,
     ) -> Result<Self> {
         let query = query.as_ref();
         let parser = stateful_query::StatefulVariableParser::new(query)?;
@@ -61,7 +61,6 @@ impl PostgresSourceInner {
             Some((stateful_var, query)) => (Some(stateful_var), query),
             None => (None, query.to_string()),
         };
-        let poll_interval = Duration::from_secs(poll_interval);
         Ok(Self {
             url: url.into(),
             origin: Arc::from(origin.as_ref()),
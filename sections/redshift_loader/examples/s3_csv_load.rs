@@ -75,7 +75,7 @@ async fn main() -> Result<(), SectionError> {
         &cli.database,
         &cli.iam_role,
         &cli.region,
-        "CSV",
+        redshift_loader::DataFormat::Csv,
         true,
     );
 
@@ -13,7 +13,7 @@ use std::{
 use section::prelude::*;
 use sqlx::{postgres::PgConnectOptions, ConnectOptions};
 
-use crate::RedshiftLoader;
+use crate::{DataFormat, RedshiftLoader};
 
 /// Escape value
 pub fn escape(name: impl AsRef<str>, symbol: char) -> String {
@@ -56,9 +56,8 @@ where
                 .connect()
                 .await?;
 
-            let data_format = match self.data_format.to_uppercase().as_str() {
-                "CSV" => "CSV",
-                other => Err(format!("unsupported data format: {other}"))?,
+            let data_format = match self.data_format {
+                DataFormat::Csv => "CSV",
             };
 
             loop {
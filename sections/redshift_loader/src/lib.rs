@@ -1,18 +1,31 @@
 #[cfg(feature = "section")]
 pub mod destination;
 
+/// Format of files loaded with `COPY`
+#[derive(Debug, Clone, Copy, PartialEq, config::Enum)]
+pub enum DataFormat {
+    Csv,
+}
+
 #[derive(Debug, Clone, config::Configuration)]
 #[section(input=bin)]
 pub struct RedshiftLoader {
+    #[field(template)]
     host: String,
+    #[validate(min = 1)]
     port: u16,
+    #[field(template)]
     user: String,
+    #[field(template)]
     #[field_type(password)]
     password: String,
+    #[field(template)]
     database: String,
+    #[field(template)]
     iam_role: String,
-    data_format: String,
+    data_format: DataFormat,
     ignore_header: bool,
+    #[field(template)]
     region: String,
 }
 
@@ -26,7 +39,7 @@ impl RedshiftLoader {
         database: impl Into<String>,
         iam_role: impl Into<String>,
         region: impl Into<String>,
-        data_format: impl Into<String>,
+        data_format: DataFormat,
         ignore_header: bool,
     ) -> Self {
         Self {
@@ -37,7 +50,7 @@ impl RedshiftLoader {
             database: database.into(),
             iam_role: iam_role.into(),
             region: region.into(),
-            data_format: data_format.into(),
+            data_format,
             ignore_header,
         }
     }
@@ -53,7 +66,7 @@ impl Default for RedshiftLoader {
             "redshift",
             "arn:aws:iam::123456789012:role/MyExampleRole",
             "us-east-1",
-            "CSV",
+            DataFormat::Csv,
             true,
         )
     }
@@ -30,7 +30,7 @@ async fn main() -> Result<(), SectionError> {
         cli.secret_key,
         false,
         "",
-        5,
+        std::time::Duration::from_secs(5),
     );
 
     let (tx, rx) = channel::<SectionMessage>(1);
@@ -5,21 +5,30 @@ pub mod source;
 #[cfg(feature = "section")]
 pub(crate) mod static_credentials_provider;
 
+use std::time::Duration;
+
 #[cfg(feature = "section")]
 pub(crate) type Result<T, E = section::SectionError> = std::result::Result<T, E>;
 
 #[derive(Debug, Clone, config::Configuration)]
 #[section(output=bin_or_dataframe)]
 pub struct S3Source {
+    #[field(template)]
+    #[validate(url)]
     endpoint: String,
+    #[field(template)]
     bucket: String,
+    #[field(template)]
     region: String,
+    #[field(template)]
     access_key_id: String,
+    #[field(template)]
     #[field_type(password)]
     secret_key: String,
     stream_binary: bool,
     start_after: String,
-    interval: u64,
+    #[validate(min = 1)]
+    interval: Duration,
 }
 
 impl S3Source {
@@ -32,7 +41,7 @@ impl S3Source {
         secret_key: impl Into<String>,
         stream_binary: bool,
         start_after: impl Into<String>,
-        interval: u64,
+        interval: Duration,
     ) -> Self {
         Self {
             endpoint: endpoint.into(),
@@ -57,7 +66,7 @@ impl Default for S3Source {
             "",
             false,
             "",
-            30,
+            Duration::from_secs(30),
         )
     }
 }
@@ -65,11 +74,19 @@ impl Default for S3Source {
 #[derive(Debug, Clone, config::Configuration)]
 #[section(input=bin)]
 pub struct S3Destination {
+    #[field(template)]
+    #[validate(url)]
     endpoint: String,
+    #[field(template)]
     bucket: String,
+    #[field(template)]
     region: String,
+    #[field(template)]
     access_key_id: String,
+    #[field(template)]
     secret_key: String,
+    // s3 multipart upload requires parts of at least 5 MiB
+    #[validate(min = 5242880)]
     max_upload_part_size: usize,
 }
 
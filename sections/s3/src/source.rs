@@ -30,7 +30,7 @@ impl TryFrom<S3Source> for S3SourceInner {
             value.secret_key,
             value.stream_binary,
             value.start_after,
-            Duration::from_secs(value.interval),
+            value.interval,
         )
     }
 }
@@ -0,0 +1,102 @@
+//! Sandboxed instance of transform module
+
+use section::SectionError;
+use wasmi::{Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+type Result<T, E = SectionError> = std::result::Result<T, E>;
+
+const MIB: usize = 1024 * 1024;
+
+pub(crate) struct Guest {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    transform: TypedFunc<(i32, i32), i64>,
+    dealloc: Option<TypedFunc<(i32, i32), ()>>,
+    fuel: u64,
+}
+
+impl std::fmt::Debug for Guest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Guest").field("fuel", &self.fuel).finish()
+    }
+}
+
+impl Guest {
+    pub fn new(wasm: &[u8], fuel: u64, memory_limit_mb: u32) -> Result<Self> {
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm)
+            .map_err(|e| format!("failed to compile wasm module: {e}"))?;
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(memory_limit_mb as usize * MIB)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        // module start function runs on the same budget as transform calls
+        store.set_fuel(fuel).map_err(|e| format!("{e}"))?;
+        let instance = Linker::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| format!("failed to instantiate wasm module: {e}"))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("wasm module doesn't export memory")?;
+        let alloc = instance
+            .get_typed_func(&store, "alloc")
+            .map_err(|e| format!("wasm module export 'alloc': {e}"))?;
+        let transform = instance
+            .get_typed_func(&store, "transform")
+            .map_err(|e| format!("wasm module export 'transform': {e}"))?;
+        let dealloc = instance.get_typed_func(&store, "dealloc").ok();
+        Ok(Self {
+            store,
+            memory,
+            alloc,
+            transform,
+            dealloc,
+            fuel,
+        })
+    }
+
+    /// pass Arrow IPC stream through module transform
+    pub fn transform(&mut self, input: &[u8]) -> Result<Vec<u8>> {
+        self.store.set_fuel(self.fuel).map_err(|e| format!("{e}"))?;
+        let len = i32::try_from(input.len()).map_err(|_| "input is too large")?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(|e| format!("wasm alloc failed: {e}"))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, input)
+            .map_err(|e| format!("failed to write input into wasm memory: {e}"))?;
+        let output = self
+            .transform
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| format!("wasm transform failed: {e}"))?;
+        let (out_ptr, out_len) = ((output >> 32) as u32, output as u32);
+        // output location is controlled by module, it's checked against memory before buffer is allocated
+        let buf = (out_ptr as usize)
+            .checked_add(out_len as usize)
+            .and_then(|end| self.memory.data(&self.store).get(out_ptr as usize..end))
+            .ok_or_else(|| {
+                format!("wasm output out of memory bounds: ptr {out_ptr}, len {out_len}")
+            })?
+            .to_vec();
+        if let Some(dealloc) = self.dealloc {
+            let mut buffers = vec![(ptr, len)];
+            // module is allowed to return input buffer as output
+            if out_ptr as i32 != ptr && out_len != 0 {
+                buffers.push((out_ptr as i32, out_len as i32));
+            }
+            for args in buffers {
+                dealloc
+                    .call(&mut self.store, args)
+                    .map_err(|e| format!("wasm dealloc failed: {e}"))?;
+            }
+        }
+        Ok(buf)
+    }
+}
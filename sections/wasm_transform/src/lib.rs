@@ -0,0 +1,67 @@
+//! User-defined dataframe transforms, compiled to WebAssembly
+//!
+//! Module is executed by interpreter inside the daemon, each transform call is limited by fuel
+//! and module linear memory is capped, so faulty module fails the section instead of the daemon.
+//!
+//! Dataframes cross the module boundary as Arrow IPC streams. Module exports:
+//!
+//! ```text
+//! memory                              linear memory
+//! alloc(len: i32) -> i32              allocate input buffer of `len` bytes, return its offset
+//! transform(ptr: i32, len: i32) -> i64
+//!                                     transform input buffer, return output buffer as `offset << 32 | len`
+//! dealloc(ptr: i32, len: i32)         optional, release buffer after host is done with it
+//! ```
+//!
+//! Output may contain any number of record batches, empty output drops the input dataframe.
+//! Module signals errors by trapping (e.g. `unreachable` or panic in Rust guest).
+#[cfg(feature = "section")]
+mod guest;
+#[cfg(feature = "section")]
+pub mod transform;
+
+#[derive(Debug, Clone, config::Configuration)]
+#[section(input=dataframe, output=dataframe)]
+pub struct WasmTransform {
+    /// Path to `.wasm` module on daemon host, ignored if module is embedded
+    #[field(placeholder = "/opt/transforms/transform.wasm", template)]
+    path: String,
+    /// Base64 encoded `.wasm` module
+    #[field_type(text_area)]
+    module: String,
+    /// Instructions budget of single dataframe transform
+    #[field(advanced)]
+    #[validate(min = 1)]
+    fuel: u64,
+    /// Maximum size of module linear memory, in MiB
+    #[field(advanced)]
+    #[validate(min = 1)]
+    memory_limit_mb: u32,
+}
+
+impl Default for WasmTransform {
+    fn default() -> Self {
+        Self {
+            path: "".into(),
+            module: "".into(),
+            fuel: 1_000_000_000,
+            memory_limit_mb: 64,
+        }
+    }
+}
+
+impl WasmTransform {
+    pub fn new(
+        path: impl Into<String>,
+        module: impl Into<String>,
+        fuel: u64,
+        memory_limit_mb: u32,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            module: module.into(),
+            fuel,
+            memory_limit_mb,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Runs incoming dataframes through wasm module
+//!
+//! Each input dataframe is transformed separately, output record batches are sent as separate dataframes
+//! of the message, which carries ack of the input message.
+
+use crate::{guest::Guest, WasmTransform};
+use arrow_msg::{df_to_ipc, ipc_to_recordbatches};
+use base64::Engine as _;
+use section::{
+    command_channel::{Command, SectionChannel},
+    futures::{self, FutureExt, Sink, SinkExt, Stream, StreamExt},
+    message::{Ack, Chunk, Message, Next},
+    section::Section,
+    SectionError, SectionFuture, SectionMessage,
+};
+use std::pin::pin;
+use tokio::sync::mpsc::{channel, Receiver};
+
+type Result<T, E = SectionError> = std::result::Result<T, E>;
+
+impl WasmTransform {
+    async fn load_module(&self) -> Result<Vec<u8>> {
+        match (self.module.trim(), self.path.as_str()) {
+            ("", "") => Err("either module or path to module should be set")?,
+            ("", path) => Ok(tokio::fs::read(path)
+                .await
+                .map_err(|e| format!("failed to read wasm module from {path}: {e}"))?),
+            (module, _) => Ok(base64::engine::general_purpose::STANDARD
+                .decode(module)
+                .map_err(|e| format!("failed to decode embedded wasm module: {e}"))?),
+        }
+    }
+}
+
+struct WasmMsg {
+    origin: String,
+    ack: Option<Ack>,
+    rx: Receiver<Option<Chunk>>,
+}
+
+impl std::fmt::Debug for WasmMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmMsg")
+            .field("origin", &self.origin)
+            .finish()
+    }
+}
+
+impl WasmMsg {
+    fn new(origin: &str, ack: Ack, rx: Receiver<Option<Chunk>>) -> Self {
+        Self {
+            origin: origin.into(),
+            ack: Some(ack),
+            rx,
+        }
+    }
+}
+
+impl Message for WasmMsg {
+    fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        Box::pin(async move {
+            match self.rx.recv().await {
+                Some(res) => Ok(res),
+                None => Err("stream closed".into()),
+            }
+        })
+    }
+
+    fn ack(&mut self) -> Ack {
+        self.ack.take().unwrap_or(Box::pin(async {}))
+    }
+}
+
+// module execution is CPU bound, so it's moved off async runtime
+async fn transform(mut guest: Guest, input: Vec<u8>) -> Result<(Guest, Vec<u8>)> {
+    tokio::task::spawn_blocking(move || guest.transform(&input).map(|output| (guest, output)))
+        .await?
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for WasmTransform
+where
+    Input: Stream<Item = SectionMessage> + Send + 'static,
+    Output: Sink<SectionMessage, Error = SectionError> + Send + 'static,
+    SectionChan: SectionChannel,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
+        Box::pin(async move {
+            let wasm = self.load_module().await?;
+            let (fuel, memory_limit_mb) = (self.fuel, self.memory_limit_mb);
+            let mut guest =
+                tokio::task::spawn_blocking(move || Guest::new(&wasm, fuel, memory_limit_mb))
+                    .await??;
+            let mut input = pin!(input);
+            let mut output = pin!(output);
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = msg.ok_or("input closed")?;
+                        let (tx, rx) = channel(1);
+                        let ack = msg.ack();
+                        let out_msg = WasmMsg::new(msg.origin(), ack, rx);
+                        output.send(Box::new(out_msg)).await?;
+                        while let Some(chunk) = msg.next().await? {
+                            let df = match chunk {
+                                Chunk::DataFrame(df) => df,
+                                Chunk::Byte(_) => Err("WasmTransform section expects dataframe input")?,
+                            };
+                            let ipc = df_to_ipc(df.as_ref())?;
+                            let (returned, ipc) = transform(guest, ipc).await?;
+                            guest = returned;
+                            if ipc.is_empty() {
+                                continue;
+                            }
+                            for rb in ipc_to_recordbatches(&ipc)? {
+                                tx.send(Some(Chunk::DataFrame(Box::new(rb)))).await.map_err(|_| "stream error")?;
+                            }
+                        }
+                        tx.send(None).await.map_err(|_| "stream error")?;
+                    }
+                }
+            }
+        })
+    }
+}
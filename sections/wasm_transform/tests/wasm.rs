@@ -0,0 +1,258 @@
+use base64::Engine as _;
+use section::futures::{SinkExt, StreamExt};
+use section::message::{Ack, Chunk, Column, DataFrame, DataType, Message, Next, Value};
+use section::section::Section as _;
+use section::{dummy::*, SectionMessage};
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::PollSender;
+use wasm_transform::WasmTransform;
+
+type StdError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+// returns input buffer as output
+const IDENTITY: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (drop (memory.grow
+          (i32.add
+            (i32.div_u
+              (i32.sub (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+              (i32.const 65536))
+            (i32.const 1))))))
+    (local.get $ptr))
+  (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len)))))
+"#;
+
+// drops every dataframe
+const FILTER_ALL: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "transform") (param i32 i32) (result i64) (i64.const 0)))
+"#;
+
+const INFINITE_LOOP: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "transform") (param i32 i32) (result i64)
+    (loop $loop (br $loop))
+    (i64.const 0)))
+"#;
+
+// returns output, which doesn't fit into memory
+const OUT_OF_BOUNDS: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "transform") (param i32 i32) (result i64) (i64.const -1)))
+"#;
+
+// 16MiB of initial memory
+const LARGE_MEMORY: &str = r#"
+(module
+  (memory (export "memory") 256)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "transform") (param i32 i32) (result i64) (i64.const 0)))
+"#;
+
+fn channel<T>(buf_size: usize) -> (PollSender<T>, ReceiverStream<T>)
+where
+    T: Send + 'static,
+{
+    let (tx, rx): (Sender<T>, Receiver<T>) = tokio::sync::mpsc::channel(buf_size);
+    (PollSender::new(tx), ReceiverStream::new(rx))
+}
+
+fn embedded(wat: &str, fuel: u64, memory_limit_mb: u32) -> WasmTransform {
+    let wasm = wat::parse_str(wat).unwrap();
+    let module = base64::engine::general_purpose::STANDARD.encode(wasm);
+    WasmTransform::new("", module, fuel, memory_limit_mb)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TestDataFrame {
+    columns: Vec<(String, DataType, Vec<Value>)>,
+}
+
+impl DataFrame for TestDataFrame {
+    fn columns(&self) -> Vec<Column<'_>> {
+        self.columns
+            .iter()
+            .map(|(name, data_type, values)| {
+                Column::new(name, *data_type, Box::new(values.iter().map(Into::into)))
+            })
+            .collect()
+    }
+}
+
+impl From<&dyn DataFrame> for TestDataFrame {
+    fn from(df: &dyn DataFrame) -> Self {
+        let columns = df
+            .columns()
+            .into_iter()
+            .map(|column| {
+                let name = column.name().to_string();
+                let data_type = column.data_type();
+                (name, data_type, column.map(|v| Value::from(&v)).collect())
+            })
+            .collect();
+        Self { columns }
+    }
+}
+
+#[derive(Debug)]
+struct TestMsg {
+    chunks: Vec<Chunk>,
+}
+
+impl TestMsg {
+    fn new(chunks: Vec<Chunk>) -> Self {
+        Self {
+            chunks: chunks.into_iter().rev().collect(),
+        }
+    }
+}
+
+impl Message for TestMsg {
+    fn origin(&self) -> &str {
+        "test"
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        let chunk = self.chunks.pop();
+        Box::pin(async move { Ok(chunk) })
+    }
+
+    fn ack(&mut self) -> Ack {
+        Box::pin(async {})
+    }
+}
+
+fn test_dataframe() -> TestDataFrame {
+    TestDataFrame {
+        columns: vec![
+            (
+                "id".into(),
+                DataType::I64,
+                vec![Value::I64(1), Value::I64(2)],
+            ),
+            (
+                "name".into(),
+                DataType::Str,
+                vec![Value::from("one".to_string()), Value::Null],
+            ),
+        ],
+    }
+}
+
+async fn run(
+    section: WasmTransform,
+    dataframes: Vec<TestDataFrame>,
+) -> Result<Vec<TestDataFrame>, StdError> {
+    let (input_tx, input_rx) = channel::<SectionMessage>(1);
+    let (output_tx, mut output_rx) = channel::<SectionMessage>(1);
+    let mut input_tx = input_tx.sink_map_err(|_| "chan closed");
+    let output_tx = output_tx.sink_map_err(|_| "chan closed".into());
+    let mut handle = tokio::spawn(section.start(input_rx, output_tx, DummySectionChannel::new()));
+    let chunks = dataframes
+        .into_iter()
+        .map(|df| Chunk::DataFrame(Box::new(df)))
+        .collect();
+    input_tx.send(Box::new(TestMsg::new(chunks))).await?;
+    // section error is reported instead of closed channel error
+    let mut msg = match output_rx.next().await {
+        Some(msg) => msg,
+        None => Err((&mut handle).await?.err().unwrap_or("output closed".into()))?,
+    };
+    let mut dataframes = vec![];
+    loop {
+        let chunk = match msg.next().await {
+            Ok(chunk) => chunk,
+            Err(e) => Err((&mut handle).await?.err().unwrap_or(e))?,
+        };
+        match chunk {
+            Some(Chunk::DataFrame(df)) => dataframes.push(TestDataFrame::from(df.as_ref())),
+            Some(Chunk::Byte(_)) => Err("unexpected binary chunk")?,
+            None => break,
+        }
+    }
+    handle.abort();
+    Ok(dataframes)
+}
+
+#[tokio::test]
+async fn wasm_identity() -> Result<(), StdError> {
+    let dataframes = vec![test_dataframe(), test_dataframe()];
+    let output = run(embedded(IDENTITY, 1_000_000, 16), dataframes.clone()).await?;
+    assert_eq!(output, dataframes);
+    Ok(())
+}
+
+#[tokio::test]
+async fn wasm_module_from_path() -> Result<(), StdError> {
+    let path = std::env::temp_dir().join(format!("wasm_transform_{}.wasm", std::process::id()));
+    std::fs::write(&path, wat::parse_str(IDENTITY)?)?;
+    let section = WasmTransform::new(path.to_string_lossy(), "", 1_000_000, 16);
+    let output = run(section, vec![test_dataframe()]).await;
+    std::fs::remove_file(&path).ok();
+    assert_eq!(output?, vec![test_dataframe()]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn wasm_empty_output_drops_dataframe() -> Result<(), StdError> {
+    let output = run(embedded(FILTER_ALL, 1_000_000, 16), vec![test_dataframe()]).await?;
+    assert_eq!(output, vec![]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn wasm_fuel_limit() -> Result<(), StdError> {
+    let result = run(
+        embedded(INFINITE_LOOP, 1_000_000, 16),
+        vec![test_dataframe()],
+    )
+    .await;
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("wasm transform failed"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn wasm_output_out_of_bounds() -> Result<(), StdError> {
+    let result = run(
+        embedded(OUT_OF_BOUNDS, 1_000_000, 16),
+        vec![test_dataframe()],
+    )
+    .await;
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("out of memory bounds"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn wasm_memory_limit() -> Result<(), StdError> {
+    let result = run(embedded(LARGE_MEMORY, 1_000_000, 1), vec![test_dataframe()]).await;
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("failed to instantiate wasm module"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn wasm_module_not_set() -> Result<(), StdError> {
+    let result = run(WasmTransform::default(), vec![test_dataframe()]).await;
+    assert!(result.is_err());
+    Ok(())
+}
@@ -0,0 +1,38 @@
+#[cfg(feature = "section")]
+mod mapping;
+#[cfg(feature = "section")]
+pub mod source;
+
+#[derive(Debug, Clone, config::Configuration)]
+#[section(input=bin, output=dataframe)]
+pub struct FromXml {
+    batch_size: usize,
+    // path to record element, e.g. `/Export/Records/Record`, `//Record` matches element at any depth
+    record_path: String,
+    // comma separated list of `column=path:type` mappings, path is relative to record element:
+    // `Name` - text of child element, `Address/City` - text of nested element,
+    // `@id` - attribute of record element, `Address/@zip` - attribute of child element.
+    // type is one of str, i64, f64, bool, defaults to str if omitted.
+    #[field_type(text_area)]
+    columns: String,
+}
+
+impl Default for FromXml {
+    fn default() -> Self {
+        Self {
+            batch_size: 512,
+            record_path: "".into(),
+            columns: "".into(),
+        }
+    }
+}
+
+impl FromXml {
+    pub fn new(batch_size: usize, record_path: &str, columns: &str) -> Self {
+        Self {
+            batch_size,
+            record_path: record_path.into(),
+            columns: columns.into(),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Record path and column mapping parsing
+
+use std::str::FromStr;
+
+use section::{
+    message::{DataType, Value},
+    SectionError,
+};
+
+type Result<T, E = SectionError> = std::result::Result<T, E>;
+
+/// XPath-lite record path
+///
+/// Supports absolute paths (`/Export/Records/Record`) and paths, which match at any depth (`//Records/Record`).
+#[derive(Debug, PartialEq)]
+pub(crate) struct RecordPath {
+    segments: Vec<String>,
+    anywhere: bool,
+}
+
+impl FromStr for RecordPath {
+    type Err = SectionError;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let path = path.trim();
+        let (anywhere, path) = match (path.strip_prefix("//"), path.strip_prefix('/')) {
+            (Some(path), _) => (true, path),
+            (None, Some(path)) => (false, path),
+            (None, None) => Err(format!(
+                "record path should start with '/' or '//': '{path}'"
+            ))?,
+        };
+        let segments = path
+            .split('/')
+            .map(|segment| match segment {
+                "" => Err(format!("empty segment in record path: '{path}'")),
+                segment => Ok(segment.to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { segments, anywhere })
+    }
+}
+
+impl RecordPath {
+    pub fn matches(&self, stack: &[String]) -> bool {
+        match self.anywhere {
+            true => stack.ends_with(&self.segments),
+            false => stack == self.segments,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ColumnType {
+    Str,
+    I64,
+    F64,
+    Bool,
+}
+
+impl FromStr for ColumnType {
+    type Err = SectionError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let ty = match s.trim().to_lowercase().as_str() {
+            "str" | "string" => Self::Str,
+            "i64" | "int" => Self::I64,
+            "f64" | "float" => Self::F64,
+            "bool" => Self::Bool,
+            other => Err(format!("unsupported column type: '{other}'"))?,
+        };
+        Ok(ty)
+    }
+}
+
+impl From<ColumnType> for DataType {
+    fn from(value: ColumnType) -> Self {
+        match value {
+            ColumnType::Str => DataType::Str,
+            ColumnType::I64 => DataType::I64,
+            ColumnType::F64 => DataType::F64,
+            ColumnType::Bool => DataType::Bool,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct ColumnMapping {
+    pub name: String,
+    // path to element, relative to record element
+    pub element: Vec<String>,
+    // attribute of element, element text is used if not set
+    pub attribute: Option<String>,
+    pub ty: ColumnType,
+}
+
+impl FromStr for ColumnMapping {
+    type Err = SectionError;
+
+    fn from_str(mapping: &str) -> Result<Self> {
+        let (name, path) = mapping.split_once('=').ok_or(format!(
+            "malformed column mapping, expected `column=path:type`: '{mapping}'"
+        ))?;
+        let (path, ty) = match path.rsplit_once(':') {
+            Some((path, ty)) => (path, ty.parse()?),
+            None => (path, ColumnType::Str),
+        };
+        let mut element = path
+            .trim()
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let attribute = match element.last().and_then(|last| last.strip_prefix('@')) {
+            Some(attribute) => {
+                let attribute = attribute.to_string();
+                element.pop();
+                Some(attribute)
+            }
+            None => None,
+        };
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            Err(format!("empty column name in mapping: '{mapping}'"))?
+        }
+        Ok(Self {
+            name,
+            element,
+            attribute,
+            ty,
+        })
+    }
+}
+
+impl ColumnMapping {
+    pub fn parse_list(mappings: &str) -> Result<Vec<Self>> {
+        let mappings = mappings
+            .split(',')
+            .map(str::trim)
+            .filter(|mapping| !mapping.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Self>>>()?;
+        if mappings.is_empty() {
+            Err("at least one column mapping is required")?
+        }
+        Ok(mappings)
+    }
+
+    pub fn parse(&self, value: String) -> Result<Value> {
+        let parsed = match self.ty {
+            ColumnType::Str => return Ok(Value::from(value)),
+            ColumnType::I64 => value.trim().parse().map(Value::I64).map_err(|_| ()),
+            ColumnType::F64 => value.trim().parse().map(Value::F64).map_err(|_| ()),
+            ColumnType::Bool => match value.trim() {
+                "true" | "1" => Ok(Value::Bool(true)),
+                "false" | "0" => Ok(Value::Bool(false)),
+                _ => Err(()),
+            },
+        };
+        Ok(parsed.map_err(|_| {
+            format!(
+                "failed to parse '{value}' in column '{}' as {:?}",
+                self.name, self.ty
+            )
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(path: &[&str]) -> Vec<String> {
+        path.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_record_path() {
+        let path: RecordPath = "/Export/Records/Record".parse().unwrap();
+        assert!(path.matches(&stack(&["Export", "Records", "Record"])));
+        assert!(!path.matches(&stack(&["Root", "Export", "Records", "Record"])));
+        assert!(!path.matches(&stack(&["Export", "Records"])));
+
+        let path: RecordPath = "//Records/Record".parse().unwrap();
+        assert!(path.matches(&stack(&["Export", "Records", "Record"])));
+        assert!(path.matches(&stack(&["Records", "Record"])));
+        assert!(!path.matches(&stack(&["Record"])));
+
+        assert!("Export/Record".parse::<RecordPath>().is_err());
+        assert!("/Export//Record".parse::<RecordPath>().is_err());
+        assert!("/".parse::<RecordPath>().is_err());
+    }
+
+    #[test]
+    fn test_column_mapping() {
+        assert_eq!(
+            ColumnMapping::parse_list("id=@id:i64, city = Address/City, zip=Address/@zip:str")
+                .unwrap(),
+            vec![
+                ColumnMapping {
+                    name: "id".into(),
+                    element: vec![],
+                    attribute: Some("id".into()),
+                    ty: ColumnType::I64,
+                },
+                ColumnMapping {
+                    name: "city".into(),
+                    element: vec!["Address".into(), "City".into()],
+                    attribute: None,
+                    ty: ColumnType::Str,
+                },
+                ColumnMapping {
+                    name: "zip".into(),
+                    element: vec!["Address".into()],
+                    attribute: Some("zip".into()),
+                    ty: ColumnType::Str,
+                },
+            ]
+        );
+        assert!(ColumnMapping::parse_list("").is_err());
+        assert!(ColumnMapping::parse_list("id").is_err());
+        assert!(ColumnMapping::parse_list("id=@id:u128").is_err());
+        assert!(ColumnMapping::parse_list("=Name").is_err());
+    }
+}
@@ -0,0 +1,436 @@
+//! Transforms incoming binary xml stream into dataframe stream
+//!
+//! Document is parsed as a stream of events, only current record is kept in memory,
+//! so documents of arbitrary size can be processed.
+
+use crate::{
+    mapping::{ColumnMapping, RecordPath},
+    FromXml,
+};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use section::{
+    command_channel::{Command, SectionChannel},
+    futures::{self, FutureExt, Sink, SinkExt, Stream, StreamExt},
+    io::ReceiverReader,
+    message::{Ack, Chunk, Column, DataFrame, Message, Next, Value},
+    section::Section,
+    SectionError, SectionFuture, SectionMessage,
+};
+use std::{io::BufReader, pin::pin, sync::Arc};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+type Result<T, E = SectionError> = std::result::Result<T, E>;
+
+struct FromXmlMsg {
+    origin: String,
+    ack: Option<Ack>,
+    rx: Receiver<Result<Option<Chunk>>>,
+}
+
+impl std::fmt::Debug for FromXmlMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FromXmlMsg")
+            .field("origin", &self.origin)
+            .finish()
+    }
+}
+
+impl FromXmlMsg {
+    fn new(origin: &str, ack: Ack, rx: Receiver<Result<Option<Chunk>>>) -> Self {
+        Self {
+            origin: origin.into(),
+            ack: Some(ack),
+            rx,
+        }
+    }
+}
+
+impl Message for FromXmlMsg {
+    fn origin(&self) -> &str {
+        self.origin.as_str()
+    }
+
+    fn ack(&mut self) -> Ack {
+        self.ack.take().unwrap_or(Box::pin(async {}))
+    }
+
+    fn next(&mut self) -> Next<'_> {
+        Box::pin(async move {
+            match self.rx.recv().await {
+                None => Err("FromXmlMsg error: receiver closed".into()),
+                Some(msg) => msg,
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+struct XmlDataFrame {
+    mappings: Arc<[ColumnMapping]>,
+    values: Vec<Vec<Value>>,
+}
+
+impl DataFrame for XmlDataFrame {
+    fn columns(&self) -> Vec<Column<'_>> {
+        self.mappings
+            .iter()
+            .zip(self.values.iter())
+            .map(|(mapping, values)| {
+                Column::new(
+                    mapping.name.as_str(),
+                    mapping.ty.into(),
+                    Box::new(values.iter().map(Into::into)),
+                )
+            })
+            .collect()
+    }
+}
+
+struct RecordParser {
+    batch_size: usize,
+    record_path: RecordPath,
+    mappings: Arc<[ColumnMapping]>,
+    tx: Sender<Result<Option<Chunk>>>,
+    // path of currently open elements
+    stack: Vec<String>,
+    // depth of currently open record element
+    record_depth: Option<usize>,
+    row: Vec<Option<String>>,
+    values: Vec<Vec<Value>>,
+    rows: usize,
+}
+
+impl RecordParser {
+    fn new(
+        batch_size: usize,
+        record_path: RecordPath,
+        mappings: Arc<[ColumnMapping]>,
+        tx: Sender<Result<Option<Chunk>>>,
+    ) -> Self {
+        let columns = mappings.len();
+        Self {
+            batch_size: batch_size.max(1),
+            record_path,
+            mappings,
+            tx,
+            stack: vec![],
+            record_depth: None,
+            row: vec![None; columns],
+            values: (0..columns).map(|_| vec![]).collect(),
+            rows: 0,
+        }
+    }
+
+    fn start<B>(&mut self, element: &BytesStart<'_>, reader: &Reader<B>) -> Result<()> {
+        self.stack
+            .push(String::from_utf8_lossy(element.local_name().as_ref()).into());
+        if self.record_depth.is_none() && self.record_path.matches(&self.stack) {
+            self.record_depth = Some(self.stack.len());
+        }
+        // path of current element relative to record element
+        let path = match self.record_depth {
+            Some(depth) => &self.stack[depth..],
+            None => return Ok(()),
+        };
+        for (pos, mapping) in self.mappings.iter().enumerate() {
+            let name = match mapping.attribute.as_deref() {
+                Some(name) if mapping.element == path => name,
+                _ => continue,
+            };
+            for attribute in element.attributes() {
+                let attribute = attribute?;
+                if attribute.key.local_name().as_ref() == name.as_bytes() {
+                    self.row[pos] = Some(attribute.decode_and_unescape_value(reader)?.into_owned());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) {
+        let path = match self.record_depth {
+            Some(depth) => &self.stack[depth..],
+            None => return,
+        };
+        for (pos, mapping) in self.mappings.iter().enumerate() {
+            if mapping.attribute.is_none() && mapping.element == path {
+                self.row[pos].get_or_insert_with(String::new).push_str(text);
+            }
+        }
+    }
+
+    fn end(&mut self) -> Result<()> {
+        if self.record_depth == Some(self.stack.len()) {
+            self.record_depth = None;
+            for ((mapping, value), values) in self
+                .mappings
+                .iter()
+                .zip(self.row.iter_mut())
+                .zip(self.values.iter_mut())
+            {
+                let value = match value.take() {
+                    Some(value) => mapping.parse(value)?,
+                    None => Value::Null,
+                };
+                values.push(value);
+            }
+            self.rows += 1;
+            if self.rows >= self.batch_size {
+                self.send_batch()?;
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn send_batch(&mut self) -> Result<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let values = self.values.iter_mut().map(std::mem::take).collect();
+        self.rows = 0;
+        let df = Box::new(XmlDataFrame {
+            mappings: Arc::clone(&self.mappings),
+            values,
+        });
+        self.tx
+            .blocking_send(Ok(Some(Chunk::DataFrame(df))))
+            .map_err(|_| "send error")?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.send_batch()?;
+        self.tx.blocking_send(Ok(None)).map_err(|_| "send error")?;
+        Ok(())
+    }
+}
+
+fn bin_to_dataframe(
+    batch_size: usize,
+    record_path: RecordPath,
+    mappings: Arc<[ColumnMapping]>,
+    rx: Receiver<Option<Vec<u8>>>,
+    tx: Sender<Result<Option<Chunk>>>,
+) -> Result<()> {
+    let mut reader = Reader::from_reader(BufReader::new(ReceiverReader::new(rx)));
+    reader.trim_text(true);
+    let mut parser = RecordParser::new(batch_size, record_path, mappings, tx);
+    let mut buf = vec![];
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(element) => parser.start(&element, &reader)?,
+            Event::Empty(element) => {
+                parser.start(&element, &reader)?;
+                parser.end()?;
+            }
+            Event::End(_) => parser.end()?,
+            Event::Text(text) => parser.text(&text.unescape()?),
+            Event::CData(data) => parser.text(&String::from_utf8_lossy(&data)),
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    parser.finish()
+}
+
+async fn stream_in(mut msg: SectionMessage, tx: Sender<Option<Vec<u8>>>) -> Result<()> {
+    loop {
+        match msg.next().await {
+            Ok(Some(Chunk::Byte(bin))) => {
+                tx.send(Some(bin)).await?;
+            }
+            Ok(None) => {
+                tx.send(None).await?;
+                return Ok(());
+            }
+            Ok(Some(Chunk::DataFrame(_))) => Err("FromXml section expects binary input")?,
+            Err(e) => Err(e)?,
+        }
+    }
+}
+
+async fn stream_out(
+    batch_size: usize,
+    record_path: RecordPath,
+    mappings: Arc<[ColumnMapping]>,
+    rx: Receiver<Option<Vec<u8>>>,
+    tx: Sender<Result<Option<Chunk>>>,
+) -> Result<()> {
+    match tokio::task::spawn_blocking(move || {
+        bin_to_dataframe(batch_size, record_path, mappings, rx, tx)
+    })
+    .await
+    {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(e)?,
+        Err(e) => Err(format!("join error: {e}"))?,
+    }
+}
+
+impl<Input, Output, SectionChan> Section<Input, Output, SectionChan> for FromXml
+where
+    Input: Stream<Item = SectionMessage> + Send + 'static,
+    Output: Sink<SectionMessage, Error = SectionError> + Send + 'static,
+    SectionChan: SectionChannel + Send + 'static,
+{
+    type Error = SectionError;
+    type Future = SectionFuture;
+
+    fn start(self, input: Input, output: Output, mut section_channel: SectionChan) -> Self::Future {
+        Box::pin(async move {
+            let mut input = pin!(input);
+            let mut output = pin!(output);
+            // validate configuration before processing any messages
+            self.record_path.parse::<RecordPath>()?;
+            let mappings: Arc<[ColumnMapping]> = ColumnMapping::parse_list(&self.columns)?.into();
+            loop {
+                futures::select! {
+                    cmd = section_channel.recv().fuse() => {
+                        if let Command::Stop = cmd? {
+                            return Ok(())
+                        }
+                    },
+                    msg = input.next().fuse() => {
+                        let mut msg = match msg {
+                            None => Err("input closed")?,
+                            Some(msg) => msg,
+                        };
+                        let (tx_in, rx_in) = channel(1);
+                        let (tx_out, rx_out) = channel(1);
+                        let ack = msg.ack();
+                        let out = FromXmlMsg::new(msg.origin(), ack, rx_out);
+                        output.send(Box::new(out)).await?;
+                        futures::try_join!(
+                            stream_in(msg, tx_in),
+                            stream_out(
+                                self.batch_size,
+                                self.record_path.parse()?,
+                                Arc::clone(&mappings),
+                                rx_in,
+                                tx_out
+                            )
+                        )?;
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_xml(config: FromXml, payload: &str) -> Result<Vec<Vec<Vec<Value>>>> {
+        // feed document in small chunks to exercise streaming
+        let chunks = payload.as_bytes().chunks(5);
+        let (tx_in, rx_in) = channel(chunks.len() + 1);
+        let (tx_out, mut rx_out) = channel(64);
+        for chunk in chunks {
+            tx_in.blocking_send(Some(chunk.to_vec()))?;
+        }
+        tx_in.blocking_send(None)?;
+        let mappings = ColumnMapping::parse_list(&config.columns)?.into();
+        bin_to_dataframe(
+            config.batch_size,
+            config.record_path.parse()?,
+            mappings,
+            rx_in,
+            tx_out,
+        )?;
+        let mut batches = vec![];
+        while let Some(chunk) = rx_out.blocking_recv().ok_or("closed")?? {
+            let df = match chunk {
+                Chunk::DataFrame(df) => df,
+                Chunk::Byte(_) => Err("unexpected binary chunk")?,
+            };
+            let columns = df
+                .columns()
+                .into_iter()
+                .map(|column| column.map(|v| Value::from(&v)).collect())
+                .collect();
+            batches.push(columns);
+        }
+        Ok(batches)
+    }
+
+    const DOC: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Export>
+  <Header><Record id="0"><Name>not a record</Name></Record></Header>
+  <Records>
+    <Record id="1">
+      <Name>Widget &amp; Co</Name>
+      <Price>10.5</Price>
+      <Address zip="02134"><City>Boston</City></Address>
+    </Record>
+    <Record id="2">
+      <Name><![CDATA[<Gadget>]]></Name>
+      <Address zip="10001"/>
+    </Record>
+    <Record id="3"/>
+  </Records>
+</Export>"#;
+
+    #[test]
+    fn test_from_xml() -> Result<()> {
+        let config = FromXml::new(
+            2,
+            "/Export/Records/Record",
+            "id=@id:i64,name=Name,price=Price:f64,city=Address/City,zip=Address/@zip",
+        );
+        let batches = read_xml(config, DOC)?;
+        assert_eq!(
+            batches,
+            vec![
+                vec![
+                    vec![Value::I64(1), Value::I64(2)],
+                    vec![
+                        Value::from("Widget & Co".to_string()),
+                        Value::from("<Gadget>".to_string())
+                    ],
+                    vec![Value::F64(10.5), Value::Null],
+                    vec![Value::from("Boston".to_string()), Value::Null],
+                    vec![
+                        Value::from("02134".to_string()),
+                        Value::from("10001".to_string())
+                    ],
+                ],
+                vec![
+                    vec![Value::I64(3)],
+                    vec![Value::Null],
+                    vec![Value::Null],
+                    vec![Value::Null],
+                    vec![Value::Null],
+                ],
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_any_depth() -> Result<()> {
+        let config = FromXml::new(512, "//Record", "id=@id:i64");
+        let batches = read_xml(config, DOC)?;
+        assert_eq!(
+            batches,
+            vec![vec![vec![
+                Value::I64(0),
+                Value::I64(1),
+                Value::I64(2),
+                Value::I64(3)
+            ]]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_parse_error() {
+        let config = FromXml::new(512, "/Export/Records/Record", "name=Name:i64");
+        assert!(read_xml(config, DOC).is_err());
+    }
+}
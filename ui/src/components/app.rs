@@ -3,7 +3,10 @@ use std::{fmt::Display, future::pending, ops::Deref, pin::Pin, rc::Rc};
 use crate::components::routing::Route;
 use chrono::{DateTime, Utc};
 use config::prelude::RawConfig;
-use config_registry::ConfigRegistry as _ConfigRegistry;
+use config_registry::{
+    plugin::{PluginConfig, SectionDescriptor},
+    ConfigRegistry as _ConfigRegistry,
+};
 use dioxus::prelude::*;
 use futures::{Future, FutureExt, StreamExt};
 use gloo_timers::future::TimeoutFuture;
@@ -205,6 +208,9 @@ pub struct Node {
     pub y: f64,
     pub config: Box<dyn config_registry::Config>,
     pub daemon_id: Option<Uuid>,
+    /// reason why assigned daemon can't run node section
+    #[serde(default)]
+    pub incompatible: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -396,9 +402,145 @@ impl ControlPlaneClient {
     }
 }
 
+// Variables API
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+}
+
+/// owner of variables, workspace variables are referenced as `${name}`,
+/// daemon variables as `${daemon.name}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariablesScope {
+    Workspace(String),
+    Daemon(Uuid),
+}
+
+impl VariablesScope {
+    fn url(&self, paths: &[&str]) -> Result<url::Url> {
+        let mut url = match self {
+            Self::Workspace(name) => vec![WORKSPACE_API.to_string(), name.clone()],
+            Self::Daemon(id) => vec![DAEMON_API.to_string(), id.to_string()],
+        };
+        url.push("variables".into());
+        url.extend(paths.iter().map(ToString::to_string));
+        get_url(&url)
+    }
+}
+
+impl ControlPlaneClient {
+    pub async fn get_variables(&self, scope: &VariablesScope) -> Result<Vec<Variable>> {
+        let response = reqwest::get(scope.url(&[])?).await?;
+        match response.status() {
+            status_code if status_code.is_success() => Ok(response.json().await?),
+            status_code => Err(AppError::from_status_code(status_code)),
+        }
+    }
+
+    pub async fn set_variable(&self, scope: &VariablesScope, variable: &Variable) -> Result<()> {
+        let response = reqwest::Client::new()
+            .post(scope.url(&[])?)
+            .json(variable)
+            .send()
+            .await?;
+        match response.status() {
+            status_code if status_code.is_success() => Ok(()),
+            status_code => Err(AppError::from_status_code(status_code)),
+        }
+    }
+
+    pub async fn delete_variable(&self, scope: &VariablesScope, name: &str) -> Result<()> {
+        let response = reqwest::Client::new()
+            .delete(scope.url(&[name])?)
+            .send()
+            .await?;
+        match response.status() {
+            status_code if status_code.is_success() => Ok(()),
+            status_code => Err(AppError::from_status_code(status_code)),
+        }
+    }
+}
+
+// Sections API
+const SECTIONS_API: &str = "/api/sections";
+
+impl ControlPlaneClient {
+    pub async fn get_plugin_sections(&self) -> Result<Vec<SectionDescriptor>> {
+        let response = reqwest::Client::new()
+            .get(get_url(&[SECTIONS_API, "plugins"])?)
+            .send()
+            .await?;
+        match response.status() {
+            status_code if status_code.is_success() => Ok(response.json().await?),
+            status_code => Err(AppError::from_status_code(status_code)),
+        }
+    }
+}
+
+// Auth API
+const AUTH_API: &str = "/api/auth";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: Uuid,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ControlPlaneClient {
+    /// session cookie is set by control plane on success
+    pub async fn login(&self, email: &str, password: &str) -> Result<User> {
+        let response = reqwest::Client::new()
+            .post(get_url(&[AUTH_API, "login"])?)
+            .json(&serde_json::json!({"email": email, "password": password}))
+            .send()
+            .await?;
+        match response.status() {
+            status_code if status_code.is_success() => Ok(response.json().await?),
+            status_code => Err(AppError::from_status_code(status_code)),
+        }
+    }
+
+    pub async fn logout(&self) -> Result<()> {
+        let response = reqwest::Client::new()
+            .post(get_url(&[AUTH_API, "logout"])?)
+            .send()
+            .await?;
+        match response.status() {
+            status_code if status_code.is_success() => Ok(()),
+            status_code => Err(AppError::from_status_code(status_code)),
+        }
+    }
+
+    pub async fn current_user(&self) -> Result<User> {
+        let response = reqwest::get(get_url(&[AUTH_API, "me"])?).await?;
+        match response.status() {
+            StatusCode::OK => Ok(response.json().await?),
+            status_code => Err(AppError::from_status_code(status_code))?,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ConfigRegistry(Rc<_ConfigRegistry>);
 
+impl ConfigRegistry {
+    /// registry with plugin sections, reported by daemons, added
+    fn with_plugin_sections(&self, sections: Vec<SectionDescriptor>) -> Self {
+        let mut registry = (*self.0).clone();
+        for descriptor in sections {
+            let result = PluginConfig::new(&descriptor)
+                .and_then(|config| registry.add_plugin_config(config));
+            if let Err(e) = result {
+                tracing::error!("failed to add plugin section {}: {e}", descriptor.name);
+            }
+        }
+        Self(Rc::new(registry))
+    }
+}
+
 impl Deref for ConfigRegistry {
     type Target = Rc<_ConfigRegistry>;
 
@@ -408,18 +550,28 @@ impl Deref for ConfigRegistry {
 }
 
 impl PartialEq for ConfigRegistry {
-    fn eq(&self, _: &Self) -> bool {
-        true
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
     }
 }
 
 pub fn App() -> Element {
     let bg_coroutine = use_coroutine(AppBackgroundCoroutine::enter_loop);
-    let _control_plane_client = use_context_provider(move || ControlPlaneClient::new(bg_coroutine));
-    let _config_registry = use_context_provider(move || {
-        ConfigRegistry(Rc::new(
+    let control_plane_client = use_context_provider(move || ControlPlaneClient::new(bg_coroutine));
+    let mut config_registry = use_context_provider(move || {
+        Signal::new(ConfigRegistry(Rc::new(
             config_registry::new().expect("failed to initialize config registry"),
-        ))
+        )))
+    });
+    let _plugin_sections_fetcher = use_resource(move || async move {
+        match control_plane_client.get_plugin_sections().await {
+            Ok(sections) if sections.is_empty() => (),
+            Ok(sections) => {
+                let registry = config_registry.peek().with_plugin_sections(sections);
+                config_registry.set(registry);
+            }
+            Err(e) => tracing::error!("failed to fetch plugin sections: {e}"),
+        }
     });
     rsx! {
         Router::<Route> { }
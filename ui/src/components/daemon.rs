@@ -1,5 +1,8 @@
+use crate::components::app::VariablesScope;
 use crate::components::card::Card;
+use crate::components::variables::Variables;
 use dioxus::prelude::*;
+use uuid::Uuid;
 
 #[component]
 pub fn Daemon(daemon: String) -> Element {
@@ -70,6 +73,12 @@ pub fn Daemon(daemon: String) -> Element {
                     content: "Control Plane Address: 100.1.1.33",
                 }
             }
+            if let Ok(id) = daemon.parse::<Uuid>() {
+                div {
+                    class: "col-span-2",
+                    Variables { scope: VariablesScope::Daemon(id) }
+                }
+            }
         }
     }
 }
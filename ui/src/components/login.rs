@@ -1,7 +1,7 @@
-use crate::components::logo::LogoDark;
+use crate::components::{app::ControlPlaneClient, logo::LogoDark};
 use dioxus::prelude::*;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use std::rc::Rc;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct LoginForm {
@@ -20,9 +20,9 @@ impl Drop for LoginGuard {
 }
 
 pub fn Login() -> Element {
-    let location: String = web_sys::window().unwrap().location().to_string().into();
-    let location: Rc<str> = Rc::from(location);
+    let control_plane_client = use_context::<ControlPlaneClient>();
     let mut logging_in = use_signal(|| None);
+    let mut error = use_signal(|| None::<String>);
 
     rsx! {
         div {
@@ -48,7 +48,6 @@ pub fn Login() -> Element {
                             // already logging in
                             return
                         }
-                        let loc = Rc::clone(&location);
                         let task = spawn(async move {
                             LoginGuard(logging_in);
                             let login = event.values().into_iter().fold(LoginForm::default(), |mut login, (key, value)| {
@@ -64,12 +63,18 @@ pub fn Login() -> Element {
                                     bad_key => panic!("bad key: {bad_key}")
                                 }
                             });
-                            // FIXME: check response success
-                            let _res = reqwest::Client::new()
-                                .post(&*loc)
-                                .json(&login)
-                                .send()
-                                .await;
+                            match control_plane_client.login(&login.email, &login.password).await {
+                                Ok(_) => {
+                                    web_sys::window().unwrap().location().set_href("/").ok();
+                                },
+                                Err(e) if e.status_code == Some(StatusCode::UNAUTHORIZED) => {
+                                    *error.write() = Some("Invalid email or password".into());
+                                },
+                                Err(e) => {
+                                    tracing::error!("failed to login: {e}");
+                                    *error.write() = Some("Failed to sign in".into());
+                                },
+                            }
                         });
                         *logging_in.write() = Some(task);
                     },
@@ -124,6 +129,12 @@ pub fn Login() -> Element {
                     }
                 }
 
+                    if let Some(error) = &*error.read() {
+                        p {
+                            class:"mt-5 text-sm text-toadstool-1",
+                            "{error}"
+                        }
+                    }
                     div {
                         button {
                             r#type:"submit",
@@ -11,5 +11,6 @@ pub mod logo;
 pub mod navbar;
 pub mod node_state_form;
 pub mod routing;
+pub mod variables;
 pub mod workspace;
 pub mod workspaces;
@@ -1,9 +1,37 @@
 use dioxus::prelude::*;
+use reqwest::StatusCode;
 
-use crate::components::{logo::Logo, routing::Route};
+use crate::components::{app::ControlPlaneClient, logo::Logo, routing::Route};
+
+fn redirect_to_login() {
+    web_sys::window()
+        .unwrap()
+        .location()
+        .set_href("/login")
+        .ok();
+}
 
 #[component]
 pub fn NavBar() -> Element {
+    let control_plane_client = use_context::<ControlPlaneClient>();
+    let user = use_resource(move || async move {
+        match control_plane_client.current_user().await {
+            Ok(user) => Some(user),
+            Err(e) if e.status_code == Some(StatusCode::UNAUTHORIZED) => {
+                redirect_to_login();
+                None
+            }
+            Err(e) => {
+                tracing::error!("failed to get current user: {e}");
+                None
+            }
+        }
+    });
+    let email = match &*user.read_unchecked() {
+        Some(Some(user)) => user.email.clone(),
+        _ => String::new(),
+    };
+
     rsx! {
         header {
             div {
@@ -21,6 +49,22 @@ pub fn NavBar() -> Element {
                     class: "flex-inital m-h-max content-center px-4",
                     Link{ to: Route::Daemons{}, "Daemons" },
                 }
+                div {
+                    class: "flex-auto m-h-max content-center text-right px-4",
+                    "{email}"
+                }
+                div {
+                    class: "flex-inital m-h-max content-center pr-8",
+                    button {
+                        onclick: move |_| async move {
+                            if let Err(e) = control_plane_client.logout().await {
+                                tracing::error!("failed to logout: {e}");
+                            }
+                            redirect_to_login();
+                        },
+                        "Sign out"
+                    }
+                }
             }
         }
         Outlet::<Route> {}
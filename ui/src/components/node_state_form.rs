@@ -8,7 +8,7 @@ pub use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::components::app::{WorkspaceOperation, WorkspaceUpdate};
+use crate::components::app::{ConfigRegistry, WorkspaceOperation, WorkspaceUpdate};
 
 use super::app::ControlPlaneClient;
 
@@ -25,6 +25,8 @@ pub struct NodeState {
     pub port_diameter: f64,
     pub config: Box<dyn config_registry::Config>,
     pub daemon_id: Option<Uuid>,
+    #[serde(skip)]
+    pub incompatible: Option<String>,
 }
 
 impl Clone for NodeState {
@@ -38,6 +40,7 @@ impl Clone for NodeState {
             port_diameter: self.port_diameter,
             config: self.config.clone(),
             daemon_id: self.daemon_id,
+            incompatible: self.incompatible.clone(),
         }
     }
 }
@@ -59,6 +62,7 @@ impl NodeState {
             port_diameter: 12.0,
             config,
             daemon_id,
+            incompatible: None,
         }
     }
 
@@ -104,44 +108,92 @@ impl From<String> for FormFieldValue {
 struct FormState {
     config: Signal<NodeState>,
     fields: HashMap<String, FormFieldValue>,
+    // values of freshly built config, used to reset fields
+    defaults: HashMap<String, String>,
 }
 
 impl FormState {
-    fn new(config: Signal<NodeState>) -> Self {
-        let fields = config
-            .read()
+    fn new(config: Signal<NodeState>, config_registry: &ConfigRegistry) -> Self {
+        let node_state = config.read();
+        let fields = node_state
             .config
             .fields()
             .into_iter()
             .map(|field| (field.name.to_string(), field.value.to_string().into()))
             .collect();
-        Self { config, fields }
+        let defaults = match config_registry.build_config(node_state.config.name()) {
+            Ok(default) => default
+                .fields()
+                .into_iter()
+                .map(|field| (field.name.to_string(), field.value.to_string()))
+                .collect(),
+            Err(e) => {
+                tracing::error!("failed to build default config: {e}");
+                HashMap::new()
+            }
+        };
+        Self {
+            config,
+            fields,
+            defaults,
+        }
     }
 
-    // check if field is valid
-    fn is_field_valid(&self, field_name: &str) -> bool {
-        let value = match self.fields.get(field_name) {
-            Some(value) => value,
-            None => {
-                tracing::error!("no field with name {field_name} found");
-                return false;
+    // validation errors for current form values
+    // cross-field checks run only when every field is valid on its own
+    fn errors(&self) -> ValidationErrors {
+        let node_state = self.config.read();
+        let config = &node_state.config;
+        let mut errors = ValidationErrors::new();
+        let mut has_references = false;
+        for field in config.fields() {
+            let value = match self.fields.get(field.name) {
+                Some(value) => value,
+                None => {
+                    tracing::error!("no field with name {} found", field.name);
+                    continue;
+                }
+            };
+            // secrets are stripped, unmodified value is not known to ui
+            if field.metadata.is_password && !value.modified {
+                continue;
             }
-        };
-        self.config
-            .read()
-            .config
-            .validate_field(field_name, value.value.as_str().into())
-            .is_ok()
+            // values with variable references are validated by control plane after substitution
+            if field.metadata.is_template {
+                match config::template::has_references(&value.value) {
+                    Ok(true) => {
+                        has_references = true;
+                        continue;
+                    }
+                    Ok(false) => (),
+                    Err(e) => {
+                        errors.push(field.name, e.to_string());
+                        continue;
+                    }
+                }
+            }
+            if let Err(e) = config.validate_field(field.name, value.value.as_str().into()) {
+                errors.push(field.name, e.to_string());
+            }
+        }
+        if !errors.is_empty() || has_references {
+            return errors;
+        }
+        let mut config = config.clone();
+        for (name, value) in self.fields.iter().filter(|(_, value)| value.modified) {
+            if let Err(e) = config.set_field_value(name, value.value.as_str().into()) {
+                errors.push(name.as_str(), e.to_string());
+            }
+        }
+        if let Err(e) = config.cross_validate() {
+            errors.extend(e);
+        }
+        errors
     }
 
     // check if form is valid
     fn is_valid(&self) -> bool {
-        let config = &self.config.read().config;
-        self.fields.iter().all(|(key, value)| {
-            config
-                .validate_field(key, value.value.as_str().into())
-                .is_ok()
-        })
+        self.errors().is_empty()
     }
 
     fn update_value(&mut self, field_name: &str, value: String) {
@@ -153,6 +205,39 @@ impl FormState {
     fn get_value(&self, field_name: &str) -> &str {
         self.fields.get(field_name).unwrap().value.as_str()
     }
+
+    // check if field value differs from default one
+    fn can_reset(&self, field_name: &str) -> bool {
+        self.defaults
+            .get(field_name)
+            .is_some_and(|default| default != self.get_value(field_name))
+    }
+
+    fn reset_value(&mut self, field_name: &str) {
+        if let Some(default) = self.defaults.get(field_name).cloned() {
+            self.update_value(field_name, default)
+        }
+    }
+
+    // list values are kept as JSON array, as config renders them, empty items are preserved while editing
+    fn update_list(&mut self, field_name: &str, f: impl FnOnce(&mut Vec<String>)) {
+        let mut items = split_list(self.get_value(field_name));
+        f(&mut items);
+        self.update_value(field_name, join_list(&items));
+    }
+}
+
+// values, which are not JSON arrays, are treated as comma separated list
+fn split_list(value: &str) -> Vec<String> {
+    if value.is_empty() {
+        return vec![];
+    }
+    serde_json::from_str(value)
+        .unwrap_or_else(|_| value.split(',').map(|item| item.to_string()).collect())
+}
+
+fn join_list(items: &[String]) -> String {
+    serde_json::to_string(items).unwrap_or_default()
 }
 
 impl IntoIterator for FormState {
@@ -164,12 +249,214 @@ impl IntoIterator for FormState {
     }
 }
 
+// field label with description tooltip and "reset to default" button
+fn field_label(
+    mut form_state: Signal<Option<FormState>>,
+    field: &Field<'_>,
+    can_reset: bool,
+) -> Element {
+    let field_name = field.name.to_string();
+    rsx! {
+        div { class: "flex items-center",
+            label {
+                r#for: "{field.name}",
+                class: "min-w-24 text-sm font-medium leading-6 text-night-1 uppercase",
+                title: field.metadata.description.unwrap_or_default(),
+                "{field.name}"
+            }
+            for description in field.metadata.description {
+                span {
+                    class: "ml-1 px-1 rounded-full border border-night-2 text-xs text-night-2 cursor-help",
+                    title: "{description}",
+                    "?"
+                }
+            }
+            if can_reset {
+                button {
+                    r#type: "button",
+                    class: "ml-auto text-xs text-night-2 hover:underline",
+                    title: "reset to default value",
+                    onclick: move |_| {
+                        if let Some(form_state) = &mut *form_state.write() {
+                            form_state.reset_value(field_name.as_str())
+                        }
+                    },
+                    "reset"
+                }
+            }
+        }
+    }
+}
+
+// input for single config field, rendered according to field type and metadata
+fn form_field(
+    mut form_state: Signal<Option<FormState>>,
+    field: &Field<'_>,
+    field_value: &str,
+    error: Option<String>,
+    can_reset: bool,
+) -> Element {
+    let field_name = field.name.to_string();
+    let placeholder = match field.metadata.placeholder {
+        Some(placeholder) => placeholder,
+        None if field.ty.inner() == FieldType::Duration => "e.g. 30s, 5m, 1h30m",
+        None => "",
+    };
+    rsx! {
+        div {
+            if field.ty.is_bool() {
+                div { class: "flex items-center justify-start",
+                    {field_label(form_state, field, can_reset)}
+                    input {
+                        id: "{field.name}",
+                        name: "{field.name}",
+                        r#type: "checkbox",
+                        class: "ml-3 rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2 focus:outline-none",
+                        readonly: field.metadata.is_read_only,
+                        onchange: move |event| {
+                            if let Some(form_state) = &mut *form_state.write() {
+                                form_state.update_value(field_name.as_str(), event.value());
+                            }
+                        },
+                        checked: "{field_value}",
+                    }
+                }
+            } else if field.metadata.is_text_area {
+                {field_label(form_state, field, can_reset)}
+                // div included here so that textarea below appears on new grid row (ie, below the label)
+                div {
+                    textarea {
+                        id: "{field.name}",
+                        name: "{field.name}",
+                        class: "w-full rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2 focus:outline-none",
+                        class: if error.is_none() { "" } else { "outline outline-red-500" },
+                        placeholder: "{placeholder}",
+                        readonly: field.metadata.is_read_only,
+                        oninput: move |event| {
+                            if let Some(form_state) = &mut *form_state.write() {
+                                form_state.update_value(field_name.as_str(), event.value())
+                            }
+                        },
+                        value: "{field_value}",
+                    }
+                }
+            } else if field.ty.variants().is_some() {
+                // enums are rendered as dropdown with list of variants
+                div {
+                    {field_label(form_state, field, can_reset)}
+                    select {
+                        id: "{field.name}",
+                        name: "{field.name}",
+                        class: "w-full rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2",
+                        class: if error.is_none() { "" } else { "outline outline-red-500" },
+                        disabled: field.metadata.is_read_only,
+                        onchange: move |event| {
+                            if let Some(form_state) = &mut *form_state.write() {
+                                form_state.update_value(field_name.as_str(), event.value())
+                            }
+                        },
+                        if field.ty.is_optional() {
+                            option { value: "", selected: field_value.is_empty(), "" }
+                        }
+                        for variant in field.ty.variants().unwrap_or_default() {
+                            option {
+                                value: "{variant}",
+                                selected: variant.eq_ignore_ascii_case(field_value.trim()),
+                                "{variant}"
+                            }
+                        }
+                    }
+                }
+            } else if field.ty == FieldType::StringList {
+                // lists are rendered as set of inputs, one per item
+                div {
+                    {field_label(form_state, field, can_reset)}
+                    for (index, item) in split_list(field_value).into_iter().enumerate() {
+                        div { class: "flex items-center mb-1",
+                            input {
+                                r#type: "text",
+                                autocomplete: "off",
+                                class: "w-full rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2",
+                                placeholder: "{placeholder}",
+                                readonly: field.metadata.is_read_only,
+                                oninput: {
+                                    let field_name = field_name.clone();
+                                    move |event: FormEvent| {
+                                        if let Some(form_state) = &mut *form_state.write() {
+                                            form_state.update_list(field_name.as_str(), |items| items[index] = event.value())
+                                        }
+                                    }
+                                },
+                                value: "{item}",
+                            }
+                            button {
+                                r#type: "button",
+                                class: "ml-2 px-2 rounded border border-toadstool-1 text-toadstool-1 hover:text-white hover:bg-toadstool-2",
+                                disabled: field.metadata.is_read_only,
+                                onclick: {
+                                    let field_name = field_name.clone();
+                                    move |_| {
+                                        if let Some(form_state) = &mut *form_state.write() {
+                                            form_state.update_list(field_name.as_str(), |items| { items.remove(index); })
+                                        }
+                                    }
+                                },
+                                "-"
+                            }
+                        }
+                    }
+                    button {
+                        r#type: "button",
+                        class: "px-2 rounded border border-forest-2 text-forest-2 hover:text-white hover:bg-forest-2",
+                        disabled: field.metadata.is_read_only,
+                        onclick: move |_| {
+                            if let Some(form_state) = &mut *form_state.write() {
+                                form_state.update_list(field_name.as_str(), |items| items.push(String::new()))
+                            }
+                        },
+                        "+"
+                    }
+                }
+            } else {
+                // returns basic text input
+                div {
+                    {field_label(form_state, field, can_reset)}
+                    input {
+                        id: "{field.name}",
+                        name: "{field.name}",
+                        r#type: if field.metadata.is_password { "password" } else if field.ty.is_number() { "number" } else { "text" },
+                        step: if field.ty.inner() == FieldType::F64 { "any" } else { "1" },
+                        placeholder: "{placeholder}",
+                        autocomplete: "off",
+                        class: "w-full rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2",
+                        class: if error.is_none() { "" } else { "outline outline-red-500" },
+                        readonly: field.metadata.is_read_only,
+                        oninput: move |event| {
+                            if let Some(form_state) = &mut *form_state.write() {
+                                form_state.update_value(field_name.as_str(), event.value())
+                            }
+                        },
+                        value: "{ field_value }",
+                    }
+                }
+            }
+            for error in error.iter() {
+                p {
+                    class: "text-sm text-red-500",
+                    "{error}"
+                }
+            }
+        }
+    }
+}
+
 #[component]
 pub fn NodeStateForm(
     workspace: Rc<str>,
     control_plane_client: ControlPlaneClient,
     selected_node: Signal<Option<Signal<NodeState>>>,
 ) -> Element {
+    let config_registry = use_context::<Signal<ConfigRegistry>>().read().clone();
     let node_state = match *selected_node.read() {
         None => return None,
         Some(signal) => signal,
@@ -183,17 +470,36 @@ pub fn NodeStateForm(
     let mut form_state: Signal<Option<FormState>> = use_signal(|| None);
     // Peeking to avoid re-render on write
     if form_state.peek().is_none() {
-        *form_state.write() = Some(FormState::new(node_state));
+        *form_state.write() = Some(FormState::new(node_state, &config_registry));
     }
     let fs_state = &*form_state.read();
     let fs = fs_state.as_ref().unwrap();
+    let errors = fs.errors();
 
-    // since field.name is not &'static str, we need to clone it so all event handlers can capture field name by value
-    let config_fields = config.fields().into_iter().map(|field| {
+    // fields are rendered in groups, ordered by first appearance of group
+    // advanced fields are collapsed at the end of the form
+    let mut groups: Vec<(Option<&'static str>, Vec<Element>)> = vec![];
+    let mut advanced_fields = vec![];
+    for field in config.fields() {
         // use value from form_state
         let value = fs.get_value(field.name);
-        (field.name.to_string(), field, value)
-    });
+        let error = errors.get(field.name).map(str::to_string);
+        // secrets are stripped, so there is nothing to compare default value with
+        let can_reset =
+            !field.metadata.is_password && !field.metadata.is_read_only && fs.can_reset(field.name);
+        let element = form_field(form_state, &field, value, error, can_reset);
+        if field.metadata.is_advanced {
+            advanced_fields.push(element);
+            continue;
+        }
+        match groups
+            .iter_mut()
+            .find(|(group, _)| *group == field.metadata.group)
+        {
+            Some((_, elements)) => elements.push(element),
+            None => groups.push((field.metadata.group, vec![element])),
+        }
+    }
     return rsx! {
         div {
             class: "border border-solid rounded-md drop-shadow px-5 py-4 mt-4 mx-4",
@@ -251,77 +557,35 @@ pub fn NodeStateForm(
                             "Editing Node {id}"
                         }
                         h3 {
+                            title: config.description().unwrap_or_default(),
                             "Section Type: {node_type}"
                         }
                     }
-                    for (field_name, field, field_value) in config_fields {
-                        div {
-                            if field.ty.is_bool() {
-                                div { class: "flex items-center justify-start",
-                                    label {
-                                        r#for: "{field_name}",
-                                        class: "min-w-24 text-sm font-medium leading-6 text-night-1 uppercase",
-                                        "{field.name}"
-                                    }
-                                    input {
-                                        id: "{field.name}",
-                                        name: "{field.name}",
-                                        r#type: "checkbox",
-                                        class: "ml-3 rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2 focus:outline-none",
-                                        readonly: field.metadata.is_read_only,
-                                        onchange: move |event| {
-                                            if let Some(form_state) = &mut *form_state.write() {
-                                                form_state.update_value(field_name.as_str(), event.value());
-                                            }
-                                        },
-                                        checked: "{field_value}",
-                                    }
-                                }
-                            } else if field.metadata.is_text_area {
-                                label {
-                                    r#for: "{field.name}",
-                                    class: "text-sm font-medium leading-6 text-night-1 uppercase",
-                                    "{field.name}"
-                                }
-                                // div included here so that textarea below appears on new grid row (ie, below the label)
-                                div {
-                                    textarea {
-                                        id: "{field.name}",
-                                        name: "{field.name}",
-                                        class: "w-full rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2 focus:outline-none",
-                                        class: if fs.is_field_valid(&field_name) { "" } else { "outline outline-red-500" },
-                                        readonly: field.metadata.is_read_only,
-                                        oninput: move |event| {
-                                            if let Some(form_state) = &mut *form_state.write() {
-                                                form_state.update_value(field_name.as_str(), event.value())
-                                            }
-                                        },
-                                        value: "{field_value}",
-                                    }
+                    for (group, elements) in groups {
+                        fieldset {
+                            class: "grid grid-flow-rows gap-2",
+                            class: if group.is_some() { "border border-solid rounded-md px-3 pb-3" } else { "" },
+                            for group in group {
+                                legend {
+                                    class: "px-1 text-sm font-semibold text-night-2 uppercase",
+                                    "{group}"
                                 }
-                            } else {
-                                // returns basic text input
-                                div {
-                                    label {
-                                        r#for: "{field_name}",
-                                        class: "text-sm font-medium leading-6 text-night-1 uppercase",
-                                        "{field.name}"
-                                    }
-                                    input {
-                                        id: "{field.name}",
-                                        name: "{field.name}",
-                                        r#type: if field.metadata.is_password { "password" } else { "text" },
-                                        autocomplete: "off",
-                                        class: "w-full rounded-md py-1.5 text-gray-900 drop-shadow-sm ring-1 ring-night-1 focus:ring-2 focus:ring-night-2",
-                                        class: if fs.is_field_valid(&field_name) { "" } else { "outline outline-red-500" },
-                                        readonly: field.metadata.is_read_only,
-                                        oninput: move |event| {
-                                            if let Some(form_state) = &mut *form_state.write() {
-                                                form_state.update_value(field_name.as_str(), event.value())
-                                            }
-                                        },
-                                        value: "{ field_value }",
-                                    }
+                            }
+                            for element in elements {
+                                {element}
+                            }
+                        }
+                    }
+                    if !advanced_fields.is_empty() {
+                        details {
+                            summary {
+                                class: "cursor-pointer text-sm font-semibold text-night-2 uppercase",
+                                "Advanced"
+                            }
+                            div {
+                                class: "grid grid-flow-rows gap-2 mt-2",
+                                for element in advanced_fields {
+                                    {element}
                                 }
                             }
                         }
@@ -331,7 +595,7 @@ pub fn NodeStateForm(
                         button {
                             r#type:"submit",
                             class:"text-stem-1 px-4 py-2 rounded bg-forest-1 border border-forest-2 hover:bg-forest-2 hover:text-white uppercase font-semibold  drop-shadow-sm",
-                            class: if !fs.is_valid() { "opacity-50 cursor-not-allowed "} else { "" },
+                            class: if !errors.is_empty() { "opacity-50 cursor-not-allowed "} else { "" },
                             "Save"
                         }
                         button {
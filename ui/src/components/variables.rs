@@ -0,0 +1,141 @@
+use crate::components::app::{ControlPlaneClient, Variable, VariablesScope};
+use dioxus::prelude::*;
+
+#[component]
+fn NewVariable(scope: VariablesScope, restart_fetcher: Signal<bool>) -> Element {
+    let control_plane_client = use_context::<ControlPlaneClient>();
+    let mut error = use_signal(|| None::<String>);
+    rsx! {
+        form {
+            class: "grid grid-flow-col auto-cols-max gap-2 py-2",
+            onsubmit: move |event| {
+                let scope = scope.clone();
+                async move {
+                    let values = event.values();
+                    let variable = match (values.get("name"), values.get("value")) {
+                        (Some(name), Some(value)) => Variable {
+                            name: name.as_value().trim().to_string(),
+                            value: value.as_value(),
+                        },
+                        _ => {
+                            tracing::error!("failed to get variable name and value from form");
+                            return;
+                        }
+                    };
+                    match control_plane_client.set_variable(&scope, &variable).await {
+                        Ok(()) => {
+                            error.set(None);
+                            restart_fetcher.set(true);
+                        }
+                        Err(e) => {
+                            tracing::error!("failed to set variable {}: {e}", variable.name);
+                            error.set(Some(format!("failed to set variable {}: {}", variable.name, e.err)));
+                        }
+                    }
+                }
+            },
+            input {
+                class: "border border-night-1 rounded py-2 px-2",
+                name: "name",
+                placeholder: "Name",
+            }
+            input {
+                class: "border border-night-1 rounded py-2 px-2",
+                name: "value",
+                placeholder: "Value",
+            }
+            button {
+                class: "text-stem-1 px-4 py-2 rounded bg-forest-1 border border-forest-1",
+                "SET"
+            }
+        }
+        if let Some(error) = &*error.read() {
+            div {
+                class: "text-toadstool-1",
+                "{error}"
+            }
+        }
+    }
+}
+
+/// table of workspace or daemon variables with form to add or update variable
+#[component]
+pub fn Variables(scope: VariablesScope) -> Element {
+    let control_plane_client = use_context::<ControlPlaneClient>();
+    let mut variables = use_signal(Vec::<Variable>::new);
+    let mut restart_fetcher = use_signal(|| true);
+    let _state_fetcher = use_resource({
+        let scope = scope.clone();
+        move || {
+            let scope = scope.clone();
+            async move {
+                if !*restart_fetcher.read() {
+                    return;
+                }
+                restart_fetcher.set(false);
+                match control_plane_client.get_variables(&scope).await {
+                    Ok(fetched) => variables.set(fetched),
+                    Err(e) => tracing::error!("failed to fetch variables: {e}"),
+                }
+            }
+        }
+    });
+    let hint = match &scope {
+        VariablesScope::Workspace(_) => "Reference as ${name} in section configs",
+        VariablesScope::Daemon(_) => {
+            "Reference as ${daemon.name} in section configs, ${daemon.id} and ${daemon.name} are always defined"
+        }
+    };
+    rsx! {
+        div {
+            class: "px-3 py-2",
+            h2 {
+                class: "text-xl font-bold",
+                "Variables"
+            }
+            p {
+                class: "text-sm text-night-1",
+                "{hint}"
+            }
+            table {
+                class: "table-fix border border-solid text-left w-full mt-2",
+                thead {
+                    tr {
+                        class: "border-b border-solid p-4 font-bold bg-night-1/25",
+                        th { class: "pl-3 w-1/3", "Name" },
+                        th { class: "pl-3 w-1/2", "Value" },
+                        th { },
+                    }
+                }
+                for variable in variables.read().iter().cloned() {
+                    tr {
+                        class: "border-b border-gray-100",
+                        td { class: "pl-3", "{variable.name}" }
+                        td { class: "pl-3", "{variable.value}" }
+                        td {
+                            class: "text-right px-1",
+                            button {
+                                onclick: {
+                                    let scope = scope.clone();
+                                    move |_| {
+                                        let scope = scope.clone();
+                                        let name = variable.name.clone();
+                                        async move {
+                                            match control_plane_client.delete_variable(&scope, &name).await {
+                                                Ok(()) => restart_fetcher.set(true),
+                                                Err(e) => tracing::error!("failed to delete variable {name}: {e}"),
+                                            }
+                                        }
+                                    }
+                                },
+                                class: "text-toadstool-1 border border-toadstool-1 px-4 py-1 my-1 mx-3 rounded bg-white hover:text-white hover:bg-toadstool-2",
+                                "DELETE"
+                            }
+                        }
+                    }
+                }
+            }
+            NewVariable { scope: scope.clone(), restart_fetcher }
+        }
+    }
+}
@@ -2,11 +2,12 @@ use std::rc::Rc;
 use std::sync::Arc;
 
 use crate::components::app::{
-    ConfigRegistry, ControlPlaneClient, Daemon, Result, WorkspaceOperation, WorkspaceState,
-    WorkspaceUpdate,
+    ConfigRegistry, ControlPlaneClient, Daemon, Result, VariablesScope, WorkspaceOperation,
+    WorkspaceState, WorkspaceUpdate,
 };
 use crate::components::icons::{Delete, Edit, Pause, Play, Restart};
 use crate::components::node_state_form::NodeStateForm;
+use crate::components::variables::Variables;
 use config::SectionIO;
 use config_registry::ConfigMetaData;
 use dioxus::prelude::*;
@@ -45,6 +46,7 @@ fn MenuItem(
     rsx! {
         div {
             class: "min-w-32 min-h-24 border border-solid rounded grid grid-flow-rows p-2 shadow",
+            title: metadata.description.unwrap_or_default(),
             draggable: true,
             onmounted: move |event| {
                 spawn(async move {
@@ -127,11 +129,13 @@ fn Node(
                     move |_event|  {
                         let dragged = &mut *dragged_edge.write();
                         if let Some(DraggedEdge{from_node, ..}) = dragged {
-                            let ops = graph
-                                .write()
-                                .add_edge(*from_node, id)
-                                .into_iter();
-                            control_plane_client.update_workspace(WorkspaceUpdate::new(&workspace, ops));
+                            if can_connect(&graph.read(), *from_node, id) {
+                                let ops = graph
+                                    .write()
+                                    .add_edge(*from_node, id)
+                                    .into_iter();
+                                control_plane_client.update_workspace(WorkspaceUpdate::new(&workspace, ops));
+                            }
                             *dragged = None;
                         }
                     }
@@ -155,6 +159,17 @@ fn Node(
         },
     };
 
+    let incompatible = match node_ref.incompatible.as_deref() {
+        None => None,
+        Some(reason) => rsx! {
+            div {
+                class: "text-sm text-red-500",
+                title: "{reason}",
+                "not supported by daemon"
+            }
+        },
+    };
+
     let no_daemon_option = rsx! {
         option {
             selected: daemon_id.is_none(),
@@ -230,6 +245,9 @@ fn Node(
                     }
                     let dragged = &mut *dragged_edge.write();
                     if let Some(DraggedEdge{from_node, ..}) = dragged.take() {
+                        if !can_connect(&graph.read(), from_node, id) {
+                            return
+                        }
                         let ops = graph.write().add_edge(from_node, id);
                         control_plane_client.update_workspace(WorkspaceUpdate::new(&workspace, ops));
                     }
@@ -239,6 +257,7 @@ fn Node(
                 class: "mt-2",
                 "{node_type}"
             }
+            {incompatible}
             div {
                 class: "mt-1 mb-1",
                 onmousedown: move |_| {
@@ -278,7 +297,10 @@ fn Node(
                                     Some(daemon_id)
                                 }
                             };
-                            node.write().daemon_id = daemon_id;
+                            // compatibility with new daemon is checked by control plane on assignment
+                            let node = &mut *node.write();
+                            node.daemon_id = daemon_id;
+                            node.incompatible = None;
                         }
                     },
                     class: "block p-1 w-full rounded-md text-gray-900 ring-1 ring-night-1 drop-shadow-sm focus:ring-1 focus:ring-night-1 focus:outline-none",
@@ -550,6 +572,11 @@ fn Edges(
         let (scroll_x_offset, scroll_y_offset) = DraggedEdge::get_scroll_xy();
         if let Some(node) = graph_ref.get_node(*from_node) {
             let offset = node.read().port_diameter / 2.0;
+            // edge, hovering over node with incompatible input, is highlighted
+            let (stroke, stroke_dasharray) = match to_node {
+                Some(to_node) if !can_connect(graph_ref, *from_node, *to_node) => ("gray", "4 4"),
+                _ => ("red", "none"),
+            };
             let (x, y) = match to_node
                 .map(|to_node| graph_ref.get_node(to_node))
                 .unwrap_or(None)
@@ -568,7 +595,8 @@ fn Edges(
             dragged_edge_element = rsx! {
                 path {
                     stroke_width: "1",
-                    stroke: "red",
+                    stroke: stroke,
+                    stroke_dasharray: stroke_dasharray,
                     fill: "none",
                     d: if out_x < x {
                         format!(
@@ -647,6 +675,18 @@ fn Edges(
     }
 }
 
+// check if output of `from` node can be connected to input of `to` node
+fn can_connect(graph: &Graph, from: Uuid, to: Uuid) -> bool {
+    match (graph.get_node(from), graph.get_node(to)) {
+        (Some(from), Some(to)) => from
+            .read()
+            .config
+            .output()
+            .can_connect_to(to.read().config.input()),
+        _ => false,
+    }
+}
+
 #[derive(Debug, Clone)]
 struct DraggedMenuItem {
     metadata: ConfigMetaData,
@@ -721,7 +761,7 @@ pub fn Workspace(workspace: String) -> Element {
     let control_plane_client = use_context::<ControlPlaneClient>();
     let mut graph: Signal<Graph> = use_signal(Graph::new);
     let mut daemons: Signal<Vec<Daemon>> = use_signal(Vec::new);
-    let config_registry = use_context::<ConfigRegistry>();
+    let config_registry = use_context::<Signal<ConfigRegistry>>().read().clone();
     let workspace = Rc::from(workspace);
     let state_fetcher: Resource<Result<WorkspaceState>> = use_resource({
         let workspace = Rc::clone(&workspace);
@@ -744,13 +784,10 @@ pub fn Workspace(workspace: String) -> Element {
                 };
                 graph.add_node(
                     node.id,
-                    Signal::new(NodeState::new(
-                        node.id,
-                        node.x,
-                        node.y,
-                        config,
-                        node.daemon_id,
-                    )),
+                    Signal::new(NodeState {
+                        incompatible: node.incompatible.clone(),
+                        ..NodeState::new(node.id, node.x, node.y, config, node.daemon_id)
+                    }),
                 );
             }
             for edge in workspace_state.edges.iter() {
@@ -772,6 +809,7 @@ pub fn Workspace(workspace: String) -> Element {
         .iter_values()
         .collect::<Vec<ConfigMetaData>>();
     let mut mounted_data = use_signal(|| None);
+    let mut show_variables = use_signal(|| false);
 
     rsx! {
         div {
@@ -822,6 +860,21 @@ pub fn Workspace(workspace: String) -> Element {
                     class: "text-lg justify-self-start",
                     "Workspace: {workspace}",
                 }
+                button {
+                    class: "justify-self-end mr-3 px-4 rounded border border-stem-1 hover:bg-night-1",
+                    onclick: move |event| {
+                        event.stop_propagation();
+                        let show = !*show_variables.read();
+                        show_variables.set(show);
+                    },
+                    "VARIABLES"
+                }
+            }
+            if *show_variables.read() {
+                div {
+                    class: "col-span-2 bg-white border-b border-solid",
+                    Variables { scope: VariablesScope::Workspace(workspace.to_string()) }
+                }
             }
             // section menu
             div {